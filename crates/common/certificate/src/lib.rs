@@ -3,7 +3,7 @@ use rcgen::Certificate;
 use rcgen::CertificateParams;
 use rcgen::RcgenError;
 use sha1::{Digest, Sha1};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use time::{Duration, OffsetDateTime};
 use zeroize::Zeroizing;
 pub mod device_id;
@@ -208,6 +208,9 @@ pub enum CertificateError {
     #[error("Could not parse certificate")]
     CertificateParseFailed,
 
+    #[error("Cannot load the root certificates from {path:?}: {cause}")]
+    RootCertificateLoadFailed { path: PathBuf, cause: String },
+
     #[error("HTTP Connection Problem: {msg} \nHint: {hint}")]
     CertificateValidationFailure { hint: String, msg: String },
 
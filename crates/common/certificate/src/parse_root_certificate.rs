@@ -1,6 +1,11 @@
 use rustls::{Certificate, ClientConfig, PrivateKey, RootCertStore};
 use rustls_pemfile::{certs, pkcs8_private_keys, rsa_private_keys};
-use std::{fs, fs::File, io::BufReader, path::PathBuf};
+use std::{
+    fs,
+    fs::File,
+    io::BufReader,
+    path::{Path, PathBuf},
+};
 
 use crate::CertificateError;
 
@@ -9,14 +14,65 @@ pub fn create_tls_config(
     client_private_key: PathBuf,
     client_certificate: PathBuf,
 ) -> Result<ClientConfig, CertificateError> {
-    let root_cert_store = new_root_store(root_certificates)?;
-    let pvt_key = read_pvt_key(client_private_key)?;
-    let cert_chain = read_cert_chain(client_certificate)?;
+    create_tls_client_config(
+        &[root_certificates],
+        Some((client_certificate, client_private_key)),
+    )
+}
+
+/// Create a TLS client configuration trusting the root certificates found in the given files and directories,
+/// the client being authenticated with the given certificate and private key, if any.
+///
+/// Each of these files and directories must exist and be readable, a file having to contain valid certificates.
+/// Only the unparsable files found under a directory are ignored.
+pub fn create_tls_client_config(
+    root_certificates: &[PathBuf],
+    client_auth: Option<(PathBuf, PathBuf)>,
+) -> Result<ClientConfig, CertificateError> {
+    let mut root_cert_store = RootCertStore::empty();
+    for cert_path in root_certificates {
+        add_configured_root_certs(&mut root_cert_store, cert_path)?;
+    }
 
-    Ok(ClientConfig::builder()
+    let config = ClientConfig::builder()
         .with_safe_defaults()
-        .with_root_certificates(root_cert_store)
-        .with_single_cert(cert_chain, pvt_key)?)
+        .with_root_certificates(root_cert_store);
+    match client_auth {
+        Some((client_certificate, client_private_key)) => {
+            let cert_chain = read_cert_chain(client_certificate)?;
+            let pvt_key = read_pvt_key(client_private_key)?;
+            Ok(config.with_single_cert(cert_chain, pvt_key)?)
+        }
+        None => Ok(config.with_no_client_auth()),
+    }
+}
+
+fn add_configured_root_certs(
+    root_store: &mut RootCertStore,
+    cert_path: &Path,
+) -> Result<(), CertificateError> {
+    let load_failure = |cause: String| CertificateError::RootCertificateLoadFailed {
+        path: cert_path.to_path_buf(),
+        cause,
+    };
+
+    let metadata = fs::metadata(cert_path).map_err(|err| load_failure(err.to_string()))?;
+    if metadata.is_dir() {
+        fs::read_dir(cert_path).map_err(|err| load_failure(err.to_string()))?;
+        rec_add_root_cert(root_store, cert_path.to_path_buf());
+    } else {
+        let certificates = read_cert_chain(cert_path.to_path_buf())
+            .map_err(|err| load_failure(err.to_string()))?;
+        if certificates.is_empty() {
+            return Err(load_failure("no certificate found".to_string()));
+        }
+        for certificate in certificates.iter() {
+            root_store
+                .add(certificate)
+                .map_err(|err| load_failure(err.to_string()))?;
+        }
+    }
+    Ok(())
 }
 
 fn new_root_store(cert_path: PathBuf) -> Result<RootCertStore, CertificateError> {
@@ -182,6 +238,43 @@ mod tests {
         assert_eq!(root_certs.len(), 3);
     }
 
+    #[test]
+    fn a_tls_client_config_can_be_created_without_client_certificate() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut ca_file = File::create(temp_dir.path().join("ca.pem")).unwrap();
+        ca_file
+            .write_all(include_str!("./test_root_cert_1.txt").as_bytes())
+            .unwrap();
+
+        assert!(create_tls_client_config(&[temp_dir.path().join("ca.pem")], None).is_ok());
+
+        let missing_key = (
+            temp_dir.path().join("ca.pem"),
+            temp_dir.path().join("missing.key"),
+        );
+        assert!(create_tls_client_config(&[temp_dir.path().into()], Some(missing_key)).is_err());
+    }
+
+    #[test]
+    fn configured_root_certificates_must_be_readable_and_valid() {
+        let temp_dir = TempDir::new().unwrap();
+        let missing_ca = temp_dir.path().join("missing.pem");
+        assert!(matches!(
+            create_tls_client_config(&[missing_ca], None),
+            Err(CertificateError::RootCertificateLoadFailed { .. })
+        ));
+
+        let mut not_a_ca = File::create(temp_dir.path().join("not_a_ca.pem")).unwrap();
+        not_a_ca.write_all(b"not a certificate").unwrap();
+        assert!(matches!(
+            create_tls_client_config(&[temp_dir.path().join("not_a_ca.pem")], None),
+            Err(CertificateError::RootCertificateLoadFailed { .. })
+        ));
+
+        // The unparsable files of a CA directory are ignored
+        assert!(create_tls_client_config(&[temp_dir.path().into()], None).is_ok());
+    }
+
     #[test]
     fn all_certificates_are_loaded_even_under_sub_directories() {
         let temp_dir = TempDir::new().unwrap();
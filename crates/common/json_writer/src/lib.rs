@@ -68,6 +68,13 @@ impl JsonWriter {
         }
     }
 
+    pub fn write_bool(&mut self, value: bool) -> Result<(), JsonWriterError> {
+        self.maybe_separate();
+        serde_json::to_writer(&mut self.buffer, &value)?;
+        self.needs_separator = true;
+        Ok(())
+    }
+
     pub fn write_open_obj(&mut self) {
         self.maybe_separate();
         self.buffer.push(b'{');
@@ -112,6 +119,19 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn write_bool_message() -> anyhow::Result<()> {
+        let mut jw = JsonWriter::default();
+        jw.write_open_obj();
+        jw.write_key("open")?;
+        jw.write_bool(true)?;
+        jw.write_key("locked")?;
+        jw.write_bool(false)?;
+        jw.write_close_obj();
+        assert_eq!(jw.into_string()?, r#"{"open":true,"locked":false}"#);
+        Ok(())
+    }
+
     #[test]
     fn write_key_with_quote() -> anyhow::Result<()> {
         let mut jw = JsonWriter::with_capacity(128);
@@ -0,0 +1,51 @@
+use tokio::sync::watch;
+
+/// Create a handle to abort the commands bound to the returned signal.
+pub fn abort_channel() -> (AbortHandle, AbortSignal) {
+    let (sender, receiver) = watch::channel(false);
+    (AbortHandle { sender }, AbortSignal { receiver })
+}
+
+/// Aborts the commands launched with the associated [`AbortSignal`].
+///
+/// Once aborted, all the commands bound to the signal are terminated,
+/// and any new command fails right away, until the handle is reset.
+#[derive(Debug)]
+pub struct AbortHandle {
+    sender: watch::Sender<bool>,
+}
+
+impl AbortHandle {
+    /// Terminate the running commands and prevent any new command to be launched.
+    pub fn abort(&self) {
+        let _ = self.sender.send(true);
+    }
+
+    /// Let the new commands run again.
+    pub fn reset(&self) {
+        let _ = self.sender.send(false);
+    }
+}
+
+/// The signal used by a [`LoggedCommand`](crate::LoggedCommand) to be notified it has to be aborted.
+#[derive(Clone, Debug)]
+pub struct AbortSignal {
+    receiver: watch::Receiver<bool>,
+}
+
+impl AbortSignal {
+    pub fn is_aborted(&self) -> bool {
+        *self.receiver.borrow()
+    }
+
+    /// Wait till the associated handle is aborted.
+    ///
+    /// If the handle has been dropped, this never returns.
+    pub async fn aborted(&mut self) {
+        while !self.is_aborted() {
+            if self.receiver.changed().await.is_err() {
+                std::future::pending::<()>().await;
+            }
+        }
+    }
+}
@@ -1,3 +1,5 @@
+mod abort;
 mod logged_command;
 
+pub use crate::abort::{abort_channel, AbortHandle, AbortSignal};
 pub use crate::logged_command::{LoggedCommand, LoggingChild};
@@ -1,18 +1,28 @@
-use log::error;
+use crate::abort::AbortSignal;
+use log::{error, warn};
+use nix::sys::signal::{killpg, Signal};
+use nix::unistd::{setpgid, Pid};
 use std::{
     ffi::OsStr,
+    io::ErrorKind,
     process::{Output, Stdio},
+    time::Duration,
 };
 use tokio::{
     fs::File,
-    io::{AsyncWriteExt, BufWriter},
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader, BufWriter},
     process::{Child, Command},
 };
 
+/// The time given to a command to terminate once it has been asked to do so, before being killed.
+const TERMINATION_GRACE_PERIOD: Duration = Duration::from_secs(5);
+
 #[derive(Debug)]
 pub struct LoggingChild {
     command_line: String,
     pub inner_child: Child,
+    timeout: Option<Duration>,
+    abort_signal: Option<AbortSignal>,
 }
 
 impl LoggingChild {
@@ -20,13 +30,107 @@ impl LoggingChild {
         self,
         logger: &mut BufWriter<File>,
     ) -> Result<Output, std::io::Error> {
-        let outcome = self.inner_child.wait_with_output().await;
-        if let Err(err) = LoggedCommand::log_outcome(&self.command_line, &outcome, logger).await {
+        let command_line = self.command_line.clone();
+        let outcome = self.wait_within_limits().await;
+        if let Err(err) = LoggedCommand::log_outcome(&command_line, &outcome, logger).await {
+            error!("Fail to log the command execution: {}", err);
+        }
+
+        outcome
+    }
+
+    /// Wait for the command to complete, as [wait_with_output](Self::wait_with_output),
+    /// while passing each line of its standard output to `on_line` as soon as printed.
+    /// The lines that are not valid UTF-8 are passed with the invalid sequences replaced by `�`.
+    ///
+    /// The whole standard output is still returned and logged once the command has completed.
+    pub async fn wait_with_output_lines(
+        mut self,
+        logger: &mut BufWriter<File>,
+        mut on_line: impl FnMut(&str),
+    ) -> Result<Output, std::io::Error> {
+        let command_line = self.command_line.clone();
+        let stdout = self.inner_child.stdout.take();
+        let read_lines = async {
+            let mut collected = Vec::new();
+            if let Some(stdout) = stdout {
+                // Read till EOF, even the lines that are not UTF-8,
+                // so the command is never blocked on a full pipe.
+                let mut stdout = BufReader::new(stdout);
+                let mut line = Vec::new();
+                while let Ok(n) = stdout.read_until(b'\n', &mut line).await {
+                    if n == 0 {
+                        break;
+                    }
+                    collected.extend_from_slice(&line);
+                    let text = String::from_utf8_lossy(&line);
+                    on_line(text.trim_end_matches('\n').trim_end_matches('\r'));
+                    line.clear();
+                }
+            }
+            collected
+        };
+
+        let (stdout, outcome) = tokio::join!(read_lines, self.wait_within_limits());
+        let outcome = outcome.map(|output| Output { stdout, ..output });
+        if let Err(err) = LoggedCommand::log_outcome(&command_line, &outcome, logger).await {
             error!("Fail to log the command execution: {}", err);
         }
 
         outcome
     }
+
+    /// Wait for the command to complete, unless the timeout elapses or the command is aborted.
+    ///
+    /// In the latter cases, the command is asked to terminate with a `SIGTERM`
+    /// and killed if still running after a grace period.
+    async fn wait_within_limits(self) -> Result<Output, std::io::Error> {
+        let LoggingChild {
+            command_line,
+            inner_child,
+            timeout,
+            mut abort_signal,
+        } = self;
+
+        let pid = inner_child.id();
+        let output = inner_child.wait_with_output();
+        tokio::pin!(output);
+
+        let deadline = async {
+            match timeout {
+                Some(timeout) => tokio::time::sleep(timeout).await,
+                None => std::future::pending().await,
+            }
+        };
+        let aborted = async {
+            match abort_signal.as_mut() {
+                Some(abort_signal) => abort_signal.aborted().await,
+                None => std::future::pending().await,
+            }
+        };
+
+        let error = tokio::select! {
+            outcome = &mut output => return outcome,
+            () = deadline => {
+                let timeout = timeout.unwrap_or_default().as_secs();
+                let reason = format!("Killed after a timeout of {timeout} seconds");
+                std::io::Error::new(ErrorKind::TimedOut, reason)
+            }
+            () = aborted => std::io::Error::new(ErrorKind::Interrupted, "Aborted"),
+        };
+
+        if let Some(pid) = pid {
+            warn!("Terminating: {}", command_line);
+            if let Err(err) = killpg(Pid::from_raw(pid as i32), Signal::SIGTERM) {
+                warn!("Fail to terminate {}: {}", command_line, err);
+            }
+        }
+
+        // The child process is killed on drop, if not terminated within the grace period
+        let _ = tokio::time::timeout(TERMINATION_GRACE_PERIOD, &mut output).await;
+
+        Err(error)
+    }
 }
 
 /// A command which execution is logged.
@@ -40,6 +144,8 @@ impl LoggingChild {
 pub struct LoggedCommand {
     command_line: String,
     command: Command,
+    timeout: Option<Duration>,
+    abort_signal: Option<AbortSignal>,
 }
 
 impl std::fmt::Display for LoggedCommand {
@@ -60,11 +166,14 @@ impl LoggedCommand {
             .current_dir("/tmp")
             .stdin(Stdio::piped())
             .stdout(Stdio::piped())
-            .stderr(Stdio::piped());
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
 
         LoggedCommand {
             command_line,
             command,
+            timeout: None,
+            abort_signal: None,
         }
     }
 
@@ -76,28 +185,67 @@ impl LoggedCommand {
         self
     }
 
+    /// Kill the command if not completed after the given timeout.
+    pub fn timeout(&mut self, timeout: Duration) -> &mut LoggedCommand {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Kill the command when the given signal is aborted.
+    pub fn abort_on(&mut self, abort_signal: AbortSignal) -> &mut LoggedCommand {
+        self.abort_signal = Some(abort_signal);
+        self
+    }
+
     /// Execute the command and log its exit status, stdout and stderr
     ///
     /// If the command has been executed the outcome is returned (successful or not).
     /// If the command fails to execute (say not found or not executable) an `std::io::Error` is returned.
+    /// An `std::io::Error` is also returned if the command is killed on timeout or aborted.
     ///
     /// If the function fails to log the execution of the command,
     /// this is logged with `log::error!` without changing the return value.
     pub async fn execute(mut self, logger: &mut BufWriter<File>) -> Result<Output, std::io::Error> {
-        let outcome = self.command.output().await;
-
-        if let Err(err) = LoggedCommand::log_outcome(&self.command_line, &outcome, logger).await {
-            error!("Fail to log the command execution: {}", err);
+        match self.spawn() {
+            Ok(child) => child.wait_with_output(logger).await,
+            Err(err) => {
+                let outcome = Err(err);
+                if let Err(err) =
+                    LoggedCommand::log_outcome(&self.command_line, &outcome, logger).await
+                {
+                    error!("Fail to log the command execution: {}", err);
+                }
+                outcome
+            }
         }
-
-        outcome
     }
 
     pub fn spawn(&mut self) -> Result<LoggingChild, std::io::Error> {
+        if let Some(abort_signal) = &self.abort_signal {
+            if abort_signal.is_aborted() {
+                return Err(std::io::Error::new(ErrorKind::Interrupted, "Aborted"));
+            }
+        }
+
+        if self.timeout.is_some() || self.abort_signal.is_some() {
+            // Run the command in its own process group,
+            // so the command and its sub-processes can be terminated all together.
+            // Notably, `sudo` doesn't relay the signals sent by a process of the same group.
+            //
+            // Safety: `setpgid` is async-signal-safe, so can be called between `fork` and `exec`.
+            unsafe {
+                self.command.pre_exec(|| {
+                    setpgid(Pid::from_raw(0), Pid::from_raw(0)).map_err(std::io::Error::from)
+                });
+            }
+        }
+
         let child = self.command.spawn()?;
         Ok(LoggingChild {
             command_line: self.command_line.clone(),
             inner_child: child,
+            timeout: self.timeout,
+            abort_signal: self.abort_signal.clone(),
         })
     }
 
@@ -142,6 +290,7 @@ impl LoggedCommand {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use assert_matches::assert_matches;
     use tedge_test_utils::fs::TempTedgeDir;
     use tokio::fs::File;
 
@@ -238,4 +387,120 @@ error: No such file or directory (os error 2)
         );
         Ok(())
     }
+
+    #[tokio::test]
+    async fn stdout_lines_are_processed_as_printed_and_logged() -> Result<(), anyhow::Error> {
+        let tmp_dir = TempTedgeDir::new();
+        let tmp_file = tmp_dir.file("operation.log");
+        let log_file = File::create(tmp_file.path()).await?;
+        let mut logger = BufWriter::new(log_file);
+
+        let mut command = LoggedCommand::new("sh");
+        command.arg("-c").arg("echo one; echo two");
+
+        let mut lines = Vec::new();
+        let output = command
+            .spawn()?
+            .wait_with_output_lines(&mut logger, |line| lines.push(line.to_string()))
+            .await?;
+
+        assert_eq!(lines, vec!["one", "two"]);
+        assert_eq!(output.stdout, b"one\ntwo\n");
+
+        let log_content = String::from_utf8(std::fs::read(tmp_file.path())?)?;
+        assert_eq!(
+            log_content,
+            r#"----- $ sh "-c" "echo one; echo two"
+exit status: 0
+
+stdout <<EOF
+one
+two
+EOF
+
+stderr <<EOF
+EOF
+"#
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn stdout_lines_are_read_till_eof_even_if_not_utf8() -> Result<(), anyhow::Error> {
+        let tmp_dir = TempTedgeDir::new();
+        let tmp_file = tmp_dir.file("operation.log");
+        let log_file = File::create(tmp_file.path()).await?;
+        let mut logger = BufWriter::new(log_file);
+
+        let mut command = LoggedCommand::new("sh");
+        command.arg("-c").arg(r"printf 'one\n\377\ntwo'");
+
+        let mut lines = Vec::new();
+        let output = command
+            .spawn()?
+            .wait_with_output_lines(&mut logger, |line| lines.push(line.to_string()))
+            .await?;
+
+        assert_eq!(lines, vec!["one", "\u{FFFD}", "two"]);
+        assert_eq!(output.stdout, b"one\n\xFF\ntwo");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn on_timeout_the_command_is_killed() -> Result<(), anyhow::Error> {
+        let tmp_dir = TempTedgeDir::new();
+        let tmp_file = tmp_dir.file("operation.log");
+        let log_file = File::create(tmp_file.path()).await?;
+        let mut logger = BufWriter::new(log_file);
+
+        // Prepare a command that would run for too long
+        let mut command = LoggedCommand::new("sleep");
+        command.arg("60").timeout(Duration::from_secs(1));
+
+        let started = std::time::Instant::now();
+        let outcome = command.execute(&mut logger).await;
+
+        assert_matches!(outcome, Err(err) if err.kind() == ErrorKind::TimedOut);
+        assert!(started.elapsed() < Duration::from_secs(10));
+
+        let log_content = String::from_utf8(std::fs::read(tmp_file.path())?)?;
+        assert_eq!(
+            log_content,
+            r#"----- $ sleep "60"
+error: Killed after a timeout of 1 seconds
+"#
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn aborted_commands_are_killed() -> Result<(), anyhow::Error> {
+        let tmp_dir = TempTedgeDir::new();
+        let tmp_file = tmp_dir.file("operation.log");
+        let log_file = File::create(tmp_file.path()).await?;
+        let mut logger = BufWriter::new(log_file);
+
+        let (abort_handle, abort_signal) = crate::abort_channel();
+        let mut command = LoggedCommand::new("sleep");
+        command.arg("60").abort_on(abort_signal.clone());
+
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            abort_handle.abort();
+        });
+
+        let started = std::time::Instant::now();
+        let outcome = command.execute(&mut logger).await;
+
+        assert_matches!(outcome, Err(err) if err.kind() == ErrorKind::Interrupted);
+        assert!(started.elapsed() < Duration::from_secs(10));
+
+        // Once aborted, no more commands are launched
+        let mut command = LoggedCommand::new("echo");
+        command.abort_on(abort_signal);
+        let outcome = command.execute(&mut logger).await;
+        assert_matches!(outcome, Err(err) if err.kind() == ErrorKind::Interrupted);
+
+        Ok(())
+    }
 }
@@ -25,8 +25,14 @@ pub trait PubChannel: SinkExt<Message> + Unpin + Send {
 #[async_trait]
 impl SubChannel for mpsc::UnboundedReceiver<Message> {}
 
+#[async_trait]
+impl SubChannel for mpsc::Receiver<Message> {}
+
 #[async_trait]
 impl ErrChannel for mpsc::UnboundedReceiver<MqttError> {}
 
 #[async_trait]
 impl PubChannel for mpsc::UnboundedSender<Message> {}
+
+#[async_trait]
+impl PubChannel for mpsc::Sender<Message> {}
@@ -1,4 +1,7 @@
-use crate::TopicFilter;
+use crate::{Message, MqttError, OverflowPolicy, Topic, TopicFilter};
+use certificate::parse_root_certificate::create_tls_client_config;
+use std::path::PathBuf;
+use std::sync::Arc;
 
 /// Configuration of an MQTT connection
 #[derive(Debug, Clone)]
@@ -33,14 +36,105 @@ pub struct Config {
 
     /// Capacity of the internal message queues
     ///
+    /// This bounds the queues of received and published messages,
+    /// as well as the queue of requests to `rumqttc`.
+    ///
+    /// The actual number of buffered messages is slightly larger,
+    /// the channels between the client and these queues buffering a few more messages.
+    ///
     /// Default: `1024`.
     ///
     pub queue_capacity: usize,
 
+    /// What to do with a droppable received message when the queue of received messages is full
+    ///
+    /// Blocking stops the reception of messages but also the publication of messages,
+    /// hence must be used only by clients that don't wait on publishing while not consuming the received messages.
+    /// The same applies to the received messages that are not droppable, for which the reception is always blocked.
+    ///
+    /// Default: `OverflowPolicy::DropOldest`.
+    pub received_overflow_policy: OverflowPolicy,
+
+    /// The topics of the received messages that can be dropped when the queue of received messages is full
+    ///
+    /// The messages received on other topics, e.g. operation requests, are never dropped:
+    /// their reception is blocked till there is room in the queue.
+    /// This is meant for telemetry, where the latest messages matter more than the completeness.
+    ///
+    /// Default: An empty topic list, i.e. no received messages are dropped
+    pub received_droppable_topics: TopicFilter,
+
+    /// What to do with a published message when the queue of published messages is full
+    ///
+    /// Default: `OverflowPolicy::Block`.
+    pub published_overflow_policy: OverflowPolicy,
+
+    /// The topic where the numbers of dropped messages are published, if set
+    ///
+    /// Default: None
+    pub dropped_messages_topic: Option<Topic>,
+
     /// Maximum size for a message payload
     ///
     /// Default: `1024 * 1024`.
     pub max_packet_size: usize,
+
+    /// Connect the broker over TLS, if set
+    ///
+    /// Default: None
+    pub tls: Option<TlsConfig>,
+
+    /// Authenticate with a username and password, if set
+    ///
+    /// Default: None
+    pub credentials: Option<Credentials>,
+
+    /// The message published by the broker on behalf of the client,
+    /// when the connection is lost without a proper disconnect.
+    ///
+    /// On a proper disconnect, this message is published by the client itself.
+    ///
+    /// Default: None
+    pub last_will_message: Option<Message>,
+
+    /// The message published by the client on each connection and re-connection.
+    ///
+    /// Default: None
+    pub birth_message: Option<Message>,
+}
+
+/// TLS configuration of an MQTT connection
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TlsConfig {
+    /// The files and directories of the CA certificates trusted to authenticate the broker
+    pub ca_paths: Vec<PathBuf>,
+
+    /// The certificate and the private key authenticating the client, if any
+    pub client_auth: Option<ClientAuth>,
+}
+
+/// Certificate and private key of an MQTT client
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientAuth {
+    pub cert_file: PathBuf,
+    pub key_file: PathBuf,
+}
+
+/// Username and password of an MQTT client
+#[derive(Clone, PartialEq, Eq)]
+pub struct Credentials {
+    pub username: String,
+    pub password: String,
+}
+
+/// The password is not displayed
+impl std::fmt::Debug for Credentials {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Credentials")
+            .field("username", &self.username)
+            .field("password", &"********")
+            .finish()
+    }
 }
 
 /// By default a client connects the local MQTT broker.
@@ -53,7 +147,15 @@ impl Default for Config {
             subscriptions: TopicFilter::empty(),
             clean_session: false,
             queue_capacity: 1024,
+            received_overflow_policy: OverflowPolicy::DropOldest,
+            received_droppable_topics: TopicFilter::empty(),
+            published_overflow_policy: OverflowPolicy::Block,
+            dropped_messages_topic: None,
             max_packet_size: 1024 * 1024,
+            tls: None,
+            credentials: None,
+            last_will_message: None,
+            birth_message: None,
         }
     }
 }
@@ -120,8 +222,81 @@ impl Config {
         }
     }
 
-    /// Wrap this config into an internal set of options for `rumqttc`.
-    pub(crate) fn mqtt_options(&self) -> rumqttc::MqttOptions {
+    /// Connect the broker over TLS
+    pub fn with_tls(self, tls: TlsConfig) -> Self {
+        Self {
+            tls: Some(tls),
+            ..self
+        }
+    }
+
+    /// Authenticate with a username and password
+    pub fn with_credentials(
+        self,
+        username: impl Into<String>,
+        password: impl Into<String>,
+    ) -> Self {
+        Self {
+            credentials: Some(Credentials {
+                username: username.into(),
+                password: password.into(),
+            }),
+            ..self
+        }
+    }
+
+    /// Set what to do with a received message when the queue of received messages is full
+    pub fn with_received_overflow_policy(self, policy: OverflowPolicy) -> Self {
+        Self {
+            received_overflow_policy: policy,
+            ..self
+        }
+    }
+
+    /// Set the topics of the received messages that can be dropped when the queue of received messages is full
+    pub fn with_received_droppable_topics(self, topics: TopicFilter) -> Self {
+        Self {
+            received_droppable_topics: topics,
+            ..self
+        }
+    }
+
+    /// Set what to do with a published message when the queue of published messages is full
+    pub fn with_published_overflow_policy(self, policy: OverflowPolicy) -> Self {
+        Self {
+            published_overflow_policy: policy,
+            ..self
+        }
+    }
+
+    /// Set the topic where the numbers of dropped messages are published
+    pub fn with_dropped_messages_topic(self, topic: Topic) -> Self {
+        Self {
+            dropped_messages_topic: Some(topic),
+            ..self
+        }
+    }
+
+    /// Set the last will message, published by the broker when the connection is lost
+    pub fn with_last_will_message(self, message: Message) -> Self {
+        Self {
+            last_will_message: Some(message),
+            ..self
+        }
+    }
+
+    /// Set the birth message, published on each connection
+    pub fn with_birth_message(self, message: Message) -> Self {
+        Self {
+            birth_message: Some(message),
+            ..self
+        }
+    }
+
+    /// Wrap this config into a set of options for `rumqttc`.
+    ///
+    /// This is used internally, but also by the clients that use `rumqttc` directly, as the `tedge mqtt` commands.
+    pub fn mqtt_options(&self) -> Result<rumqttc::MqttOptions, MqttError> {
         let id = match &self.session_name {
             None => std::iter::repeat_with(fastrand::lowercase)
                 .take(10)
@@ -140,6 +315,30 @@ impl Config {
 
         mqtt_options.set_max_packet_size(self.max_packet_size, self.max_packet_size);
 
-        mqtt_options
+        if let Some(tls) = &self.tls {
+            let client_auth = tls
+                .client_auth
+                .as_ref()
+                .map(|auth| (auth.cert_file.clone(), auth.key_file.clone()));
+            let tls_config = create_tls_client_config(&tls.ca_paths, client_auth)?;
+            mqtt_options.set_transport(rumqttc::Transport::tls_with_config(
+                rumqttc::TlsConfiguration::Rustls(Arc::new(tls_config)),
+            ));
+        }
+
+        if let Some(credentials) = &self.credentials {
+            mqtt_options.set_credentials(&credentials.username, &credentials.password);
+        }
+
+        if let Some(message) = &self.last_will_message {
+            mqtt_options.set_last_will(rumqttc::LastWill::new(
+                &message.topic.name,
+                message.payload_bytes(),
+                message.qos,
+                message.retain,
+            ));
+        }
+
+        Ok(mqtt_options)
     }
 }
@@ -1,20 +1,32 @@
-use crate::{Config, ErrChannel, Message, MqttError, PubChannel, SubChannel};
+use crate::queue::MessageQueue;
+use crate::{Config, ErrChannel, Message, MqttError, PubChannel, SubChannel, Topic};
 use futures::channel::mpsc;
 use futures::channel::oneshot;
 use futures::{SinkExt, StreamExt};
 use rumqttc::{
-    AsyncClient, ConnectionError, Event, EventLoop, Incoming, Outgoing, Packet, StateError,
+    AsyncClient, ConnectionError, Event, EventLoop, Incoming, Outgoing, Packet, QoS, StateError,
 };
 use std::time::Duration;
 use tokio::time::sleep;
 
+/// How often the numbers of dropped messages are checked, to be published when changed.
+const DROPPED_MESSAGES_PERIOD: Duration = Duration::from_secs(1);
+
+/// Capacity of the channels between the client and the message queues,
+/// so a client can send a few messages without waiting for them to be queued.
+const CHANNEL_CAPACITY: usize = 16;
+
 /// A connection to some MQTT server
+///
+/// The received and published messages are buffered in bounded queues,
+/// of `Config::queue_capacity` messages, the overflow policies being given by the config.
+/// On top of these queues, up to `CHANNEL_CAPACITY` messages are buffered by the channels to and from the client.
 pub struct Connection {
     /// The channel of the input messages received by this connection.
-    pub received: mpsc::UnboundedReceiver<Message>,
+    pub received: mpsc::Receiver<Message>,
 
     /// The channel of the output messages to be published on this connection.
-    pub published: mpsc::UnboundedSender<Message>,
+    pub published: mpsc::Sender<Message>,
 
     /// The channel of the error messages received by this connection.
     pub errors: mpsc::UnboundedReceiver<MqttError>,
@@ -70,26 +82,56 @@ impl Connection {
     ///     Connection::new(&config).await
     /// # }
     pub async fn new(config: &Config) -> Result<Connection, MqttError> {
-        let (received_sender, received_receiver) = mpsc::unbounded();
-        let (published_sender, published_receiver) = mpsc::unbounded();
+        let received_queue = MessageQueue::with_droppable_topics(
+            config.queue_capacity,
+            config.received_overflow_policy,
+            Some(config.received_droppable_topics.clone()),
+        );
+        let published_queue =
+            MessageQueue::new(config.queue_capacity, config.published_overflow_policy);
+        let (received_sender, received_receiver) = mpsc::channel(CHANNEL_CAPACITY);
+        let (published_sender, published_receiver) = mpsc::channel(CHANNEL_CAPACITY);
         let (error_sender, error_receiver) = mpsc::unbounded();
         let (pub_done_sender, pub_done_receiver) = oneshot::channel();
 
+        tokio::spawn(Connection::forward_received_messages(
+            received_queue.clone(),
+            received_sender,
+        ));
         let (mqtt_client, event_loop) =
-            Connection::open(config, received_sender.clone(), error_sender.clone()).await?;
+            match Connection::open(config, &received_queue, error_sender.clone()).await {
+                Ok(client) => client,
+                Err(err) => {
+                    received_queue.close();
+                    return Err(err);
+                }
+            };
         tokio::spawn(Connection::receiver_loop(
             mqtt_client.clone(),
             config.clone(),
             event_loop,
-            received_sender,
+            received_queue.clone(),
             error_sender.clone(),
         ));
-        tokio::spawn(Connection::sender_loop(
-            mqtt_client,
+        tokio::spawn(Connection::queue_published_messages(
             published_receiver,
+            published_queue.clone(),
+        ));
+        tokio::spawn(Connection::sender_loop(
+            mqtt_client.clone(),
+            config.last_will_message.clone(),
+            published_queue.clone(),
             error_sender,
             pub_done_sender,
         ));
+        if let Some(topic) = &config.dropped_messages_topic {
+            tokio::spawn(Connection::dropped_messages_loop(
+                mqtt_client,
+                topic.clone(),
+                received_queue,
+                published_queue,
+            ));
+        }
 
         Ok(Connection {
             received: received_receiver,
@@ -99,17 +141,17 @@ impl Connection {
         })
     }
 
-    pub async fn close(self) {
+    pub async fn close(mut self) {
         self.published.close_channel();
         let _ = self.pub_done.await;
     }
 
     async fn open(
         config: &Config,
-        mut message_sender: mpsc::UnboundedSender<Message>,
+        received_queue: &MessageQueue,
         mut error_sender: mpsc::UnboundedSender<MqttError>,
     ) -> Result<(AsyncClient, EventLoop), MqttError> {
-        let mqtt_options = config.mqtt_options();
+        let mqtt_options = config.mqtt_options()?;
         let (mqtt_client, mut event_loop) = AsyncClient::new(mqtt_options, config.queue_capacity);
 
         loop {
@@ -118,6 +160,8 @@ impl Connection {
                     if let Some(err) = MqttError::maybe_connection_error(&ack) {
                         return Err(err);
                     };
+                    Connection::publish_birth_message(&mqtt_client, config);
+
                     let subscriptions = config.subscriptions.filters();
 
                     // Need check here otherwise it will hang waiting for a SubAck, and none will come when there is no subscription.
@@ -137,8 +181,7 @@ impl Connection {
 
                 Ok(Event::Incoming(Packet::Publish(msg))) => {
                     // Messages can be received before a sub ack
-                    // Errors on send are ignored: it just means the client has closed the receiving channel.
-                    let _ = message_sender.send(msg.into()).await;
+                    received_queue.push(msg.into()).await;
                 }
 
                 Err(err) => {
@@ -162,30 +205,36 @@ impl Connection {
         mqtt_client: AsyncClient,
         config: Config,
         mut event_loop: EventLoop,
-        mut message_sender: mpsc::UnboundedSender<Message>,
+        received_queue: MessageQueue,
         mut error_sender: mpsc::UnboundedSender<MqttError>,
-    ) -> Result<(), MqttError> {
+    ) {
         loop {
             match event_loop.poll().await {
                 Ok(Event::Incoming(Packet::Publish(msg))) => {
-                    // Errors on send are ignored: it just means the client has closed the receiving channel.
+                    // The messages are queued even if the client has closed the receiving channel.
                     // One has to continue the loop though, because rumqttc relies on this polling.
-                    let _ = message_sender.send(msg.into()).await;
+                    received_queue.push(msg.into()).await;
                 }
 
                 Ok(Event::Incoming(Packet::ConnAck(ack))) => {
                     if let Some(err) = MqttError::maybe_connection_error(&ack) {
                         eprintln!("ERROR: Connection Error {}", err);
+                        continue;
                     }
+                    Connection::publish_birth_message(&mqtt_client, &config);
+
                     // Workaround for  https://github.com/bytebeamio/rumqtt/issues/250
                     // If session_name is not provided, then re-subscribe
-                    else if config.session_name.is_none() {
+                    if config.session_name.is_none() {
                         let subscriptions = config.subscriptions.filters();
                         // Need check here otherwise it will hang waiting for a SubAck, and none will come when there is no subscription.
                         if subscriptions.is_empty() {
                             break;
                         }
-                        Connection::subscribe_to_topics(&mqtt_client, subscriptions).await?;
+                        // Not awaiting the subscription, as the request channel is drained by this loop
+                        if let Err(err) = mqtt_client.try_subscribe_many(subscriptions) {
+                            eprintln!("ERROR: Fail to re-subscribe: {}", err);
+                        }
                     }
                 }
 
@@ -209,39 +258,90 @@ impl Connection {
             }
         }
         // No more messages will be forwarded to the client
-        let _ = message_sender.close().await;
+        received_queue.close();
         let _ = error_sender.close().await;
-        Ok(())
+    }
+
+    /// Forward the queued messages to the client, as fast as consumed by the client.
+    async fn forward_received_messages(
+        received_queue: MessageQueue,
+        mut message_sender: mpsc::Sender<Message>,
+    ) {
+        while let Some(message) = received_queue.pop().await {
+            // Errors on send are ignored: it just means the client has closed the receiving channel.
+            // One has to continue the loop though, to not block the receiver loop.
+            let _ = message_sender.send(message).await;
+        }
+        let _ = message_sender.close().await;
+    }
+
+    /// Queue the messages published by the client, until the client closes the sender channel.
+    async fn queue_published_messages(
+        mut messages_receiver: mpsc::Receiver<Message>,
+        published_queue: MessageQueue,
+    ) {
+        while let Some(message) = messages_receiver.next().await {
+            published_queue.push(message).await;
+        }
+        // No more messages will be published by the client
+        published_queue.close();
     }
 
     async fn sender_loop(
         mqtt_client: AsyncClient,
-        mut messages_receiver: mpsc::UnboundedReceiver<Message>,
+        last_will_message: Option<Message>,
+        published_queue: MessageQueue,
         mut error_sender: mpsc::UnboundedSender<MqttError>,
         done: oneshot::Sender<()>,
     ) {
-        loop {
-            match messages_receiver.next().await {
-                None => {
-                    // The sender channel has been closed by the client
-                    // No more messages will be published by the client
-                    break;
-                }
-                Some(message) => {
-                    let payload = Vec::from(message.payload_bytes());
-                    if let Err(err) = mqtt_client
-                        .publish(message.topic, message.qos, message.retain, payload)
-                        .await
-                    {
-                        let _ = error_sender.send(err.into()).await;
-                    }
-                }
+        while let Some(message) = published_queue.pop().await {
+            let payload = Vec::from(message.payload_bytes());
+            if let Err(err) = mqtt_client
+                .publish(message.topic, message.qos, message.retain, payload)
+                .await
+            {
+                let _ = error_sender.send(err.into()).await;
             }
         }
+        // On a clean disconnect, the broker discards the last will, which has then to be published by the client
+        if let Some(message) = last_will_message {
+            let payload = Vec::from(message.payload_bytes());
+            let _ = mqtt_client
+                .publish(message.topic, message.qos, message.retain, payload)
+                .await;
+        }
         let _ = mqtt_client.disconnect().await;
         let _ = done.send(());
     }
 
+    /// Publish the numbers of messages dropped because of full queues, each time changed.
+    ///
+    /// The payload is a JSON object, as `{"received":12,"published":0}`, published as a retained message.
+    async fn dropped_messages_loop(
+        mqtt_client: AsyncClient,
+        topic: Topic,
+        received_queue: MessageQueue,
+        published_queue: MessageQueue,
+    ) {
+        let mut last_counts = (0, 0);
+        while !published_queue.is_closed() {
+            sleep(DROPPED_MESSAGES_PERIOD).await;
+            let counts = (received_queue.dropped(), published_queue.dropped());
+            if counts == last_counts {
+                continue;
+            }
+            let payload = format!(r#"{{"received":{},"published":{}}}"#, counts.0, counts.1);
+            if mqtt_client
+                .publish(topic.clone(), QoS::AtLeastOnce, true, payload)
+                .await
+                .is_err()
+            {
+                break;
+            }
+            last_counts = counts;
+        }
+    }
+
     pub(crate) fn pause_on_error(err: &ConnectionError) -> bool {
         match &err {
             rumqttc::ConnectionError::Io(_) => true,
@@ -259,6 +359,21 @@ impl Connection {
         sleep(Duration::from_secs(1)).await;
     }
 
+    /// Publish the birth message, if any, as soon as connected or re-connected.
+    ///
+    /// The message is not awaited, as this is called by the task polling the event loop,
+    /// the only one draining the request channel that can be full of messages sent by the sender loop.
+    fn publish_birth_message(mqtt_client: &AsyncClient, config: &Config) {
+        if let Some(message) = &config.birth_message {
+            let payload = Vec::from(message.payload_bytes());
+            if let Err(err) =
+                mqtt_client.try_publish(message.topic.clone(), message.qos, message.retain, payload)
+            {
+                eprintln!("ERROR: Fail to publish the birth message: {}", err);
+            }
+        }
+    }
+
     pub(crate) async fn subscribe_to_topics(
         mqtt_client: &AsyncClient,
         subscriptions: Vec<rumqttc::SubscribeFilter>,
@@ -10,6 +10,9 @@ pub enum MqttError {
     #[error("Invalid session: a session name must be provided")]
     InvalidSessionConfig,
 
+    #[error("Invalid TLS configuration: {0}")]
+    InvalidTlsConfig(#[from] certificate::CertificateError),
+
     #[error("MQTT client error: {0}")]
     ClientError(#[from] rumqttc::ClientError),
 
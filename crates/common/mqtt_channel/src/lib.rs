@@ -34,6 +34,7 @@ mod config;
 mod connection;
 mod errors;
 mod messages;
+mod queue;
 mod session;
 mod topics;
 
@@ -44,12 +45,13 @@ pub use config::*;
 pub use connection::*;
 pub use errors::*;
 pub use messages::*;
+pub use queue::OverflowPolicy;
 pub use session::*;
 pub use topics::*;
 
 pub use futures::{
-    channel::mpsc::UnboundedReceiver, channel::mpsc::UnboundedSender, Sink, SinkExt, Stream,
-    StreamExt,
+    channel::mpsc::Receiver, channel::mpsc::Sender, channel::mpsc::UnboundedReceiver,
+    channel::mpsc::UnboundedSender, Sink, SinkExt, Stream, StreamExt,
 };
 
 pub use rumqttc::QoS;
@@ -0,0 +1,281 @@
+use crate::{Message, TopicFilter};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::Notify;
+
+/// What to do with a message that doesn't fit a full message queue.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Wait for the queue to have room for the message.
+    Block,
+
+    /// Drop the oldest message of the queue to make room for the new one.
+    DropOldest,
+
+    /// Drop the new message.
+    DropNewest,
+}
+
+/// A bounded queue of messages, between a single producer and a single consumer.
+///
+/// Only the droppable messages are subject to the overflow policy,
+/// the push of other messages being blocked while the queue is full.
+#[derive(Clone)]
+pub(crate) struct MessageQueue {
+    inner: Arc<QueueInner>,
+}
+
+struct QueueInner {
+    messages: Mutex<VecDeque<Message>>,
+    capacity: usize,
+    policy: OverflowPolicy,
+    droppable: Option<TopicFilter>,
+    not_empty: Notify,
+    not_full: Notify,
+    closed: AtomicBool,
+    dropped: AtomicU64,
+}
+
+impl MessageQueue {
+    /// A queue where all the messages are subject to the overflow policy.
+    pub(crate) fn new(capacity: usize, policy: OverflowPolicy) -> Self {
+        MessageQueue::with_droppable_topics(capacity, policy, None)
+    }
+
+    /// A queue where only the messages published on the `droppable` topics, if given,
+    /// are subject to the overflow policy, the other messages waiting for room in the queue.
+    pub(crate) fn with_droppable_topics(
+        capacity: usize,
+        policy: OverflowPolicy,
+        droppable: Option<TopicFilter>,
+    ) -> Self {
+        let capacity = capacity.max(1);
+        MessageQueue {
+            inner: Arc::new(QueueInner {
+                messages: Mutex::new(VecDeque::with_capacity(capacity)),
+                capacity,
+                policy,
+                droppable,
+                not_empty: Notify::new(),
+                not_full: Notify::new(),
+                closed: AtomicBool::new(false),
+                dropped: AtomicU64::new(0),
+            }),
+        }
+    }
+
+    /// Push a message, applying the overflow policy if the queue is full and the message droppable,
+    /// and waiting for room in the queue if the message cannot be dropped.
+    pub(crate) async fn push(&self, message: Message) {
+        let policy = if self.is_droppable(&message) {
+            self.inner.policy
+        } else {
+            OverflowPolicy::Block
+        };
+        loop {
+            // Created before checking the queue, so a notification sent meanwhile is not missed
+            let not_full = self.inner.not_full.notified();
+            {
+                let mut messages = self.inner.messages.lock().unwrap();
+                if messages.len() < self.inner.capacity {
+                    messages.push_back(message);
+                    break;
+                }
+                match policy {
+                    // No more room will be made once closed
+                    OverflowPolicy::Block if self.is_closed() => {
+                        self.inner.dropped.fetch_add(1, Ordering::Relaxed);
+                        return;
+                    }
+                    OverflowPolicy::Block => {}
+                    OverflowPolicy::DropOldest => {
+                        self.inner.dropped.fetch_add(1, Ordering::Relaxed);
+                        match messages.iter().position(|queued| self.is_droppable(queued)) {
+                            Some(oldest) => {
+                                messages.remove(oldest);
+                                messages.push_back(message);
+                                break;
+                            }
+                            None => return,
+                        }
+                    }
+                    OverflowPolicy::DropNewest => {
+                        self.inner.dropped.fetch_add(1, Ordering::Relaxed);
+                        return;
+                    }
+                }
+            }
+            not_full.await;
+        }
+        self.inner.not_empty.notify_one();
+    }
+
+    /// Pop the oldest message, waiting for one if the queue is empty.
+    ///
+    /// Returns `None` when the queue has been closed and all its messages consumed.
+    pub(crate) async fn pop(&self) -> Option<Message> {
+        loop {
+            {
+                let mut messages = self.inner.messages.lock().unwrap();
+                if let Some(message) = messages.pop_front() {
+                    self.inner.not_full.notify_one();
+                    return Some(message);
+                }
+                if self.is_closed() {
+                    return None;
+                }
+            }
+            self.inner.not_empty.notified().await;
+        }
+    }
+
+    /// Close the queue: no more messages will be pushed.
+    pub(crate) fn close(&self) {
+        self.inner.closed.store(true, Ordering::Release);
+        self.inner.not_empty.notify_one();
+        self.inner.not_full.notify_waiters();
+    }
+
+    fn is_droppable(&self, message: &Message) -> bool {
+        match &self.inner.droppable {
+            Some(droppable) => droppable.accept(message),
+            None => true,
+        }
+    }
+
+    pub(crate) fn is_closed(&self) -> bool {
+        self.inner.closed.load(Ordering::Acquire)
+    }
+
+    /// The number of messages dropped so far, because the queue was full.
+    pub(crate) fn dropped(&self) -> u64 {
+        self.inner.dropped.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Topic;
+    use std::time::Duration;
+
+    fn message(payload: &str) -> Message {
+        Message::new(&Topic::new_unchecked("test/topic"), payload)
+    }
+
+    fn telemetry(payload: &str) -> Message {
+        Message::new(&Topic::new_unchecked("test/telemetry"), payload)
+    }
+
+    async fn pop_payload(queue: &MessageQueue) -> String {
+        let message = queue.pop().await.unwrap();
+        message.payload_str().unwrap().to_string()
+    }
+
+    #[tokio::test]
+    async fn drop_oldest_keeps_the_latest_messages() {
+        let queue = MessageQueue::new(2, OverflowPolicy::DropOldest);
+        for payload in ["1", "2", "3"] {
+            queue.push(message(payload)).await;
+        }
+
+        assert_eq!(queue.dropped(), 1);
+        assert_eq!(pop_payload(&queue).await, "2");
+        assert_eq!(pop_payload(&queue).await, "3");
+    }
+
+    #[tokio::test]
+    async fn drop_newest_keeps_the_first_messages() {
+        let queue = MessageQueue::new(2, OverflowPolicy::DropNewest);
+        for payload in ["1", "2", "3"] {
+            queue.push(message(payload)).await;
+        }
+
+        assert_eq!(queue.dropped(), 1);
+        assert_eq!(pop_payload(&queue).await, "1");
+        assert_eq!(pop_payload(&queue).await, "2");
+    }
+
+    #[tokio::test]
+    async fn block_waits_for_room_in_the_queue() {
+        let queue = MessageQueue::new(1, OverflowPolicy::Block);
+        queue.push(message("1")).await;
+
+        let blocked_push =
+            tokio::time::timeout(Duration::from_millis(100), queue.push(message("2")));
+        assert!(blocked_push.await.is_err());
+
+        let producer = queue.clone();
+        let push = tokio::spawn(async move { producer.push(message("3")).await });
+        assert_eq!(pop_payload(&queue).await, "1");
+        push.await.unwrap();
+        assert_eq!(pop_payload(&queue).await, "3");
+        assert_eq!(queue.dropped(), 0);
+    }
+
+    #[tokio::test]
+    async fn only_the_droppable_messages_are_dropped() {
+        let droppable = TopicFilter::new_unchecked("test/telemetry");
+        let queue =
+            MessageQueue::with_droppable_topics(2, OverflowPolicy::DropOldest, Some(droppable));
+        for msg in [telemetry("1"), message("2"), telemetry("3")] {
+            queue.push(msg).await;
+        }
+
+        // Room is made for the telemetry by dropping the oldest telemetry message
+        assert_eq!(queue.dropped(), 1);
+        assert_eq!(pop_payload(&queue).await, "2");
+        assert_eq!(pop_payload(&queue).await, "3");
+    }
+
+    #[tokio::test]
+    async fn non_droppable_messages_wait_for_room_in_the_queue() {
+        let droppable = TopicFilter::new_unchecked("test/telemetry");
+        let queue =
+            MessageQueue::with_droppable_topics(2, OverflowPolicy::DropOldest, Some(droppable));
+        for msg in [message("1"), telemetry("2")] {
+            queue.push(msg).await;
+        }
+
+        // The queue is not extended beyond its capacity, even for a non-droppable message
+        let blocked_push =
+            tokio::time::timeout(Duration::from_millis(100), queue.push(message("3")));
+        assert!(blocked_push.await.is_err());
+
+        let producer = queue.clone();
+        let push = tokio::spawn(async move { producer.push(message("4")).await });
+        assert_eq!(pop_payload(&queue).await, "1");
+        push.await.unwrap();
+        assert_eq!(pop_payload(&queue).await, "2");
+        assert_eq!(pop_payload(&queue).await, "4");
+        assert_eq!(queue.dropped(), 0);
+    }
+
+    #[tokio::test]
+    async fn a_blocked_push_is_released_on_close() {
+        let queue = MessageQueue::new(1, OverflowPolicy::Block);
+        queue.push(message("1")).await;
+
+        let producer = queue.clone();
+        let push = tokio::spawn(async move { producer.push(message("2")).await });
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        queue.close();
+
+        tokio::time::timeout(Duration::from_secs(1), push)
+            .await
+            .expect("the push to be released")
+            .unwrap();
+        assert_eq!(queue.dropped(), 1);
+    }
+
+    #[tokio::test]
+    async fn the_pending_messages_are_consumed_once_closed() {
+        let queue = MessageQueue::new(2, OverflowPolicy::Block);
+        queue.push(message("1")).await;
+        queue.close();
+
+        assert_eq!(pop_payload(&queue).await, "1");
+        assert!(queue.pop().await.is_none());
+    }
+}
@@ -500,4 +500,158 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn an_invalid_tls_config_is_rejected_on_connect() {
+        let mqtt_config = Config::default().with_tls(TlsConfig {
+            ca_paths: vec![],
+            client_auth: Some(ClientAuth {
+                cert_file: "/does/not/exist/client.pem".into(),
+                key_file: "/does/not/exist/client.key".into(),
+            }),
+        });
+
+        let result = Connection::new(&mqtt_config).await;
+        assert!(matches!(result, Err(MqttError::InvalidTlsConfig(_))));
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn the_birth_message_is_published_on_connect() -> Result<(), anyhow::Error> {
+        // Given an MQTT broker
+        let broker = mqtt_tests::test_mqtt_broker();
+        let mut health = broker.messages_published_on("test/health/client").await;
+
+        // A client with a birth message
+        let mqtt_config = Config::default()
+            .with_port(broker.port)
+            .with_session_name("test_client_with_birth_message")
+            .with_birth_message(message("test/health/client", "up"));
+        let _con = Connection::new(&mqtt_config).await?;
+
+        // The birth message is published as soon as connected
+        mqtt_tests::assert_received(&mut health, TIMEOUT, vec!["up"]).await;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn received_messages_are_not_dropped_by_default() -> Result<(), anyhow::Error> {
+        // Given an MQTT broker
+        let broker = mqtt_tests::test_mqtt_broker();
+        let topic = "a/request/topic";
+
+        // A client with a small queue of received messages, but no droppable topics
+        let mqtt_config = Config::default()
+            .with_port(broker.port)
+            .with_session_name("test_client_with_no_droppable_topics")
+            .with_subscriptions(topic.try_into()?)
+            .with_queue_capacity(2);
+        let mut con = Connection::new(&mqtt_config).await?;
+
+        // Receiving more messages than consumed
+        for i in 0..50 {
+            broker.publish(topic, &format!("msg {i}")).await?;
+        }
+
+        // All the messages are received, the reception being paused while the queue is full
+        let mut received = vec![];
+        while let MaybeMessage::Next(msg) = next_message(&mut con.received).await {
+            received.push(msg.payload_str()?.to_string());
+        }
+        assert_eq!(received.len(), 50);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn the_oldest_received_messages_are_dropped_when_not_consumed(
+    ) -> Result<(), anyhow::Error> {
+        // Given an MQTT broker
+        let broker = mqtt_tests::test_mqtt_broker();
+        let topic = "a/storm/topic";
+        let mut dropped_messages = broker.messages_published_on("test/dropped-messages").await;
+
+        // A client with a small queue of received messages
+        let mqtt_config = Config::default()
+            .with_port(broker.port)
+            .with_session_name("test_client_with_a_small_queue")
+            .with_subscriptions(topic.try_into()?)
+            .with_queue_capacity(2)
+            .with_received_overflow_policy(OverflowPolicy::DropOldest)
+            .with_received_droppable_topics(topic.try_into()?)
+            .with_dropped_messages_topic(Topic::new_unchecked("test/dropped-messages"));
+        let mut con = Connection::new(&mqtt_config).await?;
+
+        // Receiving more messages than consumed
+        for i in 0..50 {
+            broker.publish(topic, &format!("msg {i}")).await?;
+        }
+
+        // Must publish the number of dropped messages
+        let dropped = tokio::time::timeout(Duration::from_secs(3), dropped_messages.next())
+            .await?
+            .expect("the number of dropped messages");
+        assert!(dropped.ends_with(r#","published":0}"#));
+        assert_ne!(dropped, r#"{"received":0,"published":0}"#);
+
+        // And keep the latest messages
+        let mut received = vec![];
+        while let MaybeMessage::Next(msg) = next_message(&mut con.received).await {
+            received.push(msg.payload_str()?.to_string());
+        }
+        assert!(received.len() < 50);
+        assert_eq!(received.last().map(String::as_str), Some("msg 49"));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn the_last_will_message_is_published_on_close() -> Result<(), anyhow::Error> {
+        // Given an MQTT broker
+        let broker = mqtt_tests::test_mqtt_broker();
+        let mut health = broker.messages_published_on("test/health/client").await;
+
+        // A client with a birth message and a last will
+        let mqtt_config = Config::default()
+            .with_port(broker.port)
+            .with_session_name("test_client_with_last_will")
+            .with_birth_message(message("test/health/client", "up"))
+            .with_last_will_message(message("test/health/client", "down"));
+        let con = Connection::new(&mqtt_config).await?;
+        mqtt_tests::assert_received(&mut health, TIMEOUT, vec!["up"]).await;
+
+        // The last will is published by the client on a proper disconnect
+        con.close().await;
+        mqtt_tests::assert_received(&mut health, TIMEOUT, vec!["down"]).await;
+
+        Ok(())
+    }
+
+    #[test]
+    fn the_last_will_message_is_registered_on_connect() {
+        let mqtt_config = Config::default()
+            .with_last_will_message(message("test/health/client", "down").with_retain());
+
+        let last_will = mqtt_config
+            .mqtt_options()
+            .unwrap()
+            .last_will()
+            .expect("a last will");
+        assert_eq!(last_will.topic, "test/health/client");
+        assert_eq!(&last_will.message[..], b"down");
+        assert!(last_will.retain);
+    }
+
+    #[test]
+    fn passwords_are_not_displayed() {
+        let mqtt_config = Config::default().with_credentials("tedge", "secret");
+
+        let debug = format!("{:?}", mqtt_config);
+        assert!(debug.contains("tedge"));
+        assert!(!debug.contains("secret"));
+    }
 }
@@ -7,6 +7,8 @@ pub enum SystemService {
     TEdgeMapperAz,
     /// Cumulocity TEdge mapper
     TEdgeMapperC8y,
+    /// AWS TEdge mapper
+    TEdgeMapperAws,
     /// TEdge SM agent
     TEdgeSMAgent,
 }
@@ -17,6 +19,7 @@ impl std::fmt::Display for SystemService {
             Self::Mosquitto => "mosquitto",
             Self::TEdgeMapperAz => "tedge-mapper-az",
             Self::TEdgeMapperC8y => "tedge-mapper-c8y",
+            Self::TEdgeMapperAws => "tedge-mapper-aws",
             Self::TEdgeSMAgent => "tedge-agent",
         };
         write!(f, "{}", s)
@@ -29,6 +32,7 @@ impl SystemService {
             SystemService::Mosquitto => "mosquitto",
             SystemService::TEdgeMapperAz => "tedge-mapper-az",
             SystemService::TEdgeMapperC8y => "tedge-mapper-c8y",
+            SystemService::TEdgeMapperAws => "tedge-mapper-aws",
             SystemService::TEdgeSMAgent => "tedge-agent",
         }
     }
@@ -0,0 +1,63 @@
+use std::convert::{TryFrom, TryInto};
+
+/// Represents a size in bytes.
+///
+/// We need this newtype in order to implement `TryFrom<String>` and `TryInto<String>`.
+#[derive(Debug, Copy, Clone, serde::Serialize, serde::Deserialize, Eq, PartialEq)]
+#[serde(transparent)]
+pub struct ByteSize(pub u64);
+
+#[derive(thiserror::Error, Debug)]
+#[error("Invalid size in bytes: '{input}'.")]
+pub struct InvalidByteSize {
+    input: String,
+}
+
+impl TryFrom<String> for ByteSize {
+    type Error = InvalidByteSize;
+
+    fn try_from(input: String) -> Result<Self, Self::Error> {
+        input
+            .as_str()
+            .parse::<u64>()
+            .map_err(|_| InvalidByteSize { input })
+            .map(ByteSize)
+    }
+}
+
+impl TryInto<String> for ByteSize {
+    type Error = std::convert::Infallible;
+
+    fn try_into(self) -> Result<String, Self::Error> {
+        Ok(format!("{}", self.0))
+    }
+}
+
+impl From<ByteSize> for u64 {
+    fn from(val: ByteSize) -> Self {
+        val.0
+    }
+}
+
+#[cfg(test)]
+use assert_matches::*;
+#[test]
+fn conversion_from_valid_size_succeeds() {
+    assert_matches!(
+        ByteSize::try_from("10485760".to_string()),
+        Ok(ByteSize(10485760))
+    );
+}
+
+#[test]
+fn conversion_from_negative_size_fails() {
+    assert_matches!(
+        ByteSize::try_from("-1".to_string()),
+        Err(InvalidByteSize { .. })
+    );
+}
+
+#[test]
+fn conversion_from_size_to_string() {
+    assert_matches!(TryInto::<String>::try_into(ByteSize(1024)), Ok(size_str) if size_str == "1024");
+}
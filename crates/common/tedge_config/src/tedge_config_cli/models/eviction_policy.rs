@@ -0,0 +1,72 @@
+use std::convert::{TryFrom, TryInto};
+
+/// Which messages are dropped when the mapper outbox is full.
+#[derive(Debug, Copy, Clone, serde::Serialize, serde::Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum OutboxEvictionPolicy {
+    /// Drop the oldest stored messages to make room for the new ones.
+    DropOldest,
+    /// Keep the stored messages and drop the new ones.
+    DropNewest,
+}
+
+#[derive(thiserror::Error, Debug)]
+#[error(
+    "Invalid outbox eviction policy: '{input}'. Supported values are: drop-oldest, drop-newest"
+)]
+pub struct InvalidOutboxEvictionPolicy {
+    input: String,
+}
+
+impl TryFrom<String> for OutboxEvictionPolicy {
+    type Error = InvalidOutboxEvictionPolicy;
+
+    fn try_from(input: String) -> Result<Self, Self::Error> {
+        match input.as_str() {
+            "drop-oldest" => Ok(OutboxEvictionPolicy::DropOldest),
+            "drop-newest" => Ok(OutboxEvictionPolicy::DropNewest),
+            _ => Err(InvalidOutboxEvictionPolicy { input }),
+        }
+    }
+}
+
+impl TryInto<String> for OutboxEvictionPolicy {
+    type Error = std::convert::Infallible;
+
+    fn try_into(self) -> Result<String, Self::Error> {
+        Ok(match self {
+            OutboxEvictionPolicy::DropOldest => "drop-oldest".into(),
+            OutboxEvictionPolicy::DropNewest => "drop-newest".into(),
+        })
+    }
+}
+
+#[cfg(test)]
+use assert_matches::*;
+#[test]
+fn conversion_from_valid_policies_succeeds() {
+    assert_matches!(
+        OutboxEvictionPolicy::try_from("drop-oldest".to_string()),
+        Ok(OutboxEvictionPolicy::DropOldest)
+    );
+    assert_matches!(
+        OutboxEvictionPolicy::try_from("drop-newest".to_string()),
+        Ok(OutboxEvictionPolicy::DropNewest)
+    );
+}
+
+#[test]
+fn conversion_from_unknown_policy_fails() {
+    assert_matches!(
+        OutboxEvictionPolicy::try_from("drop-all".to_string()),
+        Err(InvalidOutboxEvictionPolicy { .. })
+    );
+}
+
+#[test]
+fn conversion_from_policy_to_string() {
+    assert_matches!(
+        TryInto::<String>::try_into(OutboxEvictionPolicy::DropNewest),
+        Ok(policy) if policy == "drop-newest"
+    );
+}
@@ -1,8 +1,18 @@
+pub mod byte_size;
 pub mod connect_url;
+pub mod eviction_policy;
 pub mod file_path;
 pub mod flag;
 pub mod ipaddress;
+pub mod overflow_policy;
+pub mod plugin_timeouts;
 pub mod port;
+pub mod queue_capacity;
+pub mod seconds;
 pub mod templates_set;
 
-pub use self::{connect_url::*, file_path::*, flag::*, ipaddress::*, port::*, templates_set::*};
+pub use self::{
+    byte_size::*, connect_url::*, eviction_policy::*, file_path::*, flag::*, ipaddress::*,
+    overflow_policy::*, plugin_timeouts::*, port::*, queue_capacity::*, seconds::*,
+    templates_set::*,
+};
@@ -0,0 +1,80 @@
+use std::convert::{TryFrom, TryInto};
+
+/// What a local MQTT client does with a received telemetry message, when its queue is full.
+#[derive(Debug, Copy, Clone, serde::Serialize, serde::Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum QueueOverflowPolicy {
+    /// Pause the reception of messages till there is room in the queue.
+    Block,
+    /// Drop the oldest queued messages to make room for the new ones.
+    DropOldest,
+    /// Keep the queued messages and drop the new ones.
+    DropNewest,
+}
+
+#[derive(thiserror::Error, Debug)]
+#[error(
+    "Invalid queue overflow policy: '{input}'. Supported values are: block, drop-oldest, drop-newest"
+)]
+pub struct InvalidQueueOverflowPolicy {
+    input: String,
+}
+
+impl TryFrom<String> for QueueOverflowPolicy {
+    type Error = InvalidQueueOverflowPolicy;
+
+    fn try_from(input: String) -> Result<Self, Self::Error> {
+        match input.as_str() {
+            "block" => Ok(QueueOverflowPolicy::Block),
+            "drop-oldest" => Ok(QueueOverflowPolicy::DropOldest),
+            "drop-newest" => Ok(QueueOverflowPolicy::DropNewest),
+            _ => Err(InvalidQueueOverflowPolicy { input }),
+        }
+    }
+}
+
+impl TryInto<String> for QueueOverflowPolicy {
+    type Error = std::convert::Infallible;
+
+    fn try_into(self) -> Result<String, Self::Error> {
+        Ok(match self {
+            QueueOverflowPolicy::Block => "block".into(),
+            QueueOverflowPolicy::DropOldest => "drop-oldest".into(),
+            QueueOverflowPolicy::DropNewest => "drop-newest".into(),
+        })
+    }
+}
+
+#[cfg(test)]
+use assert_matches::*;
+#[test]
+fn conversion_from_valid_overflow_policies_succeeds() {
+    assert_matches!(
+        QueueOverflowPolicy::try_from("block".to_string()),
+        Ok(QueueOverflowPolicy::Block)
+    );
+    assert_matches!(
+        QueueOverflowPolicy::try_from("drop-oldest".to_string()),
+        Ok(QueueOverflowPolicy::DropOldest)
+    );
+    assert_matches!(
+        QueueOverflowPolicy::try_from("drop-newest".to_string()),
+        Ok(QueueOverflowPolicy::DropNewest)
+    );
+}
+
+#[test]
+fn conversion_from_unknown_overflow_policy_fails() {
+    assert_matches!(
+        QueueOverflowPolicy::try_from("drop-all".to_string()),
+        Err(InvalidQueueOverflowPolicy { .. })
+    );
+}
+
+#[test]
+fn conversion_from_overflow_policy_to_string() {
+    assert_matches!(
+        TryInto::<String>::try_into(QueueOverflowPolicy::Block),
+        Ok(policy) if policy == "block"
+    );
+}
@@ -0,0 +1,88 @@
+use std::collections::BTreeMap;
+use std::convert::{TryFrom, TryInto};
+
+/// Represents the timeouts in seconds of the software plugins, given per software type.
+///
+/// Example: `apt=1800,docker=600`
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize, Eq, PartialEq)]
+#[serde(transparent)]
+pub struct PluginTimeouts(pub BTreeMap<String, u64>);
+
+#[derive(thiserror::Error, Debug)]
+#[error("Invalid plugin timeouts: '{input}'. Expected a list of <software-type>=<seconds> separated by commas.")]
+pub struct InvalidPluginTimeouts {
+    input: String,
+}
+
+impl TryFrom<String> for PluginTimeouts {
+    type Error = InvalidPluginTimeouts;
+
+    fn try_from(input: String) -> Result<Self, Self::Error> {
+        let mut timeouts = BTreeMap::new();
+        for item in input
+            .split(',')
+            .map(str::trim)
+            .filter(|item| !item.is_empty())
+        {
+            let timeout = item.split_once('=').and_then(|(software_type, seconds)| {
+                let software_type = software_type.trim();
+                let seconds = seconds.trim().parse::<u64>().ok()?;
+                (!software_type.is_empty()).then(|| (software_type.to_string(), seconds))
+            });
+            match timeout {
+                Some((software_type, seconds)) => {
+                    timeouts.insert(software_type, seconds);
+                }
+                None => return Err(InvalidPluginTimeouts { input }),
+            }
+        }
+
+        Ok(PluginTimeouts(timeouts))
+    }
+}
+
+impl TryInto<String> for PluginTimeouts {
+    type Error = std::convert::Infallible;
+
+    fn try_into(self) -> Result<String, Self::Error> {
+        Ok(self.to_string())
+    }
+}
+
+impl std::fmt::Display for PluginTimeouts {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let timeouts: Vec<String> = self
+            .0
+            .iter()
+            .map(|(software_type, seconds)| format!("{software_type}={seconds}"))
+            .collect();
+        write!(f, "{}", timeouts.join(","))
+    }
+}
+
+#[cfg(test)]
+use assert_matches::*;
+#[test]
+fn conversion_from_valid_timeouts_succeeds() {
+    let timeouts = PluginTimeouts::try_from("apt=1800, docker=600".to_string()).unwrap();
+    assert_eq!(timeouts.0.get("apt"), Some(&1800));
+    assert_eq!(timeouts.0.get("docker"), Some(&600));
+}
+
+#[test]
+fn conversion_from_invalid_timeouts_fails() {
+    assert_matches!(
+        PluginTimeouts::try_from("apt=one hour".to_string()),
+        Err(InvalidPluginTimeouts { .. })
+    );
+    assert_matches!(
+        PluginTimeouts::try_from("apt".to_string()),
+        Err(InvalidPluginTimeouts { .. })
+    );
+}
+
+#[test]
+fn conversion_from_timeouts_to_string() {
+    let timeouts = PluginTimeouts::try_from("docker=600,apt=1800".to_string()).unwrap();
+    assert_matches!(TryInto::<String>::try_into(timeouts), Ok(timeouts_str) if timeouts_str == "apt=1800,docker=600");
+}
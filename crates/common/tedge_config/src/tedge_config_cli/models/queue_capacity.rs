@@ -0,0 +1,62 @@
+use std::convert::{TryFrom, TryInto};
+
+/// Represents a number of messages that can be queued, which must be positive.
+///
+/// We need this newtype in order to implement `TryFrom<String>` and `TryInto<String>`.
+#[derive(Debug, Copy, Clone, serde::Serialize, serde::Deserialize, Eq, PartialEq)]
+#[serde(transparent)]
+pub struct QueueCapacity(pub usize);
+
+#[derive(thiserror::Error, Debug)]
+#[error("Invalid queue capacity: '{input}'. A positive number of messages is expected.")]
+pub struct InvalidQueueCapacity {
+    input: String,
+}
+
+impl TryFrom<String> for QueueCapacity {
+    type Error = InvalidQueueCapacity;
+
+    fn try_from(input: String) -> Result<Self, Self::Error> {
+        match input.as_str().parse::<usize>() {
+            Ok(capacity) if capacity > 0 => Ok(QueueCapacity(capacity)),
+            _ => Err(InvalidQueueCapacity { input }),
+        }
+    }
+}
+
+impl TryInto<String> for QueueCapacity {
+    type Error = std::convert::Infallible;
+
+    fn try_into(self) -> Result<String, Self::Error> {
+        Ok(format!("{}", self.0))
+    }
+}
+
+impl From<QueueCapacity> for usize {
+    fn from(val: QueueCapacity) -> Self {
+        val.0
+    }
+}
+
+#[cfg(test)]
+use assert_matches::*;
+#[test]
+fn conversion_from_valid_capacity_succeeds() {
+    assert_matches!(
+        QueueCapacity::try_from("1024".to_string()),
+        Ok(QueueCapacity(1024))
+    );
+}
+
+#[test]
+fn conversion_from_empty_capacity_fails() {
+    assert_matches!(
+        QueueCapacity::try_from("0".to_string()),
+        Err(InvalidQueueCapacity { .. })
+    );
+}
+
+#[test]
+fn conversion_from_capacity_to_string() {
+    assert_matches!(TryInto::<String>::try_into(QueueCapacity(16)), Ok(capacity) if capacity == "16");
+}
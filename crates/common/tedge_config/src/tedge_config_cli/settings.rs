@@ -120,6 +120,67 @@ impl ConfigSetting for C8ySmartRestTemplates {
     type Value = TemplatesSet;
 }
 
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct C8yCommandEnableSetting;
+
+impl ConfigSetting for C8yCommandEnableSetting {
+    const KEY: &'static str = "c8y.command.enable";
+
+    const DESCRIPTION: &'static str = concat!(
+        "When set to true, the Cumulocity mapper executes the shell commands sent with c8y_Command operations. ",
+        "Example: true"
+    );
+
+    type Value = Flag;
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct C8yCommandTimeoutSetting;
+
+impl ConfigSetting for C8yCommandTimeoutSetting {
+    const KEY: &'static str = "c8y.command.timeout";
+
+    const DESCRIPTION: &'static str = concat!(
+        "The maximum time in seconds given to a c8y_Command shell command to complete, ",
+        "after which the command is killed and the operation fails. ",
+        "Example: 60"
+    );
+
+    type Value = Seconds;
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct C8yCommandUserSetting;
+
+impl ConfigSetting for C8yCommandUserSetting {
+    const KEY: &'static str = "c8y.command.user";
+
+    const DESCRIPTION: &'static str = concat!(
+        "The user running the c8y_Command shell commands, using sudo. ",
+        "When not set, the commands are run by the user of the Cumulocity mapper. ",
+        "Example: tedge"
+    );
+
+    type Value = String;
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct C8yAvailabilityIntervalSetting;
+
+impl ConfigSetting for C8yAvailabilityIntervalSetting {
+    const KEY: &'static str = "c8y.availability.interval";
+
+    const DESCRIPTION: &'static str = concat!(
+        "The required interval in seconds between two messages sent by the device to Cumulocity, ",
+        "after which the device is considered offline. The device sends a heartbeat while its services are up. ",
+        "Cumulocity counts this interval in minutes, hence rounded up. ",
+        "Disabled by default, with an interval of 0, as the heartbeat requires the tedge-agent to be running. ",
+        "Example: 3600"
+    );
+
+    type Value = Seconds;
+}
+
 ///
 /// Tenant endpoint URL of Azure IoT tenant.
 ///
@@ -177,6 +238,104 @@ impl ConfigSetting for AzureMapperTimestamp {
     type Value = Flag;
 }
 
+///
+/// The local topic prefix where the Azure mapper forwards the cloud-to-device messages
+/// and the direct methods it doesn't handle itself.
+///
+/// Example: tedge/az
+///
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct AzureMapperForwardTopicSetting;
+
+impl ConfigSetting for AzureMapperForwardTopicSetting {
+    const KEY: &'static str = "az.mapper.forward_topic";
+
+    const DESCRIPTION: &'static str = concat!(
+        "The local topic prefix where the Azure mapper forwards the cloud-to-device messages ",
+        "and the direct methods it doesn't handle itself. ",
+        "Example: tedge/az"
+    );
+
+    type Value = String;
+}
+
+///
+/// Endpoint URL of AWS IoT Core.
+///
+/// Example: your-endpoint-ats.iot.eu-west-1.amazonaws.com
+///
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct AwsUrlSetting;
+
+impl ConfigSetting for AwsUrlSetting {
+    const KEY: &'static str = "aws.url";
+
+    const DESCRIPTION: &'static str = concat!(
+        "Endpoint URL of AWS IoT Core. ",
+        "Example: your-endpoint-ats.iot.eu-west-1.amazonaws.com"
+    );
+
+    type Value = ConnectUrl;
+}
+
+///
+/// Path where AWS IoT Core root certificate(s) are located.
+///
+/// Example: /home/user/.tedge/aws-trusted-root-certificates.pem
+///
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct AwsRootCertPathSetting;
+
+impl ConfigSetting for AwsRootCertPathSetting {
+    const KEY: &'static str = "aws.root.cert.path";
+
+    const DESCRIPTION: &'static str = concat!(
+        "Path where AWS IoT Core root certificate(s) are located. ",
+        "Example: /home/user/.tedge/aws-trusted-root-certificates.pem"
+    );
+
+    type Value = FilePath;
+}
+
+///
+/// Boolean whether AWS mapper should add timestamp if timestamp is not added in the incoming payload.
+///
+/// Example: true
+///
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct AwsMapperTimestamp;
+
+impl ConfigSetting for AwsMapperTimestamp {
+    const KEY: &'static str = "aws.mapper.timestamp";
+
+    const DESCRIPTION: &'static str = concat!(
+        "Boolean whether AWS mapper should add timestamp or not. ",
+        "Example: true"
+    );
+
+    type Value = Flag;
+}
+
+///
+/// The prefix of the AWS topics where the measurements, events and alarms are published.
+///
+/// Example: thinedge
+///
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct AwsTopicPrefixSetting;
+
+impl ConfigSetting for AwsTopicPrefixSetting {
+    const KEY: &'static str = "aws.topic.prefix";
+
+    const DESCRIPTION: &'static str = concat!(
+        "The prefix of the AWS topics where the measurements, events and alarms are published, ",
+        "as in `<prefix>/<device-id>/td/measurements`. ",
+        "Example: thinedge"
+    );
+
+    type Value = String;
+}
+
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub struct MqttPortSetting;
 
@@ -320,6 +479,128 @@ impl ConfigSetting for MqttExternalKeyfileSetting {
     type Value = FilePath;
 }
 
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct MqttClientCAFileSetting;
+
+impl ConfigSetting for MqttClientCAFileSetting {
+    const KEY: &'static str = "mqtt.client.cafile";
+
+    const DESCRIPTION: &'static str = concat!(
+        "Path to a file containing the PEM encoded CA certificates ",
+        "that are trusted when checking the certificate of the local MQTT broker. ",
+        "Example: /etc/mosquitto/ca_certificates/ca.crt",
+        "Note: The local clients connect the broker over TLS when either this setting or `mqtt.client.capath` is set."
+    );
+
+    type Value = FilePath;
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct MqttClientCAPathSetting;
+
+impl ConfigSetting for MqttClientCAPathSetting {
+    const KEY: &'static str = "mqtt.client.capath";
+
+    const DESCRIPTION: &'static str = concat!(
+        "Path to a directory containing the PEM encoded CA certificates ",
+        "that are trusted when checking the certificate of the local MQTT broker. ",
+        "Example: /etc/mosquitto/ca_certificates",
+        "Note: The local clients connect the broker over TLS when either this setting or `mqtt.client.cafile` is set."
+    );
+
+    type Value = FilePath;
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct MqttClientCertfileSetting;
+
+impl ConfigSetting for MqttClientCertfileSetting {
+    const KEY: &'static str = "mqtt.client.certfile";
+
+    const DESCRIPTION: &'static str = concat!(
+        "Path to the certificate file, which is used by the local clients to authenticate with the local MQTT broker. ",
+        "Example: /etc/mosquitto/certs/client.crt",
+        "Note: This setting shall be used together with `mqtt.client.keyfile`."
+    );
+
+    type Value = FilePath;
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct MqttClientKeyfileSetting;
+
+impl ConfigSetting for MqttClientKeyfileSetting {
+    const KEY: &'static str = "mqtt.client.keyfile";
+
+    const DESCRIPTION: &'static str = concat!(
+        "Path to the private key file, which is used by the local clients to authenticate with the local MQTT broker. ",
+        "Example: /etc/mosquitto/certs/client.key",
+        "Note: This setting shall be used together with `mqtt.client.certfile`."
+    );
+
+    type Value = FilePath;
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct MqttClientUsernameSetting;
+
+impl ConfigSetting for MqttClientUsernameSetting {
+    const KEY: &'static str = "mqtt.client.username";
+
+    const DESCRIPTION: &'static str = concat!(
+        "Username used by the local clients to authenticate with the local MQTT broker. ",
+        "Example: tedge"
+    );
+
+    type Value = String;
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct MqttClientPasswordFileSetting;
+
+impl ConfigSetting for MqttClientPasswordFileSetting {
+    const KEY: &'static str = "mqtt.client.password_file";
+
+    const DESCRIPTION: &'static str = concat!(
+        "Path to a file containing the password used by the local clients to authenticate with the local MQTT broker. ",
+        "Example: /etc/tedge/mqtt-password",
+        "Note: This setting shall be used together with `mqtt.client.username`."
+    );
+
+    type Value = FilePath;
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct MqttClientQueueCapacitySetting;
+
+impl ConfigSetting for MqttClientQueueCapacitySetting {
+    const KEY: &'static str = "mqtt.client.queue_capacity";
+
+    const DESCRIPTION: &'static str = concat!(
+        "Maximum number of messages buffered by a local client, ",
+        "in each of its queues of received and published messages. ",
+        "Example: 1024"
+    );
+
+    type Value = QueueCapacity;
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct MqttClientOverflowPolicySetting;
+
+impl ConfigSetting for MqttClientOverflowPolicySetting {
+    const KEY: &'static str = "mqtt.client.overflow_policy";
+
+    const DESCRIPTION: &'static str = concat!(
+        "What a local client does with a received telemetry message when its queue is full: ",
+        "either `block`, `drop-oldest` or `drop-newest`. ",
+        "Example: drop-oldest ",
+        "Note: The reception of the other messages, e.g. operation requests, is blocked till there is room in the queue."
+    );
+
+    type Value = QueueOverflowPolicy;
+}
+
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub struct SoftwarePluginDefaultSetting;
 
@@ -334,6 +615,98 @@ impl ConfigSetting for SoftwarePluginDefaultSetting {
     type Value = String;
 }
 
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct SoftwarePluginTimeoutSetting;
+
+impl ConfigSetting for SoftwarePluginTimeoutSetting {
+    const KEY: &'static str = "software.plugin.timeout";
+
+    const DESCRIPTION: &'static str = concat!(
+        "The maximum time in seconds given to a software plugin to complete an action, ",
+        "after which the plugin is killed and the operation fails. ",
+        "Example: 3600 ",
+        "Note: Set to 0 to disable the timeout."
+    );
+
+    type Value = Seconds;
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct SoftwarePluginTimeoutsSetting;
+
+impl ConfigSetting for SoftwarePluginTimeoutsSetting {
+    const KEY: &'static str = "software.plugin.timeouts";
+
+    const DESCRIPTION: &'static str = concat!(
+        "The timeouts in seconds of specific software plugins, ",
+        "overriding `software.plugin.timeout` for these plugins. ",
+        "Example: apt=1800,docker=600"
+    );
+
+    type Value = PluginTimeouts;
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct SoftwareUpdateTransactionalSetting;
+
+impl ConfigSetting for SoftwareUpdateTransactionalSetting {
+    const KEY: &'static str = "software.update.transactional";
+
+    const DESCRIPTION: &'static str = concat!(
+        "When set to true, a software update is applied as a whole or not at all: ",
+        "on any failure, the modules already updated are restored to their previous state. ",
+        "Example: true"
+    );
+
+    type Value = Flag;
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct FirmwareInstallCommandSetting;
+
+impl ConfigSetting for FirmwareInstallCommandSetting {
+    const KEY: &'static str = "firmware.install.command";
+
+    const DESCRIPTION: &'static str = concat!(
+        "The command used by the agent to verify and install a firmware image, ",
+        "called as `<command> verify <image>` then `<command> install <image> <name> <version>`. ",
+        "Example: /usr/bin/tedge-firmware-install"
+    );
+
+    type Value = String;
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct MapperOutboxMaxSizeSetting;
+
+impl ConfigSetting for MapperOutboxMaxSizeSetting {
+    const KEY: &'static str = "mapper.outbox.max_size";
+
+    const DESCRIPTION: &'static str = concat!(
+        "Maximum disk space in bytes used by a mapper to store the messages ",
+        "that cannot be forwarded while the cloud bridge is down. ",
+        "Example: 10485760 ",
+        "Note: Set to 0 to disable the store-and-forward of messages."
+    );
+
+    type Value = ByteSize;
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct MapperOutboxEvictionSetting;
+
+impl ConfigSetting for MapperOutboxEvictionSetting {
+    const KEY: &'static str = "mapper.outbox.eviction";
+
+    const DESCRIPTION: &'static str = concat!(
+        "The messages evicted when the mapper outbox is full: ",
+        "either `drop-oldest` or `drop-newest`. ",
+        "Example: drop-oldest"
+    );
+
+    type Value = OutboxEvictionPolicy;
+}
+
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub struct TmpPathSetting;
 
@@ -172,6 +172,107 @@ impl ConfigSettingAccessor<C8ySmartRestTemplates> for TEdgeConfig {
     }
 }
 
+impl ConfigSettingAccessor<C8yCommandEnableSetting> for TEdgeConfig {
+    fn query(&self, _setting: C8yCommandEnableSetting) -> ConfigSettingResult<Flag> {
+        Ok(self
+            .data
+            .c8y
+            .command_enable
+            .map(Flag)
+            .unwrap_or_else(|| self.config_defaults.default_c8y_command_enable.clone()))
+    }
+
+    fn update(
+        &mut self,
+        _setting: C8yCommandEnableSetting,
+        value: Flag,
+    ) -> ConfigSettingResult<()> {
+        self.data.c8y.command_enable = Some(value.into());
+        Ok(())
+    }
+
+    fn unset(&mut self, _setting: C8yCommandEnableSetting) -> ConfigSettingResult<()> {
+        self.data.c8y.command_enable = None;
+        Ok(())
+    }
+}
+
+impl ConfigSettingAccessor<C8yCommandTimeoutSetting> for TEdgeConfig {
+    fn query(&self, _setting: C8yCommandTimeoutSetting) -> ConfigSettingResult<Seconds> {
+        Ok(self
+            .data
+            .c8y
+            .command_timeout
+            .map(Seconds)
+            .unwrap_or(self.config_defaults.default_c8y_command_timeout))
+    }
+
+    fn update(
+        &mut self,
+        _setting: C8yCommandTimeoutSetting,
+        value: Seconds,
+    ) -> ConfigSettingResult<()> {
+        self.data.c8y.command_timeout = Some(value.into());
+        Ok(())
+    }
+
+    fn unset(&mut self, _setting: C8yCommandTimeoutSetting) -> ConfigSettingResult<()> {
+        self.data.c8y.command_timeout = None;
+        Ok(())
+    }
+}
+
+impl ConfigSettingAccessor<C8yAvailabilityIntervalSetting> for TEdgeConfig {
+    fn query(&self, _setting: C8yAvailabilityIntervalSetting) -> ConfigSettingResult<Seconds> {
+        Ok(self
+            .data
+            .c8y
+            .availability_interval
+            .map(Seconds)
+            .unwrap_or(self.config_defaults.default_c8y_availability_interval))
+    }
+
+    fn update(
+        &mut self,
+        _setting: C8yAvailabilityIntervalSetting,
+        value: Seconds,
+    ) -> ConfigSettingResult<()> {
+        self.data.c8y.availability_interval = Some(value.into());
+        Ok(())
+    }
+
+    fn unset(&mut self, _setting: C8yAvailabilityIntervalSetting) -> ConfigSettingResult<()> {
+        self.data.c8y.availability_interval = None;
+        Ok(())
+    }
+}
+
+impl ConfigSettingAccessor<C8yCommandUserSetting> for TEdgeConfig {
+    fn query(&self, _setting: C8yCommandUserSetting) -> ConfigSettingResult<String> {
+        self.data
+            .c8y
+            .command_user
+            .clone()
+            .ok_or(ConfigSettingError::ConfigNotSet {
+                key: C8yCommandUserSetting::KEY,
+            })
+    }
+
+    fn update(
+        &mut self,
+        _setting: C8yCommandUserSetting,
+        value: String,
+    ) -> ConfigSettingResult<()> {
+        self.data.c8y.command_user = Some(value);
+        Ok(())
+    }
+
+    fn unset(&mut self, _setting: C8yCommandUserSetting) -> ConfigSettingResult<()> {
+        self.data.c8y.command_user = None;
+        Ok(())
+    }
+}
+
 impl ConfigSettingAccessor<DeviceCertPathSetting> for TEdgeConfig {
     fn query(&self, _setting: DeviceCertPathSetting) -> ConfigSettingResult<FilePath> {
         Ok(self
@@ -268,6 +369,128 @@ impl ConfigSettingAccessor<AzureMapperTimestamp> for TEdgeConfig {
     }
 }
 
+impl ConfigSettingAccessor<AzureMapperForwardTopicSetting> for TEdgeConfig {
+    fn query(&self, _setting: AzureMapperForwardTopicSetting) -> ConfigSettingResult<String> {
+        Ok(self
+            .data
+            .az
+            .mapper_forward_topic
+            .clone()
+            .unwrap_or_else(|| {
+                self.config_defaults
+                    .default_azure_mapper_forward_topic
+                    .clone()
+            }))
+    }
+
+    fn update(
+        &mut self,
+        _setting: AzureMapperForwardTopicSetting,
+        value: String,
+    ) -> ConfigSettingResult<()> {
+        self.data.az.mapper_forward_topic = Some(value);
+        Ok(())
+    }
+
+    fn unset(&mut self, _setting: AzureMapperForwardTopicSetting) -> ConfigSettingResult<()> {
+        self.data.az.mapper_forward_topic = None;
+        Ok(())
+    }
+}
+
+impl ConfigSettingAccessor<AwsUrlSetting> for TEdgeConfig {
+    fn query(&self, _setting: AwsUrlSetting) -> ConfigSettingResult<ConnectUrl> {
+        self.data
+            .aws
+            .url
+            .clone()
+            .ok_or(ConfigSettingError::ConfigNotSet {
+                key: AwsUrlSetting::KEY,
+            })
+    }
+
+    fn update(&mut self, _setting: AwsUrlSetting, value: ConnectUrl) -> ConfigSettingResult<()> {
+        self.data.aws.url = Some(value);
+        Ok(())
+    }
+
+    fn unset(&mut self, _setting: AwsUrlSetting) -> ConfigSettingResult<()> {
+        self.data.aws.url = None;
+        Ok(())
+    }
+}
+
+impl ConfigSettingAccessor<AwsRootCertPathSetting> for TEdgeConfig {
+    fn query(&self, _setting: AwsRootCertPathSetting) -> ConfigSettingResult<FilePath> {
+        Ok(self
+            .data
+            .aws
+            .root_cert_path
+            .clone()
+            .unwrap_or_else(|| self.config_defaults.default_aws_root_cert_path.clone()))
+    }
+
+    fn update(
+        &mut self,
+        _setting: AwsRootCertPathSetting,
+        value: FilePath,
+    ) -> ConfigSettingResult<()> {
+        self.data.aws.root_cert_path = Some(value);
+        Ok(())
+    }
+
+    fn unset(&mut self, _setting: AwsRootCertPathSetting) -> ConfigSettingResult<()> {
+        self.data.aws.root_cert_path = None;
+        Ok(())
+    }
+}
+
+impl ConfigSettingAccessor<AwsMapperTimestamp> for TEdgeConfig {
+    fn query(&self, _setting: AwsMapperTimestamp) -> ConfigSettingResult<Flag> {
+        Ok(self
+            .data
+            .aws
+            .mapper_timestamp
+            .map(Flag)
+            .unwrap_or_else(|| self.config_defaults.default_mapper_timestamp.clone()))
+    }
+
+    fn update(&mut self, _setting: AwsMapperTimestamp, value: Flag) -> ConfigSettingResult<()> {
+        self.data.aws.mapper_timestamp = Some(value.into());
+        Ok(())
+    }
+
+    fn unset(&mut self, _setting: AwsMapperTimestamp) -> ConfigSettingResult<()> {
+        self.data.aws.mapper_timestamp = None;
+        Ok(())
+    }
+}
+
+impl ConfigSettingAccessor<AwsTopicPrefixSetting> for TEdgeConfig {
+    fn query(&self, _setting: AwsTopicPrefixSetting) -> ConfigSettingResult<String> {
+        Ok(self
+            .data
+            .aws
+            .topic_prefix
+            .clone()
+            .unwrap_or_else(|| self.config_defaults.default_aws_topic_prefix.clone()))
+    }
+
+    fn update(
+        &mut self,
+        _setting: AwsTopicPrefixSetting,
+        value: String,
+    ) -> ConfigSettingResult<()> {
+        self.data.aws.topic_prefix = Some(value);
+        Ok(())
+    }
+
+    fn unset(&mut self, _setting: AwsTopicPrefixSetting) -> ConfigSettingResult<()> {
+        self.data.aws.topic_prefix = None;
+        Ok(())
+    }
+}
+
 impl ConfigSettingAccessor<C8yRootCertPathSetting> for TEdgeConfig {
     fn query(&self, _setting: C8yRootCertPathSetting) -> ConfigSettingResult<FilePath> {
         Ok(self
@@ -464,6 +687,217 @@ impl ConfigSettingAccessor<MqttExternalBindInterfaceSetting> for TEdgeConfig {
     }
 }
 
+impl ConfigSettingAccessor<MqttClientCAFileSetting> for TEdgeConfig {
+    fn query(&self, _setting: MqttClientCAFileSetting) -> ConfigSettingResult<FilePath> {
+        self.data
+            .mqtt
+            .client_cafile
+            .clone()
+            .ok_or(ConfigSettingError::ConfigNotSet {
+                key: MqttClientCAFileSetting::KEY,
+            })
+    }
+
+    fn update(
+        &mut self,
+        _setting: MqttClientCAFileSetting,
+        value: FilePath,
+    ) -> ConfigSettingResult<()> {
+        self.data.mqtt.client_cafile = Some(value);
+        Ok(())
+    }
+
+    fn unset(&mut self, _setting: MqttClientCAFileSetting) -> ConfigSettingResult<()> {
+        self.data.mqtt.client_cafile = None;
+        Ok(())
+    }
+}
+
+impl ConfigSettingAccessor<MqttClientCAPathSetting> for TEdgeConfig {
+    fn query(&self, _setting: MqttClientCAPathSetting) -> ConfigSettingResult<FilePath> {
+        self.data
+            .mqtt
+            .client_capath
+            .clone()
+            .ok_or(ConfigSettingError::ConfigNotSet {
+                key: MqttClientCAPathSetting::KEY,
+            })
+    }
+
+    fn update(
+        &mut self,
+        _setting: MqttClientCAPathSetting,
+        value: FilePath,
+    ) -> ConfigSettingResult<()> {
+        self.data.mqtt.client_capath = Some(value);
+        Ok(())
+    }
+
+    fn unset(&mut self, _setting: MqttClientCAPathSetting) -> ConfigSettingResult<()> {
+        self.data.mqtt.client_capath = None;
+        Ok(())
+    }
+}
+
+impl ConfigSettingAccessor<MqttClientCertfileSetting> for TEdgeConfig {
+    fn query(&self, _setting: MqttClientCertfileSetting) -> ConfigSettingResult<FilePath> {
+        self.data
+            .mqtt
+            .client_certfile
+            .clone()
+            .ok_or(ConfigSettingError::ConfigNotSet {
+                key: MqttClientCertfileSetting::KEY,
+            })
+    }
+
+    fn update(
+        &mut self,
+        _setting: MqttClientCertfileSetting,
+        value: FilePath,
+    ) -> ConfigSettingResult<()> {
+        self.data.mqtt.client_certfile = Some(value);
+        Ok(())
+    }
+
+    fn unset(&mut self, _setting: MqttClientCertfileSetting) -> ConfigSettingResult<()> {
+        self.data.mqtt.client_certfile = None;
+        Ok(())
+    }
+}
+
+impl ConfigSettingAccessor<MqttClientKeyfileSetting> for TEdgeConfig {
+    fn query(&self, _setting: MqttClientKeyfileSetting) -> ConfigSettingResult<FilePath> {
+        self.data
+            .mqtt
+            .client_keyfile
+            .clone()
+            .ok_or(ConfigSettingError::ConfigNotSet {
+                key: MqttClientKeyfileSetting::KEY,
+            })
+    }
+
+    fn update(
+        &mut self,
+        _setting: MqttClientKeyfileSetting,
+        value: FilePath,
+    ) -> ConfigSettingResult<()> {
+        self.data.mqtt.client_keyfile = Some(value);
+        Ok(())
+    }
+
+    fn unset(&mut self, _setting: MqttClientKeyfileSetting) -> ConfigSettingResult<()> {
+        self.data.mqtt.client_keyfile = None;
+        Ok(())
+    }
+}
+
+impl ConfigSettingAccessor<MqttClientUsernameSetting> for TEdgeConfig {
+    fn query(&self, _setting: MqttClientUsernameSetting) -> ConfigSettingResult<String> {
+        self.data
+            .mqtt
+            .client_username
+            .clone()
+            .ok_or(ConfigSettingError::ConfigNotSet {
+                key: MqttClientUsernameSetting::KEY,
+            })
+    }
+
+    fn update(
+        &mut self,
+        _setting: MqttClientUsernameSetting,
+        value: String,
+    ) -> ConfigSettingResult<()> {
+        self.data.mqtt.client_username = Some(value);
+        Ok(())
+    }
+
+    fn unset(&mut self, _setting: MqttClientUsernameSetting) -> ConfigSettingResult<()> {
+        self.data.mqtt.client_username = None;
+        Ok(())
+    }
+}
+
+impl ConfigSettingAccessor<MqttClientPasswordFileSetting> for TEdgeConfig {
+    fn query(&self, _setting: MqttClientPasswordFileSetting) -> ConfigSettingResult<FilePath> {
+        self.data
+            .mqtt
+            .client_password_file
+            .clone()
+            .ok_or(ConfigSettingError::ConfigNotSet {
+                key: MqttClientPasswordFileSetting::KEY,
+            })
+    }
+
+    fn update(
+        &mut self,
+        _setting: MqttClientPasswordFileSetting,
+        value: FilePath,
+    ) -> ConfigSettingResult<()> {
+        self.data.mqtt.client_password_file = Some(value);
+        Ok(())
+    }
+
+    fn unset(&mut self, _setting: MqttClientPasswordFileSetting) -> ConfigSettingResult<()> {
+        self.data.mqtt.client_password_file = None;
+        Ok(())
+    }
+}
+
+impl ConfigSettingAccessor<MqttClientQueueCapacitySetting> for TEdgeConfig {
+    fn query(
+        &self,
+        _setting: MqttClientQueueCapacitySetting,
+    ) -> ConfigSettingResult<QueueCapacity> {
+        Ok(self
+            .data
+            .mqtt
+            .client_queue_capacity
+            .map(QueueCapacity)
+            .unwrap_or(self.config_defaults.default_mqtt_client_queue_capacity))
+    }
+
+    fn update(
+        &mut self,
+        _setting: MqttClientQueueCapacitySetting,
+        value: QueueCapacity,
+    ) -> ConfigSettingResult<()> {
+        self.data.mqtt.client_queue_capacity = Some(value.into());
+        Ok(())
+    }
+
+    fn unset(&mut self, _setting: MqttClientQueueCapacitySetting) -> ConfigSettingResult<()> {
+        self.data.mqtt.client_queue_capacity = None;
+        Ok(())
+    }
+}
+
+impl ConfigSettingAccessor<MqttClientOverflowPolicySetting> for TEdgeConfig {
+    fn query(
+        &self,
+        _setting: MqttClientOverflowPolicySetting,
+    ) -> ConfigSettingResult<QueueOverflowPolicy> {
+        Ok(self
+            .data
+            .mqtt
+            .client_overflow_policy
+            .unwrap_or(self.config_defaults.default_mqtt_client_overflow_policy))
+    }
+
+    fn update(
+        &mut self,
+        _setting: MqttClientOverflowPolicySetting,
+        value: QueueOverflowPolicy,
+    ) -> ConfigSettingResult<()> {
+        self.data.mqtt.client_overflow_policy = Some(value);
+        Ok(())
+    }
+
+    fn unset(&mut self, _setting: MqttClientOverflowPolicySetting) -> ConfigSettingResult<()> {
+        self.data.mqtt.client_overflow_policy = None;
+        Ok(())
+    }
+}
+
 impl ConfigSettingAccessor<MqttExternalCAPathSetting> for TEdgeConfig {
     fn query(&self, _setting: MqttExternalCAPathSetting) -> ConfigSettingResult<FilePath> {
         self.data
@@ -568,6 +1002,166 @@ impl ConfigSettingAccessor<SoftwarePluginDefaultSetting> for TEdgeConfig {
     }
 }
 
+impl ConfigSettingAccessor<SoftwarePluginTimeoutSetting> for TEdgeConfig {
+    fn query(&self, _setting: SoftwarePluginTimeoutSetting) -> ConfigSettingResult<Seconds> {
+        Ok(self
+            .data
+            .software
+            .plugin_timeout
+            .map(Seconds)
+            .unwrap_or(self.config_defaults.default_software_plugin_timeout))
+    }
+
+    fn update(
+        &mut self,
+        _setting: SoftwarePluginTimeoutSetting,
+        value: Seconds,
+    ) -> ConfigSettingResult<()> {
+        self.data.software.plugin_timeout = Some(value.into());
+        Ok(())
+    }
+
+    fn unset(&mut self, _setting: SoftwarePluginTimeoutSetting) -> ConfigSettingResult<()> {
+        self.data.software.plugin_timeout = None;
+        Ok(())
+    }
+}
+
+impl ConfigSettingAccessor<SoftwarePluginTimeoutsSetting> for TEdgeConfig {
+    fn query(
+        &self,
+        _setting: SoftwarePluginTimeoutsSetting,
+    ) -> ConfigSettingResult<PluginTimeouts> {
+        Ok(self
+            .data
+            .software
+            .plugin_timeouts
+            .clone()
+            .unwrap_or_default())
+    }
+
+    fn update(
+        &mut self,
+        _setting: SoftwarePluginTimeoutsSetting,
+        value: PluginTimeouts,
+    ) -> ConfigSettingResult<()> {
+        self.data.software.plugin_timeouts = Some(value);
+        Ok(())
+    }
+
+    fn unset(&mut self, _setting: SoftwarePluginTimeoutsSetting) -> ConfigSettingResult<()> {
+        self.data.software.plugin_timeouts = None;
+        Ok(())
+    }
+}
+
+impl ConfigSettingAccessor<SoftwareUpdateTransactionalSetting> for TEdgeConfig {
+    fn query(&self, _setting: SoftwareUpdateTransactionalSetting) -> ConfigSettingResult<Flag> {
+        Ok(self
+            .data
+            .software
+            .update_transactional
+            .map(Flag)
+            .unwrap_or_else(|| {
+                self.config_defaults
+                    .default_software_update_transactional
+                    .clone()
+            }))
+    }
+
+    fn update(
+        &mut self,
+        _setting: SoftwareUpdateTransactionalSetting,
+        value: Flag,
+    ) -> ConfigSettingResult<()> {
+        self.data.software.update_transactional = Some(value.into());
+        Ok(())
+    }
+
+    fn unset(&mut self, _setting: SoftwareUpdateTransactionalSetting) -> ConfigSettingResult<()> {
+        self.data.software.update_transactional = None;
+        Ok(())
+    }
+}
+
+impl ConfigSettingAccessor<FirmwareInstallCommandSetting> for TEdgeConfig {
+    fn query(&self, _setting: FirmwareInstallCommandSetting) -> ConfigSettingResult<String> {
+        self.data
+            .firmware
+            .install_command
+            .clone()
+            .ok_or(ConfigSettingError::ConfigNotSet {
+                key: FirmwareInstallCommandSetting::KEY,
+            })
+    }
+
+    fn update(
+        &mut self,
+        _setting: FirmwareInstallCommandSetting,
+        value: String,
+    ) -> ConfigSettingResult<()> {
+        self.data.firmware.install_command = Some(value);
+        Ok(())
+    }
+
+    fn unset(&mut self, _setting: FirmwareInstallCommandSetting) -> ConfigSettingResult<()> {
+        self.data.firmware.install_command = None;
+        Ok(())
+    }
+}
+
+impl ConfigSettingAccessor<MapperOutboxMaxSizeSetting> for TEdgeConfig {
+    fn query(&self, _setting: MapperOutboxMaxSizeSetting) -> ConfigSettingResult<ByteSize> {
+        Ok(self
+            .data
+            .mapper
+            .outbox_max_size
+            .map(ByteSize)
+            .unwrap_or(self.config_defaults.default_mapper_outbox_max_size))
+    }
+
+    fn update(
+        &mut self,
+        _setting: MapperOutboxMaxSizeSetting,
+        value: ByteSize,
+    ) -> ConfigSettingResult<()> {
+        self.data.mapper.outbox_max_size = Some(value.into());
+        Ok(())
+    }
+
+    fn unset(&mut self, _setting: MapperOutboxMaxSizeSetting) -> ConfigSettingResult<()> {
+        self.data.mapper.outbox_max_size = None;
+        Ok(())
+    }
+}
+
+impl ConfigSettingAccessor<MapperOutboxEvictionSetting> for TEdgeConfig {
+    fn query(
+        &self,
+        _setting: MapperOutboxEvictionSetting,
+    ) -> ConfigSettingResult<OutboxEvictionPolicy> {
+        Ok(self
+            .data
+            .mapper
+            .outbox_eviction
+            .unwrap_or(self.config_defaults.default_mapper_outbox_eviction))
+    }
+
+    fn update(
+        &mut self,
+        _setting: MapperOutboxEvictionSetting,
+        value: OutboxEvictionPolicy,
+    ) -> ConfigSettingResult<()> {
+        self.data.mapper.outbox_eviction = Some(value);
+        Ok(())
+    }
+
+    fn unset(&mut self, _setting: MapperOutboxEvictionSetting) -> ConfigSettingResult<()> {
+        self.data.mapper.outbox_eviction = None;
+        Ok(())
+    }
+}
+
 /// Generic extension trait implementation for all `ConfigSetting`s of `TEdgeConfig`
 /// that provide `TryFrom`/`TryInto` implementations for `String`.
 impl<T, E, F> ConfigSettingAccessorStringExt<T> for TEdgeConfig
@@ -1,4 +1,7 @@
-use crate::tedge_config_cli::models::{FilePath, IpAddress, TemplatesSet};
+use crate::tedge_config_cli::models::{
+    ByteSize, FilePath, IpAddress, OutboxEvictionPolicy, QueueCapacity, QueueOverflowPolicy,
+    Seconds, TemplatesSet,
+};
 use crate::TEdgeConfigLocation;
 use crate::{Flag, Port};
 use std::path::Path;
@@ -10,6 +13,13 @@ pub const DEFAULT_TMP_PATH: &str = "/tmp";
 pub const DEFAULT_LOG_PATH: &str = "/var/log";
 pub const DEFAULT_RUN_PATH: &str = "/run";
 const DEFAULT_DEVICE_TYPE: &str = "thin-edge.io";
+const DEFAULT_MQTT_CLIENT_QUEUE_CAPACITY: usize = 1024;
+const DEFAULT_MAPPER_OUTBOX_MAX_SIZE: u64 = 10 * 1024 * 1024;
+const DEFAULT_SOFTWARE_PLUGIN_TIMEOUT: u64 = 3600;
+const DEFAULT_C8Y_COMMAND_TIMEOUT: u64 = 60;
+const DEFAULT_C8Y_AVAILABILITY_INTERVAL: u64 = 0;
+const DEFAULT_AZURE_MAPPER_FORWARD_TOPIC: &str = "tedge/az";
+const DEFAULT_AWS_TOPIC_PREFIX: &str = "thinedge";
 
 /// Stores default values for use by `TEdgeConfig` in case no configuration setting
 /// is available.
@@ -37,12 +47,21 @@ pub struct TEdgeConfigDefaults {
     /// Default path for c8y root certificates
     pub default_c8y_root_cert_path: FilePath,
 
+    /// Default path for AWS root certificates
+    pub default_aws_root_cert_path: FilePath,
+
+    /// Default prefix of the AWS topics
+    pub default_aws_topic_prefix: String,
+
     /// Default c8y smartrest templates
     pub default_c8y_smartrest_templates: TemplatesSet,
 
     /// Default mapper timestamp bool
     pub default_mapper_timestamp: Flag,
 
+    /// Default local topic prefix for the Azure cloud-to-device messages
+    pub default_azure_mapper_forward_topic: String,
+
     /// Default port for mqtt internal listener
     pub default_mqtt_port: Port,
 
@@ -66,6 +85,33 @@ pub struct TEdgeConfigDefaults {
 
     /// Default htpp bind address
     pub default_http_bind_address: IpAddress,
+
+    /// Default capacity of the message queues of the local MQTT clients
+    pub default_mqtt_client_queue_capacity: QueueCapacity,
+
+    /// Default overflow policy of the received message queues of the local MQTT clients
+    pub default_mqtt_client_overflow_policy: QueueOverflowPolicy,
+
+    /// Default maximum size of the mapper outbox
+    pub default_mapper_outbox_max_size: ByteSize,
+
+    /// Default eviction policy of the mapper outbox
+    pub default_mapper_outbox_eviction: OutboxEvictionPolicy,
+
+    /// Default timeout of the software plugins
+    pub default_software_plugin_timeout: Seconds,
+
+    /// Default transactional mode of the software updates
+    pub default_software_update_transactional: Flag,
+
+    /// Default activation of the c8y_Command operation
+    pub default_c8y_command_enable: Flag,
+
+    /// Default timeout of the c8y_Command shell commands
+    pub default_c8y_command_timeout: Seconds,
+
+    /// Default required interval between two messages sent to Cumulocity
+    pub default_c8y_availability_interval: Seconds,
 }
 
 impl From<&TEdgeConfigLocation> for TEdgeConfigDefaults {
@@ -86,8 +132,11 @@ impl From<&TEdgeConfigLocation> for TEdgeConfigDefaults {
                 .join("tedge-private-key.pem")
                 .into(),
             default_azure_root_cert_path: system_cert_path.clone().into(),
-            default_c8y_root_cert_path: system_cert_path.into(),
+            default_c8y_root_cert_path: system_cert_path.clone().into(),
+            default_aws_root_cert_path: system_cert_path.into(),
+            default_aws_topic_prefix: DEFAULT_AWS_TOPIC_PREFIX.into(),
             default_mapper_timestamp: Flag(true),
+            default_azure_mapper_forward_topic: DEFAULT_AZURE_MAPPER_FORWARD_TOPIC.into(),
             default_mqtt_port: Port(DEFAULT_MQTT_PORT),
             default_http_port: Port(DEFAULT_HTTP_PORT),
             default_tmp_path: tmp_path.into(),
@@ -96,7 +145,16 @@ impl From<&TEdgeConfigLocation> for TEdgeConfigDefaults {
             default_device_type: DEFAULT_DEVICE_TYPE.into(),
             default_mqtt_bind_address: IpAddress::default(),
             default_http_bind_address: IpAddress::default(),
+            default_mqtt_client_queue_capacity: QueueCapacity(DEFAULT_MQTT_CLIENT_QUEUE_CAPACITY),
+            default_mqtt_client_overflow_policy: QueueOverflowPolicy::DropOldest,
             default_c8y_smartrest_templates: TemplatesSet::default(),
+            default_mapper_outbox_max_size: ByteSize(DEFAULT_MAPPER_OUTBOX_MAX_SIZE),
+            default_mapper_outbox_eviction: OutboxEvictionPolicy::DropOldest,
+            default_software_plugin_timeout: Seconds(DEFAULT_SOFTWARE_PLUGIN_TIMEOUT),
+            default_software_update_transactional: Flag(false),
+            default_c8y_command_enable: Flag(false),
+            default_c8y_command_timeout: Seconds(DEFAULT_C8Y_COMMAND_TIMEOUT),
+            default_c8y_availability_interval: Seconds(DEFAULT_C8Y_AVAILABILITY_INTERVAL),
         }
     }
 }
@@ -117,7 +175,10 @@ fn test_from_tedge_config_location() {
             ),
             default_azure_root_cert_path: FilePath::from("/etc/ssl/certs"),
             default_c8y_root_cert_path: FilePath::from("/etc/ssl/certs"),
+            default_aws_root_cert_path: FilePath::from("/etc/ssl/certs"),
+            default_aws_topic_prefix: DEFAULT_AWS_TOPIC_PREFIX.into(),
             default_mapper_timestamp: Flag(true),
+            default_azure_mapper_forward_topic: DEFAULT_AZURE_MAPPER_FORWARD_TOPIC.into(),
             default_mqtt_port: Port(DEFAULT_MQTT_PORT),
             default_http_port: Port(DEFAULT_HTTP_PORT),
             default_tmp_path: FilePath::from("/tmp"),
@@ -126,7 +187,16 @@ fn test_from_tedge_config_location() {
             default_device_type: DEFAULT_DEVICE_TYPE.into(),
             default_mqtt_bind_address: IpAddress::default(),
             default_http_bind_address: IpAddress::default(),
+            default_mqtt_client_queue_capacity: QueueCapacity(DEFAULT_MQTT_CLIENT_QUEUE_CAPACITY),
+            default_mqtt_client_overflow_policy: QueueOverflowPolicy::DropOldest,
             default_c8y_smartrest_templates: TemplatesSet::default(),
+            default_mapper_outbox_max_size: ByteSize(DEFAULT_MAPPER_OUTBOX_MAX_SIZE),
+            default_mapper_outbox_eviction: OutboxEvictionPolicy::DropOldest,
+            default_software_plugin_timeout: Seconds(DEFAULT_SOFTWARE_PLUGIN_TIMEOUT),
+            default_software_update_transactional: Flag(false),
+            default_c8y_command_enable: Flag(false),
+            default_c8y_command_timeout: Seconds(DEFAULT_C8Y_COMMAND_TIMEOUT),
+            default_c8y_availability_interval: Seconds(DEFAULT_C8Y_AVAILABILITY_INTERVAL),
         }
     );
 }
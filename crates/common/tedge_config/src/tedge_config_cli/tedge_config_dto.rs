@@ -18,6 +18,9 @@ pub(crate) struct TEdgeConfigDto {
     #[serde(default, alias = "azure")] // for version 0.1.0 compatibility
     pub(crate) az: AzureConfigDto,
 
+    #[serde(default)]
+    pub(crate) aws: AwsConfigDto,
+
     #[serde(default)]
     pub(crate) mqtt: MqttConfigDto,
 
@@ -27,6 +30,12 @@ pub(crate) struct TEdgeConfigDto {
     #[serde(default)]
     pub(crate) software: SoftwareConfigDto,
 
+    #[serde(default)]
+    pub(crate) firmware: FirmwareConfigDto,
+
+    #[serde(default)]
+    pub(crate) mapper: MapperConfigDto,
+
     #[serde(default)]
     pub(crate) tmp: PathConfigDto,
 
@@ -79,6 +88,18 @@ pub(crate) struct CumulocityConfigDto {
 
     /// Set of c8y templates used for subscriptions.
     pub(crate) smartrest_templates: Option<TemplatesSet>,
+
+    /// Execute the shell commands of the c8y_Command operations
+    pub(crate) command_enable: Option<bool>,
+
+    /// Maximum time in seconds given to a shell command to complete
+    pub(crate) command_timeout: Option<u64>,
+
+    /// User running the shell commands
+    pub(crate) command_user: Option<String>,
+
+    /// Required interval in seconds between two messages of the device
+    pub(crate) availability_interval: Option<u64>,
 }
 
 #[tedge_derive::serde_other]
@@ -88,6 +109,18 @@ pub(crate) struct AzureConfigDto {
     pub(crate) url: Option<ConnectUrl>,
     pub(crate) root_cert_path: Option<FilePath>,
     pub(crate) mapper_timestamp: Option<bool>,
+
+    /// Local topic prefix for the cloud-to-device messages and unhandled direct methods.
+    pub(crate) mapper_forward_topic: Option<String>,
+}
+
+#[tedge_derive::serde_other]
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub(crate) struct AwsConfigDto {
+    pub(crate) url: Option<ConnectUrl>,
+    pub(crate) root_cert_path: Option<FilePath>,
+    pub(crate) mapper_timestamp: Option<bool>,
+    pub(crate) topic_prefix: Option<String>,
 }
 
 #[tedge_derive::serde_other]
@@ -101,6 +134,19 @@ pub(crate) struct MqttConfigDto {
     pub(crate) external_capath: Option<FilePath>,
     pub(crate) external_certfile: Option<FilePath>,
     pub(crate) external_keyfile: Option<FilePath>,
+    pub(crate) client_cafile: Option<FilePath>,
+    pub(crate) client_capath: Option<FilePath>,
+    pub(crate) client_certfile: Option<FilePath>,
+    pub(crate) client_keyfile: Option<FilePath>,
+    pub(crate) client_username: Option<String>,
+    pub(crate) client_password_file: Option<FilePath>,
+
+    /// Maximum number of messages in each queue of a local client
+    pub(crate) client_queue_capacity: Option<usize>,
+
+    /// What to do with a received telemetry message when the queue is full:
+    /// `block`, `drop-oldest` or `drop-newest`
+    pub(crate) client_overflow_policy: Option<QueueOverflowPolicy>,
 }
 
 #[tedge_derive::serde_other]
@@ -114,6 +160,34 @@ pub(crate) struct HttpConfigDto {
 #[derive(Debug, Default, Deserialize, Serialize)]
 pub(crate) struct SoftwareConfigDto {
     pub(crate) default_plugin_type: Option<String>,
+
+    /// Maximum time in seconds given to a plugin to complete an action
+    pub(crate) plugin_timeout: Option<u64>,
+
+    /// Plugin specific timeouts in seconds, per software type
+    pub(crate) plugin_timeouts: Option<PluginTimeouts>,
+
+    /// Roll back all the module updates of a request when one fails
+    pub(crate) update_transactional: Option<bool>,
+}
+
+#[tedge_derive::serde_other]
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub(crate) struct FirmwareConfigDto {
+    /// Command verifying and installing the firmware images
+    pub(crate) install_command: Option<String>,
+}
+
+/// Represents the cloud-agnostic mapper configurations defined in the
+/// [mapper] section of the thin edge configuration TOML file
+#[tedge_derive::serde_other]
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub(crate) struct MapperConfigDto {
+    /// Maximum disk space used to buffer the messages while the cloud bridge is down
+    pub(crate) outbox_max_size: Option<u64>,
+
+    /// What to drop when the outbox is full: `drop-oldest` or `drop-newest`
+    pub(crate) outbox_eviction: Option<OutboxEvictionPolicy>,
 }
 
 #[tedge_derive::serde_other]
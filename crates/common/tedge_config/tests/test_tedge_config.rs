@@ -480,6 +480,44 @@ bind_address = "1.2.3.4"
     Ok(())
 }
 
+#[test]
+fn test_parse_mqtt_client_tls_and_credentials_settings() -> Result<(), TEdgeConfigError> {
+    let dir = TempTedgeDir::new();
+    dir.file("password").with_raw_content("secret\n");
+    let password_file = dir.path().join("password");
+    let toml_conf = format!(
+        r#"
+[mqtt]
+port = 8883
+client_cafile = "/etc/mosquitto/ca.crt"
+client_certfile = "/etc/mosquitto/client.crt"
+client_keyfile = "/etc/mosquitto/client.key"
+client_username = "tedge"
+client_password_file = "{}"
+"#,
+        password_file.display()
+    );
+    dir.file("tedge.toml").with_raw_content(&toml_conf);
+    let config_location = TEdgeConfigLocation::from_custom_root(dir.path());
+
+    let config =
+        TEdgeConfigRepository::new_with_defaults(config_location, dummy_tedge_config_defaults())
+            .load()?;
+
+    assert_eq!(
+        config.query(MqttClientCAFileSetting)?,
+        FilePath::from("/etc/mosquitto/ca.crt")
+    );
+    assert!(config.query_optional(MqttClientCAPathSetting)?.is_none());
+    assert_eq!(config.query(MqttClientUsernameSetting)?, "tedge");
+
+    assert_eq!(
+        config.query(MqttClientPasswordFileSetting)?,
+        FilePath::from(password_file)
+    );
+    Ok(())
+}
+
 #[test]
 fn read_az_keys_from_old_version_config() -> Result<(), TEdgeConfigError> {
     let toml_conf = r#"
@@ -777,6 +815,39 @@ port = 1024
     Ok(())
 }
 
+#[test]
+fn test_parse_software_plugin_timeouts() -> Result<(), TEdgeConfigError> {
+    let toml_conf = r#"
+[software]
+plugin_timeout = 600
+
+[software.plugin_timeouts]
+apt = 1800
+docker = 300
+"#;
+
+    let (_tempdir, config_location) = create_temp_tedge_config(toml_conf)?;
+    let mut config =
+        TEdgeConfigRepository::new_with_defaults(config_location, dummy_tedge_config_defaults())
+            .load()?;
+
+    assert_eq!(config.query(SoftwarePluginTimeoutSetting)?, Seconds(600));
+    assert_eq!(
+        config.query_string(SoftwarePluginTimeoutsSetting)?,
+        "apt=1800,docker=300"
+    );
+
+    config.unset(SoftwarePluginTimeoutSetting)?;
+    config.update_string(SoftwarePluginTimeoutsSetting, "apt=60".into())?;
+    assert_eq!(config.query(SoftwarePluginTimeoutSetting)?, Seconds(3600));
+    assert_eq!(
+        config.query_string(SoftwarePluginTimeoutsSetting)?,
+        "apt=60"
+    );
+
+    Ok(())
+}
+
 #[test]
 fn test_any_device_id_provided_by_the_configuration_is_ignored() -> Result<(), TEdgeConfigError> {
     let toml_conf = r#"
@@ -872,7 +943,10 @@ fn dummy_tedge_config_defaults() -> TEdgeConfigDefaults {
         default_device_key_path: FilePath::from("/dev/null"),
         default_c8y_root_cert_path: FilePath::from("/dev/null"),
         default_azure_root_cert_path: FilePath::from("/dev/null"),
+        default_aws_root_cert_path: FilePath::from("/dev/null"),
+        default_aws_topic_prefix: String::from("thinedge"),
         default_mapper_timestamp: Flag(true),
+        default_azure_mapper_forward_topic: String::from("tedge/az"),
         default_mqtt_port: Port(1883),
         default_http_port: Port(8000),
         default_tmp_path: FilePath::from("/tmp"),
@@ -881,7 +955,16 @@ fn dummy_tedge_config_defaults() -> TEdgeConfigDefaults {
         default_device_type: String::from("test"),
         default_mqtt_bind_address: IpAddress(IpAddr::V4(Ipv4Addr::LOCALHOST)),
         default_http_bind_address: IpAddress(IpAddr::V4(Ipv4Addr::LOCALHOST)),
+        default_mqtt_client_queue_capacity: QueueCapacity(1024),
+        default_mqtt_client_overflow_policy: QueueOverflowPolicy::DropOldest,
         default_c8y_smartrest_templates: TemplatesSet::default(),
+        default_mapper_outbox_max_size: ByteSize(1024),
+        default_mapper_outbox_eviction: OutboxEvictionPolicy::DropOldest,
+        default_software_plugin_timeout: Seconds(3600),
+        default_software_update_transactional: Flag(false),
+        default_c8y_command_enable: Flag(false),
+        default_c8y_command_timeout: Seconds(60),
+        default_c8y_availability_interval: Seconds(0),
     }
 }
 
@@ -0,0 +1,219 @@
+//! The configuration of the MQTT connections to the local broker, as derived from the tedge configuration.
+//!
+//! This glue between `tedge_config` and `mqtt_channel` keeps the configuration crate independent of the MQTT stack.
+
+use tedge_config::*;
+
+pub trait MqttConfigExt {
+    /// The configuration of the MQTT connections to the local broker,
+    /// over TLS and with credentials when configured,
+    /// and with the configured queue capacity and overflow policy.
+    fn mqtt_config(&self) -> ConfigSettingResult<mqtt_channel::Config>;
+}
+
+impl MqttConfigExt for TEdgeConfig {
+    fn mqtt_config(&self) -> ConfigSettingResult<mqtt_channel::Config> {
+        let mut mqtt_config = mqtt_channel::Config::default()
+            .with_host(self.query(MqttBindAddressSetting)?.to_string())
+            .with_port(self.query(MqttPortSetting)?.into())
+            .with_queue_capacity(self.query(MqttClientQueueCapacitySetting)?.into())
+            .with_received_overflow_policy(overflow_policy(
+                self.query(MqttClientOverflowPolicySetting)?,
+            ));
+
+        if let Some(tls) = mqtt_tls_config(self)? {
+            mqtt_config = mqtt_config.with_tls(tls);
+        }
+
+        let password_file = self.query_optional(MqttClientPasswordFileSetting)?;
+        match self.query_optional(MqttClientUsernameSetting)? {
+            Some(username) => {
+                let password = match password_file {
+                    Some(password_file) => std::fs::read_to_string(&password_file)
+                        .map_err(|err| ConfigSettingError::DerivationFailed {
+                            key: MqttClientPasswordFileSetting::KEY,
+                            cause: format!("cannot read {password_file}: {err}"),
+                        })?
+                        .trim_end()
+                        .to_string(),
+                    None => String::new(),
+                };
+                mqtt_config = mqtt_config.with_credentials(username, password);
+            }
+            // A password is useless without a username
+            None if password_file.is_some() => {
+                return Err(missing_setting(MqttClientUsernameSetting::KEY))
+            }
+            None => {}
+        }
+
+        Ok(mqtt_config)
+    }
+}
+
+fn mqtt_tls_config(
+    tedge_config: &TEdgeConfig,
+) -> ConfigSettingResult<Option<mqtt_channel::TlsConfig>> {
+    let ca_paths: Vec<std::path::PathBuf> = [
+        tedge_config.query_optional(MqttClientCAFileSetting)?,
+        tedge_config.query_optional(MqttClientCAPathSetting)?,
+    ]
+    .into_iter()
+    .flatten()
+    .map(Into::into)
+    .collect();
+
+    let client_auth = match (
+        tedge_config.query_optional(MqttClientCertfileSetting)?,
+        tedge_config.query_optional(MqttClientKeyfileSetting)?,
+    ) {
+        (Some(cert_file), Some(key_file)) => Some(mqtt_channel::ClientAuth {
+            cert_file: cert_file.into(),
+            key_file: key_file.into(),
+        }),
+        (None, None) => None,
+        (Some(_), None) => return Err(missing_setting(MqttClientKeyfileSetting::KEY)),
+        (None, Some(_)) => return Err(missing_setting(MqttClientCertfileSetting::KEY)),
+    };
+
+    if ca_paths.is_empty() {
+        return match client_auth {
+            Some(_) => Err(missing_setting(MqttClientCAFileSetting::KEY)),
+            None => Ok(None),
+        };
+    }
+
+    Ok(Some(mqtt_channel::TlsConfig {
+        ca_paths,
+        client_auth,
+    }))
+}
+
+fn overflow_policy(policy: QueueOverflowPolicy) -> mqtt_channel::OverflowPolicy {
+    match policy {
+        QueueOverflowPolicy::Block => mqtt_channel::OverflowPolicy::Block,
+        QueueOverflowPolicy::DropOldest => mqtt_channel::OverflowPolicy::DropOldest,
+        QueueOverflowPolicy::DropNewest => mqtt_channel::OverflowPolicy::DropNewest,
+    }
+}
+
+fn missing_setting(key: &'static str) -> ConfigSettingError {
+    ConfigSettingError::ConfigNotSet { key }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_matches::assert_matches;
+    use tedge_test_utils::fs::TempTedgeDir;
+
+    fn load_config(dir: &TempTedgeDir, toml_conf: &str) -> anyhow::Result<TEdgeConfig> {
+        dir.file("tedge.toml").with_raw_content(toml_conf);
+        let config_location = TEdgeConfigLocation::from_custom_root(dir.path());
+        Ok(TEdgeConfigRepository::new(config_location).load()?)
+    }
+
+    #[test]
+    fn mqtt_client_configuration_with_tls_and_credentials() -> anyhow::Result<()> {
+        let dir = TempTedgeDir::new();
+        dir.file("password").with_raw_content("secret\n");
+        let password_file = dir.path().join("password");
+        let toml_conf = format!(
+            r#"
+[mqtt]
+port = 8883
+client_cafile = "/etc/mosquitto/ca.crt"
+client_certfile = "/etc/mosquitto/client.crt"
+client_keyfile = "/etc/mosquitto/client.key"
+client_username = "tedge"
+client_password_file = "{}"
+"#,
+            password_file.display()
+        );
+        let config = load_config(&dir, &toml_conf)?;
+
+        let mqtt_config = config.mqtt_config()?;
+        assert_eq!(mqtt_config.port, 8883);
+        let tls = mqtt_config.tls.expect("TLS is configured");
+        assert_eq!(
+            tls.ca_paths,
+            vec![std::path::PathBuf::from("/etc/mosquitto/ca.crt")]
+        );
+        assert!(tls.client_auth.is_some());
+        let credentials = mqtt_config.credentials.expect("credentials are configured");
+        assert_eq!(credentials.username, "tedge");
+        assert_eq!(credentials.password, "secret");
+        Ok(())
+    }
+
+    #[test]
+    fn mqtt_client_queues_are_configurable() -> anyhow::Result<()> {
+        let dir = TempTedgeDir::new();
+        let config = load_config(&dir, "")?;
+
+        let mqtt_config = config.mqtt_config()?;
+        assert_eq!(mqtt_config.queue_capacity, 1024);
+        assert_eq!(
+            mqtt_config.received_overflow_policy,
+            mqtt_channel::OverflowPolicy::DropOldest
+        );
+
+        let config = load_config(
+            &dir,
+            r#"
+[mqtt]
+client_queue_capacity = 64
+client_overflow_policy = "block"
+"#,
+        )?;
+
+        let mqtt_config = config.mqtt_config()?;
+        assert_eq!(mqtt_config.queue_capacity, 64);
+        assert_eq!(
+            mqtt_config.received_overflow_policy,
+            mqtt_channel::OverflowPolicy::Block
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn mqtt_client_certificate_requires_a_key() -> anyhow::Result<()> {
+        let dir = TempTedgeDir::new();
+        let config = load_config(
+            &dir,
+            r#"
+[mqtt]
+client_cafile = "/etc/mosquitto/ca.crt"
+client_certfile = "/etc/mosquitto/client.crt"
+"#,
+        )?;
+
+        assert_matches!(
+            config.mqtt_config(),
+            Err(ConfigSettingError::ConfigNotSet {
+                key: "mqtt.client.keyfile"
+            })
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn mqtt_client_password_requires_a_username() -> anyhow::Result<()> {
+        let dir = TempTedgeDir::new();
+        let config = load_config(
+            &dir,
+            r#"
+[mqtt]
+client_password_file = "/etc/tedge/mqtt-password"
+"#,
+        )?;
+
+        assert_matches!(
+            config.mqtt_config(),
+            Err(ConfigSettingError::ConfigNotSet {
+                key: "mqtt.client.username"
+            })
+        );
+        Ok(())
+    }
+}
@@ -1,6 +1,6 @@
 use crate::json_c8y::{
-    C8yCreateEvent, C8yEventResponse, C8yManagedObject, C8yUpdateSoftwareListResponse,
-    InternalIdResponse,
+    C8yAlarm, C8yAlarmCollection, C8yAlarmUpdate, C8yCreateEvent, C8yEventResponse,
+    C8yManagedObject, C8yUpdateSoftwareListResponse, InternalIdResponse,
 };
 
 use crate::smartrest::{
@@ -14,8 +14,9 @@ use std::path::Path;
 use std::{collections::HashMap, time::Duration};
 use tedge_config::{
     C8yRootCertPathSetting, C8yUrlSetting, ConfigSettingAccessor, ConfigSettingAccessorStringExt,
-    DeviceIdSetting, MqttBindAddressSetting, MqttPortSetting, TEdgeConfig,
+    DeviceIdSetting, TEdgeConfig,
 };
+use tedge_mqtt_config::MqttConfigExt;
 use time::OffsetDateTime;
 
 use tracing::{error, info, instrument};
@@ -55,6 +56,18 @@ pub trait C8YHttpProxy: Send + Sync {
         config_type: &str,
         child_device_id: Option<String>,
     ) -> Result<String, SMCumulocityMapperError>;
+
+    /// The alarms of the device, or of a child device, that are either active or acknowledged.
+    async fn get_unresolved_alarms(
+        &mut self,
+        child_device_id: Option<String>,
+    ) -> Result<Vec<C8yAlarm>, SMCumulocityMapperError>;
+
+    async fn update_alarm(
+        &mut self,
+        alarm_id: &str,
+        alarm_update: &C8yAlarmUpdate,
+    ) -> Result<(), SMCumulocityMapperError>;
 }
 
 /// Define a C8y endpoint
@@ -116,6 +129,22 @@ impl C8yEndPoint {
         url_event_binary
     }
 
+    fn get_url_for_unresolved_alarms(&self, source_id: &str) -> String {
+        let mut url_get_alarms = self.get_base_url();
+        url_get_alarms.push_str("/alarm/alarms?resolved=false&pageSize=2000&source=");
+        url_get_alarms.push_str(source_id);
+
+        url_get_alarms
+    }
+
+    fn get_url_for_alarm(&self, alarm_id: &str) -> String {
+        let mut url_alarm = self.get_base_url();
+        url_alarm.push_str("/alarm/alarms/");
+        url_alarm.push_str(alarm_id);
+
+        url_alarm
+    }
+
     fn url_is_in_my_tenant_domain(&self, url: &str) -> bool {
         // c8y URL may contain either `Tenant Name` or Tenant Id` so they can be one of following options:
         // * <tenant_name>.<domain> eg: sample.c8y.io
@@ -154,13 +183,10 @@ pub struct C8yMqttJwtTokenRetriever {
 
 impl C8yMqttJwtTokenRetriever {
     pub async fn try_new(tedge_config: &TEdgeConfig) -> Result<Self, SMCumulocityMapperError> {
-        let mqtt_port = tedge_config.query(MqttPortSetting)?.into();
-        let mqtt_host = tedge_config.query(MqttBindAddressSetting)?.to_string();
         let topic = TopicFilter::new("c8y/s/dat")?;
-        let mqtt_config = mqtt_channel::Config::default()
-            .with_port(mqtt_port)
+        let mqtt_config = tedge_config
+            .mqtt_config()?
             .with_clean_session(true)
-            .with_host(mqtt_host)
             .with_subscriptions(topic);
 
         Ok(C8yMqttJwtTokenRetriever { mqtt_config })
@@ -467,6 +493,44 @@ impl C8YHttpProxy for JwtAuthHttpProxy {
         let _response = self.execute(request).await?;
         Ok(binary_upload_event_url)
     }
+
+    async fn get_unresolved_alarms(
+        &mut self,
+        child_device_id: Option<String>,
+    ) -> Result<Vec<C8yAlarm>, SMCumulocityMapperError> {
+        let source_id = match child_device_id {
+            Some(child_device_id) => self.get_c8y_internal_child_id(child_device_id).await?,
+            None => self.end_point.c8y_internal_id.clone(),
+        };
+        let url = self.end_point.get_url_for_unresolved_alarms(&source_id);
+
+        let request = self.http_con.get(url).header("Accept", "application/json");
+
+        let response = self.execute(request).await?;
+        let _ = response.error_for_status_ref()?;
+        let alarms = response.json::<C8yAlarmCollection>().await?;
+
+        Ok(alarms.alarms)
+    }
+
+    async fn update_alarm(
+        &mut self,
+        alarm_id: &str,
+        alarm_update: &C8yAlarmUpdate,
+    ) -> Result<(), SMCumulocityMapperError> {
+        let url = self.end_point.get_url_for_alarm(alarm_id);
+
+        let request = self
+            .http_con
+            .put(url)
+            .json(alarm_update)
+            .header("Accept", "application/json");
+
+        let response = self.execute(request).await?;
+        let _ = response.error_for_status_ref()?;
+
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -474,6 +538,7 @@ mod tests {
     use std::io::Write;
 
     use super::*;
+    use crate::json_c8y::C8yAlarmStatus;
     use anyhow::Result;
     use mockito::{mock, Matcher};
     use serde_json::json;
@@ -606,6 +671,70 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn get_and_update_unresolved_alarms() -> anyhow::Result<()> {
+        let device_id = "test-device";
+
+        let _get_internal_id_mock = mock("GET", "/identity/externalIds/c8y_Serial/test-device")
+            .with_status(200)
+            .with_body(
+                json!({ "externalId": device_id, "managedObject": { "id": "123" } }).to_string(),
+            )
+            .create();
+
+        let _get_alarms_mock = mock("GET", "/alarm/alarms")
+            .match_query(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("source".into(), "123".into()),
+                Matcher::UrlEncoded("resolved".into(), "false".into()),
+            ]))
+            .with_status(200)
+            .with_body(
+                json!({ "alarms": [
+                    { "id": "42", "type": "temperature_alarm", "status": "ACTIVE", "severity": "MAJOR" }
+                ]})
+                .to_string(),
+            )
+            .create();
+
+        let _update_alarm_mock = mock("PUT", "/alarm/alarms/42")
+            .match_body(Matcher::Json(json!({ "status": "ACKNOWLEDGED" })))
+            .with_status(200)
+            .create();
+
+        let mut jwt_token_retriever = Box::new(MockC8yJwtTokenRetriever::new());
+        jwt_token_retriever
+            .expect_get_jwt_token()
+            .returning(|| Ok(SmartRestJwtResponse::default()));
+
+        let http_client = reqwest::ClientBuilder::new().build().unwrap();
+        let mut http_proxy = JwtAuthHttpProxy::new(
+            jwt_token_retriever,
+            http_client,
+            mockito::server_url().as_str(),
+            device_id,
+        );
+        http_proxy.init().await?;
+
+        let alarms = http_proxy.get_unresolved_alarms(None).await?;
+        assert_eq!(
+            alarms,
+            vec![C8yAlarm {
+                id: "42".into(),
+                alarm_type: "temperature_alarm".into(),
+                severity: "MAJOR".into(),
+                status: C8yAlarmStatus::Active,
+            }]
+        );
+
+        let acknowledge = C8yAlarmUpdate {
+            status: Some(C8yAlarmStatus::Acknowledged),
+            text: None,
+        };
+        http_proxy.update_alarm("42", &acknowledge).await?;
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn upload_config_file_authorized() -> anyhow::Result<()> {
         let token_expired = false;
@@ -36,6 +36,45 @@ pub struct C8yEventResponse {
     pub id: String,
 }
 
+/// An alarm as returned by the C8Y alarm API
+#[derive(Debug, Deserialize, Serialize, Clone, Eq, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct C8yAlarm {
+    pub id: String,
+
+    #[serde(rename = "type")]
+    pub alarm_type: String,
+
+    pub severity: String,
+
+    pub status: C8yAlarmStatus,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct C8yAlarmCollection {
+    pub alarms: Vec<C8yAlarm>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, Eq, PartialEq)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum C8yAlarmStatus {
+    Active,
+    Acknowledged,
+    Cleared,
+}
+
+/// The changes of an existing alarm, sent with the C8Y alarm API
+#[derive(Debug, Default, Deserialize, Serialize, Clone, Eq, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct C8yAlarmUpdate {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<C8yAlarmStatus>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
 pub struct C8yManagedObject {
@@ -1,4 +1,4 @@
-use tedge_api::alarm::{AlarmSeverity, ThinEdgeAlarm};
+use tedge_api::alarm::{AlarmSeverity, AlarmStatus, ThinEdgeAlarm};
 use time::{format_description::well_known::Rfc3339, OffsetDateTime};
 
 use crate::smartrest::error::SmartRestSerializerError;
@@ -6,8 +6,7 @@ use crate::smartrest::error::SmartRestSerializerError;
 /// Converts from thin-edge alarm to C8Y alarm SmartREST message
 pub fn serialize_alarm(alarm: ThinEdgeAlarm) -> Result<String, SmartRestSerializerError> {
     match alarm.data {
-        None => Ok(format!("306,{}", alarm.name)),
-        Some(alarm_data) => {
+        Some(alarm_data) if alarm_data.status != Some(AlarmStatus::Cleared) => {
             let smartrest_code = match alarm.severity {
                 AlarmSeverity::Critical => 301,
                 AlarmSeverity::Major => 302,
@@ -30,15 +29,27 @@ pub fn serialize_alarm(alarm: ThinEdgeAlarm) -> Result<String, SmartRestSerializ
 
             Ok(smartrest_message)
         }
+        _ => Ok(format!("306,{}", alarm.name)),
     }
 }
 
+/// Converts the new severity of an existing alarm to C8Y SmartREST message
+pub fn serialize_alarm_severity_update(alarm_name: &str, severity: AlarmSeverity) -> String {
+    let severity = match severity {
+        AlarmSeverity::Critical => "CRITICAL",
+        AlarmSeverity::Major => "MAJOR",
+        AlarmSeverity::Minor => "MINOR",
+        AlarmSeverity::Warning => "WARNING",
+    };
+    format!("305,{alarm_name},{severity}")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use assert_matches::assert_matches;
     use serde::Deserialize;
-    use tedge_api::alarm::ThinEdgeAlarmData;
+    use tedge_api::alarm::{AlarmStatus, ThinEdgeAlarmData};
     use test_case::test_case;
     use time::macros::datetime;
 
@@ -49,6 +60,7 @@ mod tests {
             data: Some(ThinEdgeAlarmData {
                 text: Some("I raised it".into()),
                 time: Some(datetime!(2021-04-23 19:00:00 +05:00)),
+                status: None,
             }),
         },
         "301,temperature_alarm,\"I raised it\",2021-04-23T19:00:00+05:00"
@@ -61,6 +73,7 @@ mod tests {
             data: Some(ThinEdgeAlarmData {
                 text: Some("I raised it".into()),
                 time: Some(datetime!(2021-04-23 19:00:00 +05:00)),
+                status: None,
             }),
         },
         "302,temperature_alarm,\"I raised it\",2021-04-23T19:00:00+05:00"
@@ -73,6 +86,7 @@ mod tests {
             data: Some(ThinEdgeAlarmData {
                 text: None,
                 time: Some(datetime!(2021-04-23 19:00:00 +05:00)),
+                status: None,
             }),
         },
         "303,temperature_alarm,\"\",2021-04-23T19:00:00+05:00"
@@ -85,6 +99,7 @@ mod tests {
             data: Some(ThinEdgeAlarmData {
                 text: Some("I, raised, it".into()),
                 time: Some(datetime!(2021-04-23 19:00:00 +05:00)),
+                status: None,
             }),
         },
         "304,temperature_alarm,\"I, raised, it\",2021-04-23T19:00:00+05:00"
@@ -99,12 +114,33 @@ mod tests {
         "306,temperature_alarm"
         ;"clear alarm translation"
     )]
+    #[test_case(
+        ThinEdgeAlarm {
+            name: "temperature_alarm".into(),
+            severity: AlarmSeverity::Minor,
+            data: Some(ThinEdgeAlarmData {
+                text: Some("Back to normal".into()),
+                time: None,
+                status: Some(AlarmStatus::Cleared),
+            }),
+        },
+        "306,temperature_alarm"
+        ;"clear alarm with reason translation"
+    )]
     fn check_alarm_translation(alarm: ThinEdgeAlarm, expected_smartrest_msg: &str) {
         let result = serialize_alarm(alarm);
 
         assert_eq!(result.unwrap(), expected_smartrest_msg);
     }
 
+    #[test]
+    fn alarm_severity_update_translation() {
+        assert_eq!(
+            serialize_alarm_severity_update("temperature_alarm", AlarmSeverity::Critical),
+            "305,temperature_alarm,CRITICAL"
+        );
+    }
+
     #[derive(Debug, Deserialize)]
     struct SmartRestAlarm {
         pub code: i32,
@@ -121,6 +157,7 @@ mod tests {
             data: Some(ThinEdgeAlarmData {
                 text: Some("I raised it".into()),
                 time: None,
+                status: None,
             }),
         };
 
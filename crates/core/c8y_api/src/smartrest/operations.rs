@@ -2,6 +2,7 @@ use std::{
     collections::{HashMap, HashSet},
     fs,
     path::{Path, PathBuf},
+    time::Duration,
 };
 
 use crate::smartrest::{
@@ -21,6 +22,7 @@ pub struct OnMessageExec {
     on_message: Option<String>,
     topic: Option<String>,
     user: Option<String>,
+    timeout: Option<u64>,
 }
 
 #[derive(Debug, Clone, Deserialize, Eq, PartialEq)]
@@ -43,6 +45,13 @@ impl Operation {
     pub fn topic(&self) -> Option<String> {
         self.exec().and_then(|exec| exec.topic.clone())
     }
+
+    /// The time given to the command to complete, if any, as set in seconds by `exec.timeout`.
+    pub fn timeout(&self) -> Option<Duration> {
+        self.exec()
+            .and_then(|exec| exec.timeout)
+            .map(Duration::from_secs)
+    }
 }
 
 #[derive(Debug, Default, Clone)]
@@ -279,6 +288,25 @@ mod tests {
         assert_eq!(operations.operations.len(), ops_count);
     }
 
+    #[test]
+    fn operation_timeout_is_read_from_the_operation_file() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let file_path = temp_dir.path().join("c8y_Custom");
+        fs::write(
+            &file_path,
+            br#"[exec]
+                command = "/usr/bin/custom_handler"
+                on_message = "530"
+                timeout = 60"#,
+        )
+        .unwrap();
+
+        let operation = get_operation(file_path).unwrap();
+
+        assert_eq!(operation.name, "c8y_Custom");
+        assert_eq!(operation.timeout(), Some(Duration::from_secs(60)));
+    }
+
     #[test_case("file_a?", false)]
     #[test_case("~file_b", false)]
     #[test_case("c8y_Command", true)]
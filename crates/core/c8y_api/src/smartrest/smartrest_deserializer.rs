@@ -4,7 +4,9 @@ use download::DownloadInfo;
 use serde::de::Error;
 use serde::{Deserialize, Deserializer, Serialize};
 use std::convert::{TryFrom, TryInto};
-use tedge_api::{SoftwareModule, SoftwareModuleUpdate, SoftwareUpdateRequest};
+use tedge_api::{
+    FirmwareUpdateRequest, SoftwareModule, SoftwareModuleUpdate, SoftwareUpdateRequest,
+};
 use time::{format_description, OffsetDateTime};
 
 #[derive(Debug)]
@@ -247,6 +249,33 @@ pub struct SmartRestRestartRequest {
 
 impl SmartRestRequestGeneric for SmartRestRestartRequest {}
 
+/// A `c8y_Command` request, to execute a shell command on the device.
+#[derive(Debug, Deserialize, Serialize, Eq, PartialEq)]
+pub struct SmartRestCommandRequest {
+    pub message_id: String,
+    pub device: String,
+    pub command: String,
+}
+
+impl SmartRestRequestGeneric for SmartRestCommandRequest {}
+
+#[derive(Debug, Deserialize, Serialize, Eq, PartialEq)]
+pub struct SmartRestFirmwareRequest {
+    pub message_id: String,
+    pub device: String,
+    pub name: String,
+    pub version: String,
+    pub url: String,
+}
+
+impl SmartRestRequestGeneric for SmartRestFirmwareRequest {}
+
+impl SmartRestFirmwareRequest {
+    pub fn to_thin_edge_json(&self) -> FirmwareUpdateRequest {
+        FirmwareUpdateRequest::new(&self.name, &self.version, &self.url)
+    }
+}
+
 #[derive(Debug, Deserialize, Serialize, Eq, PartialEq)]
 pub struct SmartRestConfigUploadRequest {
     pub message_id: String,
@@ -617,4 +646,39 @@ mod tests {
         };
         assert_eq!(request, expected_output);
     }
+
+    #[test]
+    fn deserialize_smartrest_command_request() {
+        let smartrest = r#"511,deviceId,"ls -l /var/log, df -h""#;
+        let request = SmartRestCommandRequest::from_smartrest(smartrest).unwrap();
+        let expected_output = SmartRestCommandRequest {
+            message_id: "511".to_string(),
+            device: "deviceId".to_string(),
+            command: "ls -l /var/log, df -h".to_string(),
+        };
+        assert_eq!(request, expected_output);
+    }
+
+    #[test]
+    fn deserialize_smartrest_firmware_request() {
+        let smartrest =
+            "515,deviceId,image,1.0,https://test.cumulocity.com/inventory/binaries/70208";
+        let request = SmartRestFirmwareRequest::from_smartrest(smartrest).unwrap();
+        let expected_output = SmartRestFirmwareRequest {
+            message_id: "515".to_string(),
+            device: "deviceId".to_string(),
+            name: "image".to_string(),
+            version: "1.0".to_string(),
+            url: "https://test.cumulocity.com/inventory/binaries/70208".to_string(),
+        };
+        assert_eq!(request, expected_output);
+
+        let thin_edge_request = request.to_thin_edge_json();
+        assert_eq!(thin_edge_request.name, "image");
+        assert_eq!(thin_edge_request.version, "1.0");
+        assert_eq!(
+            thin_edge_request.url,
+            "https://test.cumulocity.com/inventory/binaries/70208"
+        );
+    }
 }
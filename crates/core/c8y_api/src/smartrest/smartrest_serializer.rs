@@ -14,6 +14,7 @@ pub enum CumulocitySupportedOperations {
     C8yRestartRequest,
     C8yUploadConfigFile,
     C8yDownloadConfigFile,
+    C8yFirmware,
 }
 
 impl From<CumulocitySupportedOperations> for &'static str {
@@ -24,6 +25,7 @@ impl From<CumulocitySupportedOperations> for &'static str {
             CumulocitySupportedOperations::C8yRestartRequest => "c8y_Restart",
             CumulocitySupportedOperations::C8yUploadConfigFile => "c8y_UploadConfigFile",
             CumulocitySupportedOperations::C8yDownloadConfigFile => "c8y_DownloadConfigFile",
+            CumulocitySupportedOperations::C8yFirmware => "c8y_Firmware",
         }
     }
 }
@@ -75,6 +77,28 @@ impl<'a> SmartRestSetSupportedOperations<'a> {
 
 impl<'a> SmartRestSerializer<'a> for SmartRestSetSupportedOperations<'a> {}
 
+/// The firmware installed on the device.
+#[derive(Debug, Deserialize, Serialize, Eq, PartialEq)]
+pub struct SmartRestSetFirmware {
+    pub message_id: &'static str,
+    pub name: String,
+    pub version: String,
+    pub url: String,
+}
+
+impl SmartRestSetFirmware {
+    pub fn new(name: &str, version: &str, url: &str) -> Self {
+        Self {
+            message_id: "115",
+            name: name.into(),
+            version: version.into(),
+            url: url.into(),
+        }
+    }
+}
+
+impl<'a> SmartRestSerializer<'a> for SmartRestSetFirmware {}
+
 #[derive(Debug, Deserialize, Serialize, Eq, PartialEq)]
 pub struct SmartRestSoftwareModuleItem {
     pub software: String,
@@ -254,6 +278,15 @@ mod tests {
         assert_eq!(smartrest, "114,c8y_SoftwareUpdate,c8y_LogfileRequest\n");
     }
 
+    #[test]
+    fn serialize_smartrest_set_firmware() {
+        let smartrest =
+            SmartRestSetFirmware::new("image", "1.0", "https://example.com/image.raucb")
+                .to_smartrest()
+                .unwrap();
+        assert_eq!(smartrest, "115,image,1.0,https://example.com/image.raucb\n");
+    }
+
     #[test]
     fn serialize_smartrest_get_pending_operations() {
         let smartrest = SmartRestGetPendingOperations::default()
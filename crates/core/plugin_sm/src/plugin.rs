@@ -1,9 +1,10 @@
 use async_trait::async_trait;
 use csv::ReaderBuilder;
 use download::Downloader;
-use logged_command::LoggedCommand;
+use logged_command::{AbortSignal, LoggedCommand};
 use serde::Deserialize;
 use std::path::Path;
+use std::time::Duration;
 use std::{path::PathBuf, process::Output};
 use tedge_api::*;
 use tokio::io::BufWriter;
@@ -212,11 +213,13 @@ struct ModuleInfo {
     version: Option<String>,
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct ExternalPluginCommand {
     pub name: SoftwareType,
     pub path: PathBuf,
     pub sudo: Option<PathBuf>,
+    pub timeout: Option<Duration>,
+    pub abort_signal: Option<AbortSignal>,
 }
 
 impl ExternalPluginCommand {
@@ -225,6 +228,21 @@ impl ExternalPluginCommand {
             name: name.into(),
             path: path.into(),
             sudo: Some("sudo".into()),
+            timeout: None,
+            abort_signal: None,
+        }
+    }
+
+    /// Kill the plugin process if an action is not completed after the given timeout.
+    pub fn with_timeout(self, timeout: Option<Duration>) -> ExternalPluginCommand {
+        ExternalPluginCommand { timeout, ..self }
+    }
+
+    /// Kill the plugin process when the given signal is aborted.
+    pub fn with_abort_signal(self, abort_signal: AbortSignal) -> ExternalPluginCommand {
+        ExternalPluginCommand {
+            abort_signal: Some(abort_signal),
+            ..self
         }
     }
 
@@ -256,6 +274,13 @@ impl ExternalPluginCommand {
             }
         }
 
+        if let Some(timeout) = self.timeout {
+            command.timeout(timeout);
+        }
+        if let Some(abort_signal) = &self.abort_signal {
+            command.abort_on(abort_signal.clone());
+        }
+
         Ok(command)
     }
 
@@ -1,6 +1,8 @@
 use crate::plugin::{Plugin, LIST};
 use crate::{log_file::LogFile, plugin::ExternalPluginCommand};
+use logged_command::AbortSignal;
 use std::path::Path;
+use std::time::Duration;
 use std::{
     collections::HashMap,
     fs,
@@ -9,9 +11,11 @@ use std::{
     process::{Command, Stdio},
 };
 use tedge_api::{
-    SoftwareError, SoftwareListRequest, SoftwareListResponse, SoftwareType, SoftwareUpdateRequest,
-    SoftwareUpdateResponse, DEFAULT,
+    SoftwareError, SoftwareListRequest, SoftwareListResponse, SoftwareModule, SoftwareModuleUpdate,
+    SoftwareType, SoftwareUpdateRequest, SoftwareUpdateResponse, DEFAULT,
 };
+use tokio::fs::File;
+use tokio::io::{AsyncWriteExt, BufWriter};
 use tracing::{error, info, warn};
 
 /// The main responsibility of a `Plugins` implementation is to retrieve the appropriate plugin for a given software module.
@@ -40,12 +44,34 @@ pub trait Plugins {
     fn update_default(&mut self, new_default: &Option<SoftwareType>) -> Result<(), SoftwareError>;
 }
 
+/// The maximum time given to the plugins to complete an action.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct PluginTimeouts {
+    /// The timeout of the plugins with no specific timeout
+    pub default: Option<Duration>,
+
+    /// The timeouts specific to some plugins, `None` meaning no timeout at all
+    pub by_software_type: HashMap<SoftwareType, Option<Duration>>,
+}
+
+impl PluginTimeouts {
+    pub fn timeout(&self, software_type: &str) -> Option<Duration> {
+        match self.by_software_type.get(software_type) {
+            Some(timeout) => *timeout,
+            None => self.default,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct ExternalPlugins {
     plugin_dir: PathBuf,
     plugin_map: HashMap<SoftwareType, ExternalPluginCommand>,
     default_plugin_type: Option<SoftwareType>,
     sudo: Option<PathBuf>,
+    timeouts: PluginTimeouts,
+    abort_signal: Option<AbortSignal>,
+    transactional: bool,
 }
 
 impl Plugins for ExternalPlugins {
@@ -95,6 +121,9 @@ impl ExternalPlugins {
             plugin_map: HashMap::new(),
             default_plugin_type: default_plugin_type.clone(),
             sudo,
+            timeouts: PluginTimeouts::default(),
+            abort_signal: None,
+            transactional: false,
         };
         if let Err(e) = plugins.load() {
             warn!(
@@ -126,6 +155,33 @@ impl ExternalPlugins {
         Ok(plugins)
     }
 
+    /// Abort the software updates when the given signal is aborted.
+    ///
+    /// The software lists are not impacted.
+    pub fn with_abort_signal(self, abort_signal: AbortSignal) -> ExternalPlugins {
+        ExternalPlugins {
+            abort_signal: Some(abort_signal),
+            ..self
+        }
+    }
+
+    pub fn update_timeouts(&mut self, timeouts: PluginTimeouts) {
+        for (software_type, plugin) in self.plugin_map.iter_mut() {
+            plugin.timeout = timeouts.timeout(software_type);
+        }
+        self.timeouts = timeouts;
+    }
+
+    /// In transactional mode, the software updates are applied as a whole or not at all.
+    ///
+    /// The modules are listed before applying an update.
+    /// The updates are applied plugin per plugin, all the updates of a plugin being applied even if one fails.
+    /// When the updates of a plugin fail, the updates of the next plugins are not applied,
+    /// and the modules updated by this plugin and the previous ones are restored to their previous state.
+    pub fn update_transactional(&mut self, transactional: bool) {
+        self.transactional = transactional;
+    }
+
     pub fn load(&mut self) -> io::Result<()> {
         self.plugin_map.clear();
         for maybe_entry in fs::read_dir(&self.plugin_dir)? {
@@ -181,7 +237,8 @@ impl ExternalPlugins {
 
                 if let Some(file_name) = path.file_name() {
                     if let Some(plugin_name) = file_name.to_str() {
-                        let plugin = ExternalPluginCommand::new(plugin_name, &path);
+                        let plugin = ExternalPluginCommand::new(plugin_name, &path)
+                            .with_timeout(self.timeouts.timeout(plugin_name));
                         self.plugin_map.insert(plugin_name.into(), plugin);
                     }
                 }
@@ -234,9 +291,38 @@ impl ExternalPlugins {
         let logger = log_file.buffer();
         let mut error_count = 0;
 
+        // In transactional mode, keep the modules as installed before the update
+        let mut snapshot = HashMap::new();
+        if self.transactional {
+            for software_type in request.modules_types() {
+                let installed_modules = match self.by_software_type(&software_type) {
+                    Some(plugin) => plugin.list(logger).await,
+                    None => Err(SoftwareError::UnknownSoftwareType {
+                        software_type: software_type.clone(),
+                    }),
+                };
+                match installed_modules {
+                    Ok(modules) => {
+                        snapshot.insert(software_type, modules);
+                    }
+                    Err(err) => {
+                        error_count += 1;
+                        response.add_errors(&software_type, vec![err]);
+                    }
+                }
+            }
+        }
+
+        let mut applied_types = Vec::new();
         for software_type in request.modules_types() {
+            if self.transactional && error_count > 0 {
+                break;
+            }
+
             let errors = if let Some(plugin) = self.by_software_type(&software_type) {
+                let plugin = self.abortable(plugin);
                 let updates = request.updates_for(&software_type);
+                applied_types.push(software_type.clone());
                 plugin.apply_all(updates, logger, download_path).await
             } else {
                 vec![SoftwareError::UnknownSoftwareType {
@@ -250,6 +336,31 @@ impl ExternalPlugins {
             }
         }
 
+        let mut rollback_outcome = None;
+        if self.transactional && error_count > 0 && !applied_types.is_empty() {
+            let mut rollback_errors = 0;
+            for software_type in applied_types.iter().rev() {
+                if let Some(plugin) = self.by_software_type(software_type) {
+                    let installed_modules = snapshot.remove(software_type).unwrap_or_default();
+                    let updates = request.updates_for(software_type);
+                    let errors = ExternalPlugins::rollback(
+                        plugin,
+                        &installed_modules,
+                        &updates,
+                        logger,
+                        download_path,
+                    )
+                    .await;
+                    if !errors.is_empty() {
+                        rollback_errors += 1;
+                        response.add_errors(software_type, errors);
+                    }
+                }
+            }
+            error_count += rollback_errors;
+            rollback_outcome = Some(rollback_errors == 0);
+        }
+
         for (software_type, plugin) in self.plugin_map.iter() {
             match plugin.list(logger).await {
                 Ok(software_list) => response.add_modules(software_type, software_list),
@@ -261,12 +372,64 @@ impl ExternalPlugins {
         }
 
         if let Some(reason) = ExternalPlugins::error_message(log_file.path(), error_count) {
+            let reason = match rollback_outcome {
+                Some(true) => format!("{reason}. The update has been rolled back"),
+                Some(false) => format!("{reason}. The update has been partially rolled back"),
+                None => reason,
+            };
             response.set_error(&reason);
         }
 
         response
     }
 
+    /// Restore the modules targeted by the updates as they were installed before these updates:
+    /// removing the newly installed modules and re-installing the previous versions.
+    async fn rollback(
+        plugin: &ExternalPluginCommand,
+        installed_modules: &[SoftwareModule],
+        updates: &[SoftwareModuleUpdate],
+        logger: &mut BufWriter<File>,
+        download_path: &Path,
+    ) -> Vec<SoftwareError> {
+        let _ = logger
+            .write_all(format!("----- Rolling back the {} updates\n", plugin.name).as_bytes())
+            .await;
+
+        let current_modules = match plugin.list(logger).await {
+            Ok(modules) => modules,
+            Err(err) => return vec![err],
+        };
+        let find = |modules: &[SoftwareModule], name: &str| {
+            modules.iter().find(|module| module.name == name).cloned()
+        };
+
+        let mut reverts = Vec::new();
+        for update in updates {
+            let name = update.module().name.as_str();
+            match (find(installed_modules, name), find(&current_modules, name)) {
+                (None, Some(installed)) => reverts.push(SoftwareModuleUpdate::remove(installed)),
+                (Some(previous), None) => reverts.push(SoftwareModuleUpdate::install(previous)),
+                (Some(previous), Some(installed)) if previous.version != installed.version => {
+                    reverts.push(SoftwareModuleUpdate::install(previous))
+                }
+                _ => {}
+            }
+        }
+
+        if reverts.is_empty() {
+            return vec![];
+        }
+        plugin.apply_all(reverts, logger, download_path).await
+    }
+
+    fn abortable(&self, plugin: &ExternalPluginCommand) -> ExternalPluginCommand {
+        match &self.abort_signal {
+            Some(abort_signal) => plugin.clone().with_abort_signal(abort_signal.clone()),
+            None => plugin.clone(),
+        }
+    }
+
     fn error_message(log_file: &Path, error_count: i32) -> Option<String> {
         if error_count > 0 {
             let reason = if error_count == 1 {
@@ -403,6 +403,8 @@ mod tests {
             name: name.into(),
             path: dummy_plugin_path.clone(),
             sudo: None,
+            timeout: None,
+            abort_signal: None,
         };
         (plugin, dummy_plugin_path)
     }
@@ -1,8 +1,12 @@
 #[cfg(test)]
 mod tests {
 
-    use plugin_sm::plugin_manager::{ExternalPlugins, Plugins};
-    use std::{fs::File, path::PathBuf, str::FromStr};
+    use plugin_sm::log_file::LogFile;
+    use plugin_sm::plugin_manager::{ExternalPlugins, PluginTimeouts, Plugins};
+    use std::os::unix::fs::PermissionsExt;
+    use std::path::Path;
+    use std::{collections::HashMap, fs::File, path::PathBuf, str::FromStr, time::Duration};
+    use tedge_api::{Jsonify, OperationStatus, SoftwareUpdateRequest};
     use tempfile::NamedTempFile;
 
     #[test]
@@ -162,6 +166,116 @@ mod tests {
         assert_eq!(plugins.default().unwrap().name, plugin_name);
     }
 
+    #[test]
+    fn plugin_timeouts_are_given_per_software_type() {
+        let plugin_dir = tempfile::tempdir().unwrap();
+        let plugin1 = create_some_plugin_in(&plugin_dir);
+        let _res = std::fs::copy(get_dummy_plugin_path(), plugin1.path());
+        let plugin_name1 = plugin1
+            .path()
+            .file_name()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_owned();
+        let (_, _path) = plugin1.keep().unwrap();
+
+        let plugin2 = create_some_plugin_in(&plugin_dir);
+        let _res = std::fs::copy(get_dummy_plugin_path(), plugin2.path());
+        let plugin_name2 = plugin2
+            .path()
+            .file_name()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_owned();
+        let (_, _path) = plugin2.keep().unwrap();
+
+        let mut plugins = ExternalPlugins::open(plugin_dir.into_path(), None, None).unwrap();
+        plugins.update_timeouts(PluginTimeouts {
+            default: Some(Duration::from_secs(3600)),
+            by_software_type: HashMap::from([(plugin_name2.clone(), None)]),
+        });
+
+        assert_eq!(
+            plugins.by_software_type(&plugin_name1).unwrap().timeout,
+            Some(Duration::from_secs(3600))
+        );
+        assert_eq!(
+            plugins.by_software_type(&plugin_name2).unwrap().timeout,
+            None
+        );
+
+        // The timeouts are kept when the plugins are reloaded
+        plugins.load().unwrap();
+        assert_eq!(
+            plugins.by_software_type(&plugin_name1).unwrap().timeout,
+            Some(Duration::from_secs(3600))
+        );
+    }
+
+    #[tokio::test]
+    async fn failed_transactional_updates_are_rolled_back() {
+        let plugin_dir = tempfile::tempdir().unwrap();
+        let db_dir = tempfile::tempdir().unwrap();
+        let db = db_dir.path().join("installed");
+        std::fs::write(&db, "curl\t7.0\nvim\t8.0\n").unwrap();
+        create_fake_plugin_in(&plugin_dir, "fake", &db);
+
+        let mut plugins = ExternalPlugins::open(plugin_dir.path(), None, None).unwrap();
+        plugins.update_transactional(true);
+
+        // Update curl, remove vim, install jq and then fail to install broken
+        let request = SoftwareUpdateRequest::from_json(
+            r#"{"id":"1","updateList":[{"type":"fake","modules":[
+                {"name":"curl","version":"7.1","action":"install"},
+                {"name":"vim","action":"remove"},
+                {"name":"jq","version":"1.6","action":"install"},
+                {"name":"broken","version":"1.0","action":"install"}
+            ]}]}"#,
+        )
+        .unwrap();
+        let log_file = LogFile::try_new(db_dir.path().join("update.log"))
+            .await
+            .unwrap();
+        let response = plugins.process(&request, log_file, db_dir.path()).await;
+
+        assert_eq!(response.status(), OperationStatus::Failed);
+        assert!(response.error().unwrap().contains("rolled back"));
+
+        // All the modules are restored as they were before the update
+        let installed = std::fs::read_to_string(&db).unwrap();
+        let mut installed: Vec<&str> = installed.lines().collect();
+        installed.sort_unstable();
+        assert_eq!(installed, vec!["curl\t7.0", "vim\t8.0"]);
+    }
+
+    /// A plugin managing modules listed in a file, which fails to install the `broken` module.
+    fn create_fake_plugin_in(dir: &tempfile::TempDir, name: &str, db: &Path) {
+        let script = format!(
+            r#"#!/bin/sh
+db="{}"
+case "$1" in
+    list) cat "$db" ;;
+    prepare|finalize) ;;
+    update-list) exit 1 ;;
+    install)
+        [ "$2" = "broken" ] && exit 2
+        awk -F'\t' -v m="$2" '$1 != m' "$db" > "$db.tmp"
+        printf '%s\t%s\n' "$2" "$4" >> "$db.tmp"
+        mv "$db.tmp" "$db" ;;
+    remove)
+        awk -F'\t' -v m="$2" '$1 != m' "$db" > "$db.tmp"
+        mv "$db.tmp" "$db" ;;
+esac
+"#,
+            db.display()
+        );
+        let plugin_path = dir.path().join(name);
+        std::fs::write(&plugin_path, script).unwrap();
+        std::fs::set_permissions(&plugin_path, std::fs::Permissions::from_mode(0o755)).unwrap();
+    }
+
     #[test]
     fn invalid_default_plugin_pass_through() -> anyhow::Result<()> {
         let plugin_dir = tempfile::tempdir().unwrap();
@@ -46,9 +46,18 @@ impl ConfigKey {
             config_key!(C8yUrlSetting),
             config_key!(C8yRootCertPathSetting),
             config_key!(C8ySmartRestTemplates),
+            config_key!(C8yCommandEnableSetting),
+            config_key!(C8yCommandTimeoutSetting),
+            config_key!(C8yCommandUserSetting),
+            config_key!(C8yAvailabilityIntervalSetting),
             config_key!(AzureUrlSetting),
             config_key!(AzureRootCertPathSetting),
             config_key!(AzureMapperTimestamp),
+            config_key!(AzureMapperForwardTopicSetting),
+            config_key!(AwsUrlSetting),
+            config_key!(AwsRootCertPathSetting),
+            config_key!(AwsMapperTimestamp),
+            config_key!(AwsTopicPrefixSetting),
             config_key!(MqttBindAddressSetting),
             config_key!(HttpBindAddressSetting),
             config_key!(MqttPortSetting),
@@ -59,7 +68,21 @@ impl ConfigKey {
             config_key!(MqttExternalCAPathSetting),
             config_key!(MqttExternalCertfileSetting),
             config_key!(MqttExternalKeyfileSetting),
+            config_key!(MqttClientCAFileSetting),
+            config_key!(MqttClientCAPathSetting),
+            config_key!(MqttClientCertfileSetting),
+            config_key!(MqttClientKeyfileSetting),
+            config_key!(MqttClientUsernameSetting),
+            config_key!(MqttClientPasswordFileSetting),
+            config_key!(MqttClientQueueCapacitySetting),
+            config_key!(MqttClientOverflowPolicySetting),
             config_key!(SoftwarePluginDefaultSetting),
+            config_key!(SoftwarePluginTimeoutSetting),
+            config_key!(SoftwarePluginTimeoutsSetting),
+            config_key!(SoftwareUpdateTransactionalSetting),
+            config_key!(FirmwareInstallCommandSetting),
+            config_key!(MapperOutboxMaxSizeSetting),
+            config_key!(MapperOutboxEvictionSetting),
             config_key!(TmpPathSetting),
             config_key!(LogPathSetting),
             config_key!(RunPathSetting),
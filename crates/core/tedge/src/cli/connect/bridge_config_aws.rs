@@ -0,0 +1,131 @@
+use crate::cli::connect::BridgeConfig;
+use tedge_config::{ConnectUrl, FilePath};
+
+#[derive(Debug, Eq, PartialEq)]
+pub struct BridgeConfigAwsParams {
+    pub connect_url: ConnectUrl,
+    pub mqtt_tls_port: u16,
+    pub config_file: String,
+    pub remote_clientid: String,
+    pub bridge_root_cert_path: FilePath,
+    pub bridge_certfile: FilePath,
+    pub bridge_keyfile: FilePath,
+    pub topic_prefix: String,
+}
+
+impl From<BridgeConfigAwsParams> for BridgeConfig {
+    fn from(params: BridgeConfigAwsParams) -> Self {
+        let BridgeConfigAwsParams {
+            connect_url,
+            mqtt_tls_port,
+            config_file,
+            bridge_root_cert_path,
+            remote_clientid,
+            bridge_certfile,
+            bridge_keyfile,
+            topic_prefix,
+        } = params;
+
+        let address = format!("{}:{}", connect_url.as_str(), mqtt_tls_port);
+
+        // The measurements, events and alarms
+        let pub_msg_topic = format!(
+            "td/# out 1 aws/ {}/{}/",
+            topic_prefix.trim_end_matches('/'),
+            remote_clientid
+        );
+
+        // The device shadow: the topics are listed one by one,
+        // to avoid to receive back the updates sent by the device.
+        let shadow_topic = |topic: &str, direction: &str| {
+            format!(
+                "shadow/{topic} {direction} 1 aws/ $aws/things/{}/",
+                remote_clientid
+            )
+        };
+
+        Self {
+            cloud_name: "aws".into(),
+            config_file,
+            connection: "edge_to_aws".into(),
+            address,
+            remote_username: None,
+            bridge_root_cert_path,
+            remote_clientid: remote_clientid.clone(),
+            local_clientid: "Aws".into(),
+            bridge_certfile,
+            bridge_keyfile,
+            use_mapper: true,
+            use_agent: false,
+            try_private: false,
+            start_type: "automatic".into(),
+            clean_session: false,
+            notifications: true,
+            notifications_local_only: true,
+            notification_topic: "tedge/health/mosquitto-aws-bridge".into(),
+            bridge_attempt_unsubscribe: false,
+            topics: vec![
+                pub_msg_topic,
+                shadow_topic("get", "out"),
+                shadow_topic("get/accepted", "in"),
+                shadow_topic("get/rejected", "in"),
+                shadow_topic("update", "out"),
+                shadow_topic("update/accepted", "in"),
+                shadow_topic("update/rejected", "in"),
+            ],
+        }
+    }
+}
+
+#[test]
+fn test_bridge_config_from_aws_params() -> anyhow::Result<()> {
+    use std::convert::TryFrom;
+
+    let params = BridgeConfigAwsParams {
+        connect_url: ConnectUrl::try_from("test.test.io")?,
+        mqtt_tls_port: 8883,
+        config_file: "aws-bridge.conf".into(),
+        remote_clientid: "alpha".into(),
+        bridge_root_cert_path: "./test_root.pem".into(),
+        bridge_certfile: "./test-certificate.pem".into(),
+        bridge_keyfile: "./test-private-key.pem".into(),
+        topic_prefix: "thinedge".into(),
+    };
+
+    let bridge = BridgeConfig::from(params);
+
+    let expected = BridgeConfig {
+        cloud_name: "aws".into(),
+        config_file: "aws-bridge.conf".to_string(),
+        connection: "edge_to_aws".into(),
+        address: "test.test.io:8883".into(),
+        remote_username: None,
+        bridge_root_cert_path: "./test_root.pem".into(),
+        remote_clientid: "alpha".into(),
+        local_clientid: "Aws".into(),
+        bridge_certfile: "./test-certificate.pem".into(),
+        bridge_keyfile: "./test-private-key.pem".into(),
+        use_mapper: true,
+        use_agent: false,
+        topics: vec![
+            r##"td/# out 1 aws/ thinedge/alpha/"##.into(),
+            r#"shadow/get out 1 aws/ $aws/things/alpha/"#.into(),
+            r#"shadow/get/accepted in 1 aws/ $aws/things/alpha/"#.into(),
+            r#"shadow/get/rejected in 1 aws/ $aws/things/alpha/"#.into(),
+            r#"shadow/update out 1 aws/ $aws/things/alpha/"#.into(),
+            r#"shadow/update/accepted in 1 aws/ $aws/things/alpha/"#.into(),
+            r#"shadow/update/rejected in 1 aws/ $aws/things/alpha/"#.into(),
+        ],
+        try_private: false,
+        start_type: "automatic".into(),
+        clean_session: false,
+        notifications: true,
+        notifications_local_only: true,
+        notification_topic: "tedge/health/mosquitto-aws-bridge".into(),
+        bridge_attempt_unsubscribe: false,
+    };
+
+    assert_eq!(bridge, expected);
+
+    Ok(())
+}
@@ -30,9 +30,9 @@ impl From<BridgeConfigAzureParams> for BridgeConfig {
             connect_url.as_str(),
             remote_clientid
         );
-        let pub_msg_topic = format!("messages/events/ out 1 az/ devices/{}/", remote_clientid);
+        let pub_msg_topic = format!("messages/events/# out 1 az/ devices/{}/", remote_clientid);
         let sub_msg_topic = format!(
-            "messages/devicebound/# out 1 az/ devices/{}/",
+            "messages/devicebound/# in 1 az/ devices/{}/",
             remote_clientid
         );
         Self {
@@ -59,7 +59,11 @@ impl From<BridgeConfigAzureParams> for BridgeConfig {
                 pub_msg_topic,
                 sub_msg_topic,
                 r##"twin/res/# in 1 az/ $iothub/"##.into(),
-                r#"twin/GET/?$rid=1 out 1 az/ $iothub/"#.into(),
+                r##"twin/GET/# out 1 az/ $iothub/"##.into(),
+                r##"twin/PATCH/properties/desired/# in 1 az/ $iothub/"##.into(),
+                r##"twin/PATCH/properties/reported/# out 1 az/ $iothub/"##.into(),
+                r##"methods/POST/# in 1 az/ $iothub/"##.into(),
+                r##"methods/res/# out 1 az/ $iothub/"##.into(),
             ],
         }
     }
@@ -95,10 +99,14 @@ fn test_bridge_config_from_azure_params() -> anyhow::Result<()> {
         use_mapper: true,
         use_agent: false,
         topics: vec![
-            r#"messages/events/ out 1 az/ devices/alpha/"#.into(),
-            r##"messages/devicebound/# out 1 az/ devices/alpha/"##.into(),
+            r##"messages/events/# out 1 az/ devices/alpha/"##.into(),
+            r##"messages/devicebound/# in 1 az/ devices/alpha/"##.into(),
             r##"twin/res/# in 1 az/ $iothub/"##.into(),
-            r#"twin/GET/?$rid=1 out 1 az/ $iothub/"#.into(),
+            r##"twin/GET/# out 1 az/ $iothub/"##.into(),
+            r##"twin/PATCH/properties/desired/# in 1 az/ $iothub/"##.into(),
+            r##"twin/PATCH/properties/reported/# out 1 az/ $iothub/"##.into(),
+            r##"methods/POST/# in 1 az/ $iothub/"##.into(),
+            r##"methods/res/# out 1 az/ $iothub/"##.into(),
         ],
         try_private: false,
         start_type: "automatic".into(),
@@ -6,12 +6,14 @@ use std::sync::Arc;
 use std::time::Duration;
 use tedge_config::system_services::*;
 use tedge_config::*;
+use tedge_mqtt_config::MqttConfigExt;
 use tedge_utils::paths::{create_directories, ok_if_not_found, DraftFile};
 use which::which;
 
 const WAIT_FOR_CHECK_SECONDS: u64 = 2;
 const C8Y_CONFIG_FILENAME: &str = "c8y-bridge.conf";
 const AZURE_CONFIG_FILENAME: &str = "az-bridge.conf";
+const AWS_CONFIG_FILENAME: &str = "aws-bridge.conf";
 pub(crate) const RESPONSE_TIMEOUT: Duration = Duration::from_secs(10);
 pub(crate) const CONNECTION_TIMEOUT: Duration = Duration::from_secs(60);
 const MOSQUITTO_RESTART_TIMEOUT_SECONDS: u64 = 5;
@@ -35,6 +37,7 @@ pub enum DeviceStatus {
 #[derive(Debug)]
 pub enum Cloud {
     Azure,
+    Aws,
     C8y,
 }
 
@@ -42,6 +45,7 @@ impl Cloud {
     fn dependent_mapper_service(&self) -> SystemService {
         match self {
             Cloud::Azure => SystemService::TEdgeMapperAz,
+            Cloud::Aws => SystemService::TEdgeMapperAws,
             Cloud::C8y => SystemService::TEdgeMapperC8y,
         }
     }
@@ -51,6 +55,7 @@ impl Cloud {
     fn as_str(&self) -> &'static str {
         match self {
             Self::Azure => "Azure",
+            Self::Aws => "AWS",
             Self::C8y => "Cumulocity",
         }
     }
@@ -95,6 +100,7 @@ impl Command for ConnectCommand {
         // XXX: Do we really need to persist the defaults?
         match self.cloud {
             Cloud::Azure => assign_default(&mut config, AzureRootCertPathSetting)?,
+            Cloud::Aws => assign_default(&mut config, AwsRootCertPathSetting)?,
             Cloud::C8y => assign_default(&mut config, C8yRootCertPathSetting)?,
         }
         let bridge_config = self.bridge_config(&config)?;
@@ -163,11 +169,7 @@ impl Command for ConnectCommand {
         }
 
         if let Cloud::C8y = self.cloud {
-            check_connected_c8y_tenant_as_configured(
-                &config.query_string(C8yUrlSetting)?,
-                config.query(MqttPortSetting)?.into(),
-                config.query(MqttBindAddressSetting)?.to_string(),
-            );
+            check_connected_c8y_tenant_as_configured(&config.query_string(C8yUrlSetting)?, &config);
             enable_software_management(&bridge_config, self.service_manager.as_ref());
         }
 
@@ -191,6 +193,20 @@ impl ConnectCommand {
 
                 Ok(BridgeConfig::from(params))
             }
+            Cloud::Aws => {
+                let params = BridgeConfigAwsParams {
+                    connect_url: config.query(AwsUrlSetting)?,
+                    mqtt_tls_port: MQTT_TLS_PORT,
+                    config_file: AWS_CONFIG_FILENAME.into(),
+                    bridge_root_cert_path: config.query(AwsRootCertPathSetting)?,
+                    remote_clientid: config.query(DeviceIdSetting)?,
+                    bridge_certfile: config.query(DeviceCertPathSetting)?,
+                    bridge_keyfile: config.query(DeviceKeyPathSetting)?,
+                    topic_prefix: config.query(AwsTopicPrefixSetting)?,
+                };
+
+                Ok(BridgeConfig::from(params))
+            }
             Cloud::C8y => {
                 let params = BridgeConfigC8yParams {
                     connect_url: config.query(C8yUrlSetting)?,
@@ -209,15 +225,13 @@ impl ConnectCommand {
     }
 
     fn check_connection(&self, config: &TEdgeConfig) -> Result<DeviceStatus, ConnectError> {
-        let port = config.query(MqttPortSetting)?.into();
-        let host = config.query(MqttBindAddressSetting)?.to_string();
-
         println!(
             "Sending packets to check connection. This may take up to {} seconds.\n",
             WAIT_FOR_CHECK_SECONDS
         );
         match self.cloud {
-            Cloud::Azure => check_device_status_azure(port, host),
+            Cloud::Azure => check_device_status_azure(config),
+            Cloud::Aws => check_device_status_aws(config),
             Cloud::C8y => check_device_status_c8y(config),
         }
     }
@@ -246,6 +260,18 @@ where
     Ok(())
 }
 
+/// The options to connect the local MQTT broker, over TLS and with credentials when configured.
+pub(crate) fn local_mqtt_options(
+    tedge_config: &TEdgeConfig,
+    client_id: &str,
+) -> Result<MqttOptions, ConnectError> {
+    Ok(tedge_config
+        .mqtt_config()?
+        .with_session_name(client_id)
+        .with_clean_session(true)
+        .mqtt_options()?)
+}
+
 // Check the connection by using the jwt token retrieval over the mqtt.
 // If successful in getting the jwt token '71,xxxxx', the connection is established.
 fn check_device_status_c8y(tedge_config: &TEdgeConfig) -> Result<DeviceStatus, ConnectError> {
@@ -253,12 +279,7 @@ fn check_device_status_c8y(tedge_config: &TEdgeConfig) -> Result<DeviceStatus, C
     const C8Y_TOPIC_BUILTIN_JWT_TOKEN_UPSTREAM: &str = "c8y/s/uat";
     const CLIENT_ID: &str = "check_connection_c8y";
 
-    let mut options = MqttOptions::new(
-        CLIENT_ID,
-        tedge_config.query(MqttBindAddressSetting)?.to_string(),
-        tedge_config.query(MqttPortSetting)?.into(),
-    );
-
+    let mut options = local_mqtt_options(tedge_config, CLIENT_ID)?;
     options.set_keep_alive(RESPONSE_TIMEOUT);
     options.set_connection_timeout(CONNECTION_TIMEOUT.as_secs());
 
@@ -317,31 +338,68 @@ fn check_device_status_c8y(tedge_config: &TEdgeConfig) -> Result<DeviceStatus, C
 // Empty payload will be published to az/$iothub/twin/GET/?$rid=1, here 1 is request ID.
 // The result will be published by the iothub on the az/$iothub/twin/res/{status}/?$rid={request id}.
 // Here if the status is 200 then it's success.
-fn check_device_status_azure(port: u16, host: String) -> Result<DeviceStatus, ConnectError> {
+fn check_device_status_azure(tedge_config: &TEdgeConfig) -> Result<DeviceStatus, ConnectError> {
     const AZURE_TOPIC_DEVICE_TWIN_DOWNSTREAM: &str = r##"az/twin/res/#"##;
     const AZURE_TOPIC_DEVICE_TWIN_UPSTREAM: &str = r#"az/twin/GET/?$rid=1"#;
     const CLIENT_ID: &str = "check_connection_az";
-    const REGISTRATION_PAYLOAD: &[u8] = b"";
     const REGISTRATION_OK: &str = "200";
 
-    let mut options = MqttOptions::new(CLIENT_ID, host, port);
+    check_device_status_with_request(
+        tedge_config,
+        CLIENT_ID,
+        &[AZURE_TOPIC_DEVICE_TWIN_DOWNSTREAM],
+        AZURE_TOPIC_DEVICE_TWIN_UPSTREAM,
+        |response| response.topic.contains(REGISTRATION_OK),
+    )
+}
+
+// Here we check the connection by requesting the device shadow.
+// The mqtt client subscribes to the aws/shadow/get/accepted and aws/shadow/get/rejected topics,
+// then an empty payload is published to aws/shadow/get.
+// Any response proves that the bridge is connected: the shadow is rejected if the thing has none yet.
+fn check_device_status_aws(tedge_config: &TEdgeConfig) -> Result<DeviceStatus, ConnectError> {
+    const AWS_TOPIC_SHADOW_GET_ACCEPTED: &str = "aws/shadow/get/accepted";
+    const AWS_TOPIC_SHADOW_GET_REJECTED: &str = "aws/shadow/get/rejected";
+    const AWS_TOPIC_SHADOW_GET: &str = "aws/shadow/get";
+    const CLIENT_ID: &str = "check_connection_aws";
+
+    check_device_status_with_request(
+        tedge_config,
+        CLIENT_ID,
+        &[AWS_TOPIC_SHADOW_GET_ACCEPTED, AWS_TOPIC_SHADOW_GET_REJECTED],
+        AWS_TOPIC_SHADOW_GET,
+        |_response| true,
+    )
+}
+
+// Publish an empty request to the cloud, once subscribed to all the response topics,
+// and check the first response.
+fn check_device_status_with_request(
+    tedge_config: &TEdgeConfig,
+    client_id: &str,
+    response_topics: &[&str],
+    request_topic: &str,
+    is_success: impl Fn(&rumqttc::Publish) -> bool,
+) -> Result<DeviceStatus, ConnectError> {
+    let mut options = local_mqtt_options(tedge_config, client_id)?;
     options.set_keep_alive(RESPONSE_TIMEOUT);
 
     let (mut client, mut connection) = rumqttc::Client::new(options, 10);
     let mut acknowledged = false;
 
-    client.subscribe(AZURE_TOPIC_DEVICE_TWIN_DOWNSTREAM, AtLeastOnce)?;
+    for topic in response_topics {
+        client.subscribe(*topic, AtLeastOnce)?;
+    }
+    let mut pending_subscriptions = response_topics.len();
 
     for event in connection.iter() {
         match event {
             Ok(Event::Incoming(Packet::SubAck(_))) => {
-                // We are ready to get the response, hence send the request
-                client.publish(
-                    AZURE_TOPIC_DEVICE_TWIN_UPSTREAM,
-                    AtLeastOnce,
-                    false,
-                    REGISTRATION_PAYLOAD,
-                )?;
+                pending_subscriptions -= 1;
+                if pending_subscriptions == 0 {
+                    // We are ready to get the response, hence send the request
+                    client.publish(request_topic, AtLeastOnce, false, Vec::new())?;
+                }
             }
             Ok(Event::Incoming(Packet::PubAck(_))) => {
                 // The request has been sent
@@ -349,7 +407,7 @@ fn check_device_status_azure(port: u16, host: String) -> Result<DeviceStatus, Co
             }
             Ok(Event::Incoming(Packet::Publish(response))) => {
                 // We got a response
-                if response.topic.contains(REGISTRATION_OK) {
+                if is_success(&response) {
                     println!("Received expected response message, connection check is successful.");
                     return Ok(DeviceStatus::AlreadyExists);
                 } else {
@@ -551,8 +609,8 @@ fn get_common_mosquitto_config_file_path(
 }
 
 // To confirm the connected c8y tenant is the one that user configured.
-fn check_connected_c8y_tenant_as_configured(configured_url: &str, port: u16, host: String) {
-    match get_connected_c8y_url(port, host) {
+fn check_connected_c8y_tenant_as_configured(configured_url: &str, tedge_config: &TEdgeConfig) {
+    match get_connected_c8y_url(tedge_config) {
         Ok(url) if url == configured_url => {}
         Ok(url) => println!(
             "Warning: Connecting to {}, but the configured URL is {}.\n\
@@ -15,6 +15,9 @@ pub enum ConnectError {
     #[error(transparent)]
     MqttClient(#[from] rumqttc::ClientError),
 
+    #[error(transparent)]
+    MqttConfig(#[from] mqtt_channel::MqttError),
+
     #[error(transparent)]
     PathsError(#[from] tedge_utils::paths::PathsError),
 
@@ -1,13 +1,14 @@
-use crate::cli::connect::{ConnectError, CONNECTION_TIMEOUT, RESPONSE_TIMEOUT};
+use crate::cli::connect::{local_mqtt_options, ConnectError, CONNECTION_TIMEOUT, RESPONSE_TIMEOUT};
 use rumqttc::QoS::AtLeastOnce;
-use rumqttc::{Event, Incoming, MqttOptions, Outgoing, Packet};
+use rumqttc::{Event, Incoming, Outgoing, Packet};
+use tedge_config::TEdgeConfig;
 
-pub(crate) fn get_connected_c8y_url(port: u16, host: String) -> Result<String, ConnectError> {
+pub(crate) fn get_connected_c8y_url(tedge_config: &TEdgeConfig) -> Result<String, ConnectError> {
     const C8Y_TOPIC_BUILTIN_JWT_TOKEN_UPSTREAM: &str = "c8y/s/uat";
     const C8Y_TOPIC_BUILTIN_JWT_TOKEN_DOWNSTREAM: &str = "c8y/s/dat";
     const CLIENT_ID: &str = "get_jwt_token_c8y";
 
-    let mut options = MqttOptions::new(CLIENT_ID, host, port);
+    let mut options = local_mqtt_options(tedge_config, CLIENT_ID)?;
     options.set_keep_alive(RESPONSE_TIMEOUT);
     options.set_connection_timeout(CONNECTION_TIMEOUT.as_secs());
 
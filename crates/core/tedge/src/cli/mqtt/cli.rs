@@ -3,6 +3,7 @@ use crate::command::{BuildCommand, BuildContext, Command};
 use rumqttc::QoS;
 use std::time::Duration;
 use tedge_config::*;
+use tedge_mqtt_config::MqttConfigExt;
 
 const PUB_CLIENT_PREFIX: &str = "tedge-pub";
 const SUB_CLIENT_PREFIX: &str = "tedge-sub";
@@ -39,11 +40,7 @@ pub enum TEdgeMqttCli {
 
 impl BuildCommand for TEdgeMqttCli {
     fn build_command(self, context: BuildContext) -> Result<Box<dyn Command>, crate::ConfigError> {
-        let port = context.config_repository.load()?.query(MqttPortSetting)?;
-        let host = context
-            .config_repository
-            .load()?
-            .query(MqttBindAddressSetting)?;
+        let mqtt_config = context.config_repository.load()?.mqtt_config()?;
         let cmd = {
             match self {
                 TEdgeMqttCli::Pub {
@@ -52,8 +49,7 @@ impl BuildCommand for TEdgeMqttCli {
                     qos,
                     retain,
                 } => MqttPublishCommand {
-                    host: host.to_string(),
-                    port: port.into(),
+                    mqtt_config,
                     topic,
                     message,
                     qos,
@@ -67,8 +63,7 @@ impl BuildCommand for TEdgeMqttCli {
                     qos,
                     hide_topic,
                 } => MqttSubscribeCommand {
-                    host: host.to_string(),
-                    port: port.into(),
+                    mqtt_config,
                     topic,
                     qos,
                     hide_topic,
@@ -9,6 +9,9 @@ pub enum MqttError {
     #[error("Received message is not UTF-8 format")]
     FromUtf8(#[from] std::str::Utf8Error),
 
+    #[error("Invalid MQTT configuration")]
+    InvalidConfig(#[from] mqtt_channel::MqttError),
+
     #[error("The input QoS should be 0, 1, or 2")]
     InvalidQoS,
 
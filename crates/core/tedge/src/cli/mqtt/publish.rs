@@ -1,14 +1,13 @@
 use crate::cli::mqtt::MqttError;
 use crate::command::Command;
 use rumqttc::QoS::{AtLeastOnce, AtMostOnce, ExactlyOnce};
-use rumqttc::{Event, Incoming, MqttOptions, Outgoing, Packet};
+use rumqttc::{Event, Incoming, Outgoing, Packet};
 use std::time::Duration;
 
 const DEFAULT_QUEUE_CAPACITY: usize = 10;
 
 pub struct MqttPublishCommand {
-    pub host: String,
-    pub port: u16,
+    pub mqtt_config: mqtt_channel::Config,
     pub topic: String,
     pub message: String,
     pub qos: rumqttc::QoS,
@@ -31,8 +30,12 @@ impl Command for MqttPublishCommand {
 }
 
 fn publish(cmd: &MqttPublishCommand) -> Result<(), MqttError> {
-    let mut options = MqttOptions::new(cmd.client_id.as_str(), &cmd.host, cmd.port);
-    options.set_clean_session(true);
+    let options = cmd
+        .mqtt_config
+        .clone()
+        .with_session_name(&cmd.client_id)
+        .with_clean_session(true)
+        .mqtt_options()?;
 
     let payload = cmd.message.as_bytes();
 
@@ -1,37 +1,50 @@
 use crate::{
     error::AgentError,
+    firmware_operation_handler::firmware_operation,
     http_rest,
     restart_operation_handler::restart_operation,
     state::{
-        AgentStateRepository, RestartOperationStatus, SoftwareOperationVariants, State,
-        StateRepository, StateStatus,
+        AgentStateRepository, FirmwareOperationStatus, PendingOperation, RestartOperationStatus,
+        SoftwareOperationVariants, State, StateRepository, StateStatus,
     },
 };
 use flockfile::{check_another_instance_is_not_running, Flockfile};
+use futures::{
+    channel::mpsc,
+    future::{BoxFuture, FutureExt},
+    stream::FuturesUnordered,
+};
+use logged_command::{abort_channel, AbortHandle, AbortSignal};
 use tedge_api::{
-    control_filter_topic, software_filter_topic, Jsonify, OperationStatus, RestartOperationRequest,
-    RestartOperationResponse, SoftwareError, SoftwareListRequest, SoftwareListResponse,
-    SoftwareRequestResponse, SoftwareType, SoftwareUpdateRequest, SoftwareUpdateResponse,
+    control_filter_topic, firmware_filter_topic, software_filter_topic, CancelOperationRequest,
+    FirmwareUpdateRequest, FirmwareUpdateResponse, Jsonify, OperationStatus,
+    RestartOperationRequest, RestartOperationResponse, SoftwareError, SoftwareListRequest,
+    SoftwareListResponse, SoftwareRequestResponse, SoftwareType, SoftwareUpdateRequest,
+    SoftwareUpdateResponse,
 };
 
 use mqtt_channel::{Connection, Message, PubChannel, StreamExt, SubChannel, Topic, TopicFilter};
 use plugin_sm::{
     operation_logs::{LogKind, OperationLogs},
-    plugin_manager::{ExternalPlugins, Plugins},
+    plugin_manager::{ExternalPlugins, PluginTimeouts, Plugins},
 };
 
 use crate::http_rest::HttpConfig;
 use std::process::Command;
+use std::time::Duration;
 use std::{convert::TryInto, fmt::Debug, path::PathBuf, sync::Arc};
-use tedge_api::health::{health_check_topics, send_health_status};
+use tedge_api::health::{health_check_topics, send_health_status, with_health_status};
 use tedge_config::{
     system_services::SystemConfig, ConfigRepository, ConfigSettingAccessor,
-    ConfigSettingAccessorStringExt, HttpBindAddressSetting, HttpPortSetting, LogPathSetting,
-    MqttBindAddressSetting, MqttPortSetting, RunPathSetting, SoftwarePluginDefaultSetting,
-    TEdgeConfigLocation, TmpPathSetting, DEFAULT_LOG_PATH, DEFAULT_RUN_PATH, DEFAULT_TMP_PATH,
+    ConfigSettingAccessorStringExt, FirmwareInstallCommandSetting, HttpBindAddressSetting,
+    HttpPortSetting, LogPathSetting, RunPathSetting, Seconds, SoftwarePluginDefaultSetting,
+    SoftwarePluginTimeoutSetting, SoftwarePluginTimeoutsSetting,
+    SoftwareUpdateTransactionalSetting, TEdgeConfigLocation, TmpPathSetting, DEFAULT_LOG_PATH,
+    DEFAULT_RUN_PATH, DEFAULT_TMP_PATH,
 };
+use tedge_mqtt_config::MqttConfigExt;
 use tedge_utils::file::create_directory_with_user_group;
-use tokio::sync::Mutex;
+use tokio::sync::RwLock;
 use tracing::{debug, error, info, instrument, warn};
 
 use std::path::Path;
@@ -55,10 +68,14 @@ pub struct SmAgentConfig {
     pub request_topic_update: Topic,
     pub request_topics: TopicFilter,
     pub request_topic_restart: Topic,
+    pub request_topic_cancel_update: Topic,
+    pub request_topic_cancel_restart: Topic,
+    pub request_topic_firmware_update: Topic,
     pub response_topic_health: Topic,
     pub response_topic_list: Topic,
     pub response_topic_update: Topic,
     pub response_topic_restart: Topic,
+    pub response_topic_firmware_update: Topic,
     pub sm_home: PathBuf,
     pub log_dir: PathBuf,
     pub run_dir: PathBuf,
@@ -74,9 +91,13 @@ impl Default for SmAgentConfig {
 
         let mqtt_config = mqtt_channel::Config::default();
 
-        let mut request_topics: TopicFilter = vec![software_filter_topic(), control_filter_topic()]
-            .try_into()
-            .expect("Invalid topic filter");
+        let mut request_topics: TopicFilter = vec![
+            software_filter_topic(),
+            control_filter_topic(),
+            firmware_filter_topic(),
+        ]
+        .try_into()
+        .expect("Invalid topic filter");
 
         let request_topics_health: TopicFilter = health_check_topics("tedge-agent");
 
@@ -102,6 +123,19 @@ impl Default for SmAgentConfig {
         let response_topic_restart =
             Topic::new(RestartOperationResponse::topic_name()).expect("Invalid topic");
 
+        let request_topic_cancel_update =
+            Topic::new(CancelOperationRequest::software_update_topic_name())
+                .expect("Invalid topic");
+
+        let request_topic_cancel_restart =
+            Topic::new(CancelOperationRequest::restart_topic_name()).expect("Invalid topic");
+
+        let request_topic_firmware_update =
+            Topic::new(FirmwareUpdateRequest::topic_name()).expect("Invalid topic");
+
+        let response_topic_firmware_update =
+            Topic::new(FirmwareUpdateResponse::topic_name()).expect("Invalid topic");
+
         let sm_home = PathBuf::from("/etc/tedge");
 
         let log_dir = PathBuf::from(&format!("{DEFAULT_LOG_PATH}/{AGENT_LOG_PATH}"));
@@ -126,6 +160,10 @@ impl Default for SmAgentConfig {
             response_topic_update,
             request_topic_restart,
             response_topic_restart,
+            request_topic_cancel_update,
+            request_topic_cancel_restart,
+            request_topic_firmware_update,
+            response_topic_firmware_update,
             sm_home,
             log_dir,
             run_dir,
@@ -143,9 +181,8 @@ impl SmAgentConfig {
             tedge_config::TEdgeConfigRepository::new(tedge_config_location.clone());
         let tedge_config = config_repository.load()?;
 
-        let mqtt_config = mqtt_channel::Config::default()
-            .with_host(tedge_config.query(MqttBindAddressSetting)?.to_string())
-            .with_port(tedge_config.query(MqttPortSetting)?.into())
+        let mqtt_config = tedge_config
+            .mqtt_config()?
             .with_max_packet_size(10 * 1024 * 1024);
 
         let tedge_config_path = config_repository
@@ -228,6 +265,8 @@ pub struct SmAgent {
     config: SmAgentConfig,
     operation_logs: OperationLogs,
     persistence_store: AgentStateRepository,
+    abort_handle: AbortHandle,
+    abort_signal: AbortSignal,
     _flock: Flockfile,
 }
 
@@ -239,15 +278,22 @@ impl SmAgent {
         let persistence_store = AgentStateRepository::new(config.sm_home.clone());
         let operation_logs = OperationLogs::try_new(config.log_dir.clone())?;
 
-        config.mqtt_config = config
-            .mqtt_config
-            .with_session_name(name)
-            .with_subscriptions(config.request_topics.clone());
+        config.mqtt_config = with_health_status(
+            config
+                .mqtt_config
+                .with_session_name(name)
+                .with_subscriptions(config.request_topics.clone()),
+            name,
+        );
+
+        let (abort_handle, abort_signal) = abort_channel();
 
         Ok(Self {
             config,
             operation_logs,
             persistence_store,
+            abort_handle,
+            abort_signal,
             _flock: flock,
         })
     }
@@ -284,13 +330,16 @@ impl SmAgent {
         let mut mqtt = Connection::new(&self.config.mqtt_config).await?;
         let sm_plugins_path = self.config.sm_home.join(SM_PLUGINS);
 
-        let plugins = Arc::new(Mutex::new(ExternalPlugins::open(
+        let mut plugins = ExternalPlugins::open(
             &sm_plugins_path,
             get_default_plugin(&self.config.config_location)?,
             Some(SUDO.into()),
-        )?));
+        )?
+        .with_abort_signal(self.abort_signal.clone());
+        plugins.update_timeouts(get_plugin_timeouts(&self.config.config_location)?);
+        let plugins = Arc::new(RwLock::new(plugins));
 
-        if plugins.lock().await.empty() {
+        if plugins.read().await.empty() {
             warn!(
                 "{}",
                 AgentError::NoPlugins {
@@ -324,77 +373,417 @@ impl SmAgent {
         Ok(())
     }
 
-    async fn process_subscribed_messages(
-        &mut self,
+    /// Process the requests received by the agent.
+    ///
+    /// Each request is persisted until its completion, so the requests received
+    /// but not completed before a restart of the agent are processed on restart.
+    /// The software list requests, which are read-only, run concurrently with the other operations,
+    /// while the software updates, the firmware updates and the restarts are queued
+    /// and processed one after the other, in the order they have been received.
+    async fn process_subscribed_messages<R: PubChannel + Clone>(
+        &self,
         requests: &mut impl SubChannel,
-        responses: &mut impl PubChannel,
-        plugins: &Arc<Mutex<ExternalPlugins>>,
+        responses: &mut R,
+        plugins: &Arc<RwLock<ExternalPlugins>>,
     ) -> Result<(), AgentError> {
-        while let Some(message) = requests.next().await {
+        let (operation_queue, queued_operations) = mpsc::unbounded();
+        let sequential_operations =
+            self.process_operation_queue(queued_operations, responses.clone(), plugins);
+        tokio::pin!(sequential_operations);
+        let mut sequential_operations_done = false;
+        let mut concurrent_operations = FuturesUnordered::new();
+
+        let pending_operations = match self.persistence_store.load().await {
+            Ok(state) => state.pending_operations,
+            Err(_) => vec![],
+        };
+        for operation in pending_operations {
+            info!(
+                "Resuming the {:?} operation {}",
+                operation.operation, operation.id
+            );
+            self.schedule_operation(
+                operation,
+                &operation_queue,
+                &mut concurrent_operations,
+                responses,
+                plugins,
+            );
+        }
+
+        loop {
+            let message = tokio::select! {
+                message = requests.next() => message,
+                Some(()) = concurrent_operations.next(), if !concurrent_operations.is_empty() => {
+                    continue
+                }
+                () = &mut sequential_operations, if !sequential_operations_done => {
+                    sequential_operations_done = true;
+                    continue
+                }
+            };
+            let message = match message {
+                Some(message) => message,
+                None => break,
+            };
+
             debug!("Request {:?}", message);
-            match &message.topic {
+            let operation = match &message.topic {
                 topic if self.config.request_topics_health.accept_topic(topic) => {
                     send_health_status(responses, "tedge-agent").await;
+                    continue;
+                }
+
+                topic if topic == &self.config.request_topic_cancel_update => {
+                    let operation = StateStatus::Software(SoftwareOperationVariants::Update);
+                    self.cancel_operation(responses, &message, operation).await;
+                    continue;
+                }
+
+                topic if topic == &self.config.request_topic_cancel_restart => {
+                    let operation = StateStatus::Restart(RestartOperationStatus::Pending);
+                    self.cancel_operation(responses, &message, operation).await;
+                    continue;
                 }
 
                 topic if topic == &self.config.request_topic_list => {
-                    let _success = self
-                        .handle_software_list_request(
-                            responses,
-                            plugins.clone(),
-                            &self.config.response_topic_list,
-                            &message,
-                        )
-                        .await
-                        .map_err(|err| {
-                            error!("{:?}", err); // log error and discard such that the agent doesn't exit.
-                        });
+                    StateStatus::Software(SoftwareOperationVariants::List)
                 }
 
                 topic if topic == &self.config.request_topic_update => {
-                    plugins.lock().await.load()?;
-                    plugins
-                        .lock()
-                        .await
-                        .update_default(&get_default_plugin(&self.config.config_location)?)?;
-
-                    let _success = self
-                        .handle_software_update_request(
-                            responses,
-                            plugins.clone(),
-                            &self.config.response_topic_update,
-                            &message,
-                        )
-                        .await
-                        .map_err(|err| {
-                            error!("{:?}", err); // log error and discard such that the agent doesn't exit.
-                        });
+                    StateStatus::Software(SoftwareOperationVariants::Update)
                 }
 
                 topic if topic == &self.config.request_topic_restart => {
-                    let request = self
-                        .match_restart_operation_payload(responses, &message)
-                        .await?;
-                    if let Err(error) = self
-                        .handle_restart_operation(responses, &self.config.response_topic_restart)
-                        .await
-                    {
+                    StateStatus::Restart(RestartOperationStatus::Pending)
+                }
+
+                topic if topic == &self.config.request_topic_firmware_update => {
+                    StateStatus::Firmware(FirmwareOperationStatus::Update)
+                }
+
+                _ => {
+                    error!("Unknown operation. Discarded.");
+                    continue;
+                }
+            };
+
+            if let Some(operation) = self.accept_request(responses, &message, operation).await {
+                self.schedule_operation(
+                    operation,
+                    &operation_queue,
+                    &mut concurrent_operations,
+                    responses,
+                    plugins,
+                );
+            }
+        }
+
+        // Complete the operations already received
+        drop(operation_queue);
+        let running_operations = async { while concurrent_operations.next().await.is_some() {} };
+        let sequenced_operations = async {
+            if !sequential_operations_done {
+                sequential_operations.await
+            }
+        };
+        tokio::join!(running_operations, sequenced_operations);
+
+        Ok(())
+    }
+
+    /// Check a request and persist it,
+    /// so it is not lost if the agent restarts before its completion.
+    async fn accept_request(
+        &self,
+        responses: &mut impl PubChannel,
+        message: &Message,
+        operation: StateStatus,
+    ) -> Option<PendingOperation> {
+        let payload = message.payload_bytes();
+        let request_id = match operation {
+            StateStatus::Software(SoftwareOperationVariants::List) => {
+                SoftwareListRequest::from_slice(payload).map(|request| request.id)
+            }
+            StateStatus::Software(SoftwareOperationVariants::Update) => {
+                SoftwareUpdateRequest::from_slice(payload).map(|request| request.id)
+            }
+            StateStatus::Firmware(_) => {
+                FirmwareUpdateRequest::from_slice(payload).map(|request| request.id)
+            }
+            _ => RestartOperationRequest::from_slice(payload).map(|request| request.id),
+        };
+
+        match request_id {
+            Ok(id) => {
+                let operation = PendingOperation {
+                    id,
+                    operation,
+                    payload: message.payload_str().unwrap_or_default().into(),
+                };
+                if let Err(error) = self.persistence_store.enqueue(operation.clone()).await {
+                    error!(
+                        "Failed to persist the operation {}: {}",
+                        operation.id, error
+                    );
+                }
+                Some(operation)
+            }
+
+            Err(error) => {
+                error!("Parsing error: {}", error);
+                let _ = responses
+                    .publish(Message::new(
+                        &self.config.errors_topic,
+                        format!("{}", error),
+                    ))
+                    .await;
+                None
+            }
+        }
+    }
+
+    /// Cancel an operation, either still queued or in-flight.
+    ///
+    /// A queued operation is removed from the queue and reported as failed.
+    /// The plugin processes of an in-flight software update are terminated,
+    /// the update being then reported as failed. A restart cannot be cancelled once started.
+    async fn cancel_operation(
+        &self,
+        responses: &mut impl PubChannel,
+        message: &Message,
+        operation: StateStatus,
+    ) {
+        let request = match CancelOperationRequest::from_slice(message.payload_bytes()) {
+            Ok(request) => request,
+            Err(error) => {
+                error!("Parsing error: {}", error);
+                let _ = responses
+                    .publish(Message::new(
+                        &self.config.errors_topic,
+                        format!("{}", error),
+                    ))
+                    .await;
+                return;
+            }
+        };
+
+        let state = self.persistence_store.load().await.unwrap_or_default();
+        let queued_operation = state
+            .pending_operations
+            .into_iter()
+            .find(|pending| pending.id == request.id && pending.operation == operation);
+
+        if let Some(queued_operation) = queued_operation {
+            // The operation is removed from the queue only if not started meanwhile
+            match self.persistence_store.remove(&request.id).await {
+                Ok(true) => {
+                    info!("Cancelling the queued operation {}", request.id);
+                    if let Err(error) = self.publish_cancelled(responses, queued_operation).await {
                         error!("{}", error);
+                    }
+                    return;
+                }
+                Ok(false) => {}
+                Err(error) => {
+                    error!("{}", error);
+                    return;
+                }
+            }
+        }
+
+        let state = self.persistence_store.load().await.unwrap_or_default();
+        if state.operation_id.as_deref() == Some(request.id.as_str()) {
+            match state.operation {
+                Some(StateStatus::Software(SoftwareOperationVariants::Update))
+                    if operation == StateStatus::Software(SoftwareOperationVariants::Update) =>
+                {
+                    info!("Aborting the software update {}", request.id);
+                    self.abort_handle.abort();
+                }
+                _ => warn!("The operation {} cannot be cancelled", request.id),
+            }
+        } else {
+            warn!("No operation {} to cancel", request.id);
+        }
+    }
+
+    async fn publish_cancelled(
+        &self,
+        responses: &mut impl PubChannel,
+        operation: PendingOperation,
+    ) -> Result<(), AgentError> {
+        let payload = operation.payload.as_bytes();
+        match operation.operation {
+            StateStatus::Software(SoftwareOperationVariants::Update) => {
+                let request = SoftwareUpdateRequest::from_slice(payload)?;
+                let mut response = SoftwareUpdateResponse::new(&request);
+                response.set_error("Cancelled");
+                responses
+                    .publish(Message::new(
+                        &self.config.response_topic_update,
+                        response.to_bytes()?,
+                    ))
+                    .await?;
+            }
+
+            _ => {
+                let request = RestartOperationRequest::from_slice(payload)?;
+                let response =
+                    RestartOperationResponse::new(&request).with_status(OperationStatus::Failed);
+                responses
+                    .publish(Message::new(
+                        &self.config.response_topic_restart,
+                        response.to_bytes()?,
+                    ))
+                    .await?;
+            }
+        }
+
+        Ok(())
+    }
 
-                        self.persistence_store.clear().await?;
-                        let status = OperationStatus::Failed;
-                        let response = RestartOperationResponse::new(&request).with_status(status);
-                        responses
-                            .publish(Message::new(
-                                &self.config.response_topic_restart,
-                                response.to_bytes()?,
-                            ))
-                            .await?;
+    /// Start a software list right away, or queue the operations that cannot run concurrently.
+    fn schedule_operation<'a, R: PubChannel + Clone + 'a>(
+        &'a self,
+        operation: PendingOperation,
+        operation_queue: &mpsc::UnboundedSender<PendingOperation>,
+        concurrent_operations: &mut FuturesUnordered<BoxFuture<'a, ()>>,
+        responses: &R,
+        plugins: &Arc<RwLock<ExternalPlugins>>,
+    ) {
+        match operation.operation {
+            StateStatus::Software(SoftwareOperationVariants::List) => {
+                let mut responses = responses.clone();
+                let plugins = plugins.clone();
+                let message = Message::new(&self.config.request_topic_list, operation.payload);
+                concurrent_operations.push(
+                    async move {
+                        let _success = self
+                            .handle_software_list_request(
+                                &mut responses,
+                                plugins,
+                                &self.config.response_topic_list,
+                                &message,
+                            )
+                            .await
+                            .map_err(|err| {
+                                error!("{:?}", err); // log error and discard such that the agent doesn't exit.
+                            });
                     }
+                    .boxed(),
+                );
+            }
+
+            _ => {
+                debug!("Queuing the operation {}", operation.id);
+                let _ = operation_queue.unbounded_send(operation);
+            }
+        }
+    }
+
+    /// Process the software updates, firmware updates and restarts, one after the other.
+    async fn process_operation_queue(
+        &self,
+        mut operations: mpsc::UnboundedReceiver<PendingOperation>,
+        mut responses: impl PubChannel,
+        plugins: &Arc<RwLock<ExternalPlugins>>,
+    ) {
+        while let Some(operation) = operations.next().await {
+            let operation_id = operation.id.clone();
+            if let Err(error) = self
+                .process_queued_operation(operation, &mut responses, plugins)
+                .await
+            {
+                error!("{}", error);
+                let _ = self.persistence_store.remove(&operation_id).await;
+            }
+        }
+    }
+
+    /// Start a queued operation, unless cancelled while queued.
+    ///
+    /// The operation is marked as started, in one step with checking it has not been cancelled,
+    /// before anything else, so a cancel request received from now on aborts the operation.
+    async fn process_queued_operation(
+        &self,
+        operation: PendingOperation,
+        responses: &mut impl PubChannel,
+        plugins: &Arc<RwLock<ExternalPlugins>>,
+    ) -> Result<(), AgentError> {
+        let status = match operation.operation {
+            StateStatus::Restart(_) => StateStatus::Restart(RestartOperationStatus::Restarting),
+            ref status => status.clone(),
+        };
+        self.abort_handle.reset();
+        if !self.persistence_store.start(&operation.id, status).await? {
+            debug!("Skipping the cancelled operation {}", operation.id);
+            return Ok(());
+        }
+
+        match operation.operation {
+            StateStatus::Software(SoftwareOperationVariants::Update) => {
+                {
+                    let mut plugins = plugins.write().await;
+                    plugins.load()?;
+                    plugins.update_default(&get_default_plugin(&self.config.config_location)?)?;
+                    plugins.update_timeouts(get_plugin_timeouts(&self.config.config_location)?);
+                    plugins.update_transactional(is_update_transactional(
+                        &self.config.config_location,
+                    )?);
                 }
 
-                _ => error!("Unknown operation. Discarded."),
+                let message = Message::new(&self.config.request_topic_update, operation.payload);
+                let _success = self
+                    .handle_software_update_request(
+                        responses,
+                        plugins.clone(),
+                        &self.config.response_topic_update,
+                        &message,
+                    )
+                    .await
+                    .map_err(|err| {
+                        error!("{:?}", err); // log error and discard such that the agent doesn't exit.
+                    });
             }
+
+            StateStatus::Restart(_) => {
+                let message = Message::new(&self.config.request_topic_restart, operation.payload);
+                let request = self
+                    .match_restart_operation_payload(responses, &message)
+                    .await?;
+                if let Err(error) = self
+                    .handle_restart_operation(
+                        responses,
+                        &self.config.response_topic_restart,
+                        &request,
+                    )
+                    .await
+                {
+                    error!("{}", error);
+
+                    self.persistence_store.clear().await?;
+                    let status = OperationStatus::Failed;
+                    let response = RestartOperationResponse::new(&request).with_status(status);
+                    responses
+                        .publish(Message::new(
+                            &self.config.response_topic_restart,
+                            response.to_bytes()?,
+                        ))
+                        .await?;
+                }
+            }
+
+            StateStatus::Firmware(_) => {
+                let message = Message::new(
+                    &self.config.request_topic_firmware_update,
+                    operation.payload,
+                );
+                self.handle_firmware_update_request(responses, &message)
+                    .await?;
+            }
+
+            _ => error!("Unknown operation. Discarded."),
         }
 
         Ok(())
@@ -403,21 +792,12 @@ impl SmAgent {
     async fn handle_software_list_request(
         &self,
         responses: &mut impl PubChannel,
-        plugins: Arc<Mutex<ExternalPlugins>>,
+        plugins: Arc<RwLock<ExternalPlugins>>,
         response_topic: &Topic,
         message: &Message,
     ) -> Result<(), AgentError> {
         let request = match SoftwareListRequest::from_slice(message.payload_bytes()) {
-            Ok(request) => {
-                self.persistence_store
-                    .store(&State {
-                        operation_id: Some(request.id.clone()),
-                        operation: Some(StateStatus::Software(SoftwareOperationVariants::List)),
-                    })
-                    .await?;
-
-                request
-            }
+            Ok(request) => request,
 
             Err(error) => {
                 debug!("Parsing error: {}", error);
@@ -448,7 +828,7 @@ impl SmAgent {
             .new_log_file(LogKind::SoftwareList)
             .await
         {
-            Ok(log_file) => plugins.lock().await.list(&request, log_file).await,
+            Ok(log_file) => plugins.read().await.list(&request, log_file).await,
             Err(err) => {
                 error!("{}", err);
                 executing_response.set_error(&format!("{}", err));
@@ -460,7 +840,7 @@ impl SmAgent {
             .publish(Message::new(response_topic, response.to_bytes()?))
             .await?;
 
-        let _state: State = self.persistence_store.clear().await?;
+        let _ = self.persistence_store.remove(&request.id).await?;
 
         Ok(())
     }
@@ -468,22 +848,12 @@ impl SmAgent {
     async fn handle_software_update_request(
         &self,
         responses: &mut impl PubChannel,
-        plugins: Arc<Mutex<ExternalPlugins>>,
+        plugins: Arc<RwLock<ExternalPlugins>>,
         response_topic: &Topic,
         message: &Message,
     ) -> Result<(), AgentError> {
         let request = match SoftwareUpdateRequest::from_slice(message.payload_bytes()) {
-            Ok(request) => {
-                let _ = self
-                    .persistence_store
-                    .store(&State {
-                        operation_id: Some(request.id.clone()),
-                        operation: Some(StateStatus::Software(SoftwareOperationVariants::Update)),
-                    })
-                    .await;
-
-                request
-            }
+            Ok(request) => request,
 
             Err(error) => {
                 error!("Parsing error: {}", error);
@@ -513,7 +883,7 @@ impl SmAgent {
         {
             Ok(log_file) => {
                 plugins
-                    .lock()
+                    .read()
                     .await
                     .process(&request, log_file, &self.config.download_dir)
                     .await
@@ -540,15 +910,7 @@ impl SmAgent {
         message: &Message,
     ) -> Result<RestartOperationRequest, AgentError> {
         let request = match RestartOperationRequest::from_slice(message.payload_bytes()) {
-            Ok(request) => {
-                self.persistence_store
-                    .store(&State {
-                        operation_id: Some(request.id.clone()),
-                        operation: Some(StateStatus::Restart(RestartOperationStatus::Restarting)),
-                    })
-                    .await?;
-                request
-            }
+            Ok(request) => request,
 
             Err(error) => {
                 error!("Parsing error: {}", error);
@@ -572,16 +934,22 @@ impl SmAgent {
         &self,
         responses: &mut impl PubChannel,
         topic: &Topic,
+        request: &RestartOperationRequest,
     ) -> Result<(), AgentError> {
         self.persistence_store
             .update(&StateStatus::Restart(RestartOperationStatus::Restarting))
             .await?;
 
-        // update status to executing.
-        let executing_response = RestartOperationResponse::new(&RestartOperationRequest::default());
+        // update status to executing, the response being correlated to the request by its id.
+        let executing_response = RestartOperationResponse::new(request);
         responses
             .publish(Message::new(topic, executing_response.to_bytes()?))
             .await?;
+        self.restart_device()
+    }
+
+    /// Restart the device, marking the restart so it can be checked on the next start of the agent.
+    fn restart_device(&self) -> Result<(), AgentError> {
         restart_operation::create_tmp_restart_file(&self.config.tmp_dir)?;
 
         let command_vec =
@@ -602,6 +970,133 @@ impl SmAgent {
         Ok(())
     }
 
+    /// Download, verify and install a firmware image, then restart the device on the new firmware.
+    ///
+    /// The firmware update is reported as successful only after the restart,
+    /// when the agent starts again on the new firmware.
+    async fn handle_firmware_update_request(
+        &self,
+        responses: &mut impl PubChannel,
+        message: &Message,
+    ) -> Result<(), AgentError> {
+        let request = match FirmwareUpdateRequest::from_slice(message.payload_bytes()) {
+            Ok(request) => request,
+
+            Err(error) => {
+                error!("Parsing error: {}", error);
+                responses
+                    .publish(Message::new(
+                        &self.config.errors_topic,
+                        format!("{}", error),
+                    ))
+                    .await?;
+
+                return Err(SoftwareError::ParseError {
+                    reason: "Parsing failed".into(),
+                }
+                .into());
+            }
+        };
+
+        firmware_operation::store_request(&self.agent_dir(), &request)?;
+
+        let executing_response = FirmwareUpdateResponse::new(&request);
+        responses
+            .publish(Message::new(
+                &self.config.response_topic_firmware_update,
+                executing_response.to_bytes()?,
+            ))
+            .await?;
+
+        if let Err(error) = self.install_firmware(&request).await {
+            error!("{}", error);
+
+            let _ = firmware_operation::take_request(&self.agent_dir());
+            self.persistence_store.clear().await?;
+            let response = FirmwareUpdateResponse::new(&request).with_error(&error.to_string());
+            responses
+                .publish(Message::new(
+                    &self.config.response_topic_firmware_update,
+                    response.to_bytes()?,
+                ))
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn install_firmware(&self, request: &FirmwareUpdateRequest) -> Result<(), AgentError> {
+        let install_command = get_firmware_install_command(&self.config.config_location)?;
+        let mut log_file = self
+            .operation_logs
+            .new_log_file(LogKind::Operation("firmware-update".into()))
+            .await?;
+
+        let image = firmware_operation::download_image(request, &self.config.download_dir).await?;
+        let installed = firmware_operation::install_image(
+            SUDO,
+            &install_command,
+            image.filename(),
+            request,
+            log_file.buffer(),
+        )
+        .await;
+        let _ = image.cleanup().await;
+        installed?;
+
+        self.persistence_store
+            .update(&StateStatus::Firmware(FirmwareOperationStatus::Restarting))
+            .await?;
+        self.restart_device()
+    }
+
+    /// Report the outcome of a firmware update interrupted by the restart of the agent.
+    ///
+    /// The update is successful if the device has been restarted after the installation of the image.
+    async fn resume_firmware_update(
+        &self,
+        responses: &mut impl PubChannel,
+        id: &str,
+        status: FirmwareOperationStatus,
+    ) -> Result<(), AgentError> {
+        let request = firmware_operation::take_request(&self.agent_dir())?.unwrap_or_else(|| {
+            error!("The firmware update request {} has not been persisted", id);
+            FirmwareUpdateRequest::new_with_id(id, "", "", "")
+        });
+        let response = FirmwareUpdateResponse::new(&request);
+
+        let response = match status {
+            FirmwareOperationStatus::Restarting
+                if restart_operation::has_rebooted(&self.config.tmp_dir)? =>
+            {
+                info!(
+                    "Device restarted on firmware {} {}",
+                    request.name, request.version
+                );
+                response.with_status(OperationStatus::Successful)
+            }
+            FirmwareOperationStatus::Restarting => {
+                response.with_error("The device has not been restarted")
+            }
+            FirmwareOperationStatus::Update => {
+                response.with_error("The firmware update has been interrupted")
+            }
+        };
+
+        responses
+            .publish(Message::new(
+                &self.config.response_topic_firmware_update,
+                response.to_bytes()?,
+            ))
+            .await?;
+
+        Ok(())
+    }
+
+    fn agent_dir(&self) -> PathBuf {
+        self.config.sm_home.join(".agent")
+    }
+
     async fn process_pending_operation(
         &self,
         responses: &mut impl PubChannel,
@@ -612,13 +1107,9 @@ impl SmAgent {
         if let State {
             operation_id: Some(id),
             operation: Some(operation),
-        } = match state {
-            Ok(state) => state,
-            Err(_) => State {
-                operation_id: None,
-                operation: None,
-            },
-        } {
+            ..
+        } = state.unwrap_or_default()
+        {
             let topic = match operation {
                 StateStatus::Software(SoftwareOperationVariants::List) => {
                     &self.config.response_topic_list
@@ -641,6 +1132,12 @@ impl SmAgent {
                     &self.config.response_topic_restart
                 }
 
+                StateStatus::Firmware(status) => {
+                    self.resume_firmware_update(responses, &id, status).await?;
+                    let _state = self.persistence_store.clear().await?;
+                    return Ok(());
+                }
+
                 StateStatus::UnknownOperation => {
                     error!("UnknownOperation in store.");
                     &self.config.errors_topic
@@ -652,6 +1149,9 @@ impl SmAgent {
             responses
                 .publish(Message::new(topic, response.to_bytes()?))
                 .await?;
+
+            // The interrupted operation is over, but not the pending ones
+            let _state = self.persistence_store.clear().await?;
         }
 
         Ok(())
@@ -696,6 +1196,47 @@ fn get_default_plugin(
     Ok(tedge_config.query_string_optional(SoftwarePluginDefaultSetting)?)
 }
 
+fn get_plugin_timeouts(
+    config_location: &TEdgeConfigLocation,
+) -> Result<PluginTimeouts, AgentError> {
+    let config_repository = tedge_config::TEdgeConfigRepository::new(config_location.clone());
+    let tedge_config = config_repository.load()?;
+
+    // A timeout of 0 second disables the timeout
+    let timeout = |seconds: u64| (seconds > 0).then(|| Duration::from_secs(seconds));
+
+    let Seconds(default_timeout) = tedge_config.query(SoftwarePluginTimeoutSetting)?;
+    let by_software_type = tedge_config
+        .query(SoftwarePluginTimeoutsSetting)?
+        .0
+        .into_iter()
+        .map(|(software_type, seconds)| (software_type, timeout(seconds)))
+        .collect();
+
+    Ok(PluginTimeouts {
+        default: timeout(default_timeout),
+        by_software_type,
+    })
+}
+
+fn get_firmware_install_command(
+    config_location: &TEdgeConfigLocation,
+) -> Result<String, AgentError> {
+    let config_repository = tedge_config::TEdgeConfigRepository::new(config_location.clone());
+    let tedge_config = config_repository.load()?;
+
+    Ok(tedge_config.query(FirmwareInstallCommandSetting)?)
+}
+
+fn is_update_transactional(config_location: &TEdgeConfigLocation) -> Result<bool, AgentError> {
+    let config_repository = tedge_config::TEdgeConfigRepository::new(config_location.clone());
+    let tedge_config = config_repository.load()?;
+
+    Ok(tedge_config
+        .query(SoftwareUpdateTransactionalSetting)?
+        .is_set())
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -720,12 +1261,13 @@ mod tests {
         .unwrap();
 
         // calling handle_restart_operation should create a file in /tmp/tedge_agent_restart
-        let (_output, mut output_stream) = mqtt_tests::output_stream();
+        let (output, mut output_stream) = mqtt_tests::output_stream();
         let response_topic_restart =
             Topic::new(RestartOperationResponse::topic_name()).expect("Invalid topic");
+        let request = RestartOperationRequest::new_with_id("restart-1");
 
         agent
-            .handle_restart_operation(&mut output_stream, &response_topic_restart)
+            .handle_restart_operation(&mut output_stream, &response_topic_restart, &request)
             .await?;
 
         assert!(
@@ -733,6 +1275,13 @@ mod tests {
                 .exists()
         );
 
+        // The executing response carries the id of the request
+        drop(output_stream);
+        let responses = output.collect().await;
+        let response = RestartOperationResponse::from_slice(responses[0].payload_bytes()).unwrap();
+        assert_eq!(response.id, "restart-1");
+        assert_eq!(response.status(), OperationStatus::Executing);
+
         Ok(())
     }
 
@@ -800,7 +1349,7 @@ mod tests {
             let response_topic_restart =
                 Topic::new(SoftwareListResponse::topic_name()).expect("Invalid topic");
 
-            let plugins = Arc::new(Mutex::new(
+            let plugins = Arc::new(RwLock::new(
                 ExternalPlugins::open(
                     PathBuf::from(&dir.temp_dir.path()).join("sm-plugins"),
                     get_default_plugin(&agent.config.config_location).unwrap(),
@@ -838,13 +1387,13 @@ mod tests {
         let (dir, tedge_config_location) = create_temp_tedge_config().unwrap();
 
         tokio::spawn(async move {
-            let mut agent = SmAgent::try_new(
+            let agent = SmAgent::try_new(
                 "tedge_agent_test",
                 SmAgentConfig::try_new(tedge_config_location).unwrap(),
             )
             .unwrap();
 
-            let plugins = Arc::new(Mutex::new(
+            let plugins = Arc::new(RwLock::new(
                 ExternalPlugins::open(
                     PathBuf::from(&dir.temp_dir.path()).join("sm-plugins"),
                     get_default_plugin(&agent.config.config_location).unwrap(),
@@ -871,6 +1420,342 @@ mod tests {
         Ok(())
     }
 
+    async fn process_requests(
+        dir: &TempTedgeDir,
+        tedge_config_location: TEdgeConfigLocation,
+        requests: Vec<Message>,
+    ) -> Vec<Message> {
+        let (responses, mut response_sink) = mqtt_tests::output_stream();
+        let mut requests = mqtt_tests::input_stream(requests).await;
+        let plugins_dir = PathBuf::from(&dir.temp_dir.path()).join("sm-plugins");
+
+        tokio::spawn(async move {
+            let agent = SmAgent::try_new(
+                "tedge_agent_test",
+                SmAgentConfig::try_new(tedge_config_location).unwrap(),
+            )
+            .unwrap();
+
+            let plugins = Arc::new(RwLock::new(
+                ExternalPlugins::open(
+                    plugins_dir,
+                    get_default_plugin(&agent.config.config_location).unwrap(),
+                    Some(SUDO.into()),
+                )
+                .unwrap(),
+            ));
+            agent
+                .process_subscribed_messages(&mut requests, &mut response_sink, &plugins)
+                .await
+                .unwrap();
+        });
+
+        responses.collect().await
+    }
+
+    fn statuses_of(responses: &[Message], topic: &str) -> Vec<(String, String)> {
+        responses
+            .iter()
+            .filter(|response| response.topic.name == topic)
+            .map(|response| {
+                let response: Value = serde_json::from_slice(response.payload_bytes()).unwrap();
+                (
+                    response["id"].as_str().unwrap().to_string(),
+                    response["status"].as_str().unwrap().to_string(),
+                )
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn software_list_is_not_dropped_while_an_update_is_queued() -> Result<(), AgentError> {
+        let (dir, tedge_config_location) = create_temp_tedge_config().unwrap();
+
+        let responses = process_requests(
+            &dir,
+            tedge_config_location,
+            vec![
+                message(
+                    "tedge/commands/req/software/update",
+                    r#"{"id":"1","updateList":[]}"#,
+                ),
+                message("tedge/commands/req/software/list", r#"{"id":"2"}"#),
+                message(
+                    "tedge/commands/req/software/update",
+                    r#"{"id":"3","updateList":[]}"#,
+                ),
+            ],
+        )
+        .await;
+
+        assert_eq!(
+            statuses_of(&responses, "tedge/commands/res/software/list"),
+            vec![
+                ("2".to_string(), "executing".to_string()),
+                ("2".to_string(), "successful".to_string()),
+            ]
+        );
+        // The updates are processed one after the other
+        let update_ids: Vec<String> = statuses_of(&responses, "tedge/commands/res/software/update")
+            .into_iter()
+            .map(|(id, _status)| id)
+            .collect();
+        assert_eq!(update_ids, vec!["1", "1", "3", "3"]);
+
+        // All the operations are completed
+        let state = AgentStateRepository::new(dir.temp_dir.path().to_path_buf())
+            .load()
+            .await?;
+        assert_eq!(state, State::default());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn pending_operations_are_resumed_on_restart() -> Result<(), AgentError> {
+        let (dir, tedge_config_location) = create_temp_tedge_config().unwrap();
+        dir.dir(".agent")
+            .file("current-operation")
+            .with_raw_content(
+                r#"
+            [[pending_operations]]
+            id = "42"
+            operation = "list"
+            payload = '{"id":"42"}'
+            "#,
+            );
+
+        let responses = process_requests(&dir, tedge_config_location, vec![]).await;
+
+        assert_eq!(
+            statuses_of(&responses, "tedge/commands/res/software/list"),
+            vec![
+                ("42".to_string(), "executing".to_string()),
+                ("42".to_string(), "successful".to_string()),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn queued_operations_can_be_cancelled() -> Result<(), AgentError> {
+        let (dir, tedge_config_location) = create_temp_tedge_config().unwrap();
+        dir.dir(".agent")
+            .file("current-operation")
+            .with_raw_content(
+                r#"
+            operation_id = "1"
+            operation = "update"
+
+            [[pending_operations]]
+            id = "2"
+            operation = "update"
+            payload = '{"id":"2","updateList":[]}'
+            "#,
+            );
+
+        let agent = SmAgent::try_new(
+            "tedge_agent_test",
+            SmAgentConfig::try_new(tedge_config_location).unwrap(),
+        )
+        .unwrap();
+        let (responses, mut response_sink) = mqtt_tests::output_stream();
+        let update = StateStatus::Software(SoftwareOperationVariants::Update);
+
+        agent
+            .cancel_operation(
+                &mut response_sink,
+                &message("tedge/commands/req/software/update/cancel", r#"{"id":"2"}"#),
+                update.clone(),
+            )
+            .await;
+        drop(response_sink);
+
+        // The queued operation is reported as failed and removed from the queue
+        let responses = responses.collect().await;
+        assert_eq!(
+            statuses_of(&responses, "tedge/commands/res/software/update"),
+            vec![("2".to_string(), "failed".to_string())]
+        );
+        let state = AgentStateRepository::new(dir.temp_dir.path().to_path_buf())
+            .load()
+            .await?;
+        assert!(state.pending_operations.is_empty());
+
+        // Cancelling the in-flight operation aborts the plugin processes
+        let (_responses, mut response_sink) = mqtt_tests::output_stream();
+        agent
+            .cancel_operation(
+                &mut response_sink,
+                &message("tedge/commands/req/software/update/cancel", r#"{"id":"1"}"#),
+                update,
+            )
+            .await;
+        assert!(agent.abort_signal.is_aborted());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn running_software_updates_can_be_cancelled() -> Result<(), AgentError> {
+        let (dir, tedge_config_location) = create_temp_tedge_config().unwrap();
+        let plugins_dir = PathBuf::from(&dir.temp_dir.path()).join("sm-plugins");
+        create_slow_plugin_in(&plugins_dir, "slow");
+
+        let (request_sink, mut requests) = mpsc::unbounded();
+        let (mut response_sink, mut responses) = mpsc::unbounded();
+
+        let agent = tokio::spawn(async move {
+            let agent = SmAgent::try_new(
+                "tedge_agent_test",
+                SmAgentConfig::try_new(tedge_config_location).unwrap(),
+            )
+            .unwrap();
+
+            // No sudo, for the plugin to be actually run
+            let plugins = ExternalPlugins::open(plugins_dir, None, None)
+                .unwrap()
+                .with_abort_signal(agent.abort_signal.clone());
+            let plugins = Arc::new(RwLock::new(plugins));
+            agent
+                .process_subscribed_messages(&mut requests, &mut response_sink, &plugins)
+                .await
+                .unwrap();
+        });
+
+        let update_topic = "tedge/commands/res/software/update";
+        request_sink
+            .unbounded_send(message(
+                "tedge/commands/req/software/update",
+                r#"{"id":"1","updateList":[{"type":"slow","modules":[
+                    {"name":"curl","version":"7.1","action":"install"}
+                ]}]}"#,
+            ))
+            .unwrap();
+
+        // Cancel the update once running
+        let executing = responses.next().await.unwrap();
+        assert_eq!(
+            statuses_of(&[executing], update_topic),
+            vec![("1".to_string(), "executing".to_string())]
+        );
+        request_sink
+            .unbounded_send(message(
+                "tedge/commands/req/software/update/cancel",
+                r#"{"id":"1"}"#,
+            ))
+            .unwrap();
+        drop(request_sink);
+
+        // The plugin is killed well before the end of its installation
+        tokio::time::timeout(Duration::from_secs(30), agent)
+            .await
+            .expect("the plugin to be killed")
+            .unwrap();
+
+        let responses: Vec<Message> = responses.collect().await;
+        assert_eq!(
+            statuses_of(&responses, update_topic),
+            vec![("1".to_string(), "failed".to_string())]
+        );
+
+        Ok(())
+    }
+
+    /// A plugin that takes ages to install any module.
+    fn create_slow_plugin_in(dir: &Path, name: &str) {
+        use std::os::unix::fs::PermissionsExt;
+
+        let script = r#"#!/bin/sh
+case "$1" in
+    list|prepare|finalize) ;;
+    update-list) exit 1 ;;
+    install|remove) exec sleep 300 ;;
+esac
+"#;
+        let plugin_path = dir.join(name);
+        std::fs::write(&plugin_path, script).unwrap();
+        std::fs::set_permissions(&plugin_path, std::fs::Permissions::from_mode(0o755)).unwrap();
+    }
+
+    #[tokio::test]
+    async fn firmware_update_fails_without_install_command() -> Result<(), AgentError> {
+        let (dir, tedge_config_location) = create_temp_tedge_config().unwrap();
+
+        let responses = process_requests(
+            &dir,
+            tedge_config_location,
+            vec![message(
+                "tedge/commands/req/firmware/update",
+                r#"{"id":"1","name":"image","version":"1.0","url":"http://localhost/image"}"#,
+            )],
+        )
+        .await;
+
+        assert_eq!(
+            statuses_of(&responses, "tedge/commands/res/firmware/update"),
+            vec![
+                ("1".to_string(), "executing".to_string()),
+                ("1".to_string(), "failed".to_string()),
+            ]
+        );
+        let state = AgentStateRepository::new(dir.temp_dir.path().to_path_buf())
+            .load()
+            .await?;
+        assert_eq!(state, State::default());
+
+        Ok(())
+    }
+
+    #[test]
+    fn firmware_download_token_is_not_persisted() -> Result<(), AgentError> {
+        let dir = TempTedgeDir::new();
+        let request = FirmwareUpdateRequest::new_with_id("1", "image", "1.0", "http://c8y/image")
+            .with_auth(tedge_api::Auth::new_bearer("secret-token"));
+
+        firmware_operation::store_request(dir.path(), &request)?;
+        let persisted = std::fs::read_to_string(dir.path().join("firmware-update"))?;
+        assert!(!persisted.contains("secret-token"));
+
+        let stored = firmware_operation::take_request(dir.path())?.unwrap();
+        assert_eq!(stored.auth, None);
+        assert_eq!(stored.url, "http://c8y/image");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn installed_firmware_is_reported_after_restart() -> Result<(), AgentError> {
+        let (dir, tedge_config_location) = create_temp_tedge_config().unwrap();
+        dir.dir(".agent")
+            .file("current-operation")
+            .with_raw_content("operation_id = '1'\noperation = 'firmware_restarting'");
+        dir.dir(".agent").file("firmware-update").with_raw_content(
+            r#"{"id":"1","name":"image","version":"1.0","url":"http://localhost/image"}"#,
+        );
+
+        let agent = SmAgent::try_new(
+            "tedge_agent_test",
+            SmAgentConfig::try_new(tedge_config_location).unwrap(),
+        )
+        .unwrap();
+        let (responses, mut response_sink) = mqtt_tests::output_stream();
+        agent.process_pending_operation(&mut response_sink).await?;
+        drop(response_sink);
+
+        let responses = responses.collect().await;
+        assert_eq!(responses.len(), 1);
+        let response: Value = serde_json::from_slice(responses[0].payload_bytes()).unwrap();
+        assert_json_include!(
+            actual: response,
+            expected: json!({"id": "1", "status": "successful", "name": "image", "version": "1.0"})
+        );
+        assert!(!dir.temp_dir.path().join(".agent/firmware-update").exists());
+
+        Ok(())
+    }
+
     #[tokio::test]
     #[serial_test::serial]
     async fn check_tedge_agent_does_not_panic_when_port_is_in_use() -> Result<(), anyhow::Error> {
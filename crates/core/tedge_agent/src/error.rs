@@ -58,6 +58,15 @@ pub enum AgentError {
 
     #[error(transparent)]
     FromFileTransferError(#[from] FileTransferError),
+
+    #[error(transparent)]
+    FromDownload(#[from] tedge_api::DownloadError),
+
+    #[error("The firmware image downloaded from {url} is empty")]
+    EmptyFirmwareImage { url: String },
+
+    #[error("The firmware install command {command} failed: {reason}")]
+    FirmwareInstallFailed { command: String, reason: String },
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -0,0 +1,104 @@
+pub mod firmware_operation {
+
+    use crate::error::AgentError;
+    use logged_command::LoggedCommand;
+    use std::path::Path;
+    use tedge_api::{Downloader, FirmwareUpdateRequest, Jsonify};
+    use tokio::{fs::File, io::BufWriter};
+
+    const FIRMWARE_UPDATE_FILE: &str = "firmware-update";
+
+    /// Persist the firmware update request under the agent directory,
+    /// so the installed firmware can be reported after the restart of the device.
+    ///
+    /// The download token is not persisted: it is a secret and expires anyway.
+    /// An update interrupted before the restart is reported as failed,
+    /// the image being downloaded again with a fresh token when the update is requested again.
+    pub fn store_request(
+        agent_dir: &Path,
+        request: &FirmwareUpdateRequest,
+    ) -> Result<(), AgentError> {
+        let request = FirmwareUpdateRequest {
+            auth: None,
+            ..request.clone()
+        };
+        std::fs::write(agent_dir.join(FIRMWARE_UPDATE_FILE), request.to_bytes()?)?;
+        Ok(())
+    }
+
+    /// Get back and remove the persisted firmware update request, if any.
+    pub fn take_request(agent_dir: &Path) -> Result<Option<FirmwareUpdateRequest>, AgentError> {
+        let path = agent_dir.join(FIRMWARE_UPDATE_FILE);
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let request = FirmwareUpdateRequest::from_slice(&std::fs::read(&path)?)?;
+        std::fs::remove_file(&path)?;
+        Ok(Some(request))
+    }
+
+    /// Download the firmware image into the given directory.
+    ///
+    /// An empty image is rejected, as most likely the outcome of a misconfigured URL.
+    pub async fn download_image(
+        request: &FirmwareUpdateRequest,
+        download_dir: &Path,
+    ) -> Result<Downloader, AgentError> {
+        let downloader =
+            Downloader::new(&request.name, &Some(request.version.clone()), download_dir);
+        downloader.download(&request.download_info()).await?;
+
+        if std::fs::metadata(downloader.filename())?.len() == 0 {
+            let _ = downloader.cleanup().await;
+            return Err(AgentError::EmptyFirmwareImage {
+                url: request.url.clone(),
+            });
+        }
+
+        Ok(downloader)
+    }
+
+    /// Verify then install the firmware image, using the install command configured for the device.
+    ///
+    /// The command is called twice, with `sudo`:
+    /// - `<command> verify <image>` that checks the image can be installed on this device,
+    /// - `<command> install <image> <name> <version>` that installs the image,
+    ///   to be activated on the next restart of the device.
+    pub async fn install_image(
+        sudo: &str,
+        install_command: &str,
+        image: &Path,
+        request: &FirmwareUpdateRequest,
+        logger: &mut BufWriter<File>,
+    ) -> Result<(), AgentError> {
+        let mut verify = LoggedCommand::new(sudo);
+        verify.arg(install_command).arg("verify").arg(image);
+        run_install_step(verify, logger).await?;
+
+        let mut install = LoggedCommand::new(sudo);
+        install
+            .arg(install_command)
+            .arg("install")
+            .arg(image)
+            .arg(&request.name)
+            .arg(&request.version);
+        run_install_step(install, logger).await
+    }
+
+    async fn run_install_step(
+        command: LoggedCommand,
+        logger: &mut BufWriter<File>,
+    ) -> Result<(), AgentError> {
+        let command_line = command.to_string();
+        let output = command.execute(logger).await?;
+        if output.status.success() {
+            Ok(())
+        } else {
+            Err(AgentError::FirmwareInstallFailed {
+                command: command_line,
+                reason: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            })
+        }
+    }
+}
@@ -9,6 +9,7 @@ use tedge_config::{
 
 mod agent;
 mod error;
+mod firmware_operation_handler;
 mod http_rest;
 mod restart_operation_handler;
 mod state;
@@ -1,15 +1,17 @@
 use crate::error::StateError;
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
-use std::{path::PathBuf, str::FromStr};
+use std::{io::ErrorKind, path::PathBuf, str::FromStr};
 use tedge_utils::fs::atomically_write_file_async;
-use tokio::fs;
+use tokio::{fs, sync::Mutex};
 use tracing::error;
 
 #[derive(Debug)]
 pub struct AgentStateRepository {
     state_repo_path: PathBuf,
     state_repo_root: PathBuf,
+    // Serialize the updates of the state, which are all made of a load followed by a store
+    update_lock: Mutex<()>,
 }
 
 #[async_trait]
@@ -19,6 +21,20 @@ pub trait StateRepository {
     async fn store(&self, state: &State) -> Result<(), Self::Error>;
     async fn clear(&self) -> Result<State, Self::Error>;
     async fn update(&self, status: &StateStatus) -> Result<(), Self::Error>;
+
+    /// Append an operation to the queue of pending operations.
+    async fn enqueue(&self, operation: PendingOperation) -> Result<(), Self::Error>;
+
+    /// Mark a pending operation as the current one, removing it from the queue.
+    ///
+    /// Returns `false`, leaving the state unchanged, if the operation is no more pending,
+    /// i.e. has been cancelled meanwhile.
+    async fn start(&self, operation_id: &str, status: StateStatus) -> Result<bool, Self::Error>;
+
+    /// Remove an operation from the queue of pending operations.
+    ///
+    /// Returns `false` if the operation was not pending, i.e. has been started or removed meanwhile.
+    async fn remove(&self, operation_id: &str) -> Result<bool, Self::Error>;
 }
 
 #[async_trait]
@@ -53,17 +69,19 @@ impl StateRepository for AgentStateRepository {
         Ok(())
     }
 
+    /// Clear the current operation, keeping the pending ones.
     async fn clear(&self) -> Result<State, Self::Error> {
-        let state = State {
-            operation_id: None,
-            operation: None,
-        };
+        let _lock = self.update_lock.lock().await;
+        let mut state = self.load_or_default().await?;
+        state.operation_id = None;
+        state.operation = None;
         self.store(&state).await?;
 
         Ok(state)
     }
 
     async fn update(&self, status: &StateStatus) -> Result<(), Self::Error> {
+        let _lock = self.update_lock.lock().await;
         let mut state = self.load().await?;
         state.operation = Some(status.to_owned());
 
@@ -71,6 +89,41 @@ impl StateRepository for AgentStateRepository {
 
         Ok(())
     }
+
+    async fn enqueue(&self, operation: PendingOperation) -> Result<(), Self::Error> {
+        let _lock = self.update_lock.lock().await;
+        let mut state = self.load_or_default().await?;
+        if !state.pending_operations.contains(&operation) {
+            state.pending_operations.push(operation);
+            self.store(&state).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn start(&self, operation_id: &str, status: StateStatus) -> Result<bool, Self::Error> {
+        let _lock = self.update_lock.lock().await;
+        let mut state = self.load_or_default().await?;
+        if !state.remove_pending_operation(operation_id) {
+            return Ok(false);
+        }
+        state.operation_id = Some(operation_id.into());
+        state.operation = Some(status);
+        self.store(&state).await?;
+
+        Ok(true)
+    }
+
+    async fn remove(&self, operation_id: &str) -> Result<bool, Self::Error> {
+        let _lock = self.update_lock.lock().await;
+        let mut state = self.load_or_default().await?;
+        if !state.remove_pending_operation(operation_id) {
+            return Ok(false);
+        }
+        self.store(&state).await?;
+
+        Ok(true)
+    }
 }
 
 impl AgentStateRepository {
@@ -84,6 +137,15 @@ impl AgentStateRepository {
         Self {
             state_repo_path,
             state_repo_root,
+            update_lock: Mutex::new(()),
+        }
+    }
+
+    async fn load_or_default(&self) -> Result<State, StateError> {
+        match fs::read(&self.state_repo_path).await {
+            Ok(bytes) => Ok(toml::from_slice::<State>(bytes.as_slice())?),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(State::default()),
+            Err(err) => Err(StateError::FromIo(err)),
         }
     }
 }
@@ -93,6 +155,7 @@ impl AgentStateRepository {
 pub enum StateStatus {
     Software(SoftwareOperationVariants),
     Restart(RestartOperationStatus),
+    Firmware(FirmwareOperationStatus),
     UnknownOperation,
 }
 
@@ -109,18 +172,47 @@ pub enum RestartOperationStatus {
     Restarting,
 }
 
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum FirmwareOperationStatus {
+    #[serde(rename = "firmware_update")]
+    Update,
+    #[serde(rename = "firmware_restarting")]
+    Restarting,
+}
+
 #[derive(Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
 #[serde(deny_unknown_fields)]
 pub struct State {
     pub operation_id: Option<String>,
     pub operation: Option<StateStatus>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pending_operations: Vec<PendingOperation>,
+}
+
+impl State {
+    /// Remove an operation from the pending ones, returning `false` if not pending.
+    fn remove_pending_operation(&mut self, operation_id: &str) -> bool {
+        let pending = self.pending_operations.len();
+        self.pending_operations
+            .retain(|operation| operation.id != operation_id);
+        self.pending_operations.len() != pending
+    }
+}
+
+/// A request received by the agent and not completed yet,
+/// persisted with its payload so it can be processed again after a restart.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct PendingOperation {
+    pub id: String,
+    pub operation: StateStatus,
+    pub payload: String,
 }
 
 #[cfg(test)]
 mod tests {
     use crate::state::{
-        AgentStateRepository, RestartOperationStatus, SoftwareOperationVariants, State,
-        StateRepository, StateStatus,
+        AgentStateRepository, FirmwareOperationStatus, PendingOperation, RestartOperationStatus,
+        SoftwareOperationVariants, State, StateRepository, StateStatus,
     };
 
     use tedge_test_utils::fs::TempTedgeDir;
@@ -150,6 +242,7 @@ mod tests {
             State {
                 operation_id: Some("1234".into()),
                 operation: Some(StateStatus::Software(SoftwareOperationVariants::List)),
+                pending_operations: vec![],
             }
         );
     }
@@ -171,6 +264,29 @@ mod tests {
             State {
                 operation_id: Some("1234".into()),
                 operation: Some(StateStatus::Restart(RestartOperationStatus::Restarting)),
+                pending_operations: vec![],
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn agent_state_repository_exists_loads_some_firmware_variant() {
+        let temp_dir = TempTedgeDir::new();
+        let content = "operation_id = \'1234\'\noperation = \"firmware_restarting\"";
+        temp_dir
+            .dir(".agent")
+            .file("current-operation")
+            .with_raw_content(content);
+
+        let repo = AgentStateRepository::new(temp_dir.path().to_path_buf());
+
+        let data = repo.load().await.unwrap();
+        assert_eq!(
+            data,
+            State {
+                operation_id: Some("1234".into()),
+                operation: Some(StateStatus::Firmware(FirmwareOperationStatus::Restarting)),
+                pending_operations: vec![],
             }
         );
     }
@@ -191,7 +307,8 @@ mod tests {
             data,
             State {
                 operation_id: None,
-                operation: None
+                operation: None,
+                pending_operations: vec![],
             }
         );
     }
@@ -206,6 +323,7 @@ mod tests {
         repo.store(&State {
             operation_id: Some("1234".into()),
             operation: Some(StateStatus::Software(SoftwareOperationVariants::List)),
+            pending_operations: vec![],
         })
         .await
         .unwrap();
@@ -219,4 +337,61 @@ mod tests {
 
         assert_eq!(data, "operation_id = \'1234\'\noperation = \'list\'\n");
     }
+
+    #[tokio::test]
+    async fn agent_state_repository_persists_pending_operations() {
+        let temp_dir = TempTedgeDir::new();
+        temp_dir.dir(".agent");
+        let repo = AgentStateRepository::new(temp_dir.path().to_path_buf());
+
+        let update = PendingOperation {
+            id: "1".into(),
+            operation: StateStatus::Software(SoftwareOperationVariants::Update),
+            payload: r#"{"id":"1","updateList":[]}"#.into(),
+        };
+        let list = PendingOperation {
+            id: "2".into(),
+            operation: StateStatus::Software(SoftwareOperationVariants::List),
+            payload: r#"{"id":"2"}"#.into(),
+        };
+        repo.enqueue(update.clone()).await.unwrap();
+        repo.enqueue(list.clone()).await.unwrap();
+
+        // A new repository, as after a restart, gets the pending operations in order
+        let repo = AgentStateRepository::new(temp_dir.path().to_path_buf());
+        let state = repo.load().await.unwrap();
+        assert_eq!(state.operation_id, None);
+        assert_eq!(state.pending_operations, vec![update, list.clone()]);
+
+        assert!(repo
+            .start(
+                "1",
+                StateStatus::Software(SoftwareOperationVariants::Update),
+            )
+            .await
+            .unwrap());
+        let state = repo.load().await.unwrap();
+        assert_eq!(state.operation_id, Some("1".into()));
+        assert_eq!(state.pending_operations, vec![list.clone()]);
+
+        // An operation that is no more pending, e.g. cancelled, cannot be started nor removed
+        assert!(!repo
+            .start(
+                "1",
+                StateStatus::Software(SoftwareOperationVariants::Update),
+            )
+            .await
+            .unwrap());
+        assert!(!repo.remove("1").await.unwrap());
+
+        // Clearing the current operation keeps the pending ones
+        repo.clear().await.unwrap();
+        let state = repo.load().await.unwrap();
+        assert_eq!(state.operation_id, None);
+        assert_eq!(state.pending_operations, vec![list]);
+
+        assert!(repo.remove("2").await.unwrap());
+        let state = repo.load().await.unwrap();
+        assert_eq!(state, State::default());
+    }
 }
@@ -4,14 +4,14 @@ use clock::Timestamp;
 use serde::Deserialize;
 
 /// In-memory representation of ThinEdge JSON alarm.
-#[derive(Debug, Deserialize, Eq, PartialEq)]
+#[derive(Debug, Clone, Deserialize, Eq, PartialEq)]
 pub struct ThinEdgeAlarm {
     pub name: String,
     pub severity: AlarmSeverity,
     pub data: Option<ThinEdgeAlarmData>,
 }
 
-#[derive(Debug, Deserialize, Eq, PartialEq)]
+#[derive(Debug, Clone, Copy, Deserialize, Eq, PartialEq)]
 pub enum AlarmSeverity {
     Critical,
     Major,
@@ -19,14 +19,47 @@ pub enum AlarmSeverity {
     Warning,
 }
 
+/// The status of an alarm along its lifecycle: raised as active, possibly acknowledged, and finally cleared.
+#[derive(Debug, Clone, Copy, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum AlarmStatus {
+    Active,
+    Acknowledged,
+    Cleared,
+}
+
 /// In-memory representation of ThinEdge JSON alarm payload
-#[derive(Debug, Deserialize, Eq, PartialEq)]
+#[derive(Debug, Clone, Deserialize, Eq, PartialEq)]
 pub struct ThinEdgeAlarmData {
+    /// The alarm text, or the reason why the alarm has been cleared.
     pub text: Option<String>,
 
     #[serde(default)]
     #[serde(with = "time::serde::rfc3339::option")]
     pub time: Option<Timestamp>,
+
+    /// The alarm status, `active` if not given.
+    #[serde(default)]
+    pub status: Option<AlarmStatus>,
+}
+
+/// A change of an alarm, from one state to the next.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum AlarmTransition {
+    /// The alarm is raised, being new or cleared so far.
+    Raise,
+
+    /// The severity of a raised alarm is changed.
+    UpdateSeverity(AlarmSeverity),
+
+    /// The text of a raised alarm is changed.
+    UpdateText(String),
+
+    /// A raised alarm is acknowledged, i.e. known but not resolved yet.
+    Acknowledge,
+
+    /// The alarm is cleared, possibly with the reason why.
+    Clear { reason: Option<String> },
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -60,7 +93,60 @@ impl TryFrom<&str> for AlarmSeverity {
     }
 }
 
+impl AlarmStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AlarmStatus::Active => "active",
+            AlarmStatus::Acknowledged => "acknowledged",
+            AlarmStatus::Cleared => "cleared",
+        }
+    }
+}
+
 impl ThinEdgeAlarm {
+    /// The alarm status, an alarm with no payload being cleared.
+    pub fn status(&self) -> AlarmStatus {
+        match &self.data {
+            None => AlarmStatus::Cleared,
+            Some(data) => data.status.unwrap_or(AlarmStatus::Active),
+        }
+    }
+
+    pub fn text(&self) -> Option<&str> {
+        self.data.as_ref().and_then(|data| data.text.as_deref())
+    }
+
+    /// The transitions leading from the previous state of this alarm, if any, to this new state.
+    ///
+    /// A clear is always forwarded, even with no previous state, unless the alarm has been raised since
+    /// with a different severity: such a clear only removes the message previously published for that severity.
+    /// Once acknowledged, an alarm stays acknowledged until cleared.
+    pub fn transitions_from(&self, previous: Option<&ThinEdgeAlarm>) -> Vec<AlarmTransition> {
+        let previous = previous.filter(|alarm| alarm.status() != AlarmStatus::Cleared);
+        match (previous, self.status()) {
+            (Some(previous), AlarmStatus::Cleared) if previous.severity != self.severity => vec![],
+            (_, AlarmStatus::Cleared) => vec![AlarmTransition::Clear {
+                reason: self.text().map(str::to_string),
+            }],
+            (None, _) => vec![AlarmTransition::Raise],
+            (Some(previous), status) => {
+                let mut transitions = Vec::new();
+                if previous.severity != self.severity {
+                    transitions.push(AlarmTransition::UpdateSeverity(self.severity));
+                }
+                if let Some(text) = self.text() {
+                    if previous.text() != Some(text) {
+                        transitions.push(AlarmTransition::UpdateText(text.to_string()));
+                    }
+                }
+                if status == AlarmStatus::Acknowledged && previous.status() == AlarmStatus::Active {
+                    transitions.push(AlarmTransition::Acknowledge);
+                }
+                transitions
+            }
+        }
+    }
+
     pub fn try_from(
         mqtt_topic: &str,
         mqtt_payload: &str,
@@ -128,6 +214,7 @@ mod tests {
             data: Some(ThinEdgeAlarmData {
                 text: Some("I raised it".into()),
                 time: Some(datetime!(2021-04-23 19:00:00 +05:00)),
+                status: None,
             }),
         };
         "critical alarm parsing"
@@ -143,6 +230,7 @@ mod tests {
             data: Some(ThinEdgeAlarmData {
                 text: Some("I raised it".into()),
                 time: None,
+                status: None,
             }),
         };
         "major alarm parsing without timestamp"
@@ -158,6 +246,7 @@ mod tests {
             data: Some(ThinEdgeAlarmData {
                 text: None,
                 time: Some(datetime!(2021-04-23 19:00:00 +05:00)),
+                status: None,
             }),
         };
         "minor alarm parsing without text"
@@ -171,6 +260,7 @@ mod tests {
             data: Some(ThinEdgeAlarmData {
                 text: None,
                 time: None,
+                status: None,
             }),
         };
         "warning alarm parsing without text or timestamp"
@@ -187,6 +277,7 @@ mod tests {
             data: Some(ThinEdgeAlarmData {
                 text: Some("I raised it".into()),
                 time: Some(datetime!(2021-04-23 19:00:00 +05:00)),
+                status: None,
             }),
         };
         "critical alarm parsing with childId"
@@ -288,4 +379,93 @@ mod tests {
             Err(ThinEdgeJsonDeserializerError::UnsupportedAlarmSeverity(_))
         );
     }
+
+    #[test]
+    fn alarm_parsing_with_status() {
+        let alarm = ThinEdgeAlarm::try_from(
+            "tedge/alarms/major/temperature_alarm",
+            r#"{"text": "Fixed", "status": "cleared"}"#,
+        )
+        .unwrap();
+        assert_eq!(alarm.status(), AlarmStatus::Cleared);
+        assert_eq!(alarm.text(), Some("Fixed"));
+
+        let result = ThinEdgeAlarm::try_from(
+            "tedge/alarms/major/temperature_alarm",
+            r#"{"status": "unknown"}"#,
+        );
+        assert_matches!(
+            result,
+            Err(ThinEdgeJsonDeserializerError::SerdeJsonError(_))
+        );
+    }
+
+    fn alarm(topic: &str, payload: &str) -> ThinEdgeAlarm {
+        ThinEdgeAlarm::try_from(topic, payload).unwrap()
+    }
+
+    #[test_case(None, "", vec![AlarmTransition::Clear { reason: None }]; "clear an unknown alarm")]
+    #[test_case(None, r#"{"text": "Too hot"}"#, vec![AlarmTransition::Raise]; "raise a new alarm")]
+    #[test_case(
+        Some(r#"{"text": "Too hot", "status": "cleared"}"#),
+        r#"{"text": "Too hot"}"#,
+        vec![AlarmTransition::Raise];
+        "raise a cleared alarm"
+    )]
+    #[test_case(Some(r#"{"text": "Too hot"}"#), r#"{"text": "Too hot"}"#, vec![]; "unchanged alarm")]
+    #[test_case(
+        Some(r#"{"text": "Too hot"}"#),
+        r#"{"text": "Way too hot"}"#,
+        vec![AlarmTransition::UpdateText("Way too hot".into())];
+        "update the text"
+    )]
+    #[test_case(
+        Some(r#"{"text": "Too hot"}"#),
+        r#"{"text": "Too hot", "status": "acknowledged"}"#,
+        vec![AlarmTransition::Acknowledge];
+        "acknowledge an alarm"
+    )]
+    #[test_case(
+        Some(r#"{"text": "Too hot", "status": "acknowledged"}"#),
+        r#"{"text": "Too hot"}"#,
+        vec![];
+        "an acknowledged alarm stays acknowledged"
+    )]
+    #[test_case(
+        Some(r#"{"text": "Too hot"}"#),
+        r#"{"text": "Back to normal", "status": "cleared"}"#,
+        vec![AlarmTransition::Clear { reason: Some("Back to normal".into()) }];
+        "clear with a reason"
+    )]
+    fn alarm_transitions(
+        previous_payload: Option<&str>,
+        payload: &str,
+        expected_transitions: Vec<AlarmTransition>,
+    ) {
+        let topic = "tedge/alarms/major/temperature_alarm";
+        let previous = previous_payload.map(|payload| alarm(topic, payload));
+        let transitions = alarm(topic, payload).transitions_from(previous.as_ref());
+
+        assert_eq!(transitions, expected_transitions);
+    }
+
+    #[test]
+    fn alarm_transitions_on_severity_change() {
+        let major = alarm(
+            "tedge/alarms/major/temperature_alarm",
+            r#"{"text": "Too hot"}"#,
+        );
+        let critical = alarm(
+            "tedge/alarms/critical/temperature_alarm",
+            r#"{"text": "Too hot"}"#,
+        );
+        assert_eq!(
+            critical.transitions_from(Some(&major)),
+            vec![AlarmTransition::UpdateSeverity(AlarmSeverity::Critical)]
+        );
+
+        // Clearing the message of the former severity doesn't clear the alarm
+        let stale_clear = alarm("tedge/alarms/major/temperature_alarm", "");
+        assert_eq!(stale_clear.transitions_from(Some(&critical)), vec![]);
+    }
 }
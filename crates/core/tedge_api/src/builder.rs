@@ -25,6 +25,18 @@ impl ThinEdgeJsonBuilder {
             values: self.measurements,
         })
     }
+
+    fn push_measurement(&mut self, name: &str, value: MeasurementValue) {
+        self.push(SingleValueMeasurement::from((name, value)));
+    }
+
+    fn push(&mut self, measurement: SingleValueMeasurement) {
+        if let Some(group) = &mut self.inside_group {
+            group.values.push(measurement);
+        } else {
+            self.measurements.push(ThinEdgeValue::Single(measurement));
+        }
+    }
 }
 
 impl MeasurementVisitor for ThinEdgeJsonBuilder {
@@ -41,11 +53,27 @@ impl MeasurementVisitor for ThinEdgeJsonBuilder {
     }
 
     fn visit_measurement(&mut self, name: &str, value: f64) -> Result<(), Self::Error> {
-        if let Some(group) = &mut self.inside_group {
-            group.values.push((name, value).into());
-        } else {
-            self.measurements.push((name, value).into());
-        }
+        self.push_measurement(name, value.into());
+        Ok(())
+    }
+
+    fn visit_text_measurement(&mut self, name: &str, value: &str) -> Result<(), Self::Error> {
+        self.push_measurement(name, value.into());
+        Ok(())
+    }
+
+    fn visit_boolean_measurement(&mut self, name: &str, value: bool) -> Result<(), Self::Error> {
+        self.push_measurement(name, value.into());
+        Ok(())
+    }
+
+    fn visit_measurement_with_unit(
+        &mut self,
+        name: &str,
+        value: &MeasurementValue,
+        unit: &str,
+    ) -> Result<(), Self::Error> {
+        self.push(SingleValueMeasurement::from((name, value.clone())).with_unit(unit));
         Ok(())
     }
 
@@ -95,4 +123,7 @@ pub enum ThinEdgeJsonBuilderError {
 
     #[error("Unexpected end of group")]
     UnexpectedEndOfGroup,
+
+    #[error(transparent)]
+    UnsupportedValue(#[from] UnsupportedMeasurementValue),
 }
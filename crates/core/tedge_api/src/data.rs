@@ -1,5 +1,6 @@
 //! The in-memory data model representing ThinEdge JSON.
 
+use crate::measurement::MeasurementValue;
 use time::OffsetDateTime;
 
 /// In-memory representation of parsed ThinEdge JSON.
@@ -28,7 +29,17 @@ pub enum ThinEdgeValue {
 #[derive(Debug, PartialEq)]
 pub struct SingleValueMeasurement {
     pub name: String,
-    pub value: f64,
+    pub value: MeasurementValue,
+    pub unit: Option<String>,
+}
+
+impl SingleValueMeasurement {
+    pub fn with_unit(self, unit: impl Into<String>) -> Self {
+        SingleValueMeasurement {
+            unit: Some(unit.into()),
+            ..self
+        }
+    }
 }
 
 #[derive(Debug, PartialEq)]
@@ -37,18 +48,46 @@ pub struct MultiValueMeasurement {
     pub values: Vec<SingleValueMeasurement>,
 }
 
-impl<T> From<(T, f64)> for SingleValueMeasurement
+impl<T> From<(T, MeasurementValue)> for SingleValueMeasurement
 where
     T: Into<String>,
 {
-    fn from((name, value): (T, f64)) -> Self {
+    fn from((name, value): (T, MeasurementValue)) -> Self {
         SingleValueMeasurement {
             name: name.into(),
             value,
+            unit: None,
         }
     }
 }
 
+impl<T> From<(T, f64)> for SingleValueMeasurement
+where
+    T: Into<String>,
+{
+    fn from((name, value): (T, f64)) -> Self {
+        (name, MeasurementValue::from(value)).into()
+    }
+}
+
+impl<'a, T> From<(T, &'a str)> for SingleValueMeasurement
+where
+    T: Into<String>,
+{
+    fn from((name, value): (T, &'a str)) -> Self {
+        (name, MeasurementValue::from(value)).into()
+    }
+}
+
+impl<T> From<(T, bool)> for SingleValueMeasurement
+where
+    T: Into<String>,
+{
+    fn from((name, value): (T, bool)) -> Self {
+        (name, MeasurementValue::from(value)).into()
+    }
+}
+
 impl<T> From<(T, f64)> for ThinEdgeValue
 where
     T: Into<String>,
@@ -58,6 +97,30 @@ where
     }
 }
 
+impl<'a, T> From<(T, &'a str)> for ThinEdgeValue
+where
+    T: Into<String>,
+{
+    fn from((name, value): (T, &'a str)) -> Self {
+        ThinEdgeValue::Single((name, value).into())
+    }
+}
+
+impl<T> From<(T, bool)> for ThinEdgeValue
+where
+    T: Into<String>,
+{
+    fn from((name, value): (T, bool)) -> Self {
+        ThinEdgeValue::Single((name, value).into())
+    }
+}
+
+impl From<SingleValueMeasurement> for ThinEdgeValue {
+    fn from(measurement: SingleValueMeasurement) -> Self {
+        ThinEdgeValue::Single(measurement)
+    }
+}
+
 impl<T> From<(T, Vec<SingleValueMeasurement>)> for ThinEdgeValue
 where
     T: Into<String>,
@@ -1,7 +1,7 @@
 use std::collections::HashMap;
 use time::OffsetDateTime;
 
-use crate::measurement::MeasurementVisitor;
+use crate::measurement::{MeasurementValue, MeasurementVisitor, UnsupportedMeasurementValue};
 
 #[derive(Debug)]
 pub struct MeasurementGroup {
@@ -32,11 +32,11 @@ impl MeasurementGroup {
     ) -> Option<f64> {
         match group_key {
             Some(group_key) => match self.values.get(group_key) {
-                Some(Measurement::Multi(map)) => map.get(measurement_key).cloned(),
+                Some(Measurement::Multi(map)) => map.get(measurement_key)?.as_f64(),
                 _ => None,
             },
             None => match self.values.get(measurement_key) {
-                Some(Measurement::Single(val)) => Some(*val),
+                Some(Measurement::Single(val)) => val.as_f64(),
                 _ => None,
             },
         }
@@ -54,12 +54,12 @@ impl MeasurementGroup {
         for (key, value) in self.values.iter() {
             match value {
                 Measurement::Single(sv) => {
-                    visitor.visit_measurement(key, *sv)?;
+                    visitor.visit_measurement_value(key, sv)?;
                 }
                 Measurement::Multi(m) => {
                     visitor.visit_start_group(key)?;
                     for (key, value) in m.iter() {
-                        visitor.visit_measurement(key, *value)?;
+                        visitor.visit_measurement_value(key, value)?;
                     }
                     visitor.visit_end_group()?;
                 }
@@ -86,8 +86,8 @@ struct GroupState {
 
 #[derive(Debug)]
 pub enum Measurement {
-    Single(f64),
-    Multi(HashMap<String, f64>),
+    Single(MeasurementValue),
+    Multi(HashMap<String, MeasurementValue>),
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -106,6 +106,9 @@ pub enum MeasurementGrouperError {
 
     #[error("Unexpected end of group")]
     UnexpectedEndOfGroup,
+
+    #[error(transparent)]
+    UnsupportedValue(#[from] UnsupportedMeasurementValue),
 }
 
 impl MeasurementGrouper {
@@ -126,6 +129,26 @@ impl MeasurementGrouper {
             Ok(self.measurement_group)
         }
     }
+
+    fn insert_measurement(&mut self, name: &str, value: MeasurementValue) {
+        let key = name.to_owned();
+
+        if self.group_state.in_group {
+            let group_key = self.group_state.group.clone();
+            if let Measurement::Multi(group_map) = self
+                .measurement_group
+                .values
+                .entry(group_key)
+                .or_insert_with(|| Measurement::Multi(HashMap::new()))
+            {
+                group_map.insert(key, value);
+            }
+        } else {
+            self.measurement_group
+                .values
+                .insert(key, Measurement::Single(value));
+        }
+    }
 }
 
 impl Default for MeasurementGrouper {
@@ -163,28 +186,18 @@ impl MeasurementVisitor for MeasurementGrouper {
     }
 
     fn visit_measurement(&mut self, name: &str, value: f64) -> Result<(), Self::Error> {
-        let key = name.to_owned();
+        self.insert_measurement(name, value.into());
+        Ok(())
+    }
 
-        match self.group_state.in_group {
-            false => {
-                self.measurement_group
-                    .values
-                    .insert(key, Measurement::Single(value));
-                Ok(())
-            }
-            true => {
-                let group_key = self.group_state.group.clone();
-                if let Measurement::Multi(group_map) = self
-                    .measurement_group
-                    .values
-                    .entry(group_key)
-                    .or_insert_with(|| Measurement::Multi(HashMap::new()))
-                {
-                    group_map.insert(name.to_owned(), value);
-                }
-                Ok(())
-            }
-        }
+    fn visit_text_measurement(&mut self, name: &str, value: &str) -> Result<(), Self::Error> {
+        self.insert_measurement(name, value.into());
+        Ok(())
+    }
+
+    fn visit_boolean_measurement(&mut self, name: &str, value: bool) -> Result<(), Self::Error> {
+        self.insert_measurement(name, value.into());
+        Ok(())
     }
 }
 
@@ -199,6 +212,9 @@ mod tests {
     pub enum TestError {
         #[error("test")]
         _Test,
+
+        #[error(transparent)]
+        UnsupportedValue(#[from] UnsupportedMeasurementValue),
     }
 
     mock! {
@@ -210,6 +226,8 @@ mod tests {
 
             fn visit_timestamp(&mut self, value: OffsetDateTime) -> Result<(), TestError>;
             fn visit_measurement(&mut self, name: &str, value: f64) -> Result<(), TestError>;
+            fn visit_text_measurement(&mut self, name: &str, value: &str) -> Result<(), TestError>;
+            fn visit_boolean_measurement(&mut self, name: &str, value: bool) -> Result<(), TestError>;
             fn visit_start_group(&mut self, group: &str) -> Result<(), TestError>;
             fn visit_end_group(&mut self) -> Result<(), TestError>;
         }
@@ -347,6 +365,38 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn text_and_boolean_values_are_grouped_and_forwarded() -> anyhow::Result<()> {
+        let mut grouper = MeasurementGrouper::new();
+        grouper.visit_text_measurement("state", "RUNNING")?;
+        grouper.visit_start_group("door")?;
+        grouper.visit_boolean_measurement("open", true)?;
+        grouper.visit_end_group()?;
+
+        let group = grouper.end()?;
+        assert_eq!(group.get_measurement_value(None, "state"), None);
+
+        let mut mock = MockGroupedVisitor::new();
+        mock.expect_visit_text_measurement()
+            .times(1)
+            .with(eq("state"), eq("RUNNING"))
+            .return_const(Ok(()));
+        mock.expect_visit_start_group()
+            .times(1)
+            .with(eq("door"))
+            .return_const(Ok(()));
+        mock.expect_visit_boolean_measurement()
+            .times(1)
+            .with(eq("open"), eq(true))
+            .return_const(Ok(()));
+        mock.expect_visit_end_group().times(1).return_const(Ok(()));
+        mock.expect_visit_measurement().never();
+
+        group.accept(&mut mock)?;
+
+        Ok(())
+    }
+
     fn test_timestamp(minute: u32) -> OffsetDateTime {
         let mut dt = datetime!(2021-04-08 13:00:00 +05:00);
         dt += Duration::minutes(minute as i64);
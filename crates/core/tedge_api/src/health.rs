@@ -1,6 +1,9 @@
 use std::process;
 
+use crate::request::{Request, Response};
+use crate::SoftwareError;
 use mqtt_channel::{Message, PubChannel, Topic, TopicFilter};
+use serde::{Deserialize, Serialize};
 use serde_json::json;
 use time::OffsetDateTime;
 
@@ -13,10 +16,12 @@ pub fn health_check_topics(daemon_name: &str) -> TopicFilter {
     .expect("Invalid topic filter")
 }
 
-pub async fn send_health_status(responses: &mut impl PubChannel, daemon_name: &str) {
-    let response_topic_health =
-        Topic::new_unchecked(format!("tedge/health/{daemon_name}").as_str());
+pub fn health_status_topic(daemon_name: &str) -> Topic {
+    Topic::new_unchecked(format!("tedge/health/{daemon_name}").as_str())
+}
 
+/// The retained `up` status of a daemon, published on connect and as response to health check requests.
+pub fn health_status_up_message(daemon_name: &str) -> Message {
     let health_status = json!({
         "status": "up",
         "pid": process::id(),
@@ -24,6 +29,140 @@ pub async fn send_health_status(responses: &mut impl PubChannel, daemon_name: &s
     })
     .to_string();
 
-    let health_message = Message::new(&response_topic_health, health_status);
+    Message::new(&health_status_topic(daemon_name), health_status).with_retain()
+}
+
+/// The retained `down` status of a daemon, registered as last will and published by the broker
+/// as soon as the daemon is disconnected without notice, or by the daemon itself on a clean disconnect.
+pub fn health_status_down_message(daemon_name: &str) -> Message {
+    let health_status = json!({
+        "status": "down",
+        "pid": process::id(),
+    })
+    .to_string();
+
+    Message::new(&health_status_topic(daemon_name), health_status).with_retain()
+}
+
+/// The topic where a daemon publishes the numbers of messages dropped by its MQTT connection.
+pub fn dropped_messages_topic(daemon_name: &str) -> Topic {
+    Topic::new_unchecked(format!("tedge/health/{daemon_name}/dropped-messages").as_str())
+}
+
+/// Announce the health status of a daemon: `up` when connected, `down` when disconnected,
+/// along the numbers of messages dropped because the daemon is overloaded.
+pub fn with_health_status(
+    mqtt_config: mqtt_channel::Config,
+    daemon_name: &str,
+) -> mqtt_channel::Config {
+    mqtt_config
+        .with_birth_message(health_status_up_message(daemon_name))
+        .with_last_will_message(health_status_down_message(daemon_name))
+        .with_dropped_messages_topic(dropped_messages_topic(daemon_name))
+}
+
+pub async fn send_health_status(responses: &mut impl PubChannel, daemon_name: &str) {
+    let health_message = health_status_up_message(daemon_name);
     let _ = responses.send(health_message).await;
 }
+
+/// The health status of a daemon, as published on `tedge/health/<daemon>`.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct HealthStatus {
+    pub status: String,
+    pub pid: u32,
+    #[serde(default)]
+    pub time: i64,
+}
+
+/// A health check request, answered by an `up` status published after the request.
+///
+/// The former statuses, notably the retained one, are ignored.
+#[derive(Debug)]
+pub struct HealthCheckRequest {
+    daemon_name: String,
+    time: i64,
+}
+
+impl HealthCheckRequest {
+    pub fn new(daemon_name: &str) -> Self {
+        HealthCheckRequest {
+            daemon_name: daemon_name.to_string(),
+            time: OffsetDateTime::now_utc().unix_timestamp(),
+        }
+    }
+}
+
+impl Request for HealthCheckRequest {
+    type Response = HealthStatus;
+
+    fn to_message(&self) -> Result<Message, SoftwareError> {
+        let topic = Topic::new_unchecked(&format!("tedge/health-check/{}", self.daemon_name));
+        Ok(Message::new(&topic, ""))
+    }
+
+    fn response_from(&self, message: &Message) -> Option<HealthStatus> {
+        if message.topic != health_status_topic(&self.daemon_name) {
+            return None;
+        }
+        let health_status: HealthStatus = serde_json::from_slice(message.payload_bytes()).ok()?;
+        if health_status.status == "up" && health_status.time >= self.time {
+            Some(health_status)
+        } else {
+            None
+        }
+    }
+}
+
+impl Response for HealthStatus {
+    fn is_final(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    #[test]
+    fn health_status_messages_are_retained() {
+        let up = health_status_up_message("tedge-agent");
+        assert_eq!(up.topic.name, "tedge/health/tedge-agent");
+        assert!(up.retain);
+        let status: Value = serde_json::from_str(up.payload_str().unwrap()).unwrap();
+        assert_eq!(status["status"], "up");
+
+        let down = health_status_down_message("tedge-agent");
+        assert_eq!(down.topic.name, "tedge/health/tedge-agent");
+        assert!(down.retain);
+        let status: Value = serde_json::from_str(down.payload_str().unwrap()).unwrap();
+        assert_eq!(status["status"], "down");
+    }
+
+    #[test]
+    fn health_check_ignores_stale_and_down_statuses() {
+        let request = HealthCheckRequest {
+            daemon_name: "test-service".into(),
+            time: 3,
+        };
+        let health_message = |service: &str, status: &str, time: i64| {
+            Message::new(
+                &health_status_topic(service),
+                json!({ "status": status, "pid": 123, "time": time }).to_string(),
+            )
+        };
+
+        let response = request.response_from(&health_message("test-service", "up", 3));
+        assert_eq!(response.unwrap().pid, 123);
+        assert!(request
+            .response_from(&health_message("test-service", "up", 2))
+            .is_none());
+        assert!(request
+            .response_from(&health_message("test-service", "down", 4))
+            .is_none());
+        assert!(request
+            .response_from(&health_message("other-service", "up", 4))
+            .is_none());
+    }
+}
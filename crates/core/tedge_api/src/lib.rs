@@ -11,15 +11,18 @@ pub mod group;
 pub mod health;
 pub mod measurement;
 pub mod parser;
+pub mod request;
 pub mod serialize;
 pub mod utils;
 
 pub use download::*;
 pub use error::*;
 pub use messages::{
-    control_filter_topic, software_filter_topic, Jsonify, OperationStatus, RestartOperationRequest,
-    RestartOperationResponse, SoftwareListRequest, SoftwareListResponse, SoftwareRequestResponse,
-    SoftwareUpdateRequest, SoftwareUpdateResponse,
+    control_filter_topic, firmware_filter_topic, software_filter_topic, CancelOperationRequest,
+    FirmwareUpdateRequest, FirmwareUpdateResponse, Jsonify, OperationStatus,
+    RestartOperationRequest, RestartOperationResponse, SoftwareListRequest, SoftwareListResponse,
+    SoftwareRequestResponse, SoftwareRequestResponseSoftwareList, SoftwareUpdateRequest,
+    SoftwareUpdateResponse,
 };
 pub use software::*;
 
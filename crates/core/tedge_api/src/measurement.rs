@@ -22,6 +22,9 @@ use time::OffsetDateTime;
 ///
 ///     #[error("Unexpected start of group")]
 ///     UnexpectedStartOfGroup,
+///
+///     #[error(transparent)]
+///     UnsupportedValue(#[from] UnsupportedMeasurementValue),
 /// }
 ///
 /// impl MeasurementVisitor for MeasurementPrinter {
@@ -38,11 +41,15 @@ use time::OffsetDateTime;
 ///     }
 ///
 ///     fn visit_measurement(&mut self, name: &str, value: f64) -> Result<(), Self::Error> {
-///         if let Some(group_name) = self.group.as_ref() {
-///             Ok(println!("{}.{} = {}", group_name, name, value))
-///         } else {
-///             Ok(println!("{} = {}", name, value))
-///         }
+///         self.visit_any_measurement(name, value)
+///     }
+///
+///     fn visit_text_measurement(&mut self, name: &str, value: &str) -> Result<(), Self::Error> {
+///         self.visit_any_measurement(name, value)
+///     }
+///
+///     fn visit_boolean_measurement(&mut self, name: &str, value: bool) -> Result<(), Self::Error> {
+///         self.visit_any_measurement(name, value)
 ///     }
 ///
 ///     fn visit_start_group(&mut self, group: &str) -> Result<(), Self::Error> {
@@ -63,10 +70,23 @@ use time::OffsetDateTime;
 ///         }
 ///     }
 /// }
+///
+/// impl MeasurementPrinter {
+///     fn visit_any_measurement(&mut self, name: &str, value: impl std::fmt::Display) -> Result<(), MeasurementError> {
+///         if let Some(group_name) = self.group.as_ref() {
+///             Ok(println!("{}.{} = {}", group_name, name, value))
+///         } else {
+///             Ok(println!("{} = {}", name, value))
+///         }
+///     }
+/// }
 /// ```
 pub trait MeasurementVisitor {
     /// Error type specific to this visitor.
-    type Error: std::error::Error + std::fmt::Debug;
+    ///
+    /// The conversion from `UnsupportedMeasurementValue` is used by the default implementations
+    /// of the methods visiting the kinds of values not supported by the visitor.
+    type Error: std::error::Error + std::fmt::Debug + From<UnsupportedMeasurementValue>;
 
     /// Set the timestamp shared by all the measurements of this series.
     fn visit_timestamp(&mut self, value: OffsetDateTime) -> Result<(), Self::Error>;
@@ -74,6 +94,43 @@ pub trait MeasurementVisitor {
     /// Add a new measurement, attached to the current group if any.
     fn visit_measurement(&mut self, name: &str, value: f64) -> Result<(), Self::Error>;
 
+    /// Add a new text measurement, attached to the current group if any.
+    /// Defaults to an `UnsupportedMeasurementValue` error.
+    fn visit_text_measurement(&mut self, name: &str, _value: &str) -> Result<(), Self::Error> {
+        Err(UnsupportedMeasurementValue::new(name, "text").into())
+    }
+
+    /// Add a new boolean measurement, attached to the current group if any.
+    /// Defaults to an `UnsupportedMeasurementValue` error.
+    fn visit_boolean_measurement(&mut self, name: &str, _value: bool) -> Result<(), Self::Error> {
+        Err(UnsupportedMeasurementValue::new(name, "boolean").into())
+    }
+
+    /// Add a new measurement of any kind, attached to the current group if any.
+    /// Defaults to the `visit_*_measurement` method matching the kind of value.
+    fn visit_measurement_value(
+        &mut self,
+        name: &str,
+        value: &MeasurementValue,
+    ) -> Result<(), Self::Error> {
+        match value {
+            MeasurementValue::Number(value) => self.visit_measurement(name, *value),
+            MeasurementValue::Text(value) => self.visit_text_measurement(name, value),
+            MeasurementValue::Boolean(value) => self.visit_boolean_measurement(name, *value),
+        }
+    }
+
+    /// Add a new measurement along with its unit, attached to the current group if any.
+    /// Defaults to `visit_measurement_value`, ignoring the unit.
+    fn visit_measurement_with_unit(
+        &mut self,
+        name: &str,
+        value: &MeasurementValue,
+        _unit: &str,
+    ) -> Result<(), Self::Error> {
+        self.visit_measurement_value(name, value)
+    }
+
     /// Start to gather measurements for a group.
     fn visit_start_group(&mut self, group: &str) -> Result<(), Self::Error>;
 
@@ -94,3 +151,120 @@ pub trait MeasurementVisitor {
         Ok(())
     }
 }
+
+/// Error returned by a visitor for a kind of measurement value it doesn't support.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+#[error("Unsupported {kind} value for the measurement {name:?}")]
+pub struct UnsupportedMeasurementValue {
+    pub name: String,
+    pub kind: &'static str,
+}
+
+impl UnsupportedMeasurementValue {
+    pub fn new(name: &str, kind: &'static str) -> Self {
+        UnsupportedMeasurementValue {
+            name: name.into(),
+            kind,
+        }
+    }
+}
+
+/// The value of a single measurement: a number, a text or a boolean.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MeasurementValue {
+    Number(f64),
+    Text(String),
+    Boolean(bool),
+}
+
+impl MeasurementValue {
+    /// Returns the numeric value, if this is a number.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            MeasurementValue::Number(value) => Some(*value),
+            _ => None,
+        }
+    }
+}
+
+impl From<f64> for MeasurementValue {
+    fn from(value: f64) -> Self {
+        MeasurementValue::Number(value)
+    }
+}
+
+impl From<&str> for MeasurementValue {
+    fn from(value: &str) -> Self {
+        MeasurementValue::Text(value.into())
+    }
+}
+
+impl From<String> for MeasurementValue {
+    fn from(value: String) -> Self {
+        MeasurementValue::Text(value)
+    }
+}
+
+impl From<bool> for MeasurementValue {
+    fn from(value: bool) -> Self {
+        MeasurementValue::Boolean(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_matches::assert_matches;
+
+    #[derive(thiserror::Error, Debug)]
+    enum NumericError {
+        #[error(transparent)]
+        UnsupportedValue(#[from] UnsupportedMeasurementValue),
+    }
+
+    /// A visitor implementing only the methods required for numeric measurements.
+    #[derive(Default)]
+    struct NumericVisitor {
+        measurements: Vec<(String, f64)>,
+    }
+
+    impl MeasurementVisitor for NumericVisitor {
+        type Error = NumericError;
+
+        fn visit_timestamp(&mut self, _value: OffsetDateTime) -> Result<(), Self::Error> {
+            Ok(())
+        }
+
+        fn visit_measurement(&mut self, name: &str, value: f64) -> Result<(), Self::Error> {
+            self.measurements.push((name.into(), value));
+            Ok(())
+        }
+
+        fn visit_start_group(&mut self, _group: &str) -> Result<(), Self::Error> {
+            Ok(())
+        }
+
+        fn visit_end_group(&mut self) -> Result<(), Self::Error> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn text_and_boolean_values_are_unsupported_by_default() {
+        let mut visitor = NumericVisitor::default();
+
+        visitor
+            .visit_measurement_value("temperature", &MeasurementValue::Number(21.5))
+            .unwrap();
+        assert_eq!(visitor.measurements, vec![("temperature".into(), 21.5)]);
+
+        assert_matches!(
+            visitor.visit_measurement_value("state", &"RUNNING".into()),
+            Err(NumericError::UnsupportedValue(UnsupportedMeasurementValue { name, kind: "text" })) if name == "state"
+        );
+        assert_matches!(
+            visitor.visit_boolean_measurement("open", true),
+            Err(NumericError::UnsupportedValue(UnsupportedMeasurementValue { name, kind: "boolean" })) if name == "open"
+        );
+    }
+}
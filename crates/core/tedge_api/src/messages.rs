@@ -1,5 +1,5 @@
 use crate::{error::SoftwareError, software::*};
-use download::DownloadInfo;
+use download::{Auth, DownloadInfo};
 use nanoid::nanoid;
 use serde::{Deserialize, Serialize};
 
@@ -33,6 +33,10 @@ pub const fn control_filter_topic() -> &'static str {
     "tedge/commands/req/control/#"
 }
 
+pub const fn firmware_filter_topic() -> &'static str {
+    "tedge/commands/req/firmware/#"
+}
+
 /// Message payload definition for SoftwareList request.
 #[derive(Debug, Deserialize, Serialize, Eq, PartialEq)]
 #[serde(deny_unknown_fields)]
@@ -524,6 +528,140 @@ impl RestartOperationResponse {
     }
 }
 
+/// Message payload definition for firmware update request.
+///
+/// The firmware image is downloaded from the `url`, installed and activated by restarting the device.
+#[derive(Debug, Clone, Deserialize, Serialize, Eq, PartialEq)]
+#[serde(deny_unknown_fields)]
+#[serde(rename_all = "camelCase")]
+pub struct FirmwareUpdateRequest {
+    pub id: String,
+    pub name: String,
+    pub version: String,
+    pub url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub auth: Option<Auth>,
+}
+
+impl<'a> Jsonify<'a> for FirmwareUpdateRequest {}
+
+impl FirmwareUpdateRequest {
+    pub fn new(name: &str, version: &str, url: &str) -> FirmwareUpdateRequest {
+        let id = nanoid!();
+        FirmwareUpdateRequest::new_with_id(&id, name, version, url)
+    }
+
+    pub fn new_with_id(id: &str, name: &str, version: &str, url: &str) -> FirmwareUpdateRequest {
+        FirmwareUpdateRequest {
+            id: id.to_string(),
+            name: name.to_string(),
+            version: version.to_string(),
+            url: url.to_string(),
+            auth: None,
+        }
+    }
+
+    pub fn with_auth(self, auth: Auth) -> Self {
+        Self {
+            auth: Some(auth),
+            ..self
+        }
+    }
+
+    /// Where and how to download the firmware image.
+    pub fn download_info(&self) -> DownloadInfo {
+        let download_info = DownloadInfo::new(&self.url);
+        match &self.auth {
+            Some(auth) => download_info.with_auth(auth.clone()),
+            None => download_info,
+        }
+    }
+
+    pub fn topic_name() -> &'static str {
+        "tedge/commands/req/firmware/update"
+    }
+}
+
+/// Message payload definition for firmware update response.
+///
+/// The firmware is given along the response, so the installed firmware can be reported once successful.
+#[derive(Debug, Clone, Deserialize, Serialize, Eq, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct FirmwareUpdateResponse {
+    pub id: String,
+    pub status: OperationStatus,
+    pub name: String,
+    pub version: String,
+    pub url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+impl<'a> Jsonify<'a> for FirmwareUpdateResponse {}
+
+impl FirmwareUpdateResponse {
+    pub fn new(req: &FirmwareUpdateRequest) -> Self {
+        Self {
+            id: req.id.clone(),
+            status: OperationStatus::Executing,
+            name: req.name.clone(),
+            version: req.version.clone(),
+            url: req.url.clone(),
+            reason: None,
+        }
+    }
+
+    pub fn with_status(self, status: OperationStatus) -> Self {
+        Self { status, ..self }
+    }
+
+    pub fn with_error(self, reason: &str) -> Self {
+        Self {
+            status: OperationStatus::Failed,
+            reason: Some(reason.into()),
+            ..self
+        }
+    }
+
+    pub fn topic_name() -> &'static str {
+        "tedge/commands/res/firmware/update"
+    }
+
+    pub fn status(&self) -> OperationStatus {
+        self.status
+    }
+
+    pub fn error(&self) -> Option<String> {
+        self.reason.clone()
+    }
+}
+
+/// Message payload definition for the cancellation of an operation.
+///
+/// The `id` is the one of the request to be cancelled.
+#[derive(Debug, Deserialize, Serialize, Eq, PartialEq)]
+#[serde(deny_unknown_fields)]
+#[serde(rename_all = "camelCase")]
+pub struct CancelOperationRequest {
+    pub id: String,
+}
+
+impl<'a> Jsonify<'a> for CancelOperationRequest {}
+
+impl CancelOperationRequest {
+    pub fn new_with_id(id: &str) -> CancelOperationRequest {
+        CancelOperationRequest { id: id.to_string() }
+    }
+
+    pub fn software_update_topic_name() -> &'static str {
+        "tedge/commands/req/software/update/cancel"
+    }
+
+    pub fn restart_topic_name() -> &'static str {
+        "tedge/commands/req/control/restart/cancel"
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -544,6 +682,41 @@ mod tests {
         assert_eq!(request, de_request);
     }
 
+    #[test]
+    fn serde_cancel_operation_request() {
+        let request = CancelOperationRequest::new_with_id("1234");
+        let expected_json = r#"{"id":"1234"}"#;
+
+        let actual_json = request.to_json().expect("Failed to serialize");
+
+        assert_eq!(actual_json, expected_json);
+
+        let de_request =
+            CancelOperationRequest::from_json(actual_json.as_str()).expect("failed to deserialize");
+        assert_eq!(request, de_request);
+    }
+
+    #[test]
+    fn serde_firmware_update_messages() {
+        let request = FirmwareUpdateRequest::new_with_id(
+            "1234",
+            "image",
+            "1.0",
+            "https://example.com/image.raucb",
+        );
+        let expected_json = r#"{"id":"1234","name":"image","version":"1.0","url":"https://example.com/image.raucb"}"#;
+        let actual_json = request.to_json().expect("Failed to serialize");
+        assert_eq!(actual_json, expected_json);
+
+        let de_request =
+            FirmwareUpdateRequest::from_json(actual_json.as_str()).expect("failed to deserialize");
+        assert_eq!(request, de_request);
+
+        let response = FirmwareUpdateResponse::new(&request).with_error("Invalid image");
+        let expected_json = r#"{"id":"1234","status":"failed","name":"image","version":"1.0","url":"https://example.com/image.raucb","reason":"Invalid image"}"#;
+        assert_eq!(response.to_json().unwrap(), expected_json);
+    }
+
     #[test]
     fn serde_software_request_update() {
         let debian_module1 = SoftwareModuleItem {
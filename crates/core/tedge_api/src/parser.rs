@@ -1,10 +1,11 @@
 //! A streaming, almost non-allocating [^1] ThinEdge JSON parser using `serde`.
 //!
-//! [^1]: It only allocates in presence of escaped strings as keys.
+//! [^1]: It only allocates in presence of escaped strings as keys,
+//!       and for the `value` and `unit` entries of a measurement object.
 //!
-use crate::measurement::MeasurementVisitor;
+use crate::measurement::{MeasurementValue, MeasurementVisitor};
 use serde::{
-    de::{self, DeserializeSeed, MapAccess},
+    de::{self, DeserializeSeed, MapAccess, Visitor},
     Deserializer,
 };
 use std::borrow::Cow;
@@ -42,8 +43,10 @@ pub struct ThinEdgeJsonParserError {
 /// ```grammar
 /// {
 ///     time?: string,
-///     [key: string]: number | {[key: string]: number},
+///     [key: string]: value | {[key: string]: value},
 /// }
+///
+/// value = number | string | boolean | {value: number | string | boolean, unit: string}
 /// ```
 ///
 struct ThinEdgeJsonParser<'vis, T>
@@ -53,17 +56,20 @@ where
     visitor: &'vis mut T,
 }
 
-/// Parses a single value (number) or multi-value measurement:
+/// Parses a single value (number, string or boolean, possibly with a unit) or multi-value measurement:
 ///
 /// ```grammar
-/// number | {[key: string]: number}
+/// value | {[key: string]: value}
 /// ```
 ///
+/// A measurement object with only a `value` and a `unit` of type string,
+/// is a single value with a unit and not a multi-value measurement.
+///
 struct ThinEdgeValueParser<'key, 'vis, T> {
     /// Recursion depth.
     ///
-    /// When `depth = 0`, we accept both number or multi-value measurements.
-    /// When `depth > 0`, we only accept numbers.
+    /// When `depth = 0`, we accept both single-value or multi-value measurements.
+    /// When `depth > 0`, we only accept single values.
     depth: usize,
     /// The associated key of the single or multi-value measurement.
     key: Cow<'key, str>,
@@ -144,24 +150,40 @@ where
         }
     }
 
-    /// Parses a multi-value measurement: `{[string]: number}` or fails if depth > 0.
+    /// Parses a multi-value measurement: `{[string]: value}` or fails if depth > 0,
+    /// or a single value with a unit: `{value: value, unit: string}`.
     ///
-    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    /// A single value with a unit can only be told apart from a multi-value measurement
+    /// with `value` or `unit` measurements once all the keys have been read.
+    /// Hence, the `value` and `unit` entries are buffered till another key proves
+    /// that this is a multi-value measurement.
+    fn visit_map<A>(mut self, mut map: A) -> Result<Self::Value, A::Error>
     where
         A: MapAccess<'de>,
     {
-        // To support arbitrarily nested measurements remove the following line.
-        if self.depth > 0 {
-            return Err(de::Error::custom("Expect single-value measurement"));
-        }
-
-        self.visitor
-            .visit_start_group(self.key.as_ref())
-            .map_err(de::Error::custom)?;
-
-        let mut measurements_count: usize = 0;
+        let mut value: Option<MeasurementValue> = None;
+        let mut unit: Option<MeasurementValue> = None;
+        let mut is_group = false;
+
+        while let Some(key) = map.next_key::<Cow<str>>()? {
+            if !is_group {
+                match key.as_ref() {
+                    "value" if value.is_none() => {
+                        value = Some(map.next_value_seed(MeasurementValueParser { key: &key })?);
+                        continue;
+                    }
+                    "unit" if unit.is_none() => {
+                        unit = Some(map.next_value_seed(MeasurementValueParser { key: &key })?);
+                        continue;
+                    }
+                    _ => {
+                        self.start_group(value.take(), unit.take())
+                            .map_err(de::Error::custom)?;
+                        is_group = true;
+                    }
+                }
+            }
 
-        while let Some(key) = map.next_key()? {
             let parser = ThinEdgeValueParser {
                 depth: self.depth + 1,
                 key,
@@ -169,19 +191,157 @@ where
             };
 
             map.next_value_seed(parser)?;
-            measurements_count += 1;
         }
 
-        if measurements_count == 0 {
-            return Err(de::Error::custom(invalid_empty_measurement(&self.key)));
+        if is_group {
+            self.visitor.visit_end_group().map_err(de::Error::custom)?;
+            return Ok(());
+        }
+
+        match (value, unit) {
+            (Some(value), Some(MeasurementValue::Text(unit))) => {
+                self.visitor
+                    .visit_measurement_with_unit(self.key.as_ref(), &value, &unit)
+                    .map_err(de::Error::custom)?;
+            }
+            (None, None) if self.depth == 0 => {
+                return Err(de::Error::custom(invalid_empty_measurement(&self.key)));
+            }
+            (value, unit) => {
+                // A multi-value measurement with only `value` or `unit` measurements
+                self.start_group(value, unit).map_err(de::Error::custom)?;
+                self.visitor.visit_end_group().map_err(de::Error::custom)?;
+            }
         }
 
-        self.visitor.visit_end_group().map_err(de::Error::custom)?;
+        Ok(())
+    }
+
+    /// Parses a single-value numeric measurement.
+    ///
+    /// The number is checked by `MeasurementValueParser`.
+    fn visit_f64<E>(self, value: f64) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        let value = MeasurementValueParser { key: &self.key }.visit_f64(value)?;
+        self.visit_value(value)
+    }
+
+    /// Parses a single-value numeric measurement. See `visit_f64`.
+    fn visit_i64<E>(self, value: i64) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        let value = MeasurementValueParser { key: &self.key }.visit_i64(value)?;
+        self.visit_value(value)
+    }
+
+    /// Parses a single-value numeric measurement. See `visit_f64`.
+    fn visit_u64<E>(self, value: u64) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        let value = MeasurementValueParser { key: &self.key }.visit_u64(value)?;
+        self.visit_value(value)
+    }
+
+    /// Parses a single-value text measurement.
+    ///
+    /// Escaped strings are also handled here,
+    /// serde_json calling `visit_borrowed_str` only for unescaped strings,
+    /// which defaults to `visit_str`.
+    fn visit_str<E>(self, value: &str) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        self.visitor
+            .visit_text_measurement(self.key.as_ref(), value)
+            .map_err(de::Error::custom)?;
+
+        Ok(())
+    }
+
+    /// Parses a single-value boolean measurement.
+    fn visit_bool<E>(self, value: bool) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        self.visitor
+            .visit_boolean_measurement(self.key.as_ref(), value)
+            .map_err(de::Error::custom)?;
 
         Ok(())
     }
+}
+
+impl<'key, 'vis, T> ThinEdgeValueParser<'key, 'vis, T>
+where
+    T: MeasurementVisitor,
+{
+    /// Forward a single value to the visitor.
+    fn visit_value<E>(self, value: MeasurementValue) -> Result<(), E>
+    where
+        E: serde::de::Error,
+    {
+        self.visitor
+            .visit_measurement_value(self.key.as_ref(), &value)
+            .map_err(de::Error::custom)
+    }
 
-    /// Parses a single-value measurement.
+    /// Start a multi-value measurement, with the `value` and `unit` measurements read so far.
+    ///
+    /// As a `{value, unit}` object is accepted at any depth,
+    /// a nested multi-value measurement is only rejected once its first other key has been read.
+    fn start_group(
+        &mut self,
+        value: Option<MeasurementValue>,
+        unit: Option<MeasurementValue>,
+    ) -> Result<(), String> {
+        // To support arbitrarily nested measurements remove the following lines.
+        if self.depth > 0 {
+            return Err("Expect single-value measurement".into());
+        }
+
+        self.visitor
+            .visit_start_group(self.key.as_ref())
+            .map_err(|err| err.to_string())?;
+
+        for (name, value) in [("value", value), ("unit", unit)] {
+            if let Some(value) = value {
+                self.visitor
+                    .visit_measurement_value(name, &value)
+                    .map_err(|err| err.to_string())?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Parses a single value, number, string or boolean, returning this value.
+///
+/// Used for the `value` and `unit` entries of a measurement object,
+/// that have to be buffered.
+struct MeasurementValueParser<'key> {
+    key: &'key str,
+}
+
+impl<'key, 'de> de::Visitor<'de> for MeasurementValueParser<'key> {
+    type Value = MeasurementValue;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("ThinEdge single-value measurement")
+    }
+
+    fn visit_map<A>(self, _map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        Err(de::Error::custom("Expect single-value measurement"))
+    }
+
+    /// Parses a number, rejecting the infinite, NaN and subnormal values.
     ///
     /// `serde_json` requires us to handle three cases:
     ///     - floating point numbers (f64),
@@ -199,39 +359,60 @@ where
         E: serde::de::Error,
     {
         if value != 0.0 && !value.is_normal() {
-            return Err(de::Error::custom(invalid_json_number(&self.key)));
+            return Err(de::Error::custom(invalid_json_number(self.key)));
         }
 
-        self.visitor
-            .visit_measurement(self.key.as_ref(), value)
-            .map_err(de::Error::custom)?;
-
-        Ok(())
+        Ok(MeasurementValue::Number(value))
     }
 
-    /// Parses a single-value measurement. See `visit_f64`.
+    /// Parses a number. See `visit_f64`.
     fn visit_i64<E>(self, value: i64) -> Result<Self::Value, E>
     where
         E: serde::de::Error,
     {
         let value = i32::try_from(value)
-            .map_err(|_| de::Error::custom(invalid_json_number(&self.key)))?
+            .map_err(|_| de::Error::custom(invalid_json_number(self.key)))?
             .into();
 
         self.visit_f64(value)
     }
 
-    /// Parses a single-value measurement. See `visit_f64`.
+    /// Parses a number. See `visit_f64`.
     fn visit_u64<E>(self, value: u64) -> Result<Self::Value, E>
     where
         E: serde::de::Error,
     {
         let value = u32::try_from(value)
-            .map_err(|_| de::Error::custom(invalid_json_number(&self.key)))?
+            .map_err(|_| de::Error::custom(invalid_json_number(self.key)))?
             .into();
 
         self.visit_f64(value)
     }
+
+    fn visit_str<E>(self, value: &str) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        Ok(MeasurementValue::Text(value.into()))
+    }
+
+    fn visit_bool<E>(self, value: bool) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        Ok(MeasurementValue::Boolean(value))
+    }
+}
+
+impl<'key, 'de> DeserializeSeed<'de> for MeasurementValueParser<'key> {
+    type Value = MeasurementValue;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(self)
+    }
 }
 
 /// The `DeserializeSeed` trait enables us to inject state required for deserialization. In our case
@@ -341,6 +522,85 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn it_deserializes_text_and_boolean_values() -> anyhow::Result<()> {
+        use crate::builder::ThinEdgeJsonBuilder;
+        let input = r#"{
+        "state": "RUNNING",
+        "door": {
+            "open": true,
+            "label": "front \"door\""
+        },
+        "temperature": 24
+    }"#;
+
+        let mut builder = ThinEdgeJsonBuilder::default();
+
+        parse_str(input, &mut builder)?;
+
+        let output = builder.done()?;
+
+        assert_eq!(
+            output.values,
+            vec![
+                ("state", "RUNNING").into(),
+                (
+                    "door",
+                    vec![("open", true).into(), ("label", r#"front "door""#).into()]
+                )
+                    .into(),
+                ("temperature", 24.0).into(),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn it_deserializes_values_with_units() -> anyhow::Result<()> {
+        use crate::builder::ThinEdgeJsonBuilder;
+        use crate::data::SingleValueMeasurement;
+        let input = r#"{
+        "temperature": {"value": 24, "unit": "°C"},
+        "location": {
+            "altitude": {"unit": "m", "value": 98.6},
+            "city": "Paris"
+        },
+        "sensor": {
+            "value": 42,
+            "raw": 4200
+        }
+    }"#;
+
+        let mut builder = ThinEdgeJsonBuilder::default();
+
+        parse_str(input, &mut builder)?;
+
+        let output = builder.done()?;
+
+        assert_eq!(
+            output.values,
+            vec![
+                SingleValueMeasurement::from(("temperature", 24.0))
+                    .with_unit("°C")
+                    .into(),
+                (
+                    "location",
+                    vec![
+                        SingleValueMeasurement::from(("altitude", 98.6)).with_unit("m"),
+                        ("city", "Paris").into()
+                    ]
+                )
+                    .into(),
+                (
+                    "sensor",
+                    vec![("value", 42.0).into(), ("raw", 4200.0).into()]
+                )
+                    .into(),
+            ]
+        );
+        Ok(())
+    }
+
     #[test]
     fn it_shows_input_excerpt_on_error() -> anyhow::Result<()> {
         use crate::builder::ThinEdgeJsonBuilder;
@@ -0,0 +1,386 @@
+//! Request/response over MQTT.
+//!
+//! A request is published on a request topic and answered on a response topic,
+//! possibly with progress responses (`executing`) before the final response (`successful` or `failed`).
+//! The responses are correlated to their request: by id for the operations, by time for the health checks.
+//!
+//! This client is meant for components awaiting the responses of their own requests, as the watchdog.
+//! It doesn't fit the agent, which answers the requests rather than sending them,
+//! nor the configuration plugin, which handles the responses of the child devices as they come in its event loop.
+
+use crate::{
+    FirmwareUpdateRequest, FirmwareUpdateResponse, Jsonify, OperationStatus,
+    RestartOperationRequest, RestartOperationResponse, SoftwareError, SoftwareListRequest,
+    SoftwareListResponse, SoftwareUpdateRequest, SoftwareUpdateResponse,
+};
+use mqtt_channel::{Message, MqttError, PubChannel, StreamExt, SubChannel, Topic};
+use serde::de::DeserializeOwned;
+use std::collections::VecDeque;
+use std::time::Duration;
+
+/// How long a response is awaited by default.
+pub const DEFAULT_RESPONSE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// How many received messages are kept for the requests that are not awaited yet.
+const MAX_PENDING_RESPONSES: usize = 64;
+
+#[derive(thiserror::Error, Debug)]
+pub enum RequestError {
+    #[error("No response received within {0:?}")]
+    Timeout(Duration),
+
+    #[error("The connection has been closed while waiting for a response")]
+    ConnectionClosed,
+
+    #[error(transparent)]
+    FromMqtt(#[from] MqttError),
+
+    #[error(transparent)]
+    FromSoftware(#[from] SoftwareError),
+}
+
+/// A request sent over MQTT.
+pub trait Request {
+    type Response: Response;
+
+    /// The message to be published for this request.
+    fn to_message(&self) -> Result<Message, SoftwareError>;
+
+    /// The response to this request carried by a message, if any.
+    ///
+    /// Returns `None` for the messages that are not a response to this request,
+    /// notably the responses to other requests.
+    fn response_from(&self, message: &Message) -> Option<Self::Response>;
+}
+
+/// A response to a request.
+pub trait Response {
+    /// `false` for a progress response, `true` for the final response.
+    fn is_final(&self) -> bool;
+}
+
+/// Send requests and await their responses, over a pair of MQTT channels.
+///
+/// The response channel must be subscribed to the response topics of the requests sent with this client.
+///
+/// Several requests can be in flight: the messages received while awaiting the responses to a request
+/// are kept for the other requests, up to the `MAX_PENDING_RESPONSES` latest ones.
+pub struct RequestClient<P, S> {
+    publisher: P,
+    responses: S,
+    pending: VecDeque<Message>,
+    timeout: Duration,
+}
+
+impl<P: PubChannel, S: SubChannel> RequestClient<P, S> {
+    pub fn new(publisher: P, responses: S) -> Self {
+        RequestClient {
+            publisher,
+            responses,
+            pending: VecDeque::new(),
+            timeout: DEFAULT_RESPONSE_TIMEOUT,
+        }
+    }
+
+    /// Set how long each response is awaited.
+    pub fn with_timeout(self, timeout: Duration) -> Self {
+        Self { timeout, ..self }
+    }
+
+    /// Publish a request, without waiting for any response.
+    pub async fn send(&mut self, request: &impl Request) -> Result<(), RequestError> {
+        let message = request.to_message()?;
+        self.publisher.publish(message).await?;
+        Ok(())
+    }
+
+    /// Await the next response to a request, either a progress or the final response.
+    ///
+    /// The responses already received for this request are returned first, in order,
+    /// the other messages being kept for the other requests.
+    pub async fn next_response<R: Request>(
+        &mut self,
+        request: &R,
+    ) -> Result<R::Response, RequestError> {
+        let pending_response = self
+            .pending
+            .iter()
+            .enumerate()
+            .find_map(|(index, message)| Some((index, request.response_from(message)?)));
+        if let Some((index, response)) = pending_response {
+            self.pending.remove(index);
+            return Ok(response);
+        }
+
+        let timeout = self.timeout;
+        let responses = &mut self.responses;
+        let pending = &mut self.pending;
+        let response = async {
+            while let Some(message) = responses.next().await {
+                if let Some(response) = request.response_from(&message) {
+                    return Ok(response);
+                }
+                if pending.len() == MAX_PENDING_RESPONSES {
+                    pending.pop_front();
+                }
+                pending.push_back(message);
+            }
+            Err(RequestError::ConnectionClosed)
+        };
+
+        tokio::time::timeout(timeout, response)
+            .await
+            .map_err(|_| RequestError::Timeout(timeout))?
+    }
+
+    /// Send a request and await its final response.
+    ///
+    /// The progress responses are skipped, each resetting the timeout.
+    pub async fn request<R: Request>(&mut self, request: &R) -> Result<R::Response, RequestError> {
+        self.send(request).await?;
+        loop {
+            let response = self.next_response(request).await?;
+            if response.is_final() {
+                return Ok(response);
+            }
+        }
+    }
+}
+
+fn operation_message<'a>(
+    topic: &str,
+    request: &impl Jsonify<'a>,
+) -> Result<Message, SoftwareError> {
+    Ok(Message::new(
+        &Topic::new_unchecked(topic),
+        request.to_bytes()?,
+    ))
+}
+
+fn operation_response<T: DeserializeOwned>(message: &Message, topic: &str) -> Option<T> {
+    if message.topic.name != topic {
+        return None;
+    }
+    serde_json::from_slice(message.payload_bytes()).ok()
+}
+
+impl Request for SoftwareListRequest {
+    type Response = SoftwareListResponse;
+
+    fn to_message(&self) -> Result<Message, SoftwareError> {
+        operation_message(Self::topic_name(), self)
+    }
+
+    fn response_from(&self, message: &Message) -> Option<SoftwareListResponse> {
+        operation_response(message, SoftwareListResponse::topic_name())
+            .filter(|response: &SoftwareListResponse| response.id() == self.id)
+    }
+}
+
+impl Response for SoftwareListResponse {
+    fn is_final(&self) -> bool {
+        self.status() != OperationStatus::Executing
+    }
+}
+
+impl Request for SoftwareUpdateRequest {
+    type Response = SoftwareUpdateResponse;
+
+    fn to_message(&self) -> Result<Message, SoftwareError> {
+        operation_message(Self::topic_name(), self)
+    }
+
+    fn response_from(&self, message: &Message) -> Option<SoftwareUpdateResponse> {
+        operation_response(message, SoftwareUpdateResponse::topic_name())
+            .filter(|response: &SoftwareUpdateResponse| response.id() == self.id)
+    }
+}
+
+impl Response for SoftwareUpdateResponse {
+    fn is_final(&self) -> bool {
+        self.status() != OperationStatus::Executing
+    }
+}
+
+impl Request for RestartOperationRequest {
+    type Response = RestartOperationResponse;
+
+    fn to_message(&self) -> Result<Message, SoftwareError> {
+        operation_message(Self::topic_name(), self)
+    }
+
+    fn response_from(&self, message: &Message) -> Option<RestartOperationResponse> {
+        operation_response(message, RestartOperationResponse::topic_name())
+            .filter(|response: &RestartOperationResponse| response.id == self.id)
+    }
+}
+
+impl Response for RestartOperationResponse {
+    fn is_final(&self) -> bool {
+        self.status() != OperationStatus::Executing
+    }
+}
+
+impl Request for FirmwareUpdateRequest {
+    type Response = FirmwareUpdateResponse;
+
+    fn to_message(&self) -> Result<Message, SoftwareError> {
+        operation_message(Self::topic_name(), self)
+    }
+
+    fn response_from(&self, message: &Message) -> Option<FirmwareUpdateResponse> {
+        operation_response(message, FirmwareUpdateResponse::topic_name())
+            .filter(|response: &FirmwareUpdateResponse| response.id == self.id)
+    }
+}
+
+impl Response for FirmwareUpdateResponse {
+    fn is_final(&self) -> bool {
+        self.status() != OperationStatus::Executing
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_matches::assert_matches;
+    use futures::channel::mpsc;
+
+    fn software_list_response(id: &str, status: &str) -> Message {
+        Message::new(
+            &Topic::new_unchecked(SoftwareListResponse::topic_name()),
+            format!(r#"{{"id":"{id}","status":"{status}"}}"#),
+        )
+    }
+
+    #[tokio::test]
+    async fn request_awaits_the_final_correlated_response() {
+        let (requests, mut published) = mpsc::unbounded();
+        let (mut responses, received) = mpsc::unbounded();
+        let mut client = RequestClient::new(requests, received);
+
+        responses
+            .publish(software_list_response("other-request", "successful"))
+            .await
+            .unwrap();
+        responses
+            .publish(software_list_response("123", "executing"))
+            .await
+            .unwrap();
+        responses
+            .publish(software_list_response("123", "failed"))
+            .await
+            .unwrap();
+
+        let request = SoftwareListRequest::new_with_id("123");
+        let response = client.request(&request).await.unwrap();
+        assert_eq!(response.id(), "123");
+        assert_eq!(response.status(), OperationStatus::Failed);
+
+        let message = published.next().await.unwrap();
+        assert_eq!(message.topic.name, "tedge/commands/req/software/list");
+        assert_eq!(message.payload_str().unwrap(), r#"{"id":"123"}"#);
+    }
+
+    #[tokio::test]
+    async fn progress_responses_are_returned_by_next_response() {
+        let (requests, _published) = mpsc::unbounded();
+        let (mut responses, received) = mpsc::unbounded();
+        let mut client = RequestClient::new(requests, received);
+
+        responses
+            .publish(software_list_response("123", "executing"))
+            .await
+            .unwrap();
+        responses
+            .publish(software_list_response("123", "successful"))
+            .await
+            .unwrap();
+
+        let request = SoftwareListRequest::new_with_id("123");
+        client.send(&request).await.unwrap();
+
+        let response = client.next_response(&request).await.unwrap();
+        assert!(!response.is_final());
+        let response = client.next_response(&request).await.unwrap();
+        assert!(response.is_final());
+        assert_eq!(response.status(), OperationStatus::Successful);
+    }
+
+    #[tokio::test]
+    async fn responses_to_other_requests_are_kept_for_these_requests() {
+        let (requests, _published) = mpsc::unbounded();
+        let (mut responses, received) = mpsc::unbounded();
+        let mut client = RequestClient::new(requests, received);
+
+        let first = SoftwareListRequest::new_with_id("1");
+        let second = SoftwareListRequest::new_with_id("2");
+        client.send(&first).await.unwrap();
+        client.send(&second).await.unwrap();
+
+        responses
+            .publish(software_list_response("2", "successful"))
+            .await
+            .unwrap();
+        responses
+            .publish(software_list_response("1", "successful"))
+            .await
+            .unwrap();
+
+        let response = client.next_response(&first).await.unwrap();
+        assert_eq!(response.id(), "1");
+        let response = client.next_response(&second).await.unwrap();
+        assert_eq!(response.id(), "2");
+    }
+
+    #[tokio::test]
+    async fn restart_requests_are_correlated_to_the_responses_of_the_agent() {
+        let (requests, _published) = mpsc::unbounded();
+        let (mut responses, received) = mpsc::unbounded();
+        let mut client = RequestClient::new(requests, received);
+
+        // The restart responses of the agent are built from the request
+        let request = RestartOperationRequest::default();
+        let executing = RestartOperationResponse::new(&request);
+        let successful =
+            RestartOperationResponse::new(&request).with_status(OperationStatus::Successful);
+        for response in [executing, successful] {
+            responses
+                .publish(Message::new(
+                    &Topic::new_unchecked(RestartOperationResponse::topic_name()),
+                    response.to_bytes().unwrap(),
+                ))
+                .await
+                .unwrap();
+        }
+
+        let response = client.request(&request).await.unwrap();
+        assert_eq!(response.id, request.id);
+        assert_eq!(response.status(), OperationStatus::Successful);
+    }
+
+    #[tokio::test]
+    async fn request_times_out_without_a_correlated_response() {
+        let (requests, _published) = mpsc::unbounded();
+        let (mut responses, received) = mpsc::unbounded();
+        let mut client =
+            RequestClient::new(requests, received).with_timeout(Duration::from_millis(100));
+
+        responses
+            .publish(software_list_response("other-request", "successful"))
+            .await
+            .unwrap();
+
+        let request = SoftwareListRequest::new_with_id("123");
+        assert_matches!(
+            client.request(&request).await,
+            Err(RequestError::Timeout(_))
+        );
+
+        drop(responses);
+        assert_matches!(
+            client.next_response(&request).await,
+            Err(RequestError::ConnectionClosed)
+        );
+    }
+}
@@ -1,4 +1,4 @@
-use crate::measurement::MeasurementVisitor;
+use crate::measurement::{MeasurementValue, MeasurementVisitor, UnsupportedMeasurementValue};
 use json_writer::{JsonWriter, JsonWriterError};
 use time::{format_description, OffsetDateTime};
 
@@ -25,6 +25,9 @@ pub enum ThinEdgeJsonSerializationError {
 
     #[error(transparent)]
     JsonWriterError(#[from] JsonWriterError),
+
+    #[error(transparent)]
+    UnsupportedValue(#[from] UnsupportedMeasurementValue),
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -115,6 +118,33 @@ impl MeasurementVisitor for ThinEdgeJsonSerializer {
         Ok(())
     }
 
+    fn visit_text_measurement(&mut self, name: &str, value: &str) -> Result<(), Self::Error> {
+        self.json.write_key(name)?;
+        self.json.write_str(value)?;
+        Ok(())
+    }
+
+    fn visit_boolean_measurement(&mut self, name: &str, value: bool) -> Result<(), Self::Error> {
+        self.json.write_key(name)?;
+        self.json.write_bool(value)?;
+        Ok(())
+    }
+
+    fn visit_measurement_with_unit(
+        &mut self,
+        name: &str,
+        value: &MeasurementValue,
+        unit: &str,
+    ) -> Result<(), Self::Error> {
+        self.json.write_key(name)?;
+        self.json.write_open_obj();
+        self.visit_measurement_value("value", value)?;
+        self.json.write_key("unit")?;
+        self.json.write_str(unit)?;
+        self.json.write_close_obj();
+        Ok(())
+    }
+
     fn visit_start_group(&mut self, group: &str) -> Result<(), Self::Error> {
         if self.is_within_group {
             return Err(MeasurementStreamError::UnexpectedStartOfGroup.into());
@@ -203,6 +233,33 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn serialize_text_and_boolean_values() -> anyhow::Result<()> {
+        let mut serializer = ThinEdgeJsonSerializer::new();
+        serializer.visit_text_measurement("state", "RUNNING")?;
+        serializer.visit_start_group("door")?;
+        serializer.visit_boolean_measurement("open", true)?;
+        serializer.visit_text_measurement("label", "front")?;
+        serializer.visit_end_group()?;
+        let expected_output = r#"{"state":"RUNNING","door":{"open":true,"label":"front"}}"#;
+        let output = serializer.into_string()?;
+        assert_eq!(expected_output, output);
+        Ok(())
+    }
+
+    #[test]
+    fn serialize_values_with_units() -> anyhow::Result<()> {
+        let mut serializer = ThinEdgeJsonSerializer::new();
+        serializer.visit_measurement_with_unit("temperature", &25.5.into(), "°C")?;
+        serializer.visit_start_group("location")?;
+        serializer.visit_measurement_with_unit("alti", &2100.4.into(), "m")?;
+        serializer.visit_end_group()?;
+        let expected_output = r#"{"temperature":{"value":25.5,"unit":"°C"},"location":{"alti":{"value":2100.4,"unit":"m"}}}"#;
+        let output = serializer.into_string()?;
+        assert_eq!(expected_output, output);
+        Ok(())
+    }
+
     #[test]
     fn serialize_empty_message() -> anyhow::Result<()> {
         let mut serializer = ThinEdgeJsonSerializer::new();
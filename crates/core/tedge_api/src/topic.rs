@@ -5,6 +5,7 @@ pub enum ResponseTopic {
     SoftwareListResponse,
     SoftwareUpdateResponse,
     RestartResponse,
+    FirmwareUpdateResponse,
 }
 
 impl ResponseTopic {
@@ -13,8 +14,21 @@ impl ResponseTopic {
             Self::SoftwareListResponse => r#"tedge/commands/res/software/list"#,
             Self::SoftwareUpdateResponse => r#"tedge/commands/res/software/update"#,
             Self::RestartResponse => r#"tedge/commands/res/control/restart"#,
+            Self::FirmwareUpdateResponse => r#"tedge/commands/res/firmware/update"#,
         }
     }
+
+    /// The topic of this response for a child device: `tedge/<child-id>/commands/res/...`
+    pub fn for_child(&self, child_id: &str) -> String {
+        child_topic(self.as_str(), child_id)
+    }
+
+    /// The child device and the kind of response published on a child device response topic.
+    pub fn from_child_topic(topic: &str) -> Option<(String, ResponseTopic)> {
+        let (child_id, main_topic) = topic.strip_prefix("tedge/")?.split_once('/')?;
+        let response = ResponseTopic::try_from(format!("tedge/{main_topic}")).ok()?;
+        Some((child_id.to_string(), response))
+    }
 }
 
 impl TryFrom<String> for ResponseTopic {
@@ -25,6 +39,7 @@ impl TryFrom<String> for ResponseTopic {
             r#"tedge/commands/res/software/list"# => Ok(ResponseTopic::SoftwareListResponse),
             r#"tedge/commands/res/software/update"# => Ok(ResponseTopic::SoftwareUpdateResponse),
             r#"tedge/commands/res/control/restart"# => Ok(ResponseTopic::RestartResponse),
+            r#"tedge/commands/res/firmware/update"# => Ok(ResponseTopic::FirmwareUpdateResponse),
             err => Err(TopicError::UnknownTopic {
                 topic: err.to_string(),
             }),
@@ -45,6 +60,7 @@ pub enum RequestTopic {
     SoftwareListRequest,
     SoftwareUpdateRequest,
     RestartRequest,
+    FirmwareUpdateRequest,
 }
 
 impl RequestTopic {
@@ -53,8 +69,18 @@ impl RequestTopic {
             Self::SoftwareListRequest => r#"tedge/commands/req/software/list"#,
             Self::SoftwareUpdateRequest => r#"tedge/commands/req/software/update"#,
             Self::RestartRequest => r#"tedge/commands/req/control/restart"#,
+            Self::FirmwareUpdateRequest => r#"tedge/commands/req/firmware/update"#,
         }
     }
+
+    /// The topic of this request for a child device: `tedge/<child-id>/commands/req/...`
+    pub fn for_child(&self, child_id: &str) -> String {
+        child_topic(self.as_str(), child_id)
+    }
+}
+
+fn child_topic(main_topic: &str, child_id: &str) -> String {
+    main_topic.replacen("tedge/", &format!("tedge/{child_id}/"), 1)
 }
 
 #[cfg(test)]
@@ -96,4 +122,28 @@ mod tests {
             "tedge/commands/req/software/update"
         );
     }
+
+    #[test]
+    fn child_device_topics() {
+        assert_eq!(
+            RequestTopic::RestartRequest.for_child("child1"),
+            "tedge/child1/commands/req/control/restart"
+        );
+        assert_eq!(
+            ResponseTopic::SoftwareUpdateResponse.for_child("child1"),
+            "tedge/child1/commands/res/software/update"
+        );
+        assert_eq!(
+            ResponseTopic::from_child_topic("tedge/child1/commands/res/control/restart"),
+            Some(("child1".to_string(), ResponseTopic::RestartResponse))
+        );
+        assert_eq!(
+            ResponseTopic::from_child_topic("tedge/commands/res/control/restart"),
+            None
+        );
+        assert_eq!(
+            ResponseTopic::from_child_topic("tedge/child1/measurements"),
+            None
+        );
+    }
 }
@@ -0,0 +1,328 @@
+use crate::aws::shadow::ShadowManager;
+use crate::core::{
+    cloud_json::{alarm_fields, event_fields},
+    converter::*,
+    error::*,
+    size_threshold::SizeThreshold,
+};
+
+use async_trait::async_trait;
+use clock::Clock;
+use mqtt_channel::{Message, Topic, TopicFilter};
+use serde_json::Value;
+use tedge_api::{alarm::ThinEdgeAlarm, event::ThinEdgeEvent, serialize::ThinEdgeJsonSerializer};
+use time::OffsetDateTime;
+
+const AWS_MEASUREMENTS_TOPIC: &str = "aws/td/measurements";
+const AWS_EVENTS_TOPIC: &str = "aws/td/events";
+const AWS_ALARMS_TOPIC: &str = "aws/td/alarms";
+const TEDGE_MEASUREMENTS_TOPIC: &str = "tedge/measurements/";
+const TEDGE_ALARMS_TOPIC: &str = "tedge/alarms/";
+const TEDGE_EVENTS_TOPIC: &str = "tedge/events/";
+
+/// Convert the thin-edge measurements, events and alarms into messages published on `aws/td/#`,
+/// which the bridge forwards to AWS IoT Core under the configured topic prefix
+/// as `<prefix>/<device-id>/td/#`.
+///
+/// The local topic structure is preserved, e.g. `tedge/alarms/<severity>/<type>[/<child-id>]`
+/// is published on `aws/td/alarms/<severity>/<type>[/<child-id>]`,
+/// so IoT rules can select messages with topic filters.
+pub struct AwsConverter {
+    pub(crate) add_timestamp: bool,
+    pub(crate) clock: Box<dyn Clock>,
+    pub(crate) size_threshold: SizeThreshold,
+    pub(crate) mapper_config: MapperConfig,
+    pub(crate) shadow: ShadowManager,
+}
+
+impl AwsConverter {
+    pub fn new(add_timestamp: bool, clock: Box<dyn Clock>, size_threshold: SizeThreshold) -> Self {
+        let mapper_config = MapperConfig {
+            in_topic_filter: Self::in_topic_filter(),
+            out_topic: make_valid_topic_or_panic(AWS_MEASUREMENTS_TOPIC),
+            errors_topic: make_valid_topic_or_panic("tedge/errors"),
+        };
+        AwsConverter {
+            add_timestamp,
+            clock,
+            size_threshold,
+            mapper_config,
+            shadow: ShadowManager::new(),
+        }
+    }
+
+    pub fn in_topic_filter() -> TopicFilter {
+        let mut topic_filter: TopicFilter = vec![
+            "tedge/measurements",
+            "tedge/measurements/+",
+            "tedge/alarms/+/+",
+            "tedge/alarms/+/+/+",
+            "tedge/events/+",
+            "tedge/events/+/+",
+        ]
+        .try_into()
+        .expect("topics that mapper should subscribe to");
+        topic_filter.add_all(ShadowManager::topic_filter());
+        topic_filter
+    }
+
+    /// Convert a `tedge/measurements[/<child-id>]` message
+    /// into a `aws/td/measurements[/<child-id>]` message.
+    fn try_convert_measurement(
+        &mut self,
+        input: &Message,
+    ) -> Result<Vec<Message>, ConversionError> {
+        let default_timestamp = self.default_time();
+        let mut serializer = ThinEdgeJsonSerializer::new_with_timestamp(default_timestamp);
+        tedge_api::parser::parse_str(input.payload_str()?, &mut serializer)?;
+
+        let payload = serializer.into_string()?;
+        let topic = match input.topic.name.strip_prefix(TEDGE_MEASUREMENTS_TOPIC) {
+            Some(child_id) => Topic::new_unchecked(&format!("{AWS_MEASUREMENTS_TOPIC}/{child_id}")),
+            None => self.mapper_config.out_topic.clone(),
+        };
+        Ok(vec![(Message::new(&topic, payload))])
+    }
+
+    /// Convert a `tedge/alarms/<severity>/<type>[/<child-id>]` message
+    /// into a `aws/td/alarms/<severity>/<type>[/<child-id>]` message.
+    fn try_convert_alarm(&mut self, input: &Message) -> Result<Vec<Message>, ConversionError> {
+        let topic = input.topic.name.as_str();
+        let alarm = ThinEdgeAlarm::try_from(topic, input.payload_str()?)?;
+        let severity = topic.split('/').nth(2).unwrap_or_default();
+        let fields = alarm_fields(&alarm, severity, self.default_time())?;
+
+        Ok(vec![Message::new(
+            &aws_topic(AWS_ALARMS_TOPIC, topic, TEDGE_ALARMS_TOPIC),
+            Value::Object(fields).to_string(),
+        )])
+    }
+
+    /// Convert a `tedge/events/<type>[/<child-id>]` message
+    /// into a `aws/td/events/<type>[/<child-id>]` message.
+    fn try_convert_event(&mut self, input: &Message) -> Result<Vec<Message>, ConversionError> {
+        let topic = input.topic.name.as_str();
+        let event = ThinEdgeEvent::try_from(topic, input.payload_str()?)?;
+        let fields = event_fields(&event, self.default_time())?;
+
+        Ok(vec![Message::new(
+            &aws_topic(AWS_EVENTS_TOPIC, topic, TEDGE_EVENTS_TOPIC),
+            Value::Object(fields).to_string(),
+        )])
+    }
+
+    /// The time added to the messages without time, if `add_timestamp` is set.
+    fn default_time(&self) -> Option<OffsetDateTime> {
+        self.add_timestamp.then(|| self.clock.now())
+    }
+}
+
+#[async_trait]
+impl Converter for AwsConverter {
+    type Error = ConversionError;
+
+    fn get_mapper_config(&self) -> &MapperConfig {
+        &self.mapper_config
+    }
+
+    async fn try_convert(&mut self, input: &Message) -> Result<Vec<Message>, Self::Error> {
+        self.size_threshold.validate(input)?;
+        if ShadowManager::is_shadow_message(input) {
+            return Ok(self.shadow.process_message(input)?);
+        }
+        match &input.topic {
+            topic if topic.name.starts_with(TEDGE_ALARMS_TOPIC) => self.try_convert_alarm(input),
+            topic if topic.name.starts_with(TEDGE_EVENTS_TOPIC) => self.try_convert_event(input),
+            _ => self.try_convert_measurement(input),
+        }
+    }
+
+    /// Request the device shadow, to publish locally the current desired state.
+    fn try_init_messages(&mut self) -> Result<Vec<Message>, Self::Error> {
+        Ok(vec![self.shadow.get_shadow_request()])
+    }
+}
+
+/// Replace the local prefix of a topic by the AWS one, keeping the remaining levels.
+fn aws_topic(aws_prefix: &str, tedge_topic: &str, tedge_prefix: &str) -> Topic {
+    let suffix = tedge_topic
+        .strip_prefix(tedge_prefix)
+        .unwrap_or(tedge_topic);
+    Topic::new_unchecked(&format!("{aws_prefix}/{suffix}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        aws::converter::AwsConverter,
+        core::{converter::*, error::ConversionError, size_threshold::SizeThreshold},
+    };
+
+    use assert_json_diff::*;
+    use assert_matches::*;
+    use clock::Clock;
+    use mqtt_channel::{Message, Topic};
+    use serde_json::json;
+    use time::macros::datetime;
+
+    struct TestClock;
+
+    impl Clock for TestClock {
+        fn now(&self) -> clock::Timestamp {
+            datetime!(2021-04-08 00:00:00 +05:00)
+        }
+    }
+
+    fn extract_first_message_payload(mut messages: Vec<Message>) -> serde_json::Value {
+        let payload = messages.pop().unwrap().payload_str().unwrap().to_string();
+        serde_json::from_str(&payload).unwrap()
+    }
+
+    #[tokio::test]
+    async fn converting_invalid_json_is_invalid() {
+        let mut converter =
+            AwsConverter::new(false, Box::new(TestClock), SizeThreshold(128 * 1024));
+
+        let input = Message::new(
+            &Topic::new_unchecked("tedge/measurements"),
+            "This is not Thin Edge JSON",
+        );
+        let result = converter.try_convert(&input).await;
+
+        assert_matches!(result, Err(ConversionError::FromThinEdgeJsonParser(_)))
+    }
+
+    #[tokio::test]
+    async fn converting_measurements_adds_timestamp_given_add_timestamp_is_true() {
+        let mut converter = AwsConverter::new(true, Box::new(TestClock), SizeThreshold(128 * 1024));
+
+        let input = Message::new(
+            &Topic::new_unchecked("tedge/measurements"),
+            r#"{"temperature": 23.0}"#,
+        );
+        let output = converter.convert(&input).await;
+
+        assert_eq!(output[0].topic.name, "aws/td/measurements");
+        assert_json_eq!(
+            extract_first_message_payload(output),
+            json!({
+                "temperature": 23.0,
+                "time": "2021-04-08T00:00:00+05:00"
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn converting_child_measurements_keeps_the_child_id() {
+        let mut converter =
+            AwsConverter::new(false, Box::new(TestClock), SizeThreshold(128 * 1024));
+
+        let input = Message::new(
+            &Topic::new_unchecked("tedge/measurements/child1"),
+            r#"{"temperature": 23.0}"#,
+        );
+        let output = converter.convert(&input).await;
+
+        assert_eq!(output[0].topic.name, "aws/td/measurements/child1");
+        assert_json_eq!(
+            extract_first_message_payload(output),
+            json!({"temperature": 23.0})
+        );
+    }
+
+    #[tokio::test]
+    async fn converting_alarms() {
+        let mut converter =
+            AwsConverter::new(false, Box::new(TestClock), SizeThreshold(128 * 1024));
+
+        let input = Message::new(
+            &Topic::new_unchecked("tedge/alarms/critical/temperature_high/child1"),
+            r#"{"text": "Temperature is too high", "time": "2021-04-23T19:00:00+05:00"}"#,
+        );
+        let output = converter.convert(&input).await;
+
+        assert_eq!(
+            output[0].topic.name,
+            "aws/td/alarms/critical/temperature_high/child1"
+        );
+        assert_json_eq!(
+            extract_first_message_payload(output),
+            json!({
+                "type": "temperature_high",
+                "severity": "critical",
+                "status": "active",
+                "text": "Temperature is too high",
+                "time": "2021-04-23T19:00:00+05:00"
+            })
+        );
+
+        let input = Message::new(
+            &Topic::new_unchecked("tedge/alarms/critical/temperature_high"),
+            "",
+        );
+        let output = converter.convert(&input).await;
+
+        assert_eq!(
+            output[0].topic.name,
+            "aws/td/alarms/critical/temperature_high"
+        );
+        assert_json_eq!(
+            extract_first_message_payload(output),
+            json!({
+                "type": "temperature_high",
+                "severity": "critical",
+                "status": "cleared"
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn converting_events() {
+        let mut converter = AwsConverter::new(true, Box::new(TestClock), SizeThreshold(128 * 1024));
+
+        let input = Message::new(
+            &Topic::new_unchecked("tedge/events/login_event"),
+            r#"{"text": "Someone logged in", "user": "alice"}"#,
+        );
+        let output = converter.convert(&input).await;
+
+        assert_eq!(output[0].topic.name, "aws/td/events/login_event");
+        assert_json_eq!(
+            extract_first_message_payload(output),
+            json!({
+                "type": "login_event",
+                "text": "Someone logged in",
+                "user": "alice",
+                "time": "2021-04-08T00:00:00+05:00"
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn shadow_is_requested_on_start() {
+        let mut converter =
+            AwsConverter::new(false, Box::new(TestClock), SizeThreshold(128 * 1024));
+
+        let messages = converter.try_init_messages().unwrap();
+
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].topic.name, "aws/shadow/get");
+    }
+
+    #[tokio::test]
+    async fn exceeding_threshold_returns_error() {
+        let mut converter = AwsConverter::new(false, Box::new(TestClock), SizeThreshold(1));
+
+        let input = Message::new(&Topic::new_unchecked("tedge/measurements"), "ABC");
+        let result = converter.try_convert(&input).await;
+
+        assert_matches!(
+            result,
+            Err(ConversionError::SizeThresholdExceeded {
+                actual_size: 3,
+                threshold: 1,
+                ..
+            })
+        );
+    }
+}
@@ -0,0 +1,78 @@
+use std::path::Path;
+
+use crate::{
+    aws::converter::AwsConverter,
+    core::{
+        component::TEdgeComponent, mapper::create_mapper, outbox::create_outbox,
+        size_threshold::SizeThreshold,
+    },
+};
+
+use async_trait::async_trait;
+use clock::WallClock;
+use tedge_config::ConfigSettingAccessor;
+use tedge_config::{AwsMapperTimestamp, TEdgeConfig};
+use tedge_mqtt_config::MqttConfigExt;
+use tedge_utils::file::create_directory_with_user_group;
+use tracing::{info, info_span, Instrument};
+
+const AWS_MAPPER_NAME: &str = "tedge-mapper-aws";
+const AWS_BRIDGE_HEALTH_TOPIC: &str = "tedge/health/mosquitto-aws-bridge";
+
+pub struct AwsMapper {}
+
+impl AwsMapper {
+    pub fn new() -> AwsMapper {
+        AwsMapper {}
+    }
+}
+
+#[async_trait]
+impl TEdgeComponent for AwsMapper {
+    fn session_name(&self) -> &str {
+        AWS_MAPPER_NAME
+    }
+
+    async fn init(&self, config_dir: &Path) -> Result<(), anyhow::Error> {
+        info!("Initialize tedge mapper aws");
+        create_directory_with_user_group(
+            format!("{}/.{AWS_MAPPER_NAME}", config_dir.display()),
+            "tedge",
+            "tedge",
+            0o775,
+        )?;
+
+        self.init_session(AwsConverter::in_topic_filter()).await?;
+        Ok(())
+    }
+
+    async fn start(
+        &self,
+        tedge_config: TEdgeConfig,
+        config_dir: &Path,
+    ) -> Result<(), anyhow::Error> {
+        let add_timestamp = tedge_config.query(AwsMapperTimestamp)?.is_set();
+        let mqtt_config = tedge_config.mqtt_config()?;
+        let clock = Box::new(WallClock);
+        // The maximum size of a message published to AWS IoT Core
+        let size_threshold = SizeThreshold(128 * 1024);
+        let outbox = create_outbox(
+            &tedge_config,
+            &config_dir.join(format!(".{AWS_MAPPER_NAME}")),
+            AWS_BRIDGE_HEALTH_TOPIC,
+            // Only the telemetry: the shadow requests are not to be replayed
+            "aws/td/",
+        )?;
+
+        let converter = Box::new(AwsConverter::new(add_timestamp, clock, size_threshold));
+
+        let mut mapper = create_mapper(AWS_MAPPER_NAME, mqtt_config, converter, outbox).await?;
+
+        mapper
+            .run(None)
+            .instrument(info_span!(AWS_MAPPER_NAME))
+            .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,293 @@
+use crate::core::json_merge_patch::merge_patch;
+
+use mqtt_channel::{Message, Topic, TopicFilter};
+use serde_json::{json, Map, Value};
+use tracing::info;
+
+/// The desired state of the device shadow, republished as a whole and retained on each update.
+pub const TEDGE_SHADOW_DESIRED_TOPIC: &str = "tedge/shadow/desired";
+
+/// The reported state: either a JSON object with the properties to update
+/// on `tedge/shadow/reported`, or a JSON value for a single property on `tedge/shadow/reported/<name>`.
+pub const TEDGE_SHADOW_REPORTED_TOPIC: &str = "tedge/shadow/reported";
+
+const AWS_SHADOW_TOPIC: &str = "aws/shadow/";
+const AWS_SHADOW_GET_TOPIC: &str = "aws/shadow/get";
+const AWS_SHADOW_GET_ACCEPTED_TOPIC: &str = "aws/shadow/get/accepted";
+const AWS_SHADOW_GET_REJECTED_TOPIC: &str = "aws/shadow/get/rejected";
+const AWS_SHADOW_UPDATE_TOPIC: &str = "aws/shadow/update";
+const AWS_SHADOW_UPDATE_ACCEPTED_TOPIC: &str = "aws/shadow/update/accepted";
+const AWS_SHADOW_UPDATE_REJECTED_TOPIC: &str = "aws/shadow/update/rejected";
+
+/// The error code returned by AWS IoT Core when the thing has no shadow yet.
+const SHADOW_NOT_FOUND: u64 = 404;
+
+#[derive(thiserror::Error, Debug)]
+pub enum ShadowError {
+    #[error("Invalid JSON payload received on {topic}: {error}")]
+    InvalidJson {
+        topic: String,
+        error: serde_json::Error,
+    },
+
+    #[error("The reported state published on {topic} must be a JSON object")]
+    NotAnObject { topic: String },
+
+    #[error("AWS IoT Core rejected the shadow request with code {code}: {message}")]
+    RequestRejected { code: u64, message: String },
+
+    #[error("Unsupported shadow topic: {0}")]
+    UnsupportedTopic(String),
+}
+
+/// Synchronise the AWS IoT Core device shadow with the local MQTT bus.
+///
+/// - On start, the whole shadow is requested and its desired state is published locally.
+/// - Each accepted update of the desired state is merged into this desired state,
+///   which is then published again.
+/// - The state reported by local components is sent as shadow updates.
+pub struct ShadowManager {
+    desired: Value,
+}
+
+impl ShadowManager {
+    pub fn new() -> Self {
+        ShadowManager {
+            desired: Value::Object(Map::new()),
+        }
+    }
+
+    pub fn topic_filter() -> TopicFilter {
+        vec![
+            AWS_SHADOW_GET_ACCEPTED_TOPIC,
+            AWS_SHADOW_GET_REJECTED_TOPIC,
+            AWS_SHADOW_UPDATE_ACCEPTED_TOPIC,
+            AWS_SHADOW_UPDATE_REJECTED_TOPIC,
+            TEDGE_SHADOW_REPORTED_TOPIC,
+            "tedge/shadow/reported/+",
+        ]
+        .try_into()
+        .expect("topics that mapper should subscribe to")
+    }
+
+    pub fn is_shadow_message(message: &Message) -> bool {
+        let topic = message.topic.name.as_str();
+        topic.starts_with(AWS_SHADOW_TOPIC) || topic.starts_with(TEDGE_SHADOW_REPORTED_TOPIC)
+    }
+
+    /// The request to get the whole device shadow, which response is on `aws/shadow/get/accepted`.
+    pub fn get_shadow_request(&self) -> Message {
+        Message::new(&Topic::new_unchecked(AWS_SHADOW_GET_TOPIC), "")
+    }
+
+    pub fn process_message(&mut self, message: &Message) -> Result<Vec<Message>, ShadowError> {
+        let topic = message.topic.name.as_str();
+        match topic {
+            AWS_SHADOW_GET_ACCEPTED_TOPIC => {
+                let shadow = parse_json(message)?;
+                self.desired = shadow
+                    .pointer("/state/desired")
+                    .cloned()
+                    .unwrap_or_else(|| Value::Object(Map::new()));
+                info!("Received the device shadow from AWS IoT Core");
+                Ok(vec![self.desired_state_message()])
+            }
+            AWS_SHADOW_GET_REJECTED_TOPIC => match rejection(message)? {
+                ShadowError::RequestRejected {
+                    code: SHADOW_NOT_FOUND,
+                    ..
+                } => {
+                    info!("The device has no shadow yet on AWS IoT Core");
+                    self.desired = Value::Object(Map::new());
+                    Ok(vec![self.desired_state_message()])
+                }
+                error => Err(error),
+            },
+            AWS_SHADOW_UPDATE_ACCEPTED_TOPIC => {
+                let update = parse_json(message)?;
+                match update.pointer("/state/desired") {
+                    Some(patch) => {
+                        merge_patch(&mut self.desired, patch);
+                        Ok(vec![self.desired_state_message()])
+                    }
+                    // Acknowledgement of a reported state update
+                    None => Ok(vec![]),
+                }
+            }
+            AWS_SHADOW_UPDATE_REJECTED_TOPIC => Err(rejection(message)?),
+            TEDGE_SHADOW_REPORTED_TOPIC => match parse_json(message)? {
+                patch @ Value::Object(_) => Ok(vec![reported_state_message(patch)]),
+                _ => Err(ShadowError::NotAnObject {
+                    topic: topic.into(),
+                }),
+            },
+            _ => match topic.strip_prefix("tedge/shadow/reported/") {
+                Some(name) => {
+                    let mut patch = Map::new();
+                    patch.insert(name.into(), parse_json(message)?);
+                    Ok(vec![reported_state_message(Value::Object(patch))])
+                }
+                None => Err(ShadowError::UnsupportedTopic(topic.into())),
+            },
+        }
+    }
+
+    fn desired_state_message(&self) -> Message {
+        Message::new(
+            &Topic::new_unchecked(TEDGE_SHADOW_DESIRED_TOPIC),
+            self.desired.to_string(),
+        )
+        .with_retain()
+    }
+}
+
+impl Default for ShadowManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn reported_state_message(patch: Value) -> Message {
+    let update = json!({ "state": { "reported": patch } });
+    Message::new(
+        &Topic::new_unchecked(AWS_SHADOW_UPDATE_TOPIC),
+        update.to_string(),
+    )
+}
+
+/// Extract the error sent by AWS IoT Core on a rejected topic: `{"code": <code>, "message": <text>}`.
+fn rejection(message: &Message) -> Result<ShadowError, ShadowError> {
+    let error = parse_json(message)?;
+    Ok(ShadowError::RequestRejected {
+        code: error
+            .get("code")
+            .and_then(Value::as_u64)
+            .unwrap_or_default(),
+        message: error
+            .get("message")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .into(),
+    })
+}
+
+fn parse_json(message: &Message) -> Result<Value, ShadowError> {
+    serde_json::from_slice(message.payload_bytes()).map_err(|error| ShadowError::InvalidJson {
+        topic: message.topic.name.clone(),
+        error,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_json_diff::assert_json_eq;
+    use assert_matches::assert_matches;
+
+    fn message(topic: &str, payload: &str) -> Message {
+        Message::new(&Topic::new_unchecked(topic), payload)
+    }
+
+    fn payload_json(message: &Message) -> Value {
+        serde_json::from_str(message.payload_str().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn desired_state_is_published_on_get_shadow_response() {
+        let mut shadow = ShadowManager::new();
+        let request = shadow.get_shadow_request();
+        assert_eq!(request.topic.name, "aws/shadow/get");
+
+        let response = message(
+            "aws/shadow/get/accepted",
+            r#"{"state":{"desired":{"interval":30},"reported":{"firmware":"1.0"}},"version":4}"#,
+        );
+        let output = shadow.process_message(&response).unwrap();
+
+        assert_eq!(output.len(), 1);
+        assert_eq!(output[0].topic.name, "tedge/shadow/desired");
+        assert!(output[0].retain);
+        assert_json_eq!(payload_json(&output[0]), json!({"interval": 30}));
+    }
+
+    #[test]
+    fn missing_shadow_is_an_empty_desired_state() {
+        let mut shadow = ShadowManager::new();
+
+        let response = message(
+            "aws/shadow/get/rejected",
+            r#"{"code":404,"message":"No shadow exists with name: 'alpha'"}"#,
+        );
+        let output = shadow.process_message(&response).unwrap();
+
+        assert_eq!(output.len(), 1);
+        assert_json_eq!(payload_json(&output[0]), json!({}));
+    }
+
+    #[test]
+    fn desired_state_updates_are_merged() {
+        let mut shadow = ShadowManager::new();
+        let response = message(
+            "aws/shadow/get/accepted",
+            r#"{"state":{"desired":{"interval":30,"log":{"level":"info","size":10}}}}"#,
+        );
+        shadow.process_message(&response).unwrap();
+
+        let update = message(
+            "aws/shadow/update/accepted",
+            r#"{"state":{"desired":{"interval":null,"log":{"level":"debug"}}},"version":5}"#,
+        );
+        let output = shadow.process_message(&update).unwrap();
+
+        assert_eq!(output.len(), 1);
+        assert_json_eq!(
+            payload_json(&output[0]),
+            json!({"log": {"level": "debug", "size": 10}})
+        );
+    }
+
+    #[test]
+    fn reported_state_is_sent_as_updates() {
+        let mut shadow = ShadowManager::new();
+
+        let output = shadow
+            .process_message(&message("tedge/shadow/reported", r#"{"firmware":"1.1"}"#))
+            .unwrap();
+        assert_eq!(output[0].topic.name, "aws/shadow/update");
+        assert_json_eq!(
+            payload_json(&output[0]),
+            json!({"state": {"reported": {"firmware": "1.1"}}})
+        );
+
+        let output = shadow
+            .process_message(&message("tedge/shadow/reported/battery", "87"))
+            .unwrap();
+        assert_json_eq!(
+            payload_json(&output[0]),
+            json!({"state": {"reported": {"battery": 87}}})
+        );
+
+        // The acknowledgements are ignored
+        let output = shadow
+            .process_message(&message(
+                "aws/shadow/update/accepted",
+                r#"{"state":{"reported":{"battery":87}},"version":6}"#,
+            ))
+            .unwrap();
+        assert!(output.is_empty());
+    }
+
+    #[test]
+    fn rejected_updates_are_reported() {
+        let mut shadow = ShadowManager::new();
+
+        let result = shadow.process_message(&message("tedge/shadow/reported", "42"));
+        assert_matches!(result, Err(ShadowError::NotAnObject { .. }));
+
+        let result = shadow.process_message(&message(
+            "aws/shadow/update/rejected",
+            r#"{"code":400,"message":"Missing required node: state"}"#,
+        ));
+        assert_matches!(result, Err(ShadowError::RequestRejected { code: 400, .. }));
+    }
+}
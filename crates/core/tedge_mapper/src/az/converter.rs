@@ -1,22 +1,38 @@
-use crate::core::{converter::*, error::*, size_threshold::SizeThreshold};
+use crate::az::{methods::DirectMethodsManager, twin::TwinManager};
+use crate::core::{
+    cloud_json::{alarm_fields, event_fields},
+    converter::*,
+    error::*,
+    size_threshold::SizeThreshold,
+};
 
 use async_trait::async_trait;
 use clock::Clock;
-use mqtt_channel::{Message, TopicFilter};
-use tedge_api::serialize::ThinEdgeJsonSerializer;
+use mqtt_channel::{Message, Topic, TopicFilter};
+use serde_json::Value;
+use tedge_api::{alarm::ThinEdgeAlarm, event::ThinEdgeEvent, serialize::ThinEdgeJsonSerializer};
+use time::OffsetDateTime;
+
+const AZ_MESSAGES_EVENTS_TOPIC: &str = "az/messages/events/";
+const TEDGE_ALARMS_TOPIC: &str = "tedge/alarms/";
+const TEDGE_EVENTS_TOPIC: &str = "tedge/events/";
+const DEFAULT_FORWARD_TOPIC: &str = "tedge/az";
 
 pub struct AzureConverter {
     pub(crate) add_timestamp: bool,
     pub(crate) clock: Box<dyn Clock>,
     pub(crate) size_threshold: SizeThreshold,
     pub(crate) mapper_config: MapperConfig,
+    pub(crate) twin: TwinManager,
+    pub(crate) methods: DirectMethodsManager,
 }
 
 impl AzureConverter {
     pub fn new(add_timestamp: bool, clock: Box<dyn Clock>, size_threshold: SizeThreshold) -> Self {
+        let methods = DirectMethodsManager::new(DEFAULT_FORWARD_TOPIC);
         let mapper_config = MapperConfig {
-            in_topic_filter: Self::in_topic_filter(),
-            out_topic: make_valid_topic_or_panic("az/messages/events/"),
+            in_topic_filter: Self::forwarding_topic_filter(&methods),
+            out_topic: make_valid_topic_or_panic(AZ_MESSAGES_EVENTS_TOPIC),
             errors_topic: make_valid_topic_or_panic("tedge/errors"),
         };
         AzureConverter {
@@ -24,11 +40,103 @@ impl AzureConverter {
             clock,
             size_threshold,
             mapper_config,
+            twin: TwinManager::new(),
+            methods,
         }
     }
 
+    /// Set the local topic prefix where the cloud-to-device messages
+    /// and the direct methods not handled by the mapper are forwarded.
+    pub fn with_forward_topic(mut self, forward_topic: &str) -> Self {
+        self.methods = DirectMethodsManager::new(forward_topic);
+        self.mapper_config.in_topic_filter = Self::forwarding_topic_filter(&self.methods);
+        self
+    }
+
+    /// The `in_topic_filter` extended with the topics where the local components
+    /// respond to the direct methods forwarded by `methods`.
+    fn forwarding_topic_filter(methods: &DirectMethodsManager) -> TopicFilter {
+        let mut topic_filter = Self::in_topic_filter();
+        topic_filter.add_all(methods.local_responses_topic_filter().clone());
+        topic_filter
+    }
+
     pub fn in_topic_filter() -> TopicFilter {
-        make_valid_topic_filter_or_panic("tedge/measurements")
+        let mut topic_filter: TopicFilter = vec![
+            "tedge/measurements",
+            "tedge/alarms/+/+",
+            "tedge/alarms/+/+/+",
+            "tedge/events/+",
+            "tedge/events/+/+",
+        ]
+        .try_into()
+        .expect("topics that mapper should subscribe to");
+        topic_filter.add_all(TwinManager::topic_filter());
+        topic_filter.add_all(DirectMethodsManager::topic_filter());
+        topic_filter
+    }
+
+    fn try_convert_measurement(
+        &mut self,
+        input: &Message,
+    ) -> Result<Vec<Message>, ConversionError> {
+        let default_timestamp = self.default_time();
+        let mut serializer = ThinEdgeJsonSerializer::new_with_timestamp(default_timestamp);
+        tedge_api::parser::parse_str(input.payload_str()?, &mut serializer)?;
+
+        let payload = serializer.into_string()?;
+        Ok(vec![(Message::new(&self.mapper_config.out_topic, payload))])
+    }
+
+    /// Convert a `tedge/alarms/<severity>/<name>[/<child-id>]` message
+    /// into a device-to-cloud message with `type`, `severity` and `source` properties.
+    fn try_convert_alarm(&mut self, input: &Message) -> Result<Vec<Message>, ConversionError> {
+        let topic = input.topic.name.as_str();
+        let alarm = ThinEdgeAlarm::try_from(topic, input.payload_str()?)?;
+        let severity = topic.split('/').nth(2).unwrap_or_default();
+        let source = topic.split('/').nth(4);
+
+        let mut fields = alarm_fields(&alarm, severity, self.default_time())?;
+        if let Some(source) = source {
+            fields.insert("source".into(), source.into());
+        }
+
+        let mut properties = vec![("kind", "alarm"), ("type", alarm.name.as_str())];
+        properties.push(("severity", severity));
+        if let Some(source) = source {
+            properties.push(("source", source));
+        }
+
+        Ok(vec![Message::new(
+            &d2c_topic(&properties),
+            Value::Object(fields).to_string(),
+        )])
+    }
+
+    /// Convert a `tedge/events/<name>[/<child-id>]` message
+    /// into a device-to-cloud message with `type` and `source` properties.
+    fn try_convert_event(&mut self, input: &Message) -> Result<Vec<Message>, ConversionError> {
+        let event = ThinEdgeEvent::try_from(input.topic.name.as_str(), input.payload_str()?)?;
+
+        let mut fields = event_fields(&event, self.default_time())?;
+        if let Some(source) = &event.source {
+            fields.insert("source".into(), source.as_str().into());
+        }
+
+        let mut properties = vec![("kind", "event"), ("type", event.name.as_str())];
+        if let Some(source) = &event.source {
+            properties.push(("source", source.as_str()));
+        }
+
+        Ok(vec![Message::new(
+            &d2c_topic(&properties),
+            Value::Object(fields).to_string(),
+        )])
+    }
+
+    /// The time added to the messages without time, if `add_timestamp` is set.
+    fn default_time(&self) -> Option<OffsetDateTime> {
+        self.add_timestamp.then(|| self.clock.now())
     }
 }
 
@@ -42,15 +150,58 @@ impl Converter for AzureConverter {
 
     async fn try_convert(&mut self, input: &Message) -> Result<Vec<Message>, Self::Error> {
         self.size_threshold.validate(input)?;
-        let default_timestamp = self.add_timestamp.then(|| self.clock.now());
-        let mut serializer = ThinEdgeJsonSerializer::new_with_timestamp(default_timestamp);
-        tedge_api::parser::parse_str(input.payload_str()?, &mut serializer)?;
+        if TwinManager::is_twin_message(input) {
+            return Ok(self.twin.process_message(input)?);
+        }
+        if self.methods.is_method_message(input) {
+            return Ok(self.methods.process_message(input)?);
+        }
+        match &input.topic {
+            topic if topic.name.starts_with(TEDGE_ALARMS_TOPIC) => self.try_convert_alarm(input),
+            topic if topic.name.starts_with(TEDGE_EVENTS_TOPIC) => self.try_convert_event(input),
+            _ => self.try_convert_measurement(input),
+        }
+    }
 
-        let payload = serializer.into_string()?;
-        Ok(vec![(Message::new(&self.mapper_config.out_topic, payload))])
+    /// Request the device twin, to publish locally the current desired properties.
+    fn try_init_messages(&mut self) -> Result<Vec<Message>, Self::Error> {
+        Ok(vec![self.twin.get_twin_request()])
     }
 }
 
+/// The topic of a device-to-cloud message with the given application properties.
+///
+/// IoT Hub reads the message properties from the topic suffix,
+/// a url-encoded property bag: `devices/{device_id}/messages/events/{property_bag}`.
+/// The body being always JSON, the content type and encoding are also set
+/// so the routing rules can query the message body.
+fn d2c_topic(properties: &[(&str, &str)]) -> Topic {
+    let mut property_bag = String::from("$.ct=application%2Fjson&$.ce=utf-8");
+    for (key, value) in properties {
+        property_bag.push('&');
+        property_bag.push_str(&url_encode(key));
+        property_bag.push('=');
+        property_bag.push_str(&url_encode(value));
+    }
+
+    Topic::new_unchecked(&format!("{AZ_MESSAGES_EVENTS_TOPIC}{property_bag}"))
+}
+
+/// Percent-encode all the characters but the unreserved ones (RFC 3986),
+/// so `&`, `=`, as well as the MQTT wildcards, never appear in a property.
+fn url_encode(input: &str) -> String {
+    let mut encoded = String::with_capacity(input.len());
+    for byte in input.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    encoded
+}
+
 #[cfg(test)]
 mod tests {
     use crate::{
@@ -115,6 +266,36 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn converting_text_and_boolean_values_passes_them_through() {
+        let mut converter =
+            AzureConverter::new(false, Box::new(TestClock), SizeThreshold(255 * 1024));
+
+        let input = r#"{
+            "state": "RUNNING",
+            "door": {
+                "open": true
+            },
+            "temperature": 23.0
+        }"#;
+
+        let expected_output = json!({
+            "state": "RUNNING",
+            "door": {
+                "open": true
+            },
+            "temperature": 23.0
+        });
+
+        let output = converter.convert(&new_tedge_message(input)).await;
+
+        assert_json_eq!(
+            serde_json::from_str::<serde_json::Value>(&extract_first_message_payload(output))
+                .unwrap(),
+            expected_output
+        );
+    }
+
     #[tokio::test]
     async fn converting_input_with_timestamp_produces_output_with_timestamp_given_add_timestamp_is_false(
     ) {
@@ -189,6 +370,113 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn converting_alarm_adds_message_properties() {
+        let mut converter =
+            AzureConverter::new(false, Box::new(TestClock), SizeThreshold(255 * 1024));
+
+        let input = Message::new(
+            &Topic::new_unchecked("tedge/alarms/critical/temperature_high"),
+            r#"{"text": "Temperature is too high", "time": "2021-04-23T19:00:00+05:00"}"#,
+        );
+
+        let mut output = converter.convert(&input).await;
+        assert_eq!(output.len(), 1);
+        let message = output.pop().unwrap();
+
+        assert_eq!(
+            message.topic.name,
+            "az/messages/events/$.ct=application%2Fjson&$.ce=utf-8&kind=alarm&type=temperature_high&severity=critical"
+        );
+        assert_json_eq!(
+            serde_json::from_str::<serde_json::Value>(message.payload_str().unwrap()).unwrap(),
+            json!({
+                "type": "temperature_high",
+                "severity": "critical",
+                "status": "active",
+                "text": "Temperature is too high",
+                "time": "2021-04-23T19:00:00+05:00"
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn converting_cleared_child_alarm_adds_source_property() {
+        let mut converter =
+            AzureConverter::new(true, Box::new(TestClock), SizeThreshold(255 * 1024));
+
+        let input = Message::new(
+            &Topic::new_unchecked("tedge/alarms/minor/door open/child1"),
+            "",
+        );
+
+        let mut output = converter.convert(&input).await;
+        assert_eq!(output.len(), 1);
+        let message = output.pop().unwrap();
+
+        assert_eq!(
+            message.topic.name,
+            "az/messages/events/$.ct=application%2Fjson&$.ce=utf-8&kind=alarm&type=door%20open&severity=minor&source=child1"
+        );
+        assert_json_eq!(
+            serde_json::from_str::<serde_json::Value>(message.payload_str().unwrap()).unwrap(),
+            json!({
+                "type": "door open",
+                "severity": "minor",
+                "status": "cleared",
+                "time": "2021-04-08T00:00:00+05:00",
+                "source": "child1"
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn converting_event_adds_message_properties() {
+        let mut converter =
+            AzureConverter::new(true, Box::new(TestClock), SizeThreshold(255 * 1024));
+
+        let input = Message::new(
+            &Topic::new_unchecked("tedge/events/login_event/child1"),
+            r#"{"text": "Someone logged in", "user": "alice"}"#,
+        );
+
+        let mut output = converter.convert(&input).await;
+        assert_eq!(output.len(), 1);
+        let message = output.pop().unwrap();
+
+        assert_eq!(
+            message.topic.name,
+            "az/messages/events/$.ct=application%2Fjson&$.ce=utf-8&kind=event&type=login_event&source=child1"
+        );
+        assert_json_eq!(
+            serde_json::from_str::<serde_json::Value>(message.payload_str().unwrap()).unwrap(),
+            json!({
+                "type": "login_event",
+                "text": "Someone logged in",
+                "user": "alice",
+                "time": "2021-04-08T00:00:00+05:00",
+                "source": "child1"
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn converting_invalid_alarm_is_invalid() {
+        let mut converter =
+            AzureConverter::new(false, Box::new(TestClock), SizeThreshold(255 * 1024));
+
+        let input = Message::new(
+            &Topic::new_unchecked("tedge/alarms/unknown/temperature_high"),
+            r#"{"text": "Temperature is too high"}"#,
+        );
+        let result = converter.try_convert(&input).await;
+
+        assert_matches!(
+            result,
+            Err(ConversionError::FromThinEdgeJsonAlarmDeserialization(_))
+        )
+    }
+
     #[tokio::test]
     async fn exceeding_threshold_returns_error() {
         let mut converter = AzureConverter::new(false, Box::new(TestClock), SizeThreshold(1));
@@ -206,4 +494,15 @@ mod tests {
             })
         );
     }
+
+    #[test]
+    fn local_method_responses_are_subscribed_whatever_the_forward_topic() {
+        let converter = AzureConverter::new(false, Box::new(TestClock), SizeThreshold(255 * 1024));
+        let response = Message::new(&Topic::new_unchecked("tedge/az/methods/res/200/10"), "{}");
+        assert!(converter.get_in_topic_filter().accept(&response));
+
+        let converter = converter.with_forward_topic("local/az");
+        let response = Message::new(&Topic::new_unchecked("local/az/methods/res/200/10"), "{}");
+        assert!(converter.get_in_topic_filter().accept(&response));
+    }
 }
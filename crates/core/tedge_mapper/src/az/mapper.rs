@@ -2,17 +2,22 @@ use std::path::Path;
 
 use crate::{
     az::converter::AzureConverter,
-    core::{component::TEdgeComponent, mapper::create_mapper, size_threshold::SizeThreshold},
+    core::{
+        component::TEdgeComponent, mapper::create_mapper, outbox::create_outbox,
+        size_threshold::SizeThreshold,
+    },
 };
 
 use async_trait::async_trait;
 use clock::WallClock;
-use tedge_config::{AzureMapperTimestamp, MqttBindAddressSetting, TEdgeConfig};
-use tedge_config::{ConfigSettingAccessor, MqttPortSetting};
+use tedge_config::ConfigSettingAccessor;
+use tedge_config::{AzureMapperForwardTopicSetting, AzureMapperTimestamp, TEdgeConfig};
+use tedge_mqtt_config::MqttConfigExt;
 use tedge_utils::file::create_directory_with_user_group;
 use tracing::{info, info_span, Instrument};
 
 const AZURE_MAPPER_NAME: &str = "tedge-mapper-az";
+const AZURE_BRIDGE_HEALTH_TOPIC: &str = "tedge/health/mosquitto-az-bridge";
 
 pub struct AzureMapper {}
 
@@ -30,6 +35,12 @@ impl TEdgeComponent for AzureMapper {
 
     async fn init(&self, config_dir: &Path) -> Result<(), anyhow::Error> {
         info!("Initialize tedge mapper az");
+        create_directory_with_user_group(
+            format!("{}/.{AZURE_MAPPER_NAME}", config_dir.display()),
+            "tedge",
+            "tedge",
+            0o775,
+        )?;
         create_directory_with_user_group(
             format!("{}/operations/az", config_dir.display()),
             "tedge",
@@ -44,17 +55,27 @@ impl TEdgeComponent for AzureMapper {
     async fn start(
         &self,
         tedge_config: TEdgeConfig,
-        _config_dir: &Path,
+        config_dir: &Path,
     ) -> Result<(), anyhow::Error> {
         let add_timestamp = tedge_config.query(AzureMapperTimestamp)?.is_set();
-        let mqtt_port = tedge_config.query(MqttPortSetting)?.into();
-        let mqtt_host = tedge_config.query(MqttBindAddressSetting)?.to_string();
+        let forward_topic = tedge_config.query(AzureMapperForwardTopicSetting)?;
+        let mqtt_config = tedge_config.mqtt_config()?;
         let clock = Box::new(WallClock);
         let size_threshold = SizeThreshold(255 * 1024);
+        let outbox = create_outbox(
+            &tedge_config,
+            &config_dir.join(format!(".{AZURE_MAPPER_NAME}")),
+            AZURE_BRIDGE_HEALTH_TOPIC,
+            // Only the telemetry: the twin requests and the method responses are not to be replayed
+            "az/messages/events/",
+        )?;
 
-        let converter = Box::new(AzureConverter::new(add_timestamp, clock, size_threshold));
+        let converter = Box::new(
+            AzureConverter::new(add_timestamp, clock, size_threshold)
+                .with_forward_topic(&forward_topic),
+        );
 
-        let mut mapper = create_mapper(AZURE_MAPPER_NAME, mqtt_host, mqtt_port, converter).await?;
+        let mut mapper = create_mapper(AZURE_MAPPER_NAME, mqtt_config, converter, outbox).await?;
 
         mapper
             .run(None)
@@ -0,0 +1,435 @@
+use crate::az::twin::{request_id_parameter, TEDGE_TWIN_REPORTED_TOPIC};
+
+use mqtt_channel::{Message, Topic, TopicFilter};
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::{HashMap, HashSet};
+use tedge_api::{
+    Jsonify, OperationStatus, RestartOperationRequest, RestartOperationResponse,
+    SoftwareRequestResponseSoftwareList, SoftwareUpdateRequest, SoftwareUpdateResponse,
+};
+use tracing::{info, warn};
+
+const AZ_METHODS_REQUEST_TOPIC: &str = "az/methods/POST/";
+const AZ_METHODS_RESPONSE_TOPIC: &str = "az/methods/res/";
+const AZ_C2D_MESSAGES_TOPIC: &str = "az/messages/devicebound/";
+
+/// The direct method triggering a `RestartOperationRequest`.
+pub const RESTART_METHOD: &str = "restart";
+
+/// The direct method triggering a `SoftwareUpdateRequest`,
+/// which payload is the `updateList` of the request: `{"updateList": [...]}`.
+pub const SOFTWARE_UPDATE_METHOD: &str = "software_update";
+
+/// The reported property where the outcome of the last `software_update` method is published.
+pub const SOFTWARE_UPDATE_PROPERTY: &str = "softwareUpdate";
+
+#[derive(thiserror::Error, Debug)]
+pub enum DirectMethodError {
+    #[error(transparent)]
+    FromSoftware(#[from] tedge_api::SoftwareError),
+
+    #[error("Unsupported direct method topic: {0}")]
+    UnsupportedTopic(String),
+}
+
+/// The payload of the `software_update` direct method.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+#[serde(rename_all = "camelCase")]
+struct SoftwareUpdateMethod {
+    update_list: Vec<SoftwareRequestResponseSoftwareList>,
+}
+
+/// Map the Azure IoT Hub direct methods and cloud-to-device messages.
+///
+/// - The `restart` and `software_update` methods are turned into requests for the agent,
+///   and the method response is sent as soon as the agent starts to process these requests.
+///   As an update can last longer than the timeout of a direct method,
+///   its outcome is reported later as the `softwareUpdate` reported property of the device twin.
+/// - The other methods are forwarded on `<forward_topic>/methods/req/<method>/<request_id>`,
+///   and the response published by a local component on `<forward_topic>/methods/res/<status>/<request_id>`
+///   is sent back to IoT Hub.
+/// - The cloud-to-device messages are forwarded on `<forward_topic>/messages/devicebound/`
+///   with the property bag, if any, appended to the topic.
+pub struct DirectMethodsManager {
+    forward_topic: String,
+    local_responses: TopicFilter,
+    pending_restarts: HashMap<String, String>,
+    pending_software_updates: HashMap<String, String>,
+    executing_software_updates: HashSet<String>,
+}
+
+impl DirectMethodsManager {
+    pub fn new(forward_topic: &str) -> Self {
+        let forward_topic = forward_topic.trim_end_matches('/').to_string();
+        let local_responses =
+            TopicFilter::new_unchecked(&format!("{}/methods/res/+/+", forward_topic));
+        DirectMethodsManager {
+            forward_topic,
+            local_responses,
+            pending_restarts: HashMap::new(),
+            pending_software_updates: HashMap::new(),
+            executing_software_updates: HashSet::new(),
+        }
+    }
+
+    /// The topics that have to be subscribed whatever the forward topic.
+    pub fn topic_filter() -> TopicFilter {
+        vec![
+            "az/methods/POST/#",
+            "az/messages/devicebound/#",
+            RestartOperationResponse::topic_name(),
+            SoftwareUpdateResponse::topic_name(),
+        ]
+        .try_into()
+        .expect("topics that mapper should subscribe to")
+    }
+
+    /// The topics where the local components respond to the forwarded direct methods.
+    pub fn local_responses_topic_filter(&self) -> &TopicFilter {
+        &self.local_responses
+    }
+
+    pub fn is_method_message(&self, message: &Message) -> bool {
+        let topic = message.topic.name.as_str();
+        topic.starts_with(AZ_METHODS_REQUEST_TOPIC)
+            || topic.starts_with(AZ_C2D_MESSAGES_TOPIC)
+            || topic == RestartOperationResponse::topic_name()
+            || topic == SoftwareUpdateResponse::topic_name()
+            || self.local_responses.accept(message)
+    }
+
+    pub fn process_message(
+        &mut self,
+        message: &Message,
+    ) -> Result<Vec<Message>, DirectMethodError> {
+        let topic = message.topic.name.as_str();
+        if let Some(method) = topic.strip_prefix(AZ_METHODS_REQUEST_TOPIC) {
+            self.process_method_request(method, message)
+        } else if let Some(properties) = topic.strip_prefix(AZ_C2D_MESSAGES_TOPIC) {
+            let topic = format!("{}/messages/devicebound/{properties}", self.forward_topic);
+            Ok(vec![Message::new(
+                &Topic::new_unchecked(&topic),
+                message.payload_bytes(),
+            )])
+        } else if topic == RestartOperationResponse::topic_name() {
+            self.process_restart_response(message)
+        } else if topic == SoftwareUpdateResponse::topic_name() {
+            self.process_software_update_response(message)
+        } else if let Some(response) = topic
+            .strip_prefix(&self.forward_topic)
+            .and_then(|topic| topic.strip_prefix("/methods/res/"))
+        {
+            let (status, request_id) = response
+                .split_once('/')
+                .ok_or_else(|| DirectMethodError::UnsupportedTopic(topic.into()))?;
+            Ok(vec![method_response(
+                request_id,
+                status,
+                message.payload_bytes(),
+            )])
+        } else {
+            Err(DirectMethodError::UnsupportedTopic(topic.into()))
+        }
+    }
+
+    /// Process a direct method call, which topic suffix is `<method>/?$rid=<id>`.
+    fn process_method_request(
+        &mut self,
+        method: &str,
+        message: &Message,
+    ) -> Result<Vec<Message>, DirectMethodError> {
+        let (method, parameters) = method
+            .split_once('/')
+            .ok_or_else(|| DirectMethodError::UnsupportedTopic(message.topic.name.clone()))?;
+        let request_id = request_id_parameter(parameters)
+            .ok_or_else(|| DirectMethodError::UnsupportedTopic(message.topic.name.clone()))?
+            .to_string();
+        info!("Direct method {method} called with request id {request_id}");
+
+        match method {
+            RESTART_METHOD => {
+                let request = RestartOperationRequest::default();
+                let payload = request.to_json()?;
+                self.pending_restarts.insert(request.id, request_id);
+                Ok(vec![Message::new(
+                    &Topic::new_unchecked(RestartOperationRequest::topic_name()),
+                    payload,
+                )])
+            }
+            SOFTWARE_UPDATE_METHOD => {
+                let update_list =
+                    match serde_json::from_slice::<SoftwareUpdateMethod>(message.payload_bytes()) {
+                        Ok(method) => method.update_list,
+                        Err(err) => {
+                            let error =
+                                json!({ "error": format!("Invalid software update: {err}") });
+                            return Ok(vec![method_response(
+                                &request_id,
+                                "400",
+                                error.to_string(),
+                            )]);
+                        }
+                    };
+                let request = SoftwareUpdateRequest {
+                    update_list,
+                    ..SoftwareUpdateRequest::default()
+                };
+                let payload = request.to_json()?;
+                self.pending_software_updates.insert(request.id, request_id);
+                Ok(vec![Message::new(
+                    &Topic::new_unchecked(SoftwareUpdateRequest::topic_name()),
+                    payload,
+                )])
+            }
+            _ => {
+                let topic = format!("{}/methods/req/{method}/{request_id}", self.forward_topic);
+                Ok(vec![Message::new(
+                    &Topic::new_unchecked(&topic),
+                    message.payload_bytes(),
+                )])
+            }
+        }
+    }
+
+    /// The restart method returns as soon as the agent starts the restart,
+    /// as the device cannot respond while restarting.
+    fn process_restart_response(
+        &mut self,
+        message: &Message,
+    ) -> Result<Vec<Message>, DirectMethodError> {
+        let response = RestartOperationResponse::from_slice(message.payload_bytes())?;
+        let status = match response.status() {
+            OperationStatus::Executing | OperationStatus::Successful => "200",
+            OperationStatus::Failed => "500",
+        };
+
+        match self.pending_restarts.remove(&response.id) {
+            Some(request_id) => Ok(vec![method_response(
+                &request_id,
+                status,
+                response.to_json()?,
+            )]),
+            None => Ok(vec![]),
+        }
+    }
+
+    /// The software update method returns, with status `202`, as soon as the update starts,
+    /// the outcome of the update being then published as a reported property of the device twin.
+    fn process_software_update_response(
+        &mut self,
+        message: &Message,
+    ) -> Result<Vec<Message>, DirectMethodError> {
+        let response = SoftwareUpdateResponse::from_slice(message.payload_bytes())?;
+        let id = response.id().to_string();
+        let status = match response.status() {
+            OperationStatus::Executing => "202",
+            OperationStatus::Successful => "200",
+            OperationStatus::Failed => "500",
+        };
+
+        if let Some(request_id) = self.pending_software_updates.remove(&id) {
+            if response.status() == OperationStatus::Executing {
+                self.executing_software_updates.insert(id);
+            }
+            return Ok(vec![method_response(
+                &request_id,
+                status,
+                message.payload_bytes(),
+            )]);
+        }
+
+        if response.status() != OperationStatus::Executing
+            && self.executing_software_updates.remove(&id)
+        {
+            let topic = format!("{TEDGE_TWIN_REPORTED_TOPIC}/{SOFTWARE_UPDATE_PROPERTY}");
+            return Ok(vec![Message::new(
+                &Topic::new_unchecked(&topic),
+                message.payload_bytes(),
+            )]);
+        }
+
+        if !self.executing_software_updates.contains(&id) {
+            warn!(
+                "Ignoring the response to a software update not requested by a direct method: {id}"
+            );
+        }
+        Ok(vec![])
+    }
+}
+
+fn method_response(request_id: &str, status: &str, payload: impl Into<Vec<u8>>) -> Message {
+    let topic = format!("{AZ_METHODS_RESPONSE_TOPIC}{status}/?$rid={request_id}");
+    Message::new(&Topic::new_unchecked(&topic), payload)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_json_diff::assert_json_eq;
+    use serde_json::Value;
+
+    fn message(topic: &str, payload: &str) -> Message {
+        Message::new(&Topic::new_unchecked(topic), payload)
+    }
+
+    fn payload_json(message: &Message) -> Value {
+        serde_json::from_str(message.payload_str().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn restart_method_is_answered_when_the_restart_starts() {
+        let mut methods = DirectMethodsManager::new("tedge/az");
+
+        let output = methods
+            .process_message(&message("az/methods/POST/restart/?$rid=7", "{}"))
+            .unwrap();
+        assert_eq!(output.len(), 1);
+        assert_eq!(output[0].topic.name, "tedge/commands/req/control/restart");
+        let request = RestartOperationRequest::from_slice(output[0].payload_bytes()).unwrap();
+
+        // The executing response, as built by the agent from the request
+        let executing = RestartOperationResponse::new(&request);
+        let output = methods
+            .process_message(&message(
+                "tedge/commands/res/control/restart",
+                &executing.to_json().unwrap(),
+            ))
+            .unwrap();
+        assert_eq!(output.len(), 1);
+        assert_eq!(output[0].topic.name, "az/methods/res/200/?$rid=7");
+
+        // The final response after the restart is not sent, the method having already returned
+        let successful =
+            RestartOperationResponse::new(&request).with_status(OperationStatus::Successful);
+        let output = methods
+            .process_message(&message(
+                "tedge/commands/res/control/restart",
+                &successful.to_json().unwrap(),
+            ))
+            .unwrap();
+        assert!(output.is_empty());
+    }
+
+    #[test]
+    fn software_update_method_is_answered_when_the_update_starts() {
+        let mut methods = DirectMethodsManager::new("tedge/az");
+
+        let payload = r#"{"updateList":[{"type":"apt","modules":[{"name":"nodered","version":"1.0.0","action":"install"}]}]}"#;
+        let output = methods
+            .process_message(&message("az/methods/POST/software_update/?$rid=8", payload))
+            .unwrap();
+        assert_eq!(output.len(), 1);
+        assert_eq!(output[0].topic.name, "tedge/commands/req/software/update");
+        let request = SoftwareUpdateRequest::from_slice(output[0].payload_bytes()).unwrap();
+        assert_eq!(request.update_list.len(), 1);
+        assert_eq!(request.update_list[0].modules[0].name, "nodered");
+
+        let executing = format!(r#"{{"id":"{}","status":"executing"}}"#, request.id);
+        let output = methods
+            .process_message(&message("tedge/commands/res/software/update", &executing))
+            .unwrap();
+        assert_eq!(output.len(), 1);
+        assert_eq!(output[0].topic.name, "az/methods/res/202/?$rid=8");
+
+        // The outcome of the update is reported as a twin property
+        let failed = format!(
+            r#"{{"id":"{}","status":"failed","reason":"Partial failure"}}"#,
+            request.id
+        );
+        let output = methods
+            .process_message(&message("tedge/commands/res/software/update", &failed))
+            .unwrap();
+        assert_eq!(output.len(), 1);
+        assert_eq!(output[0].topic.name, "tedge/twin/reported/softwareUpdate");
+        assert_json_eq!(
+            payload_json(&output[0]),
+            serde_json::json!({"id": request.id, "status": "failed", "reason": "Partial failure"})
+        );
+    }
+
+    #[test]
+    fn software_update_method_failing_before_starting_is_answered_with_the_failure() {
+        let mut methods = DirectMethodsManager::new("tedge/az");
+
+        let payload = r#"{"updateList":[]}"#;
+        let output = methods
+            .process_message(&message(
+                "az/methods/POST/software_update/?$rid=11",
+                payload,
+            ))
+            .unwrap();
+        let request = SoftwareUpdateRequest::from_slice(output[0].payload_bytes()).unwrap();
+
+        let failed = format!(
+            r#"{{"id":"{}","status":"failed","reason":"Cancelled"}}"#,
+            request.id
+        );
+        let output = methods
+            .process_message(&message("tedge/commands/res/software/update", &failed))
+            .unwrap();
+        assert_eq!(output.len(), 1);
+        assert_eq!(output[0].topic.name, "az/methods/res/500/?$rid=11");
+    }
+
+    #[test]
+    fn invalid_software_update_method_is_rejected() {
+        let mut methods = DirectMethodsManager::new("tedge/az");
+
+        let output = methods
+            .process_message(&message(
+                "az/methods/POST/software_update/?$rid=9",
+                r#"{"modules":[]}"#,
+            ))
+            .unwrap();
+        assert_eq!(output.len(), 1);
+        assert_eq!(output[0].topic.name, "az/methods/res/400/?$rid=9");
+    }
+
+    #[test]
+    fn other_methods_are_forwarded_to_local_components() {
+        let mut methods = DirectMethodsManager::new("tedge/az/");
+
+        let output = methods
+            .process_message(&message(
+                "az/methods/POST/reboot_modem/?$rid=10",
+                r#"{"delay":5}"#,
+            ))
+            .unwrap();
+        assert_eq!(
+            output,
+            vec![message(
+                "tedge/az/methods/req/reboot_modem/10",
+                r#"{"delay":5}"#
+            )]
+        );
+
+        let response = message("tedge/az/methods/res/200/10", r#"{"done":true}"#);
+        assert!(methods.is_method_message(&response));
+        let output = methods.process_message(&response).unwrap();
+        assert_eq!(
+            output,
+            vec![message("az/methods/res/200/?$rid=10", r#"{"done":true}"#)]
+        );
+    }
+
+    #[test]
+    fn cloud_to_device_messages_are_forwarded_to_local_components() {
+        let mut methods = DirectMethodsManager::new("tedge/az");
+
+        let output = methods
+            .process_message(&message(
+                "az/messages/devicebound/%24.to=%2Fdevices%2Falpha&priority=high",
+                "hello",
+            ))
+            .unwrap();
+        assert_eq!(
+            output,
+            vec![message(
+                "tedge/az/messages/devicebound/%24.to=%2Fdevices%2Falpha&priority=high",
+                "hello"
+            )]
+        );
+    }
+}
@@ -1,2 +1,4 @@
 mod converter;
 pub mod mapper;
+pub mod methods;
+pub mod twin;
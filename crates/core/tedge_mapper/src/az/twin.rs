@@ -0,0 +1,296 @@
+use crate::core::json_merge_patch::merge_patch;
+
+use mqtt_channel::{Message, Topic, TopicFilter};
+use serde_json::{Map, Value};
+use tracing::info;
+
+/// The desired properties of the device twin, republished as a whole and retained on each update.
+pub const TEDGE_TWIN_DESIRED_TOPIC: &str = "tedge/twin/desired";
+
+/// The reported properties: either a JSON object with the properties to update
+/// on `tedge/twin/reported`, or a JSON value for a single property on `tedge/twin/reported/<name>`.
+pub const TEDGE_TWIN_REPORTED_TOPIC: &str = "tedge/twin/reported";
+
+const AZ_TWIN_RESPONSE_TOPIC: &str = "az/twin/res/";
+const AZ_TWIN_DESIRED_PATCH_TOPIC: &str = "az/twin/PATCH/properties/desired/";
+const AZ_TWIN_REPORTED_PATCH_TOPIC: &str = "az/twin/PATCH/properties/reported/";
+const AZ_TWIN_GET_TOPIC: &str = "az/twin/GET/";
+
+#[derive(thiserror::Error, Debug)]
+pub enum TwinError {
+    #[error("Invalid JSON payload received on {topic}: {error}")]
+    InvalidJson {
+        topic: String,
+        error: serde_json::Error,
+    },
+
+    #[error("The reported properties published on {topic} must be a JSON object")]
+    NotAnObject { topic: String },
+
+    #[error("Azure IoT Hub rejected the twin request {request_id} with status {status}")]
+    RequestFailed { request_id: String, status: u16 },
+
+    #[error("Unsupported twin topic: {0}")]
+    UnsupportedTopic(String),
+}
+
+/// Synchronise the Azure IoT Hub device twin with the local MQTT bus.
+///
+/// - On start, the whole twin is requested and its desired properties are published locally.
+/// - Each desired properties patch sent by IoT Hub is merged into these desired properties,
+///   which are then published again.
+/// - The properties reported by local components are sent as reported properties patches.
+pub struct TwinManager {
+    next_request_id: u64,
+    pending_get_request: Option<String>,
+    desired: Value,
+}
+
+impl TwinManager {
+    pub fn new() -> Self {
+        TwinManager {
+            next_request_id: 1,
+            pending_get_request: None,
+            desired: Value::Object(Map::new()),
+        }
+    }
+
+    pub fn topic_filter() -> TopicFilter {
+        vec![
+            "az/twin/res/#",
+            "az/twin/PATCH/properties/desired/#",
+            TEDGE_TWIN_REPORTED_TOPIC,
+            "tedge/twin/reported/+",
+        ]
+        .try_into()
+        .expect("topics that mapper should subscribe to")
+    }
+
+    pub fn is_twin_message(message: &Message) -> bool {
+        let topic = message.topic.name.as_str();
+        topic.starts_with(AZ_TWIN_RESPONSE_TOPIC)
+            || topic.starts_with(AZ_TWIN_DESIRED_PATCH_TOPIC)
+            || topic.starts_with(TEDGE_TWIN_REPORTED_TOPIC)
+    }
+
+    /// The request to get the whole device twin, which response is on `az/twin/res/<status>/?$rid=<id>`.
+    pub fn get_twin_request(&mut self) -> Message {
+        let request_id = self.new_request_id();
+        let topic = Topic::new_unchecked(&format!("{AZ_TWIN_GET_TOPIC}?$rid={request_id}"));
+        self.pending_get_request = Some(request_id);
+        Message::new(&topic, "")
+    }
+
+    pub fn process_message(&mut self, message: &Message) -> Result<Vec<Message>, TwinError> {
+        let topic = message.topic.name.as_str();
+        if let Some(response) = topic.strip_prefix(AZ_TWIN_RESPONSE_TOPIC) {
+            self.process_response(response, message)
+        } else if topic.starts_with(AZ_TWIN_DESIRED_PATCH_TOPIC) {
+            let patch = parse_json(message)?;
+            merge_patch(&mut self.desired, &patch);
+            Ok(vec![self.desired_properties_message()])
+        } else if topic == TEDGE_TWIN_REPORTED_TOPIC {
+            match parse_json(message)? {
+                patch @ Value::Object(_) => Ok(vec![self.reported_properties_message(&patch)]),
+                _ => Err(TwinError::NotAnObject {
+                    topic: topic.into(),
+                }),
+            }
+        } else if let Some(name) = topic.strip_prefix("tedge/twin/reported/") {
+            let mut patch = Map::new();
+            patch.insert(name.into(), parse_json(message)?);
+            Ok(vec![self.reported_properties_message(&Value::Object(patch))])
+        } else {
+            Err(TwinError::UnsupportedTopic(topic.into()))
+        }
+    }
+
+    /// Process a response, which topic suffix is `<status>/?$rid=<id>[&$version=<version>]`.
+    fn process_response(
+        &mut self,
+        response: &str,
+        message: &Message,
+    ) -> Result<Vec<Message>, TwinError> {
+        let (status, parameters) = response
+            .split_once('/')
+            .ok_or_else(|| TwinError::UnsupportedTopic(message.topic.name.clone()))?;
+        let status: u16 = status
+            .parse()
+            .map_err(|_| TwinError::UnsupportedTopic(message.topic.name.clone()))?;
+        let request_id = request_id_parameter(parameters)
+            .unwrap_or_default()
+            .to_string();
+
+        if status >= 300 {
+            if self.pending_get_request.as_ref() == Some(&request_id) {
+                self.pending_get_request = None;
+            }
+            return Err(TwinError::RequestFailed { request_id, status });
+        }
+
+        if self.pending_get_request.as_ref() != Some(&request_id) {
+            // Acknowledgement of a reported properties patch
+            return Ok(vec![]);
+        }
+
+        self.pending_get_request = None;
+        let twin = parse_json(message)?;
+        self.desired = twin
+            .get("desired")
+            .cloned()
+            .unwrap_or_else(|| Value::Object(Map::new()));
+        info!("Received the device twin from Azure IoT Hub");
+
+        Ok(vec![self.desired_properties_message()])
+    }
+
+    fn desired_properties_message(&self) -> Message {
+        Message::new(
+            &Topic::new_unchecked(TEDGE_TWIN_DESIRED_TOPIC),
+            self.desired.to_string(),
+        )
+        .with_retain()
+    }
+
+    fn reported_properties_message(&mut self, patch: &Value) -> Message {
+        let request_id = self.new_request_id();
+        let topic =
+            Topic::new_unchecked(&format!("{AZ_TWIN_REPORTED_PATCH_TOPIC}?$rid={request_id}"));
+        Message::new(&topic, patch.to_string())
+    }
+
+    fn new_request_id(&mut self) -> String {
+        let request_id = self.next_request_id;
+        self.next_request_id += 1;
+        request_id.to_string()
+    }
+}
+
+impl Default for TwinManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Extract the request id from the parameters of an IoT Hub topic: `?$rid=<id>[&<key>=<value>]*`.
+pub(crate) fn request_id_parameter(parameters: &str) -> Option<&str> {
+    parameters
+        .trim_start_matches('?')
+        .split('&')
+        .find_map(|parameter| parameter.strip_prefix("$rid="))
+}
+
+fn parse_json(message: &Message) -> Result<Value, TwinError> {
+    serde_json::from_slice(message.payload_bytes()).map_err(|error| TwinError::InvalidJson {
+        topic: message.topic.name.clone(),
+        error,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_json_diff::assert_json_eq;
+    use assert_matches::assert_matches;
+    use serde_json::json;
+
+    fn message(topic: &str, payload: &str) -> Message {
+        Message::new(&Topic::new_unchecked(topic), payload)
+    }
+
+    fn payload_json(message: &Message) -> Value {
+        serde_json::from_str(message.payload_str().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn desired_properties_are_published_on_get_twin_response() {
+        let mut twin = TwinManager::new();
+        let request = twin.get_twin_request();
+        assert_eq!(request.topic.name, "az/twin/GET/?$rid=1");
+
+        let response = message(
+            "az/twin/res/200/?$rid=1",
+            r#"{"desired":{"interval":30,"$version":4},"reported":{"firmware":"1.0"}}"#,
+        );
+        let output = twin.process_message(&response).unwrap();
+
+        assert_eq!(output.len(), 1);
+        assert_eq!(output[0].topic.name, "tedge/twin/desired");
+        assert!(output[0].retain);
+        assert_json_eq!(
+            payload_json(&output[0]),
+            json!({"interval": 30, "$version": 4})
+        );
+    }
+
+    #[test]
+    fn desired_properties_patches_are_merged() {
+        let mut twin = TwinManager::new();
+        let _ = twin.get_twin_request();
+        let response = message(
+            "az/twin/res/200/?$rid=1",
+            r#"{"desired":{"interval":30,"log":{"level":"info","size":10},"$version":4}}"#,
+        );
+        twin.process_message(&response).unwrap();
+
+        let patch = message(
+            "az/twin/PATCH/properties/desired/?$version=5",
+            r#"{"interval":null,"log":{"level":"debug"},"$version":5}"#,
+        );
+        let output = twin.process_message(&patch).unwrap();
+
+        assert_eq!(output.len(), 1);
+        assert_json_eq!(
+            payload_json(&output[0]),
+            json!({"log": {"level": "debug", "size": 10}, "$version": 5})
+        );
+    }
+
+    #[test]
+    fn reported_properties_are_sent_as_patches() {
+        let mut twin = TwinManager::new();
+
+        let output = twin
+            .process_message(&message("tedge/twin/reported", r#"{"firmware":"1.1"}"#))
+            .unwrap();
+        assert_eq!(
+            output[0].topic.name,
+            "az/twin/PATCH/properties/reported/?$rid=1"
+        );
+        assert_json_eq!(payload_json(&output[0]), json!({"firmware": "1.1"}));
+
+        let output = twin
+            .process_message(&message("tedge/twin/reported/battery", "87"))
+            .unwrap();
+        assert_eq!(
+            output[0].topic.name,
+            "az/twin/PATCH/properties/reported/?$rid=2"
+        );
+        assert_json_eq!(payload_json(&output[0]), json!({"battery": 87}));
+
+        // The acknowledgements are ignored
+        let output = twin
+            .process_message(&message("az/twin/res/204/?$rid=2&$version=7", ""))
+            .unwrap();
+        assert!(output.is_empty());
+    }
+
+    #[test]
+    fn invalid_reported_properties_are_rejected() {
+        let mut twin = TwinManager::new();
+
+        let result = twin.process_message(&message("tedge/twin/reported", "42"));
+        assert_matches!(result, Err(TwinError::NotAnObject { .. }));
+
+        let result = twin.process_message(&message("tedge/twin/reported/name", "not json"));
+        assert_matches!(result, Err(TwinError::InvalidJson { .. }));
+    }
+
+    #[test]
+    fn failed_requests_are_reported() {
+        let mut twin = TwinManager::new();
+
+        let result = twin.process_message(&message("az/twin/res/400/?$rid=3", ""));
+        assert_matches!(result, Err(TwinError::RequestFailed { status: 400, .. }));
+    }
+}
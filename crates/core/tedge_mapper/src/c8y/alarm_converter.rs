@@ -1,14 +1,18 @@
+use c8y_api::json_c8y::{C8yAlarm, C8yAlarmStatus, C8yAlarmUpdate};
 use c8y_api::smartrest::topic::SMARTREST_PUBLISH_TOPIC;
-use std::collections::{hash_map::Entry, HashMap};
+use std::collections::{hash_map::Entry, BTreeSet, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use tracing::error;
 
 use c8y_api::smartrest::alarm;
 use mqtt_channel::{Message, Topic};
-use tedge_api::alarm::ThinEdgeAlarm;
+use tedge_api::alarm::{AlarmStatus, AlarmTransition, ThinEdgeAlarm};
 
 use crate::core::error::ConversionError;
 
 const TEDGE_ALARMS_TOPIC: &str = "tedge/alarms/";
 const INTERNAL_ALARMS_TOPIC: &str = "c8y-internal/alarms/";
+const PUBLISHED_ALARMS_FILE: &str = "published-alarms.json";
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub(crate) enum AlarmConverter {
@@ -16,7 +20,92 @@ pub(crate) enum AlarmConverter {
         pending_alarms_map: HashMap<String, Message>,
         old_alarms_map: HashMap<String, Message>,
     },
-    Synced,
+    Synced {
+        /// The current state of the alarms, indexed by `<type>[/<child-id>]`.
+        alarms: HashMap<String, ThinEdgeAlarm>,
+    },
+}
+
+/// The changes of an alarm that have no SmartREST template, and that have to be sent over HTTP.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct AlarmHttpUpdate {
+    pub child_id: Option<String>,
+    pub alarm_type: String,
+    pub update: C8yAlarmUpdate,
+}
+
+impl AlarmHttpUpdate {
+    pub fn alarm_key(&self) -> String {
+        cloud_alarm_key(&self.child_id, &self.alarm_type)
+    }
+}
+
+/// The translation of an alarm message.
+///
+/// The HTTP updates are to be sent before the messages,
+/// so the text of an alarm can be updated with the reason why the alarm is cleared.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(crate) struct ConvertedAlarm {
+    pub messages: Vec<Message>,
+    pub http_updates: Vec<AlarmHttpUpdate>,
+
+    /// The key of the alarm, when raised or cleared by the messages,
+    /// the former cloud alarm of the same type being then resolved.
+    pub renewed_alarm: Option<String>,
+}
+
+/// The alarms published by thin-edge so far, persisted in the mapper directory.
+///
+/// Only these alarms are cleared on the cloud when found stale on sync,
+/// the other alarms of the device being raised by other clients or from the cloud.
+#[derive(Debug, Default)]
+pub struct PublishedAlarms {
+    file: Option<PathBuf>,
+    /// The keys of the published alarms, `<type>[/<child-id>]`.
+    keys: BTreeSet<String>,
+}
+
+impl PublishedAlarms {
+    /// Load the alarms published by the previous runs of the mapper, from the mapper directory.
+    pub fn load(mapper_dir: &Path) -> Self {
+        let file = mapper_dir.join(PUBLISHED_ALARMS_FILE);
+        let keys = match std::fs::read_to_string(&file) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|err| {
+                error!(
+                    "Ignoring the invalid list of published alarms {}: {err}",
+                    file.display()
+                );
+                BTreeSet::new()
+            }),
+            Err(_) => BTreeSet::new(),
+        };
+        PublishedAlarms {
+            file: Some(file),
+            keys,
+        }
+    }
+
+    pub fn contains(&self, alarm_key: &str) -> bool {
+        self.keys.contains(alarm_key)
+    }
+
+    /// Record an alarm published by thin-edge, given its key `<type>[/<child-id>]`.
+    pub fn record(&mut self, alarm_key: &str) -> Result<(), ConversionError> {
+        if self.keys.insert(alarm_key.to_string()) {
+            self.persist()?;
+        }
+        Ok(())
+    }
+
+    fn persist(&self) -> Result<(), ConversionError> {
+        if let Some(file) = &self.file {
+            if let Some(dir) = file.parent() {
+                std::fs::create_dir_all(dir)?;
+            }
+            std::fs::write(file, serde_json::to_string_pretty(&self.keys)?)?;
+        }
+        Ok(())
+    }
 }
 
 impl AlarmConverter {
@@ -30,8 +119,8 @@ impl AlarmConverter {
     pub(crate) fn try_convert_alarm(
         &mut self,
         input_message: &Message,
-    ) -> Result<Vec<Message>, ConversionError> {
-        let mut output_messages: Vec<Message> = Vec::new();
+    ) -> Result<ConvertedAlarm, ConversionError> {
+        let mut converted = ConvertedAlarm::default();
         match self {
             Self::Syncing {
                 pending_alarms_map,
@@ -45,45 +134,85 @@ impl AlarmConverter {
                     .to_string();
                 pending_alarms_map.insert(alarm_id, input_message.clone());
             }
-            Self::Synced => {
+            Self::Synced { alarms } => {
                 //Regular conversion phase
-                let tedge_alarm = ThinEdgeAlarm::try_from(
-                    input_message.topic.name.as_str(),
-                    input_message.payload_str()?,
-                )?;
-                let smartrest_alarm = alarm::serialize_alarm(tedge_alarm)?;
-                let c8y_alarm_topic = Topic::new_unchecked(
-                    self.get_c8y_alarm_topic(input_message.topic.name.as_str())?
-                        .as_str(),
-                );
-                output_messages.push(Message::new(&c8y_alarm_topic, smartrest_alarm));
+                let topic = input_message.topic.name.as_str();
+                let tedge_alarm = ThinEdgeAlarm::try_from(topic, input_message.payload_str()?)?;
+                let c8y_alarm_topic = Topic::new_unchecked(&get_c8y_alarm_topic(topic)?);
+                let child_id = topic.split('/').nth(4).map(str::to_string);
 
-                // Persist a copy of the alarm to an internal topic for reconciliation on next restart
-                let alarm_id = input_message
-                    .topic
-                    .name
+                let alarm_id = topic
                     .strip_prefix(TEDGE_ALARMS_TOPIC)
                     .expect("Expected tedge/alarms prefix")
                     .to_string();
+                let alarm_key = alarm_key(&alarm_id);
+                let transitions = tedge_alarm.transitions_from(alarms.get(&alarm_key));
+
+                let http_update = |update: C8yAlarmUpdate| AlarmHttpUpdate {
+                    child_id: child_id.clone(),
+                    alarm_type: tedge_alarm.name.clone(),
+                    update,
+                };
+                for transition in transitions.iter() {
+                    match transition {
+                        AlarmTransition::Raise => {
+                            converted.renewed_alarm = Some(alarm_key.clone());
+                            let smartrest_alarm = alarm::serialize_alarm(tedge_alarm.clone())?;
+                            converted
+                                .messages
+                                .push(Message::new(&c8y_alarm_topic, smartrest_alarm));
+                        }
+                        AlarmTransition::UpdateSeverity(severity) => {
+                            let smartrest_update = alarm::serialize_alarm_severity_update(
+                                &tedge_alarm.name,
+                                *severity,
+                            );
+                            converted
+                                .messages
+                                .push(Message::new(&c8y_alarm_topic, smartrest_update));
+                        }
+                        AlarmTransition::UpdateText(text) => {
+                            converted.http_updates.push(http_update(C8yAlarmUpdate {
+                                status: None,
+                                text: Some(text.clone()),
+                            }));
+                        }
+                        AlarmTransition::Acknowledge => {
+                            converted.http_updates.push(http_update(C8yAlarmUpdate {
+                                status: Some(C8yAlarmStatus::Acknowledged),
+                                text: None,
+                            }));
+                        }
+                        AlarmTransition::Clear { reason } => {
+                            converted.renewed_alarm = Some(alarm_key.clone());
+                            if let Some(reason) = reason {
+                                converted.http_updates.push(http_update(C8yAlarmUpdate {
+                                    status: None,
+                                    text: Some(reason.clone()),
+                                }));
+                            }
+                            let smartrest_clear = alarm::serialize_alarm(tedge_alarm.clone())?;
+                            converted
+                                .messages
+                                .push(Message::new(&c8y_alarm_topic, smartrest_clear));
+                        }
+                    }
+                }
+
+                // A clear of a former severity of the alarm is not a change of the alarm itself
+                if !transitions.is_empty() || tedge_alarm.status() != AlarmStatus::Cleared {
+                    alarms.insert(alarm_key, tedge_alarm);
+                }
+
+                // Persist a copy of the alarm to an internal topic for reconciliation on next restart
                 let topic =
                     Topic::new_unchecked(format!("{INTERNAL_ALARMS_TOPIC}{alarm_id}").as_str());
                 let alarm_copy =
                     Message::new(&topic, input_message.payload_bytes().to_owned()).with_retain();
-                output_messages.push(alarm_copy);
+                converted.messages.push(alarm_copy);
             }
         }
-        Ok(output_messages)
-    }
-
-    pub(crate) fn get_c8y_alarm_topic(&self, topic: &str) -> Result<String, ConversionError> {
-        let topic_split: Vec<&str> = topic.split('/').collect();
-        if topic_split.len() == 4 {
-            Ok(SMARTREST_PUBLISH_TOPIC.to_string())
-        } else if topic_split.len() == 5 {
-            Ok(format!("{SMARTREST_PUBLISH_TOPIC}/{}", topic_split[4]))
-        } else {
-            Err(ConversionError::UnsupportedTopic(topic.to_string()))
-        }
+        Ok(converted)
     }
 
     pub(crate) fn process_internal_alarm(&mut self, input: &Message) {
@@ -100,7 +229,7 @@ impl AlarmConverter {
                     .to_string();
                 old_alarms_map.insert(alarm_id, input.clone());
             }
-            Self::Synced => {
+            Self::Synced { .. } => {
                 // Ignore
             }
         }
@@ -120,7 +249,18 @@ impl AlarmConverter {
     /// is one that was raised while the mapper process was down.
     /// An alarm present in both, if their payload is the same, is one that was already processed before the restart
     /// and hence can be ignored during sync.
-    pub(crate) fn sync(&mut self) -> Vec<Message> {
+    ///
+    /// The unresolved alarms of the cloud, given with the id of the child device they are for, if any,
+    /// are also cleared when there is no such alarm in either of these two sets,
+    /// provided these alarms have been published by thin-edge.
+    /// These are stale alarms, the journal of the mapper being either lost or out of sync.
+    ///
+    /// Once synced, the previously processed alarms are the current state of the alarms.
+    pub(crate) fn sync(
+        &mut self,
+        cloud_alarms: &[(Option<String>, C8yAlarm)],
+        published_alarms: &PublishedAlarms,
+    ) -> Vec<Message> {
         let mut sync_messages: Vec<Message> = Vec::new();
 
         match self {
@@ -128,15 +268,30 @@ impl AlarmConverter {
                 pending_alarms_map,
                 old_alarms_map,
             } => {
+                let mut alarms = HashMap::new();
+                let known_alarms: HashSet<String> = old_alarms_map
+                    .keys()
+                    .chain(pending_alarms_map.keys())
+                    .map(|alarm_id| alarm_key(alarm_id))
+                    .collect();
+
                 // Compare the differences between alarms in tedge/alarms topic to the ones in c8y-internal/alarms topic
                 old_alarms_map.drain().for_each(|(alarm_id, old_message)| {
+                    let tedge_topic = format!("{TEDGE_ALARMS_TOPIC}{alarm_id}");
+                    if let Ok(old_alarm) = ThinEdgeAlarm::try_from(
+                        &tedge_topic,
+                        old_message.payload_str().unwrap_or(""),
+                    ) {
+                        if old_alarm.status() != AlarmStatus::Cleared {
+                            alarms.insert(alarm_key(&alarm_id), old_alarm);
+                        }
+                    }
+
                     match pending_alarms_map.entry(alarm_id.clone()) {
                         // If an alarm that is present in c8y-internal/alarms topic is not present in tedge/alarms topic,
                         // it is assumed to have been cleared while the mapper process was down
                         Entry::Vacant(_) => {
-                            let topic = Topic::new_unchecked(
-                                format!("{TEDGE_ALARMS_TOPIC}{alarm_id}").as_str(),
-                            );
+                            let topic = Topic::new_unchecked(&tedge_topic);
                             let message = Message::new(&topic, vec![]).with_retain();
                             // Recreate the clear alarm message and add it to the pending alarms list to be processed later
                             sync_messages.push(message);
@@ -155,11 +310,52 @@ impl AlarmConverter {
                 pending_alarms_map
                     .drain()
                     .for_each(|(_key, message)| sync_messages.push(message));
+
+                // Clear the stale alarms of the cloud
+                for (child_id, cloud_alarm) in cloud_alarms {
+                    let key = cloud_alarm_key(child_id, &cloud_alarm.alarm_type);
+                    if !known_alarms.contains(&key) && published_alarms.contains(&key) {
+                        let alarm_id = format!("{}/{key}", cloud_alarm.severity.to_lowercase());
+                        let topic =
+                            Topic::new_unchecked(&format!("{TEDGE_ALARMS_TOPIC}{alarm_id}"));
+                        sync_messages.push(Message::new(&topic, vec![]));
+                    }
+                }
+
+                *self = Self::Synced { alarms };
             }
-            Self::Synced => {
+            Self::Synced { .. } => {
                 // Ignore
             }
         }
         sync_messages
     }
 }
+
+pub(crate) fn get_c8y_alarm_topic(topic: &str) -> Result<String, ConversionError> {
+    let topic_split: Vec<&str> = topic.split('/').collect();
+    if topic_split.len() == 4 {
+        Ok(SMARTREST_PUBLISH_TOPIC.to_string())
+    } else if topic_split.len() == 5 {
+        Ok(format!("{SMARTREST_PUBLISH_TOPIC}/{}", topic_split[4]))
+    } else {
+        Err(ConversionError::UnsupportedTopic(topic.to_string()))
+    }
+}
+
+/// The key identifying an alarm, whatever its severity: `<type>[/<child-id>]`
+/// extracted from an alarm id `<severity>/<type>[/<child-id>]`.
+pub(crate) fn alarm_key(alarm_id: &str) -> String {
+    alarm_id
+        .split_once('/')
+        .map_or(alarm_id, |(_severity, key)| key)
+        .to_string()
+}
+
+/// The key identifying the alarm of a cloud device: `<type>[/<child-id>]`.
+pub(crate) fn cloud_alarm_key(child_id: &Option<String>, alarm_type: &str) -> String {
+    match child_id {
+        Some(child_id) => format!("{alarm_type}/{child_id}"),
+        None => alarm_type.to_string(),
+    }
+}
@@ -0,0 +1,188 @@
+//! The availability of the device, as monitored by Cumulocity.
+//!
+//! The device declares on start its required availability interval with the `c8y_RequiredAvailability` fragment:
+//! the device is considered offline by Cumulocity when no message is received during this interval.
+//! While the thin-edge services are up, the mapper sends heartbeat messages to keep the device available,
+//! i.e. empty updates of the device managed object.
+//! The health of the services is checked before each heartbeat, using the `tedge/health-check` requests:
+//! no heartbeat is sent if a monitored service did not respond, and the device turns offline.
+
+use super::error::CumulocityMapperError;
+use super::inventory::INVENTORY_MANAGED_OBJECTS_TOPIC;
+use super::service_monitor::health_status;
+use mqtt_channel::{Message, Sender, SinkExt, Topic};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tedge_config::{C8yAvailabilityIntervalSetting, ConfigSettingAccessor, TEdgeConfig};
+use tokio::time::Instant;
+use tracing::{error, warn};
+
+pub const TEDGE_HEALTH_TOPIC: &str = "tedge/health/";
+const TEDGE_HEALTH_CHECK_TOPIC: &str = "tedge/health-check";
+
+/// The services that must be up for the device to be available.
+const MONITORED_SERVICES: &[&str] = &["tedge-agent"];
+
+#[derive(Debug, Clone)]
+pub struct Availability {
+    /// The interval after which Cumulocity considers the device offline.
+    required_interval: Duration,
+
+    /// The period of the heartbeat messages.
+    heartbeat_period: Duration,
+
+    /// When each service last reported to be up.
+    services_health: Arc<Mutex<HashMap<String, Instant>>>,
+}
+
+impl Availability {
+    /// The availability monitoring, unless disabled with an interval of 0.
+    pub fn try_new(tedge_config: &TEdgeConfig) -> Result<Option<Self>, CumulocityMapperError> {
+        let interval = tedge_config.query(C8yAvailabilityIntervalSetting)?.0;
+        Ok((interval > 0).then(|| Availability::new(Duration::from_secs(interval))))
+    }
+
+    /// Heartbeats are sent twice per interval, so a single missed heartbeat doesn't turn the device offline.
+    pub fn new(required_interval: Duration) -> Self {
+        Availability {
+            required_interval,
+            heartbeat_period: required_interval / 2,
+            services_health: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// The required interval in minutes, as counted by Cumulocity, rounded up.
+    pub fn required_interval_minutes(&self) -> u64 {
+        ((self.required_interval.as_secs() + 59) / 60).max(1)
+    }
+
+    /// Record the health status published by a service on `tedge/health/<service>`.
+    ///
+    /// Payloads that are not a health status, as the one of the bridge, are ignored.
+    pub fn process_health_message(&self, message: &Message) {
+        let (service, status) = match health_status(message) {
+            Some(health) => health,
+            None => return,
+        };
+
+        let mut services_health = self.services_health.lock().expect("services health lock");
+        if status == "up" {
+            services_health.insert(service.to_string(), Instant::now());
+        } else {
+            services_health.remove(service);
+        }
+    }
+
+    /// Are all the monitored services up since the given health check request?
+    fn services_are_up_since(&self, request_time: Instant) -> bool {
+        let services_health = self.services_health.lock().expect("services health lock");
+        MONITORED_SERVICES.iter().all(|service| {
+            services_health
+                .get(*service)
+                .map_or(false, |last_up| *last_up >= request_time)
+        })
+    }
+
+    /// Periodically check the health of the services and send a heartbeat if they are all up.
+    pub fn start_heartbeat(&self, device_name: &str, mut mqtt_publisher: Sender<Message>) {
+        let availability = self.clone();
+        let heartbeat_topic =
+            Topic::new_unchecked(&format!("{INVENTORY_MANAGED_OBJECTS_TOPIC}/{device_name}"));
+        let health_check_topic = Topic::new_unchecked(TEDGE_HEALTH_CHECK_TOPIC);
+
+        tokio::spawn(async move {
+            let mut last_request: Option<Instant> = None;
+            let mut heartbeat = tokio::time::interval(availability.heartbeat_period);
+            loop {
+                heartbeat.tick().await;
+
+                // The messages sent on start are enough for the first period
+                if let Some(request_time) = last_request {
+                    if availability.services_are_up_since(request_time) {
+                        let message = Message::new(&heartbeat_topic, "{}");
+                        if let Err(err) = mqtt_publisher.send(message).await {
+                            error!("Failed to send a heartbeat to Cumulocity: {err}");
+                        }
+                    } else {
+                        warn!(
+                            "No heartbeat sent to Cumulocity, a service is down among: {}",
+                            MONITORED_SERVICES.join(", ")
+                        );
+                    }
+                }
+
+                last_request = Some(Instant::now());
+                let request = Message::new(&health_check_topic, "");
+                if let Err(err) = mqtt_publisher.send(request).await {
+                    error!("Failed to check the health of the services: {err}");
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::channel::mpsc;
+    use futures::StreamExt;
+    use test_case::test_case;
+
+    fn health_message(service: &str, payload: &str) -> Message {
+        Message::new(
+            &Topic::new_unchecked(&format!("tedge/health/{service}")),
+            payload,
+        )
+    }
+
+    #[test_case(30, 1)]
+    #[test_case(60, 1)]
+    #[test_case(61, 2)]
+    #[test_case(3600, 60)]
+    fn required_interval_is_rounded_up_to_minutes(seconds: u64, minutes: u64) {
+        let availability = Availability::new(Duration::from_secs(seconds));
+        assert_eq!(availability.required_interval_minutes(), minutes);
+    }
+
+    #[test]
+    fn services_health_is_tracked() {
+        let availability = Availability::new(Duration::from_secs(60));
+        let request_time = Instant::now();
+        assert!(!availability.services_are_up_since(request_time));
+
+        availability.process_health_message(&health_message("tedge-agent", r#"{"status":"up"}"#));
+        assert!(availability.services_are_up_since(request_time));
+
+        // The bridge health status is not a JSON object
+        availability.process_health_message(&health_message("mosquitto-c8y-bridge", "0"));
+        assert!(availability.services_are_up_since(request_time));
+
+        availability.process_health_message(&health_message("tedge-agent", r#"{"status":"down"}"#));
+        assert!(!availability.services_are_up_since(request_time));
+    }
+
+    #[tokio::test]
+    async fn heartbeats_are_sent_only_when_the_services_are_up() {
+        let availability = Availability::new(Duration::from_millis(200));
+        let (mqtt_publisher, mut published) = mpsc::channel(10);
+        availability.start_heartbeat("test-device", mqtt_publisher);
+
+        // First period: no heartbeat, only a health check
+        let request = published.next().await.unwrap();
+        assert_eq!(request.topic.name, "tedge/health-check");
+
+        // The agent doesn't respond: no heartbeat
+        let request = published.next().await.unwrap();
+        assert_eq!(request.topic.name, "tedge/health-check");
+
+        // The agent responds: a heartbeat is sent on the next period
+        availability.process_health_message(&health_message("tedge-agent", r#"{"status":"up"}"#));
+        let heartbeat = published.next().await.unwrap();
+        assert_eq!(
+            heartbeat.topic.name,
+            "c8y/inventory/managedObjects/update/test-device"
+        );
+        assert_eq!(heartbeat.payload_str().unwrap(), "{}");
+    }
+}
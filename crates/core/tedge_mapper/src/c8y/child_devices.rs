@@ -0,0 +1,207 @@
+//! Explicit registration of child devices, published by local processes on `tedge/register/<child-id>`.
+//!
+//! The payload of a registration request is a JSON object with optional fields:
+//!
+//! ```json
+//! {
+//!     "name": "Temperature sensor",
+//!     "type": "thin-edge.io-sensor",
+//!     "parent": "gateway1",
+//!     "fragments": { "c8y_Hardware": { "model": "DS18B20" } }
+//! }
+//! ```
+//!
+//! A child device is created under its parent, the main device by default or a child device for nested children.
+//! The registered child devices are persisted, so a child device is only registered again when its registration changes.
+//! An empty payload removes the child device from the registered devices,
+//! a parent device being only removed once its nested child devices have been removed.
+
+use super::error::CumulocityMapperError;
+use crate::core::error::ConversionError;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use tracing::error;
+
+pub const TEDGE_REGISTER_TOPIC: &str = "tedge/register/";
+pub const DEFAULT_CHILD_DEVICE_TYPE: &str = "thin-edge.io-child";
+const CHILD_DEVICES_FILE: &str = "child-devices.json";
+
+/// A registration request, as published on `tedge/register/<child-id>`.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ChildDeviceRegistration {
+    pub name: Option<String>,
+
+    #[serde(rename = "type")]
+    pub device_type: Option<String>,
+
+    pub parent: Option<String>,
+
+    #[serde(default)]
+    pub fragments: Map<String, Value>,
+}
+
+/// A registered child device.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub struct ChildDevice {
+    pub name: String,
+
+    #[serde(rename = "type")]
+    pub device_type: String,
+
+    /// The parent device, if not the main device.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent: Option<String>,
+}
+
+/// The child devices registered so far, persisted in the given file if any.
+#[derive(Debug, Default)]
+pub struct ChildDeviceRegistry {
+    file: Option<PathBuf>,
+    devices: BTreeMap<String, ChildDevice>,
+}
+
+impl ChildDeviceRegistry {
+    /// Load the child devices registered by a previous run of the mapper, from the mapper directory.
+    ///
+    /// An unreadable registry is reset, the child devices being then registered again.
+    pub fn load(mapper_dir: &Path) -> Self {
+        let file = mapper_dir.join(CHILD_DEVICES_FILE);
+        let devices = match std::fs::read_to_string(&file) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|err| {
+                error!(
+                    "Ignoring the invalid child device registry {}: {err}",
+                    file.display()
+                );
+                BTreeMap::new()
+            }),
+            Err(_) => BTreeMap::new(),
+        };
+        ChildDeviceRegistry {
+            file: Some(file),
+            devices,
+        }
+    }
+
+    pub fn device_ids(&self) -> impl Iterator<Item = &String> {
+        self.devices.keys()
+    }
+
+    /// Register a child device, returning `true` if either new or changed.
+    pub fn register(
+        &mut self,
+        child_id: &str,
+        device: ChildDevice,
+    ) -> Result<bool, ConversionError> {
+        if self.devices.get(child_id) == Some(&device) {
+            return Ok(false);
+        }
+        self.devices.insert(child_id.to_string(), device);
+        self.persist()?;
+        Ok(true)
+    }
+
+    /// The ids of the child devices registered under the given parent.
+    pub fn children_of(&self, parent: &str) -> Vec<String> {
+        self.devices
+            .iter()
+            .filter(|(_, device)| device.parent.as_deref() == Some(parent))
+            .map(|(child_id, _)| child_id.clone())
+            .collect()
+    }
+
+    /// Remove a child device, returning `true` if it was registered.
+    pub fn remove(&mut self, child_id: &str) -> Result<bool, ConversionError> {
+        if self.devices.remove(child_id).is_none() {
+            return Ok(false);
+        }
+        self.persist()?;
+        Ok(true)
+    }
+
+    fn persist(&self) -> Result<(), ConversionError> {
+        if let Some(file) = &self.file {
+            if let Some(dir) = file.parent() {
+                std::fs::create_dir_all(dir)?;
+            }
+            std::fs::write(file, serde_json::to_string_pretty(&self.devices)?)?;
+        }
+        Ok(())
+    }
+}
+
+impl ChildDeviceRegistration {
+    pub fn from_payload(payload: &str) -> Result<Self, CumulocityMapperError> {
+        serde_json::from_str(payload).map_err(|err| {
+            CumulocityMapperError::InvalidChildDeviceRegistration {
+                reason: err.to_string(),
+            }
+        })
+    }
+
+    /// The child device to be registered, with the default name and type if not given.
+    pub fn device(&self, child_id: &str) -> ChildDevice {
+        ChildDevice {
+            name: self.name.clone().unwrap_or_else(|| child_id.to_string()),
+            device_type: self
+                .device_type
+                .clone()
+                .unwrap_or_else(|| DEFAULT_CHILD_DEVICE_TYPE.to_string()),
+            parent: self.parent.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tedge_test_utils::fs::TempTedgeDir;
+
+    #[test]
+    fn registered_child_devices_are_persisted() {
+        let mapper_dir = TempTedgeDir::new();
+        let mut registry = ChildDeviceRegistry::load(mapper_dir.path());
+        let device = ChildDevice {
+            name: "Sensor".into(),
+            device_type: DEFAULT_CHILD_DEVICE_TYPE.into(),
+            parent: Some("gateway1".into()),
+        };
+
+        assert!(registry.register("sensor1", device.clone()).unwrap());
+        assert!(!registry.register("sensor1", device.clone()).unwrap());
+        assert!(registry.register("sensor2", device).unwrap());
+        assert!(registry.remove("sensor2").unwrap());
+        assert!(!registry.remove("sensor2").unwrap());
+
+        let registry = ChildDeviceRegistry::load(mapper_dir.path());
+        assert_eq!(registry.device_ids().collect::<Vec<_>>(), vec!["sensor1"]);
+    }
+
+    #[test]
+    fn invalid_registry_is_reset() {
+        let mapper_dir = TempTedgeDir::new();
+        mapper_dir
+            .file(CHILD_DEVICES_FILE)
+            .with_raw_content("not json");
+
+        let registry = ChildDeviceRegistry::load(mapper_dir.path());
+        assert_eq!(registry.device_ids().count(), 0);
+    }
+
+    #[test]
+    fn registration_defaults_to_the_child_id_and_default_type() {
+        let registration = ChildDeviceRegistration::from_payload("{}").unwrap();
+        assert_eq!(
+            registration.device("sensor1"),
+            ChildDevice {
+                name: "sensor1".into(),
+                device_type: DEFAULT_CHILD_DEVICE_TYPE.into(),
+                parent: None,
+            }
+        );
+
+        assert!(ChildDeviceRegistration::from_payload(r#"{"nme":"typo"}"#).is_err());
+    }
+}
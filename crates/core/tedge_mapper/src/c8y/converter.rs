@@ -1,36 +1,42 @@
 use crate::c8y::dynamic_discovery::*;
 use crate::c8y::json;
+use crate::c8y::operation_command::{command_failure, OperationCommand};
+use crate::c8y::operation_output::result_parameters;
 use crate::core::{converter::*, error::*, size_threshold::SizeThreshold};
 use async_trait::async_trait;
 use c8y_api::smartrest::smartrest_deserializer::SmartRestRequestGeneric;
 use c8y_api::smartrest::{
     error::SmartRestDeserializerError,
     operations::{get_operation, Operations},
-    smartrest_deserializer::{SmartRestRestartRequest, SmartRestUpdateSoftware},
+    smartrest_deserializer::{
+        SmartRestFirmwareRequest, SmartRestRestartRequest, SmartRestUpdateSoftware,
+    },
     smartrest_serializer::{
         CumulocitySupportedOperations, SmartRestGetPendingOperations, SmartRestSerializer,
-        SmartRestSetOperationToExecuting, SmartRestSetOperationToFailed,
+        SmartRestSetFirmware, SmartRestSetOperationToExecuting, SmartRestSetOperationToFailed,
         SmartRestSetOperationToSuccessful,
     },
 };
 use c8y_api::{
     http_proxy::C8YHttpProxy,
-    json_c8y::{C8yCreateEvent, C8yUpdateSoftwareListResponse},
+    json_c8y::{C8yAlarm, C8yCreateEvent, C8yUpdateSoftwareListResponse},
 };
 use logged_command::LoggedCommand;
-use mqtt_channel::{Message, Topic, TopicFilter};
+use mqtt_channel::{Message, Sender, Topic, TopicFilter};
 use plugin_sm::operation_logs::OperationLogs;
 use std::collections::HashMap;
 use std::fs;
+use std::process::Output;
+use std::time::Duration;
 use std::{
     fs::File,
     io::Read,
     path::{Path, PathBuf},
 };
-use tedge_api::event::ThinEdgeEvent;
+use tedge_api::event::{ThinEdgeEvent, ThinEdgeEventData};
 use tedge_api::{
     topic::{RequestTopic, ResponseTopic},
-    Auth, DownloadInfo, Jsonify, OperationStatus, RestartOperationRequest,
+    Auth, DownloadInfo, FirmwareUpdateResponse, Jsonify, OperationStatus, RestartOperationRequest,
     RestartOperationResponse, SoftwareListRequest, SoftwareListResponse, SoftwareUpdateResponse,
 };
 use tedge_config::{get_tedge_config, ConfigSettingAccessor, LogPathSetting};
@@ -38,7 +44,20 @@ use time::format_description::well_known::Rfc3339;
 
 use tracing::{debug, info, log::error};
 
-use super::alarm_converter::AlarmConverter;
+use super::alarm_converter::{
+    alarm_key, cloud_alarm_key, AlarmConverter, AlarmHttpUpdate, PublishedAlarms,
+};
+use super::availability::{Availability, TEDGE_HEALTH_TOPIC};
+use super::child_devices::{
+    ChildDeviceRegistration, ChildDeviceRegistry, DEFAULT_CHILD_DEVICE_TYPE, TEDGE_REGISTER_TOPIC,
+};
+use super::inventory::{
+    parse_inventory_topic, InventoryConverter, INVENTORY_MANAGED_OBJECTS_TOPIC,
+    TEDGE_INVENTORY_TOPIC,
+};
+use super::service_monitor::ServiceMonitor;
+use super::shell_command::{execute_shell_command, ShellCommandConfig, SHELL_COMMAND_TEMPLATE};
+use super::smartrest_templates::SmartRestTemplates;
 use super::{
     error::CumulocityMapperError,
     fragments::{C8yAgentFragment, C8yDeviceDataFragment},
@@ -50,13 +69,16 @@ use c8y_api::smartrest::topic::{C8yTopic, MapperSubscribeTopic, SMARTREST_PUBLIS
 const C8Y_CLOUD: &str = "c8y";
 const INVENTORY_FRAGMENTS_FILE_LOCATION: &str = "device/inventory.json";
 const SUPPORTED_OPERATIONS_DIRECTORY: &str = "operations";
-const INVENTORY_MANAGED_OBJECTS_TOPIC: &str = "c8y/inventory/managedObjects/update";
 const INTERNAL_ALARMS_TOPIC: &str = "c8y-internal/alarms/";
 const TEDGE_EVENTS_TOPIC: &str = "tedge/events/";
 const C8Y_JSON_MQTT_EVENTS_TOPIC: &str = "c8y/event/events/create";
 const TEDGE_AGENT_LOG_DIR: &str = "tedge/agent";
 
-const CREATE_EVENT_SMARTREST_CODE: u16 = 400;
+pub(crate) const CREATE_EVENT_SMARTREST_CODE: u16 = 400;
+
+/// The size limit of the messages sent to Cumulocity over MQTT.
+pub(crate) const MAX_SMARTREST_MESSAGE_SIZE: usize = 16184;
+const TRUNCATION_MARKER: &str = "\n[truncated]";
 
 #[derive(Debug)]
 pub struct CumulocityConverter<Proxy>
@@ -68,11 +90,49 @@ where
     device_name: String,
     device_type: String,
     alarm_converter: AlarmConverter,
+    inventory_converter: InventoryConverter,
     pub operations: Operations,
     operation_logs: OperationLogs,
     http_proxy: Proxy,
     cfg_dir: PathBuf,
     pub children: HashMap<String, Operations>,
+    mqtt_publisher: Sender<Message>,
+    shell_command: Option<ShellCommandConfig>,
+    availability: Option<Availability>,
+    service_monitor: ServiceMonitor,
+    smartrest_templates: SmartRestTemplates,
+    child_registry: ChildDeviceRegistry,
+    published_alarms: PublishedAlarms,
+    /// The ids of the unresolved alarms of the cloud, by alarm key `<type>[/<child-id>]`.
+    cloud_alarm_ids: HashMap<String, String>,
+}
+
+/// The configuration of the mapper, with the subscriptions required by the given operations.
+pub fn create_mapper_config(operations: &Operations) -> MapperConfig {
+    let mut topic_filter: TopicFilter = vec![
+        "tedge/measurements",
+        "tedge/measurements/+",
+        "tedge/alarms/+/+",
+        "tedge/alarms/+/+/+",
+        "c8y-internal/alarms/+/+",
+        "c8y-internal/alarms/+/+/+",
+        "tedge/events/+",
+        "tedge/events/+/+",
+        "tedge/inventory/+",
+        "tedge/inventory/+/+",
+        "tedge/health/+",
+        "tedge/register/+",
+    ]
+    .try_into()
+    .expect("topics that mapper should subscribe to");
+
+    topic_filter.add_all(CumulocityMapper::subscriptions(operations).unwrap());
+
+    MapperConfig {
+        in_topic_filter: topic_filter,
+        out_topic: make_valid_topic_or_panic("c8y/measurement/measurements/create"),
+        errors_topic: make_valid_topic_or_panic("tedge/errors"),
+    }
 }
 
 impl<Proxy> CumulocityConverter<Proxy>
@@ -87,27 +147,9 @@ where
         http_proxy: Proxy,
         cfg_dir: &Path,
         children: HashMap<String, Operations>,
+        mqtt_publisher: Sender<Message>,
     ) -> Result<Self, CumulocityMapperError> {
-        let mut topic_filter: TopicFilter = vec![
-            "tedge/measurements",
-            "tedge/measurements/+",
-            "tedge/alarms/+/+",
-            "tedge/alarms/+/+/+",
-            "c8y-internal/alarms/+/+",
-            "c8y-internal/alarms/+/+/+",
-            "tedge/events/+",
-            "tedge/events/+/+",
-        ]
-        .try_into()
-        .expect("topics that mapper should subscribe to");
-
-        topic_filter.add_all(CumulocityMapper::subscriptions(&operations).unwrap());
-
-        let mapper_config = MapperConfig {
-            in_topic_filter: topic_filter,
-            out_topic: make_valid_topic_or_panic("c8y/measurement/measurements/create"),
-            errors_topic: make_valid_topic_or_panic("tedge/errors"),
-        };
+        let mapper_config = create_mapper_config(&operations);
 
         let alarm_converter = AlarmConverter::new();
 
@@ -124,11 +166,20 @@ where
             device_name,
             device_type,
             alarm_converter,
+            inventory_converter: InventoryConverter::new(),
             operations,
             operation_logs,
             http_proxy,
             cfg_dir: cfg_dir.to_path_buf(),
             children,
+            mqtt_publisher,
+            shell_command: None,
+            availability: None,
+            service_monitor: ServiceMonitor::default(),
+            smartrest_templates: SmartRestTemplates::default(),
+            child_registry: ChildDeviceRegistry::default(),
+            published_alarms: PublishedAlarms::default(),
+            cloud_alarm_ids: HashMap::new(),
         })
     }
 
@@ -141,27 +192,9 @@ where
         http_proxy: Proxy,
         logs_path: PathBuf,
         cfg_dir: PathBuf,
+        mqtt_publisher: Sender<Message>,
     ) -> Result<Self, CumulocityMapperError> {
-        let mut topic_filter: TopicFilter = vec![
-            "tedge/measurements",
-            "tedge/measurements/+",
-            "tedge/alarms/+/+",
-            "tedge/alarms/+/+/+",
-            "c8y-internal/alarms/+/+",
-            "c8y-internal/alarms/+/+/+",
-            "tedge/events/+",
-            "tedge/events/+/+",
-        ]
-        .try_into()
-        .expect("topics that mapper should subscribe to");
-
-        topic_filter.add_all(CumulocityMapper::subscriptions(&operations).unwrap());
-
-        let mapper_config = MapperConfig {
-            in_topic_filter: topic_filter,
-            out_topic: make_valid_topic_or_panic("c8y/measurement/measurements/create"),
-            errors_topic: make_valid_topic_or_panic("tedge/errors"),
-        };
+        let mapper_config = create_mapper_config(&operations);
 
         let alarm_converter = AlarmConverter::new();
 
@@ -179,59 +212,153 @@ where
             device_name,
             device_type,
             alarm_converter,
+            inventory_converter: InventoryConverter::new(),
             operations,
             operation_logs,
             http_proxy,
             cfg_dir,
             children,
+            mqtt_publisher,
+            shell_command: None,
+            availability: None,
+            service_monitor: ServiceMonitor::default(),
+            smartrest_templates: SmartRestTemplates::default(),
+            child_registry: ChildDeviceRegistry::default(),
+            published_alarms: PublishedAlarms::default(),
+            cloud_alarm_ids: HashMap::new(),
         })
     }
 
-    fn try_convert_measurement(
+    /// Execute the shell commands of the `c8y_Command` operations, with the given configuration.
+    pub fn with_shell_command(self, shell_command: ShellCommandConfig) -> Self {
+        Self {
+            shell_command: Some(shell_command),
+            ..self
+        }
+    }
+
+    /// Declare the required availability of the device and track the health of the services.
+    pub fn with_availability(self, availability: Availability) -> Self {
+        Self {
+            availability: Some(availability),
+            ..self
+        }
+    }
+
+    /// Register the custom SmartREST templates and use them to send measurements and events.
+    pub fn with_smartrest_templates(self, smartrest_templates: SmartRestTemplates) -> Self {
+        Self {
+            smartrest_templates,
+            ..self
+        }
+    }
+
+    /// Persist the explicitly registered child devices, the ones already registered being known from start.
+    pub fn with_child_registry(mut self, child_registry: ChildDeviceRegistry) -> Self {
+        for child_id in child_registry.device_ids() {
+            self.children.entry(child_id.clone()).or_default();
+        }
+        Self {
+            child_registry,
+            ..self
+        }
+    }
+
+    /// Persist the alarms published by thin-edge, the only ones cleared on the cloud when found stale.
+    pub fn with_published_alarms(self, published_alarms: PublishedAlarms) -> Self {
+        Self {
+            published_alarms,
+            ..self
+        }
+    }
+
+    async fn try_convert_measurement(
         &mut self,
         input: &Message,
     ) -> Result<Vec<Message>, ConversionError> {
         let mut mqtt_messages: Vec<Message> = Vec::new();
 
         let maybe_child_id = get_child_id_from_measurement_topic(&input.topic.name)?;
-        let c8y_json_payload = match maybe_child_id {
-            Some(child_id) => {
-                // Need to check if the input Thin Edge JSON is valid before adding a child ID to list
-                let c8y_json_child_payload =
-                    json::from_thin_edge_json_with_child(input.payload_str()?, child_id.as_str())?;
-                add_external_device_registration_message(
-                    child_id,
-                    &mut self.children,
-                    &mut mqtt_messages,
-                );
-                c8y_json_child_payload
+
+        // The custom templates are only used for the measurements of the main device
+        let tedge_measurement = if maybe_child_id.is_none() && !self.smartrest_templates.is_empty()
+        {
+            // Check the input is valid Thin Edge JSON before sending any part of it
+            let _ = json::from_thin_edge_json_with_text_values(input.payload_str()?, None)?;
+            let (mut template_messages, remaining) = self
+                .smartrest_templates
+                .convert_measurement(input.payload_str()?)?;
+            mqtt_messages.append(&mut template_messages);
+            match remaining {
+                Some(remaining) => remaining,
+                None => return Ok(mqtt_messages),
             }
-            None => json::from_thin_edge_json(input.payload_str()?)?,
+        } else {
+            input.payload_str()?.to_string()
         };
 
-        if c8y_json_payload.len() < self.size_threshold.0 {
-            mqtt_messages.push(Message::new(
-                &self.mapper_config.out_topic,
-                c8y_json_payload,
-            ));
-        } else {
-            return Err(ConversionError::TranslatedSizeExceededThreshold {
-                payload: input.payload_str()?[0..50].into(),
-                topic: input.topic.name.clone(),
-                actual_size: c8y_json_payload.len(),
-                threshold: self.size_threshold.0,
-            });
+        let (c8y_json_payload, text_values) = json::from_thin_edge_json_with_text_values(
+            &tedge_measurement,
+            maybe_child_id.as_deref(),
+        )?;
+
+        // The input Thin Edge JSON being valid, the child ID can be added to the list
+        if let Some(child_id) = maybe_child_id.clone() {
+            add_external_device_registration_message(
+                child_id,
+                &mut self.children,
+                &mut mqtt_messages,
+            );
+        }
+
+        if let Some(c8y_json_payload) = c8y_json_payload {
+            if c8y_json_payload.len() < self.size_threshold.0 {
+                mqtt_messages.push(Message::new(
+                    &self.mapper_config.out_topic,
+                    c8y_json_payload,
+                ));
+            } else {
+                return Err(ConversionError::TranslatedSizeExceededThreshold {
+                    payload: input.payload_str()?[0..50].into(),
+                    topic: input.topic.name.clone(),
+                    actual_size: c8y_json_payload.len(),
+                    threshold: self.size_threshold.0,
+                });
+            }
         }
+
+        // Cumulocity measurements only support numeric values: text values are sent as events
+        for text_value in text_values {
+            let tedge_event = ThinEdgeEvent {
+                name: text_value.name,
+                data: Some(ThinEdgeEventData {
+                    text: Some(text_value.value),
+                    time: Some(text_value.time),
+                    extras: HashMap::new(),
+                }),
+                source: maybe_child_id.clone(),
+            };
+            let mut event_messages = self.convert_tedge_event(tedge_event).await?;
+            mqtt_messages.append(&mut event_messages);
+        }
+
         Ok(mqtt_messages)
     }
 
     async fn try_convert_event(
         &mut self,
         input: &Message,
+    ) -> Result<Vec<Message>, ConversionError> {
+        let tedge_event = ThinEdgeEvent::try_from(&input.topic.name, input.payload_str()?)?;
+        self.convert_tedge_event(tedge_event).await
+    }
+
+    async fn convert_tedge_event(
+        &mut self,
+        tedge_event: ThinEdgeEvent,
     ) -> Result<Vec<Message>, ConversionError> {
         let mut messages = Vec::new();
 
-        let tedge_event = ThinEdgeEvent::try_from(&input.topic.name, input.payload_str()?)?;
         let child_id = tedge_event.source.clone();
         let need_registration = if let Some(child_id) = child_id.clone() {
             add_external_device_registration_message(child_id, &mut self.children, &mut messages)
@@ -241,8 +368,19 @@ where
 
         let c8y_event = C8yCreateEvent::try_from(tedge_event)?;
 
+        let template_message = match (&child_id, c8y_event.extras.is_empty()) {
+            (None, true) => self.smartrest_templates.convert_event(
+                &c8y_event.event_type,
+                &c8y_event.text,
+                &c8y_event.time,
+            )?,
+            _ => None,
+        };
+
         // If the message doesn't contain any fields other than `text` and `time`, convert to SmartREST
-        let message = if c8y_event.extras.is_empty() {
+        let message = if let Some(template_message) = template_message {
+            template_message
+        } else if c8y_event.extras.is_empty() {
             let smartrest_event = Self::serialize_to_smartrest(&c8y_event)?;
             let smartrest_topic = Topic::new_unchecked(SMARTREST_PUBLISH_TOPIC);
             Message::new(&smartrest_topic, smartrest_event)
@@ -269,7 +407,7 @@ where
         Ok(messages)
     }
 
-    pub fn process_alarm_messages(
+    pub async fn process_alarm_messages(
         &mut self,
         topic: &Topic,
         message: &Message,
@@ -277,8 +415,8 @@ where
         if topic.name.starts_with("tedge/alarms") {
             let mut mqtt_messages: Vec<Message> = Vec::new();
             self.size_threshold.validate(message)?;
-            let mut messages = self.alarm_converter.try_convert_alarm(message)?;
-            if !messages.is_empty() {
+            let mut converted = self.alarm_converter.try_convert_alarm(message)?;
+            if !converted.messages.is_empty() {
                 // When there is some messages to be sent on behalf of a child device,
                 // this child device must be declared first, if not done yet
                 let topic_split: Vec<&str> = topic.name.split('/').collect();
@@ -291,7 +429,22 @@ where
                     );
                 }
             }
-            mqtt_messages.append(&mut messages);
+            for http_update in converted.http_updates {
+                if let Err(err) = self.send_alarm_http_update(http_update).await {
+                    mqtt_messages.push(self.new_error_message(err));
+                }
+            }
+            // The cloud alarm raised or cleared by this message replaces the former one, if any
+            if let Some(renewed_alarm) = converted.renewed_alarm {
+                self.cloud_alarm_ids.remove(&renewed_alarm);
+            }
+            if !message.payload_bytes().is_empty() {
+                let alarm_id = topic.name.trim_start_matches("tedge/alarms/");
+                if let Err(err) = self.published_alarms.record(&alarm_key(alarm_id)) {
+                    mqtt_messages.push(self.new_error_message(err));
+                }
+            }
+            mqtt_messages.append(&mut converted.messages);
             Ok(mqtt_messages)
         } else if topic.name.starts_with(INTERNAL_ALARMS_TOPIC) {
             self.alarm_converter.process_internal_alarm(message);
@@ -301,6 +454,169 @@ where
         }
     }
 
+    /// Update an unresolved alarm over HTTP, as there is no SmartREST template for such an update.
+    ///
+    /// The id of the cloud alarm is only fetched when unknown,
+    /// i.e. when the alarm has been raised since the mapper started and not yet updated.
+    async fn send_alarm_http_update(
+        &mut self,
+        http_update: AlarmHttpUpdate,
+    ) -> Result<(), ConversionError> {
+        let alarm_key = http_update.alarm_key();
+        if !self.cloud_alarm_ids.contains_key(&alarm_key) {
+            let alarms = self
+                .http_proxy
+                .get_unresolved_alarms(http_update.child_id.clone())
+                .await?;
+            if let Some(alarm) = alarms
+                .into_iter()
+                .find(|alarm| alarm.alarm_type == http_update.alarm_type)
+            {
+                self.cloud_alarm_ids.insert(alarm_key.clone(), alarm.id);
+            }
+        }
+
+        match self.cloud_alarm_ids.get(&alarm_key) {
+            Some(alarm_id) => {
+                self.http_proxy
+                    .update_alarm(alarm_id, &http_update.update)
+                    .await?
+            }
+            None => debug!(
+                "No unresolved {} alarm to be updated on the cloud",
+                http_update.alarm_type
+            ),
+        }
+        Ok(())
+    }
+
+    /// The unresolved alarms of the cloud, for the device and the known child devices.
+    async fn get_cloud_alarms(&mut self) -> Vec<(Option<String>, C8yAlarm)> {
+        let mut cloud_alarms = Vec::new();
+        let devices = std::iter::once(None).chain(self.children.keys().cloned().map(Some));
+        for child_id in devices.collect::<Vec<_>>() {
+            match self
+                .http_proxy
+                .get_unresolved_alarms(child_id.clone())
+                .await
+            {
+                Ok(alarms) => {
+                    cloud_alarms.extend(alarms.into_iter().map(|alarm| (child_id.clone(), alarm)))
+                }
+                Err(err) => error!("Failed to get the alarms of the cloud: {err}"),
+            }
+        }
+        cloud_alarms
+    }
+
+    /// Record the fragment update, to be sent along other fragment updates received in a short period of time.
+    fn process_inventory_message(
+        &mut self,
+        message: &Message,
+    ) -> Result<Vec<Message>, ConversionError> {
+        self.size_threshold.validate(message)?;
+        let mut mqtt_messages: Vec<Message> = Vec::new();
+        let (maybe_child_id, fragment) = parse_inventory_topic(&message.topic.name)?;
+        let device_id = match maybe_child_id {
+            Some(child_id) => {
+                add_external_device_registration_message(
+                    child_id.to_string(),
+                    &mut self.children,
+                    &mut mqtt_messages,
+                );
+                child_id
+            }
+            None => &self.device_name,
+        };
+
+        self.inventory_converter.process_fragment(
+            device_id,
+            fragment,
+            message.payload_str()?,
+            &self.mqtt_publisher,
+        )?;
+        Ok(mqtt_messages)
+    }
+
+    /// Register a child device under its parent, or forget it on an empty payload.
+    fn process_registration_message(
+        &mut self,
+        message: &Message,
+    ) -> Result<Vec<Message>, ConversionError> {
+        let child_id = message
+            .topic
+            .name
+            .strip_prefix(TEDGE_REGISTER_TOPIC)
+            .filter(|child_id| !child_id.is_empty())
+            .ok_or_else(|| ConversionError::UnsupportedTopic(message.topic.name.clone()))?;
+
+        let payload = message.payload_str()?;
+        if payload.trim().is_empty() {
+            // A parent device cannot be removed before its nested child devices
+            let children = self.child_registry.children_of(child_id);
+            if !children.is_empty() {
+                return Err(CumulocityMapperError::ParentDeviceWithChildren {
+                    parent: child_id.to_string(),
+                    children: children.join(", "),
+                }
+                .into());
+            }
+            let ops_dir = self
+                .cfg_dir
+                .join(SUPPORTED_OPERATIONS_DIRECTORY)
+                .join(C8Y_CLOUD)
+                .join(child_id);
+            // The child devices with operations are still known from their operation directory
+            if self.child_registry.remove(child_id)? && !ops_dir.is_dir() {
+                self.children.remove(child_id);
+            }
+            return Ok(vec![]);
+        }
+
+        let registration = ChildDeviceRegistration::from_payload(payload)?;
+        let mut device = registration.device(child_id);
+        if device.parent.as_deref() == Some(self.device_name.as_str()) {
+            device.parent = None;
+        }
+        if let Some(parent) = &device.parent {
+            if parent == child_id || !self.children.contains_key(parent) {
+                return Err(CumulocityMapperError::UnknownParentDevice {
+                    child_id: child_id.to_string(),
+                    parent: parent.clone(),
+                }
+                .into());
+            }
+        }
+
+        let mut mqtt_messages = Vec::new();
+        let smartrest_topic = match &device.parent {
+            Some(parent) => format!("{SMARTREST_PUBLISH_TOPIC}/{parent}"),
+            None => SMARTREST_PUBLISH_TOPIC.to_string(),
+        };
+        let registration_message = Message::new(
+            &Topic::new_unchecked(&smartrest_topic),
+            format!(
+                "101,{child_id},{},{}",
+                quote_smartrest_value(&device.name),
+                quote_smartrest_value(&device.device_type)
+            ),
+        );
+        if self.child_registry.register(child_id, device)? {
+            mqtt_messages.push(registration_message);
+        }
+        self.children.entry(child_id.to_string()).or_default();
+
+        for (fragment, value) in registration.fragments.iter() {
+            self.inventory_converter.process_fragment(
+                child_id,
+                fragment,
+                &value.to_string(),
+                &self.mqtt_publisher,
+            )?;
+        }
+        Ok(mqtt_messages)
+    }
+
     fn serialize_to_smartrest(c8y_event: &C8yCreateEvent) -> Result<String, ConversionError> {
         Ok(format!(
             "{},{},\"{}\",{}",
@@ -330,17 +646,30 @@ where
         match &message.topic {
             topic if topic.name.starts_with("tedge/measurements") => {
                 self.size_threshold.validate(message)?;
-                self.try_convert_measurement(message)
+                self.try_convert_measurement(message).await
             }
             topic
                 if topic.name.starts_with("tedge/alarms")
                     | topic.name.starts_with(INTERNAL_ALARMS_TOPIC) =>
             {
-                self.process_alarm_messages(topic, message)
+                self.service_monitor.process_alarm_message(message);
+                self.process_alarm_messages(topic, message).await
             }
             topic if topic.name.starts_with(TEDGE_EVENTS_TOPIC) => {
                 self.try_convert_event(message).await
             }
+            topic if topic.name.starts_with(TEDGE_INVENTORY_TOPIC) => {
+                self.process_inventory_message(message)
+            }
+            topic if topic.name.starts_with(TEDGE_REGISTER_TOPIC) => {
+                self.process_registration_message(message)
+            }
+            topic if topic.name.starts_with(TEDGE_HEALTH_TOPIC) => {
+                if let Some(availability) = &self.availability {
+                    availability.process_health_message(message);
+                }
+                Ok(self.service_monitor.process_health_message(message))
+            }
             topic => match topic.clone().try_into() {
                 Ok(MapperSubscribeTopic::ResponseTopic(ResponseTopic::SoftwareListResponse)) => {
                     debug!("Software list");
@@ -358,21 +687,40 @@ where
                     )
                 }
                 Ok(MapperSubscribeTopic::ResponseTopic(ResponseTopic::RestartResponse)) => {
-                    Ok(publish_restart_operation_status(message.payload_str()?).await?)
+                    Ok(publish_restart_operation_status(
+                        message.payload_str()?,
+                        C8yTopic::SmartRestResponse,
+                    )
+                    .await?)
+                }
+                Ok(MapperSubscribeTopic::ResponseTopic(ResponseTopic::FirmwareUpdateResponse)) => {
+                    Ok(publish_firmware_operation_status(
+                        message.payload_str()?,
+                        C8yTopic::SmartRestResponse,
+                    )?)
                 }
                 Ok(MapperSubscribeTopic::C8yTopic(_)) => {
-                    parse_c8y_topics(
-                        message,
-                        &self.operations,
-                        &mut self.http_proxy,
-                        &self.operation_logs,
-                        &self.device_name,
-                    )
-                    .await
+                    let context = OperationContext {
+                        operations: &self.operations,
+                        children: &self.children,
+                        operation_logs: &self.operation_logs,
+                        mqtt_publisher: &self.mqtt_publisher,
+                        shell_command: self.shell_command.as_ref(),
+                        device_name: &self.device_name,
+                    };
+                    parse_c8y_topics(message, &context, &mut self.http_proxy).await
                 }
-                _ => Err(ConversionError::UnsupportedTopic(
-                    message.topic.name.clone(),
-                )),
+                _ => match ResponseTopic::from_child_topic(&message.topic.name) {
+                    Some((child_id, response_topic)) => Ok(publish_child_operation_status(
+                        &child_id,
+                        response_topic,
+                        message.payload_str()?,
+                    )
+                    .await?),
+                    None => Err(ConversionError::UnsupportedTopic(
+                        message.topic.name.clone(),
+                    )),
+                },
             },
         }
     }
@@ -393,6 +741,9 @@ where
         let device_data_message = self.wrap_error(create_device_data_fragments(
             &self.device_name,
             &self.device_type,
+            self.availability
+                .as_ref()
+                .map(Availability::required_interval_minutes),
         ));
 
         let pending_operations_message = self.wrap_error(create_get_pending_operations_message());
@@ -405,12 +756,20 @@ where
             software_list_message,
         ];
         msg.append(&mut supported_child_operations_message);
+        msg.append(&mut self.smartrest_templates.registration_messages());
         Ok(msg)
     }
 
-    fn sync_messages(&mut self) -> Vec<Message> {
-        let sync_messages: Vec<Message> = self.alarm_converter.sync();
-        self.alarm_converter = AlarmConverter::Synced;
+    async fn sync_messages(&mut self) -> Vec<Message> {
+        let cloud_alarms = self.get_cloud_alarms().await;
+        let mut sync_messages: Vec<Message> = self
+            .alarm_converter
+            .sync(&cloud_alarms, &self.published_alarms);
+        self.cloud_alarm_ids = cloud_alarms
+            .into_iter()
+            .map(|(child_id, alarm)| (cloud_alarm_key(&child_id, &alarm.alarm_type), alarm.id))
+            .collect();
+        sync_messages.append(&mut self.inventory_converter.sync());
         sync_messages
     }
 
@@ -472,24 +831,30 @@ fn add_or_remove_operation(
     Ok(())
 }
 
+/// What is needed to process the operation requests received from Cumulocity.
+#[derive(Clone, Copy)]
+struct OperationContext<'a> {
+    /// The operations supported by the main device.
+    operations: &'a Operations,
+    /// The operations supported by the child devices, by child id.
+    children: &'a HashMap<String, Operations>,
+    operation_logs: &'a OperationLogs,
+    /// Where the outcome of the operations executed in the background is published.
+    mqtt_publisher: &'a Sender<Message>,
+    /// The configuration of the `c8y_Command` operation, if enabled.
+    shell_command: Option<&'a ShellCommandConfig>,
+    device_name: &'a str,
+}
+
 async fn parse_c8y_topics(
     message: &Message,
-    operations: &Operations,
+    context: &OperationContext<'_>,
     http_proxy: &mut impl C8YHttpProxy,
-    operation_logs: &OperationLogs,
-    device_name: &str,
 ) -> Result<Vec<Message>, ConversionError> {
+    let device_name = context.device_name;
     let mut output: Vec<Message> = Vec::new();
     for smartrest_message in message.payload_str()?.split('\n') {
-        match process_smartrest(
-            smartrest_message,
-            operations,
-            http_proxy,
-            operation_logs,
-            device_name,
-        )
-        .await
-        {
+        match process_smartrest(smartrest_message, context, http_proxy).await {
             Err(
                 ref err @ CumulocityMapperError::FromSmartRestDeserializer(
                     SmartRestDeserializerError::InvalidParameter { ref operation, .. },
@@ -499,7 +864,12 @@ async fn parse_c8y_topics(
                     ..
                 },
             ) => {
-                let topic = C8yTopic::SmartRestResponse.to_topic()?;
+                let topic = match get_smartrest_device_id(smartrest_message) {
+                    Some(device_id) if device_id != device_name => {
+                        C8yTopic::ChildSmartRestResponse(device_id).to_topic()?
+                    }
+                    _ => C8yTopic::SmartRestResponse.to_topic()?,
+                };
                 let msg1 = Message::new(&topic, format!("501,{operation}"));
                 let msg2 =
                     Message::new(&topic, format!("502,{operation},\"{}\"", &err.to_string()));
@@ -519,8 +889,12 @@ async fn parse_c8y_topics(
 fn create_device_data_fragments(
     device_name: &str,
     device_type: &str,
+    required_availability: Option<u64>,
 ) -> Result<Message, ConversionError> {
-    let device_data = C8yDeviceDataFragment::from_type(device_type)?;
+    let mut device_data = C8yDeviceDataFragment::from_type(device_type)?;
+    if let Some(response_interval) = required_availability {
+        device_data = device_data.with_required_availability(response_interval);
+    }
     let ops_msg = device_data.to_json()?;
 
     let topic = Topic::new_unchecked(&format!("{INVENTORY_MANAGED_OBJECTS_TOPIC}/{device_name}",));
@@ -630,7 +1004,7 @@ fn add_external_device_registration_message(
         children.insert(child_id.to_string(), Operations::default());
         mqtt_messages.push(Message::new(
             &Topic::new_unchecked(SMARTREST_PUBLISH_TOPIC),
-            format!("101,{child_id},{child_id},thin-edge.io-child"),
+            format!("101,{child_id},{child_id},{DEFAULT_CHILD_DEVICE_TYPE}"),
         ));
         return true;
     }
@@ -650,9 +1024,10 @@ fn create_inventory_fragments_message(
 
 async fn publish_restart_operation_status(
     json_response: &str,
+    c8y_topic: C8yTopic,
 ) -> Result<Vec<Message>, CumulocityMapperError> {
     let response = RestartOperationResponse::from_json(json_response)?;
-    let topic = C8yTopic::SmartRestResponse.to_topic()?;
+    let topic = c8y_topic.to_topic()?;
 
     match response.status() {
         OperationStatus::Executing => {
@@ -681,6 +1056,47 @@ async fn publish_restart_operation_status(
     }
 }
 
+/// Forward to Cumulocity the status of a firmware update,
+/// along with the installed firmware when successful.
+fn publish_firmware_operation_status(
+    json_response: &str,
+    c8y_topic: C8yTopic,
+) -> Result<Vec<Message>, CumulocityMapperError> {
+    let response = FirmwareUpdateResponse::from_json(json_response)?;
+    let topic = c8y_topic.to_topic()?;
+
+    match response.status() {
+        OperationStatus::Executing => {
+            let smartrest_set_operation =
+                SmartRestSetOperationToExecuting::new(CumulocitySupportedOperations::C8yFirmware)
+                    .to_smartrest()?;
+            Ok(vec![Message::new(&topic, smartrest_set_operation)])
+        }
+        OperationStatus::Successful => {
+            let smartrest_set_firmware =
+                SmartRestSetFirmware::new(&response.name, &response.version, &response.url)
+                    .to_smartrest()?;
+            let smartrest_set_operation =
+                SmartRestSetOperationToSuccessful::new(CumulocitySupportedOperations::C8yFirmware)
+                    .to_smartrest()?;
+            Ok(vec![
+                Message::new(&topic, smartrest_set_firmware),
+                Message::new(&topic, smartrest_set_operation),
+            ])
+        }
+        OperationStatus::Failed => {
+            let smartrest_set_operation = SmartRestSetOperationToFailed::new(
+                CumulocitySupportedOperations::C8yFirmware,
+                response
+                    .error()
+                    .unwrap_or_else(|| "Firmware update failed".into()),
+            )
+            .to_smartrest()?;
+            Ok(vec![Message::new(&topic, smartrest_set_operation)])
+        }
+    }
+}
+
 async fn publish_operation_status(
     json_response: &str,
     http_proxy: &mut impl C8YHttpProxy,
@@ -709,6 +1125,45 @@ async fn publish_operation_status(
     }
 }
 
+/// Forward to Cumulocity the status of an operation executed by a child device.
+async fn publish_child_operation_status(
+    child_id: &str,
+    response_topic: ResponseTopic,
+    json_response: &str,
+) -> Result<Vec<Message>, CumulocityMapperError> {
+    let c8y_topic = C8yTopic::ChildSmartRestResponse(child_id.to_string());
+    match response_topic {
+        ResponseTopic::RestartResponse => {
+            publish_restart_operation_status(json_response, c8y_topic).await
+        }
+        ResponseTopic::SoftwareUpdateResponse => {
+            let response = SoftwareUpdateResponse::from_json(json_response)?;
+            let smartrest_set_operation = match response.status() {
+                OperationStatus::Executing => {
+                    SmartRestSetOperationToExecuting::from_thin_edge_json(response)?
+                        .to_smartrest()?
+                }
+                OperationStatus::Successful => {
+                    SmartRestSetOperationToSuccessful::from_thin_edge_json(response)?
+                        .to_smartrest()?
+                }
+                OperationStatus::Failed => {
+                    SmartRestSetOperationToFailed::from_thin_edge_json(response)?.to_smartrest()?
+                }
+            };
+            Ok(vec![Message::new(
+                &c8y_topic.to_topic()?,
+                smartrest_set_operation,
+            )])
+        }
+        ResponseTopic::FirmwareUpdateResponse => {
+            publish_firmware_operation_status(json_response, c8y_topic)
+        }
+        // The software list of a child device is not requested by the mapper
+        ResponseTopic::SoftwareListResponse => Ok(vec![]),
+    }
+}
+
 async fn validate_and_publish_software_list(
     payload: &str,
     http_proxy: &mut impl C8YHttpProxy,
@@ -733,71 +1188,178 @@ async fn validate_and_publish_software_list(
     Ok(vec![])
 }
 
+/// Launch the command of a custom operation, reporting its outcome to Cumulocity once completed.
+///
+/// The operation is marked as executing (`501`) as soon as the command has been launched.
+/// The operation is then marked as successful (`503`) or failed (`502`)
+/// depending on the exit status of the command, unless it is killed when the timeout elapses.
+///
+/// Along its execution, the command can report progress messages and result parameters
+/// using the protocol defined in [operation_output](crate::c8y::operation_output).
+///
+/// All these messages are published on the `status_topic` of the target device.
 async fn execute_operation(
     payload: &str,
     command: &str,
     operation_name: &str,
+    timeout: Option<Duration>,
     operation_logs: &OperationLogs,
+    mqtt_publisher: &Sender<Message>,
+    status_topic: &Topic,
 ) -> Result<(), CumulocityMapperError> {
-    let command = command.to_owned();
-    let payload = payload.to_string();
-
-    let mut logged = LoggedCommand::new(&command);
-    logged.arg(&payload);
-
-    let child = logged
-        .spawn()
-        .map_err(|e| CumulocityMapperError::ExecuteFailed {
-            error_message: e.to_string(),
-            command: command.to_string(),
-            operation_name: operation_name.to_string(),
-        });
+    let mut logged = LoggedCommand::new(command);
+    logged.arg(payload);
+    if let Some(timeout) = timeout {
+        logged.timeout(timeout);
+    }
 
-    let mut log_file = operation_logs
-        .new_log_file(plugin_sm::operation_logs::LogKind::Operation(
-            operation_name.to_string(),
-        ))
-        .await?;
+    OperationCommand {
+        operation_name: operation_name.to_string(),
+        command: logged,
+        report_progress: true,
+        result_parameters: |output| result_parameters(&String::from_utf8_lossy(&output.stdout)),
+        failure_reason: operation_failure_reason,
+    }
+    .launch(operation_logs, mqtt_publisher, status_topic)
+    .await
+}
 
-    match child {
-        Ok(child) => {
-            tokio::spawn(async move {
-                let logger = log_file.buffer();
-                let _result = child.wait_with_output(logger).await.unwrap();
-            });
-            Ok(())
+/// The reason of an operation failure, built from the exit status and the error output of the command.
+fn operation_failure_reason(output: &Output) -> String {
+    let failure = command_failure(output);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    match stderr.trim() {
+        "" => format!("Operation {failure}"),
+        stderr => format!("Operation {failure}: {stderr}"),
+    }
+}
+
+/// Quote a free-text value of a SmartREST message, as it might contain commas or quotes.
+pub(crate) fn quote_smartrest_value(value: &str) -> String {
+    format!("\"{}\"", value.replace('"', "\"\""))
+}
+
+/// Quote a free-text value of a SmartREST message, truncating it so the quoted value is at most `max_size` bytes.
+///
+/// The truncation applies to the quoted value, so the size limit holds whatever the number of escaped quotes,
+/// and never splits an escaped quote nor a UTF-8 character.
+/// Returns `None` if there is not even room for a truncated value.
+pub(crate) fn quote_smartrest_value_truncated(value: &str, max_size: usize) -> Option<String> {
+    let quoted = quote_smartrest_value(value);
+    if quoted.len() <= max_size {
+        return Some(quoted);
+    }
+
+    let max_content_size = max_size.checked_sub(TRUNCATION_MARKER.len() + 2)?;
+    let mut truncated = String::from("\"");
+    for c in value.chars() {
+        let escaped_size = if c == '"' { 2 } else { c.len_utf8() };
+        if truncated.len() - 1 + escaped_size > max_content_size {
+            break;
         }
-        Err(err) => Err(err),
+        if c == '"' {
+            truncated.push('"');
+        }
+        truncated.push(c);
     }
+    truncated.push_str(TRUNCATION_MARKER);
+    truncated.push('"');
+    Some(truncated)
 }
 
 async fn process_smartrest(
     payload: &str,
-    operations: &Operations,
+    context: &OperationContext<'_>,
     http_proxy: &mut impl C8YHttpProxy,
-    operation_logs: &OperationLogs,
-    device_name: &str,
 ) -> Result<Vec<Message>, CumulocityMapperError> {
+    let OperationContext {
+        operations,
+        children,
+        operation_logs,
+        mqtt_publisher,
+        shell_command,
+        device_name,
+    } = *context;
     match get_smartrest_device_id(payload) {
         Some(device_id) if device_id == device_name => {
             match get_smartrest_template_id(payload).as_str() {
-                "528" => forward_software_request(payload, http_proxy).await,
-                "510" => forward_restart_request(payload),
-                template => {
-                    forward_operation_request(payload, template, operations, operation_logs).await
+                "528" => {
+                    let topic = Topic::new(RequestTopic::SoftwareUpdateRequest.as_str())?;
+                    forward_software_request(payload, topic, http_proxy).await
+                }
+                "510" => {
+                    let topic = Topic::new(RequestTopic::RestartRequest.as_str())?;
+                    forward_restart_request(payload, topic)
                 }
+                "515" => {
+                    let topic = Topic::new(RequestTopic::FirmwareUpdateRequest.as_str())?;
+                    forward_firmware_request(payload, topic, http_proxy).await
+                }
+                // Unless enabled, c8y_Command is handled as any custom operation
+                template => match shell_command.filter(|_| template == SHELL_COMMAND_TEMPLATE) {
+                    Some(shell_command) => {
+                        execute_shell_command(
+                            payload,
+                            shell_command,
+                            operation_logs,
+                            mqtt_publisher,
+                            &C8yTopic::SmartRestResponse.to_topic()?,
+                        )
+                        .await?;
+                        Ok(vec![])
+                    }
+                    None => {
+                        forward_operation_request(
+                            payload,
+                            template,
+                            operations,
+                            operation_logs,
+                            mqtt_publisher,
+                            &C8yTopic::SmartRestResponse.to_topic()?,
+                        )
+                        .await
+                    }
+                },
             }
         }
-        // Ignore all operations for child devices as not yet supported
-        _ => Ok(vec![]),
+        // The requests for a child device are forwarded on the topics of this child device
+        Some(child_id) => match get_smartrest_template_id(payload).as_str() {
+            "528" => {
+                let topic = Topic::new(&RequestTopic::SoftwareUpdateRequest.for_child(&child_id))?;
+                forward_software_request(payload, topic, http_proxy).await
+            }
+            "510" => {
+                let topic = Topic::new(&RequestTopic::RestartRequest.for_child(&child_id))?;
+                forward_restart_request(payload, topic)
+            }
+            "515" => {
+                let topic = Topic::new(&RequestTopic::FirmwareUpdateRequest.for_child(&child_id))?;
+                forward_firmware_request(payload, topic, http_proxy).await
+            }
+            template => match children.get(&child_id) {
+                Some(child_operations) => {
+                    forward_operation_request(
+                        payload,
+                        template,
+                        child_operations,
+                        operation_logs,
+                        mqtt_publisher,
+                        &C8yTopic::ChildSmartRestResponse(child_id).to_topic()?,
+                    )
+                    .await
+                }
+                None => Ok(vec![]),
+            },
+        },
+        None => Ok(vec![]),
     }
 }
 
 async fn forward_software_request(
     smartrest: &str,
+    topic: Topic,
     http_proxy: &mut impl C8YHttpProxy,
 ) -> Result<Vec<Message>, CumulocityMapperError> {
-    let topic = Topic::new(RequestTopic::SoftwareUpdateRequest.as_str())?;
     let update_software = SmartRestUpdateSoftware::default();
     let mut software_update_request = update_software
         .from_smartrest(smartrest)?
@@ -828,32 +1390,62 @@ async fn forward_software_request(
     )])
 }
 
-fn forward_restart_request(smartrest: &str) -> Result<Vec<Message>, CumulocityMapperError> {
-    let topic = Topic::new(RequestTopic::RestartRequest.as_str())?;
+fn forward_restart_request(
+    smartrest: &str,
+    topic: Topic,
+) -> Result<Vec<Message>, CumulocityMapperError> {
     let _ = SmartRestRestartRequest::from_smartrest(smartrest)?;
 
     let request = RestartOperationRequest::default();
     Ok(vec![Message::new(&topic, request.to_json()?)])
 }
 
+async fn forward_firmware_request(
+    smartrest: &str,
+    topic: Topic,
+    http_proxy: &mut impl C8YHttpProxy,
+) -> Result<Vec<Message>, CumulocityMapperError> {
+    let mut request = SmartRestFirmwareRequest::from_smartrest(smartrest)?.to_thin_edge_json();
+
+    // The images stored in the Cumulocity firmware repository are only accessible with a token
+    if http_proxy.url_is_in_my_tenant_domain(&request.url) {
+        let token = http_proxy.get_jwt_token().await?;
+        request = request.with_auth(Auth::new_bearer(&token.token()));
+    }
+
+    Ok(vec![Message::new(&topic, request.to_json()?)])
+}
+
 async fn forward_operation_request(
     payload: &str,
     template: &str,
     operations: &Operations,
     operation_logs: &OperationLogs,
+    mqtt_publisher: &Sender<Message>,
+    status_topic: &Topic,
 ) -> Result<Vec<Message>, CumulocityMapperError> {
     match operations.matching_smartrest_template(template) {
-        Some(operation) => {
-            if let Some(command) = operation.command() {
-                execute_operation(payload, command.as_str(), &operation.name, operation_logs)
-                    .await?;
+        Some(operation) => match operation.command() {
+            Some(command) => {
+                // The status messages are published when the command is launched and completed
+                execute_operation(
+                    payload,
+                    command.as_str(),
+                    &operation.name,
+                    operation.timeout(),
+                    operation_logs,
+                    mqtt_publisher,
+                    status_topic,
+                )
+                .await?;
+                Ok(vec![])
             }
-            let topic = C8yTopic::SmartRestResponse.to_topic()?;
-            let msg1 = Message::new(&topic, format!("501,{}", operation.name));
-            let msg2 = Message::new(&topic, format!("503,{}", operation.name));
-
-            Ok(vec![msg1, msg2])
-        }
+            None => {
+                let msg1 = Message::new(status_topic, format!("501,{}", operation.name));
+                let msg2 = Message::new(status_topic, format!("503,{}", operation.name));
+                Ok(vec![msg1, msg2])
+            }
+        },
         None => Ok(vec![]),
     }
 }
@@ -917,21 +1509,46 @@ pub fn get_child_id_from_measurement_topic(topic: &str) -> Result<Option<String>
 #[cfg(test)]
 mod tests {
     use crate::c8y::tests::FakeC8YHttpProxy;
+    use c8y_api::smartrest::topic::C8yTopic;
+    use futures::channel::mpsc;
+    use futures::StreamExt;
+    use mqtt_channel::Message;
     use plugin_sm::operation_logs::OperationLogs;
+    use std::os::unix::fs::PermissionsExt;
+    use std::time::Duration;
+    use tedge_api::topic::ResponseTopic;
+    use tedge_api::{Auth, FirmwareUpdateRequest, Jsonify};
     use tedge_test_utils::fs::TempTedgeDir;
 
     #[tokio::test]
     async fn test_execute_operation_is_not_blocked() {
         let log_dir = TempTedgeDir::new();
         let operation_logs = OperationLogs::try_new(log_dir.path().to_path_buf()).unwrap();
+        let (mqtt_publisher, _) = mpsc::channel(10);
 
         let now = std::time::Instant::now();
-        super::execute_operation("5", "sleep", "sleep_one", &operation_logs)
-            .await
-            .unwrap();
-        super::execute_operation("5", "sleep", "sleep_two", &operation_logs)
-            .await
-            .unwrap();
+        super::execute_operation(
+            "5",
+            "sleep",
+            "sleep_one",
+            None,
+            &operation_logs,
+            &mqtt_publisher,
+            &C8yTopic::SmartRestResponse.to_topic().unwrap(),
+        )
+        .await
+        .unwrap();
+        super::execute_operation(
+            "5",
+            "sleep",
+            "sleep_two",
+            None,
+            &operation_logs,
+            &mqtt_publisher,
+            &C8yTopic::SmartRestResponse.to_topic().unwrap(),
+        )
+        .await
+        .unwrap();
 
         // a result between now and elapsed that is not 0 probably means that the operations are
         // blocking and that you probably removed a tokio::spawn handle (;
@@ -939,18 +1556,348 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn ignore_operations_for_child_device() {
+    async fn custom_operation_status_is_the_outcome_of_the_command() {
+        let log_dir = TempTedgeDir::new();
+        let operation_logs = OperationLogs::try_new(log_dir.path().to_path_buf()).unwrap();
+        let (mqtt_publisher, mut published) = mpsc::channel(10);
+
+        let command = log_dir.path().join("failing_handler");
+        std::fs::write(
+            &command,
+            "#!/bin/sh\necho \"cannot process \\\"$1\\\"\" >&2\nexit 3\n",
+        )
+        .unwrap();
+        std::fs::set_permissions(&command, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        super::execute_operation(
+            "511,device,ping",
+            "true",
+            "c8y_Ping",
+            None,
+            &operation_logs,
+            &mqtt_publisher,
+            &C8yTopic::SmartRestResponse.to_topic().unwrap(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(next_status(&mut published).await, "501,c8y_Ping");
+        assert_eq!(next_status(&mut published).await, "503,c8y_Ping");
+
+        super::execute_operation(
+            "530,device,payload",
+            command.to_str().unwrap(),
+            "c8y_Custom",
+            None,
+            &operation_logs,
+            &mqtt_publisher,
+            &C8yTopic::SmartRestResponse.to_topic().unwrap(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(next_status(&mut published).await, "501,c8y_Custom");
+        assert_eq!(
+            next_status(&mut published).await,
+            r#"502,c8y_Custom,"Operation failed with exit status: 3: cannot process ""530,device,payload""""#
+        );
+    }
+
+    #[tokio::test]
+    async fn custom_operation_progress_and_results_are_forwarded() {
+        let log_dir = TempTedgeDir::new();
+        let operation_logs = OperationLogs::try_new(log_dir.path().to_path_buf()).unwrap();
+        let (mqtt_publisher, mut published) = mpsc::channel(10);
+
+        let command = log_dir.path().join("diagnostic_handler");
+        std::fs::write(
+            &command,
+            r#"#!/bin/sh
+echo "checking the network"
+echo ":::progress::: network OK"
+echo ":::result::: OK"
+echo ":::begin-result:::"
+echo "eth0: up"
+echo "wlan0: down"
+echo ":::end-result:::"
+"#,
+        )
+        .unwrap();
+        std::fs::set_permissions(&command, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        super::execute_operation(
+            "530,device",
+            command.to_str().unwrap(),
+            "c8y_Diagnostic",
+            None,
+            &operation_logs,
+            &mqtt_publisher,
+            &C8yTopic::SmartRestResponse.to_topic().unwrap(),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(next_status(&mut published).await, "501,c8y_Diagnostic");
+        assert_eq!(
+            next_status(&mut published).await,
+            r#"400,c8y_OperationProgress,"c8y_Diagnostic: network OK""#
+        );
+        assert_eq!(
+            next_status(&mut published).await,
+            "503,c8y_Diagnostic,\"OK\",\"eth0: up\nwlan0: down\""
+        );
+    }
+
+    #[tokio::test]
+    async fn custom_operation_fails_when_the_timeout_elapses() {
+        let log_dir = TempTedgeDir::new();
+        let operation_logs = OperationLogs::try_new(log_dir.path().to_path_buf()).unwrap();
+        let (mqtt_publisher, mut published) = mpsc::channel(10);
+
+        super::execute_operation(
+            "10",
+            "sleep",
+            "c8y_Sleep",
+            Some(Duration::from_secs(1)),
+            &operation_logs,
+            &mqtt_publisher,
+            &C8yTopic::SmartRestResponse.to_topic().unwrap(),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(next_status(&mut published).await, "501,c8y_Sleep");
+        assert_eq!(
+            next_status(&mut published).await,
+            r#"502,c8y_Sleep,"Killed after a timeout of 1 seconds""#
+        );
+    }
+
+    #[tokio::test]
+    async fn custom_operation_results_are_truncated_to_the_smartrest_size_limit() {
+        let log_dir = TempTedgeDir::new();
+        let operation_logs = OperationLogs::try_new(log_dir.path().to_path_buf()).unwrap();
+        let (mqtt_publisher, mut published) = mpsc::channel(10);
+
+        let command = log_dir.path().join("verbose_handler");
+        std::fs::write(
+            &command,
+            r#"#!/bin/sh
+echo ":::begin-result:::"
+i=0
+while [ $i -lt 5000 ]; do echo '"quoted"'; i=$((i+1)); done
+echo ":::end-result:::"
+echo ":::result::: dropped"
+"#,
+        )
+        .unwrap();
+        std::fs::set_permissions(&command, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        super::execute_operation(
+            "530,device",
+            command.to_str().unwrap(),
+            "c8y_Verbose",
+            None,
+            &operation_logs,
+            &mqtt_publisher,
+            &C8yTopic::SmartRestResponse.to_topic().unwrap(),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(next_status(&mut published).await, "501,c8y_Verbose");
+        let status = next_status(&mut published).await;
+        assert!(status.len() <= super::MAX_SMARTREST_MESSAGE_SIZE);
+        assert!(status.starts_with("503,c8y_Verbose,\"\"\"quoted\"\""));
+        assert!(status.ends_with("\n[truncated]\""));
+    }
+
+    #[test]
+    fn smartrest_values_are_quoted_without_truncation_when_they_fit() {
+        assert_eq!(
+            super::quote_smartrest_value_truncated(r#"a "b""#, 100),
+            Some(r#""a ""b""""#.to_string())
+        );
+    }
+
+    #[test]
+    fn smartrest_values_are_truncated_after_quoting() {
+        let truncated = super::quote_smartrest_value_truncated(&"\"".repeat(100), 40).unwrap();
+        assert!(truncated.len() <= 40);
+        assert_eq!(truncated, format!("\"{}\n[truncated]\"", "\"\"".repeat(13)));
+
+        let truncated = super::quote_smartrest_value_truncated(&"é".repeat(100), 40).unwrap();
+        assert!(truncated.len() <= 40);
+        assert_eq!(truncated, format!("\"{}\n[truncated]\"", "é".repeat(13)));
+    }
+
+    #[test]
+    fn smartrest_values_are_dropped_when_there_is_no_room() {
+        assert_eq!(super::quote_smartrest_value_truncated("too long", 5), None);
+    }
+
+    async fn next_status(published: &mut mpsc::Receiver<Message>) -> String {
+        let message = tokio::time::timeout(Duration::from_secs(10), published.next())
+            .await
+            .expect("a status message")
+            .expect("an open channel");
+        assert_eq!(message.topic.name, "c8y/s/us");
+        message.payload_str().unwrap().to_string()
+    }
+
+    #[tokio::test]
+    async fn operations_for_child_device_are_forwarded_to_the_child_topics() {
+        let (mqtt_publisher, _) = mpsc::channel(10);
+        let operation_logs = OperationLogs {
+            log_dir: Default::default(),
+        };
+        let context = super::OperationContext {
+            operations: &Default::default(),
+            children: &Default::default(),
+            operation_logs: &operation_logs,
+            mqtt_publisher: &mqtt_publisher,
+            shell_command: None,
+            device_name: "testDevice",
+        };
+
         let output = super::process_smartrest(
-            "528,childId,software_a,version_a,url_a,install",
-            &Default::default(),
+            "528,childId,software_a,version_a,,install",
+            &context,
             &mut FakeC8YHttpProxy {},
-            &OperationLogs {
-                log_dir: Default::default(),
-            },
-            "testDevice",
         )
         .await
         .unwrap();
+        assert_eq!(output.len(), 1);
+        assert_eq!(
+            output[0].topic.name,
+            "tedge/childId/commands/req/software/update"
+        );
+
+        let output = super::process_smartrest("510,childId", &context, &mut FakeC8YHttpProxy {})
+            .await
+            .unwrap();
+        assert_eq!(output.len(), 1);
+        assert_eq!(
+            output[0].topic.name,
+            "tedge/childId/commands/req/control/restart"
+        );
+
+        // Custom operations are ignored for unknown child devices
+        let output = super::process_smartrest("511,childId,ls", &context, &mut FakeC8YHttpProxy {})
+            .await
+            .unwrap();
         assert_eq!(output, vec![]);
     }
+
+    #[tokio::test]
+    async fn child_operation_status_is_published_on_the_child_topic() {
+        let output = super::publish_child_operation_status(
+            "childId",
+            ResponseTopic::RestartResponse,
+            r#"{"id":"1","status":"successful"}"#,
+        )
+        .await
+        .unwrap();
+        assert_eq!(output[0].topic.name, "c8y/s/us/childId");
+        assert_eq!(output[0].payload_str().unwrap(), "503,c8y_Restart\n");
+
+        let output = super::publish_child_operation_status(
+            "childId",
+            ResponseTopic::SoftwareUpdateResponse,
+            r#"{"id":"1","status":"failed","reason":"Not enough space"}"#,
+        )
+        .await
+        .unwrap();
+        assert_eq!(output[0].topic.name, "c8y/s/us/childId");
+        assert_eq!(
+            output[0].payload_str().unwrap(),
+            "502,c8y_SoftwareUpdate,\"Not enough space\"\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn firmware_requests_are_forwarded_with_a_token_for_tenant_images() {
+        let (mqtt_publisher, _) = mpsc::channel(10);
+        let operation_logs = OperationLogs {
+            log_dir: Default::default(),
+        };
+        let context = super::OperationContext {
+            operations: &Default::default(),
+            children: &Default::default(),
+            operation_logs: &operation_logs,
+            mqtt_publisher: &mqtt_publisher,
+            shell_command: None,
+            device_name: "testDevice",
+        };
+
+        let output = super::process_smartrest(
+            "515,testDevice,image,1.0,https://test.c8y.io/inventory/binaries/70208",
+            &context,
+            &mut FakeC8YHttpProxy {},
+        )
+        .await
+        .unwrap();
+        assert_eq!(output.len(), 1);
+        assert_eq!(output[0].topic.name, "tedge/commands/req/firmware/update");
+        let request = FirmwareUpdateRequest::from_slice(output[0].payload_bytes()).unwrap();
+        assert_eq!(request.name, "image");
+        assert_eq!(request.version, "1.0");
+        assert_eq!(request.auth, Some(Auth::new_bearer("fake-token")));
+
+        let output = super::process_smartrest(
+            "515,childId,image,1.0,https://test.c8y.io/inventory/binaries/70208",
+            &context,
+            &mut FakeC8YHttpProxy {},
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            output[0].topic.name,
+            "tedge/childId/commands/req/firmware/update"
+        );
+    }
+
+    #[test]
+    fn installed_firmware_is_reported_on_success() {
+        let response = r#"{"id":"1","status":"successful","name":"image","version":"1.0","url":"https://example.com/image"}"#;
+        let output =
+            super::publish_firmware_operation_status(response, C8yTopic::SmartRestResponse)
+                .unwrap();
+        let payloads: Vec<&str> = output
+            .iter()
+            .map(|message| message.payload_str().unwrap())
+            .collect();
+        assert_eq!(
+            payloads,
+            vec![
+                "115,image,1.0,https://example.com/image\n",
+                "503,c8y_Firmware\n"
+            ]
+        );
+
+        let response = r#"{"id":"1","status":"failed","name":"image","version":"1.0","url":"https://example.com/image","reason":"Invalid image"}"#;
+        let output =
+            super::publish_firmware_operation_status(response, C8yTopic::SmartRestResponse)
+                .unwrap();
+        assert_eq!(
+            output[0].payload_str().unwrap(),
+            "502,c8y_Firmware,\"Invalid image\"\n"
+        );
+    }
+
+    #[test]
+    fn required_availability_is_declared_with_the_device_data() {
+        let message =
+            super::create_device_data_fragments("test-device", "thin-edge.io", None).unwrap();
+        assert_eq!(
+            message.topic.name,
+            "c8y/inventory/managedObjects/update/test-device"
+        );
+        assert_eq!(message.payload_str().unwrap(), r#"{"type":"thin-edge.io"}"#);
+
+        let message =
+            super::create_device_data_fragments("test-device", "thin-edge.io", Some(60)).unwrap();
+        assert_eq!(
+            message.payload_str().unwrap(),
+            r#"{"type":"thin-edge.io","c8y_RequiredAvailability":{"responseInterval":60}}"#
+        );
+    }
 }
@@ -55,4 +55,16 @@ pub enum CumulocityMapperError {
 
     #[error(transparent)]
     TedgeConfig(#[from] tedge_config::TEdgeConfigError),
+
+    #[error("Invalid SmartREST templates in {path}: {reason}")]
+    InvalidSmartRestTemplate { path: String, reason: String },
+
+    #[error("Invalid child device registration: {reason}")]
+    InvalidChildDeviceRegistration { reason: String },
+
+    #[error("The parent '{parent}' of the child device '{child_id}' is not a registered device")]
+    UnknownParentDevice { child_id: String, parent: String },
+
+    #[error("The device '{parent}' cannot be removed before its child devices: {children}")]
+    ParentDeviceWithChildren { parent: String, children: String },
 }
@@ -67,15 +67,36 @@ pub fn get_tedge_version() -> Result<String, ConversionError> {
 pub struct C8yDeviceDataFragment {
     #[serde(rename = "type")]
     device_type: String,
+
+    #[serde(
+        rename = "c8y_RequiredAvailability",
+        skip_serializing_if = "Option::is_none"
+    )]
+    required_availability: Option<C8yRequiredAvailability>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct C8yRequiredAvailability {
+    /// The interval in minutes after which the device is considered offline.
+    #[serde(rename = "responseInterval")]
+    response_interval: u64,
 }
 
 impl C8yDeviceDataFragment {
     pub fn from_type(device_type: &str) -> Result<Self, ConversionError> {
         Ok(Self {
             device_type: device_type.into(),
+            required_availability: None,
         })
     }
 
+    pub fn with_required_availability(self, response_interval: u64) -> Self {
+        Self {
+            required_availability: Some(C8yRequiredAvailability { response_interval }),
+            ..self
+        }
+    }
+
     pub fn to_json(&self) -> Result<serde_json::Value, ConversionError> {
         let json_string = serde_json::to_string(&self)?;
         let jsond: serde_json::Value = serde_json::from_str(&json_string)?;
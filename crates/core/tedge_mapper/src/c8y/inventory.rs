@@ -0,0 +1,287 @@
+//! Inventory fragments published by local processes on `tedge/inventory/<fragment>`,
+//! or `tedge/inventory/<fragment>/<child-id>` for a child device.
+//!
+//! The payload of such a message is the JSON value of the fragment, and an empty payload removes the fragment.
+//! The fragments received in a short period of time are merged into a single update of the device managed object,
+//! skipping the fragments whose value is unchanged since the last update.
+//! The retained fragments received on start are sent as a single update at the end of the sync window.
+
+use crate::core::error::ConversionError;
+use mqtt_channel::{Message, Sender, SinkExt, Topic};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::error;
+
+pub const TEDGE_INVENTORY_TOPIC: &str = "tedge/inventory/";
+pub(crate) const INVENTORY_MANAGED_OBJECTS_TOPIC: &str = "c8y/inventory/managedObjects/update";
+
+/// Period during which the fragment updates are collected before being sent to Cumulocity.
+const INVENTORY_UPDATE_DELAY: Duration = Duration::from_secs(1);
+
+#[derive(Debug)]
+pub(crate) struct InventoryConverter {
+    updates: Arc<Mutex<InventoryUpdates>>,
+    update_delay: Duration,
+    synced: bool,
+}
+
+#[derive(Debug, Default)]
+struct InventoryUpdates {
+    /// The fragments not sent yet, per device.
+    pending: HashMap<String, Map<String, Value>>,
+
+    /// The fragments last sent to Cumulocity, per device.
+    reported: HashMap<String, Map<String, Value>>,
+
+    /// Is an update already scheduled for the pending fragments?
+    scheduled: bool,
+}
+
+impl InventoryConverter {
+    pub(crate) fn new() -> Self {
+        Self::with_update_delay(INVENTORY_UPDATE_DELAY)
+    }
+
+    pub(crate) fn with_update_delay(update_delay: Duration) -> Self {
+        InventoryConverter {
+            updates: Arc::new(Mutex::new(InventoryUpdates::default())),
+            update_delay,
+            synced: false,
+        }
+    }
+
+    /// Record a fragment update received for a device,
+    /// scheduling the update of the device managed object unless the mapper is still syncing.
+    pub(crate) fn process_fragment(
+        &mut self,
+        device_id: &str,
+        fragment: &str,
+        payload: &str,
+        mqtt_publisher: &Sender<Message>,
+    ) -> Result<(), ConversionError> {
+        // Cumulocity removes the fragments set to null
+        let value = if payload.trim().is_empty() {
+            Value::Null
+        } else {
+            serde_json::from_str(payload)?
+        };
+
+        let mut updates = self.updates.lock().expect("inventory updates lock");
+        updates
+            .pending
+            .entry(device_id.to_string())
+            .or_default()
+            .insert(fragment.to_string(), value);
+
+        if self.synced && !updates.scheduled {
+            updates.scheduled = true;
+            self.schedule_update(mqtt_publisher.clone());
+        }
+        Ok(())
+    }
+
+    /// Mark the end of the sync window, returning the updates for the retained fragments.
+    pub(crate) fn sync(&mut self) -> Vec<Message> {
+        self.synced = true;
+        self.updates
+            .lock()
+            .expect("inventory updates lock")
+            .take_messages()
+    }
+
+    fn schedule_update(&self, mut mqtt_publisher: Sender<Message>) {
+        let updates = self.updates.clone();
+        let update_delay = self.update_delay;
+        tokio::spawn(async move {
+            tokio::time::sleep(update_delay).await;
+
+            let messages = {
+                let mut updates = updates.lock().expect("inventory updates lock");
+                updates.scheduled = false;
+                updates.take_messages()
+            };
+            for message in messages {
+                if let Err(err) = mqtt_publisher.send(message).await {
+                    error!("Failed to publish an inventory update: {err}");
+                }
+            }
+        });
+    }
+}
+
+impl InventoryUpdates {
+    /// One update message per device with changed fragments.
+    fn take_messages(&mut self) -> Vec<Message> {
+        let mut messages = Vec::new();
+        for (device_id, fragments) in self.pending.drain() {
+            let reported = self.reported.entry(device_id.clone()).or_default();
+            let changed: Map<String, Value> = fragments
+                .into_iter()
+                .filter(|(fragment, value)| reported.get(fragment) != Some(value))
+                .collect();
+            if changed.is_empty() {
+                continue;
+            }
+
+            reported.extend(changed.clone());
+            let topic =
+                Topic::new_unchecked(&format!("{INVENTORY_MANAGED_OBJECTS_TOPIC}/{device_id}"));
+            messages.push(Message::new(&topic, Value::Object(changed).to_string()));
+        }
+        messages
+    }
+}
+
+/// Extract the child id, if any, and the fragment name from an inventory topic.
+pub(crate) fn parse_inventory_topic(topic: &str) -> Result<(Option<&str>, &str), ConversionError> {
+    let unsupported_topic = || ConversionError::UnsupportedTopic(topic.to_string());
+    let path = topic
+        .strip_prefix(TEDGE_INVENTORY_TOPIC)
+        .ok_or_else(unsupported_topic)?;
+    match path.split('/').collect::<Vec<_>>()[..] {
+        [fragment] if !fragment.is_empty() => Ok((None, fragment)),
+        [fragment, child_id] if !fragment.is_empty() && !child_id.is_empty() => {
+            Ok((Some(child_id), fragment))
+        }
+        _ => Err(unsupported_topic()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::channel::mpsc;
+    use futures::StreamExt;
+    use serde_json::json;
+    use test_case::test_case;
+
+    #[test_case("tedge/inventory/c8y_Hardware", Some((None, "c8y_Hardware")); "main device")]
+    #[test_case("tedge/inventory/c8y_Hardware/child1", Some((Some("child1"), "c8y_Hardware")); "child device")]
+    #[test_case("tedge/inventory/", None; "missing fragment")]
+    #[test_case("tedge/inventory/c8y_Hardware/", None; "missing child id")]
+    #[test_case("tedge/inventory/a/b/c", None; "too many levels")]
+    fn inventory_topics(topic: &str, expected: Option<(Option<&str>, &str)>) {
+        assert_eq!(parse_inventory_topic(topic).ok(), expected);
+    }
+
+    #[test]
+    fn retained_fragments_are_sent_on_sync() {
+        let (mqtt_publisher, _) = mpsc::channel(10);
+        let mut converter = InventoryConverter::new();
+
+        converter
+            .process_fragment(
+                "device",
+                "c8y_Hardware",
+                r#"{"model":"BCM2708"}"#,
+                &mqtt_publisher,
+            )
+            .unwrap();
+        converter
+            .process_fragment(
+                "device",
+                "c8y_Position",
+                r#"{"lat":1,"lng":2}"#,
+                &mqtt_publisher,
+            )
+            .unwrap();
+        converter
+            .process_fragment(
+                "child1",
+                "c8y_Hardware",
+                r#"{"model":"ESP32"}"#,
+                &mqtt_publisher,
+            )
+            .unwrap();
+
+        let mut messages = converter.sync();
+        messages.sort_by(|a, b| a.topic.name.cmp(&b.topic.name));
+        assert_eq!(messages.len(), 2);
+        assert_eq!(
+            messages[0].topic.name,
+            "c8y/inventory/managedObjects/update/child1"
+        );
+        assert_eq!(
+            messages[1].topic.name,
+            "c8y/inventory/managedObjects/update/device"
+        );
+        assert_eq!(
+            serde_json::from_str::<Value>(messages[1].payload_str().unwrap()).unwrap(),
+            json!({"c8y_Hardware": {"model":"BCM2708"}, "c8y_Position": {"lat":1,"lng":2}})
+        );
+    }
+
+    #[test]
+    fn invalid_fragments_are_rejected() {
+        let (mqtt_publisher, _) = mpsc::channel(10);
+        let mut converter = InventoryConverter::new();
+
+        assert!(converter
+            .process_fragment("device", "c8y_Hardware", "not json", &mqtt_publisher)
+            .is_err());
+        assert!(converter.sync().is_empty());
+    }
+
+    #[tokio::test]
+    async fn fragment_updates_are_merged_and_deduplicated() {
+        let (mqtt_publisher, mut published) = mpsc::channel(10);
+        let mut converter = InventoryConverter::with_update_delay(Duration::from_millis(100));
+        assert!(converter.sync().is_empty());
+
+        converter
+            .process_fragment(
+                "device",
+                "c8y_Hardware",
+                r#"{"model":"v1"}"#,
+                &mqtt_publisher,
+            )
+            .unwrap();
+        converter
+            .process_fragment(
+                "device",
+                "c8y_Hardware",
+                r#"{"model":"v2"}"#,
+                &mqtt_publisher,
+            )
+            .unwrap();
+        converter
+            .process_fragment(
+                "device",
+                "c8y_Firmware",
+                r#"{"name":"fw"}"#,
+                &mqtt_publisher,
+            )
+            .unwrap();
+
+        let update = published.next().await.unwrap();
+        assert_eq!(
+            update.topic.name,
+            "c8y/inventory/managedObjects/update/device"
+        );
+        assert_eq!(
+            serde_json::from_str::<Value>(update.payload_str().unwrap()).unwrap(),
+            json!({"c8y_Hardware": {"model":"v2"}, "c8y_Firmware": {"name":"fw"}})
+        );
+
+        // Unchanged fragments are not sent again, and an empty payload removes the fragment
+        converter
+            .process_fragment(
+                "device",
+                "c8y_Hardware",
+                r#"{"model":"v2"}"#,
+                &mqtt_publisher,
+            )
+            .unwrap();
+        converter
+            .process_fragment("device", "c8y_Firmware", "", &mqtt_publisher)
+            .unwrap();
+
+        let update = published.next().await.unwrap();
+        assert_eq!(
+            serde_json::from_str::<Value>(update.payload_str().unwrap()).unwrap(),
+            json!({ "c8y_Firmware": null })
+        );
+    }
+}
@@ -13,7 +13,7 @@
 //! let output = from_thin_edge_json(single_value_thin_edge_json);
 //! ```
 
-use crate::c8y::serializer;
+use crate::c8y::serializer::{self, C8yTextMeasurement};
 use clock::{Clock, WallClock};
 use tedge_api::parser::*;
 use time::{self, OffsetDateTime};
@@ -44,6 +44,20 @@ pub fn from_thin_edge_json_with_child(
     Ok(c8y_vec)
 }
 
+/// Converts from thin-edge measurement JSON to C8Y measurement JSON,
+/// returning apart the text values that cannot be sent as c8y measurements.
+///
+/// No C8Y measurement is returned if the input has only text values.
+pub fn from_thin_edge_json_with_text_values(
+    input: &str,
+    maybe_child_id: Option<&str>,
+) -> Result<(Option<String>, Vec<C8yTextMeasurement>), CumulocityJsonError> {
+    let timestamp = WallClock.now();
+    let mut serializer = serializer::C8yJsonSerializer::new(timestamp, maybe_child_id);
+    parse_str(input, &mut serializer)?;
+    Ok(serializer.into_measurement_and_text_values()?)
+}
+
 fn from_thin_edge_json_with_timestamp(
     input: &str,
     timestamp: OffsetDateTime,
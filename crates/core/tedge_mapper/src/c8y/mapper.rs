@@ -1,24 +1,38 @@
 use std::path::{Path, PathBuf};
 
 use crate::{
-    c8y::converter::CumulocityConverter,
-    core::{component::TEdgeComponent, mapper::create_mapper, size_threshold::SizeThreshold},
+    c8y::alarm_converter::PublishedAlarms,
+    c8y::availability::Availability,
+    c8y::child_devices::ChildDeviceRegistry,
+    c8y::converter::{create_mapper_config, CumulocityConverter},
+    c8y::error::CumulocityMapperError,
+    c8y::shell_command::{declare_shell_command_operation, ShellCommandConfig},
+    c8y::smartrest_templates::{SmartRestTemplates, SMARTREST_TEMPLATES_DIRECTORY},
+    core::{
+        component::TEdgeComponent,
+        mapper::{async_messages_channel, create_mapper_with_client, create_mqtt_client},
+        outbox::create_outbox,
+        size_threshold::SizeThreshold,
+    },
 };
 
 use async_trait::async_trait;
 use c8y_api::http_proxy::{C8YHttpProxy, JwtAuthHttpProxy};
 use c8y_api::smartrest::{operations::Operations, topic::C8yTopic};
+use c8y_api::utils::bridge::C8Y_BRIDGE_HEALTH_TOPIC;
 use mqtt_channel::TopicFilter;
 use tedge_api::topic::ResponseTopic;
 use tedge_config::{
-    ConfigSettingAccessor, DeviceIdSetting, DeviceTypeSetting, MqttBindAddressSetting,
-    MqttPortSetting, TEdgeConfig,
+    ConfigSettingAccessor, ConfigSettingAccessorStringExt, DeviceIdSetting, DeviceTypeSetting,
+    FirmwareInstallCommandSetting, TEdgeConfig,
 };
+use tedge_mqtt_config::MqttConfigExt;
 use tedge_utils::file::*;
 use tracing::{info, info_span, Instrument};
 
 const CUMULOCITY_MAPPER_NAME: &str = "tedge-mapper-c8y";
 const MQTT_MESSAGE_SIZE_THRESHOLD: usize = 16184;
+const FIRMWARE_OPERATION: &str = "c8y_Firmware";
 
 pub struct CumulocityMapper {}
 
@@ -32,6 +46,10 @@ impl CumulocityMapper {
         topic_filter.add(ResponseTopic::SoftwareUpdateResponse.as_str())?;
         topic_filter.add(C8yTopic::SmartRestRequest.to_string().as_str())?;
         topic_filter.add(ResponseTopic::RestartResponse.as_str())?;
+        topic_filter.add(&ResponseTopic::SoftwareUpdateResponse.for_child("+"))?;
+        topic_filter.add(&ResponseTopic::RestartResponse.for_child("+"))?;
+        topic_filter.add(ResponseTopic::FirmwareUpdateResponse.as_str())?;
+        topic_filter.add(&ResponseTopic::FirmwareUpdateResponse.for_child("+"))?;
 
         for topic in operations.topics_for_operations() {
             topic_filter.add(&topic)?
@@ -60,32 +78,76 @@ impl TEdgeComponent for CumulocityMapper {
         let size_threshold = SizeThreshold(MQTT_MESSAGE_SIZE_THRESHOLD);
         let config_dir = cfg_dir.display().to_string();
 
+        let shell_command = ShellCommandConfig::try_new(&tedge_config)?;
+        let availability = Availability::try_new(&tedge_config)?;
+        let smartrest_templates =
+            SmartRestTemplates::load(&cfg_dir.join(SMARTREST_TEMPLATES_DIRECTORY))?;
+        declare_shell_command_operation(
+            &cfg_dir.join("operations").join("c8y"),
+            shell_command.is_some(),
+        )?;
+        // The firmware updates are only offered when the agent has a command to install the images
+        declare_operation(
+            &cfg_dir.join("operations").join("c8y"),
+            FIRMWARE_OPERATION,
+            tedge_config
+                .query_string_optional(FirmwareInstallCommandSetting)?
+                .is_some(),
+        )?;
+
         let operations = Operations::try_new(format!("{config_dir}/operations/c8y"))?;
         let child_ops = Operations::get_child_ops(format!("{config_dir}/operations/c8y"))?;
         let mut http_proxy = JwtAuthHttpProxy::try_new(&tedge_config).await?;
         http_proxy.init().await?;
         let device_name = tedge_config.query(DeviceIdSetting)?;
         let device_type = tedge_config.query(DeviceTypeSetting)?;
-        let mqtt_port = tedge_config.query(MqttPortSetting)?.into();
-        let mqtt_host = tedge_config.query(MqttBindAddressSetting)?.to_string();
+        let mqtt_config = tedge_config.mqtt_config()?;
+        let outbox = create_outbox(
+            &tedge_config,
+            &cfg_dir.join(format!(".{CUMULOCITY_MAPPER_NAME}")),
+            C8Y_BRIDGE_HEALTH_TOPIC,
+            "c8y/",
+        )?;
+
+        let mqtt_client = create_mqtt_client(
+            CUMULOCITY_MAPPER_NAME,
+            mqtt_config,
+            &create_mapper_config(&operations).in_topic_filter,
+            outbox.as_ref(),
+        )
+        .await?;
 
-        let converter = Box::new(CumulocityConverter::new(
+        // The outcome of the custom operations is published when the operation commands complete,
+        // the heartbeats and the inventory updates when due: all through the mapper, hence the outbox
+        let (async_messages, async_receiver) = async_messages_channel();
+        let mut converter = CumulocityConverter::new(
             size_threshold,
-            device_name,
+            device_name.clone(),
             device_type,
             operations,
             http_proxy,
             cfg_dir,
             child_ops,
-        )?);
+            async_messages.clone(),
+        )?;
+        if let Some(shell_command) = shell_command {
+            converter = converter.with_shell_command(shell_command);
+        }
+        if let Some(availability) = availability {
+            availability.start_heartbeat(&device_name, async_messages);
+            converter = converter.with_availability(availability);
+        }
+        let mapper_dir = cfg_dir.join(format!(".{CUMULOCITY_MAPPER_NAME}"));
+        let converter = Box::new(
+            converter
+                .with_smartrest_templates(smartrest_templates)
+                .with_child_registry(ChildDeviceRegistry::load(&mapper_dir))
+                .with_published_alarms(PublishedAlarms::load(&mapper_dir)),
+        );
 
-        let mut mapper = create_mapper(
-            CUMULOCITY_MAPPER_NAME,
-            mqtt_host.clone(),
-            mqtt_port,
-            converter,
-        )
-        .await?;
+        let mut mapper = create_mapper_with_client(CUMULOCITY_MAPPER_NAME, mqtt_client, converter)
+            .with_outbox(outbox)
+            .with_async_messages(async_receiver);
 
         let ops_dir = PathBuf::from(format!("{}/operations/c8y", &config_dir));
 
@@ -98,7 +160,33 @@ impl TEdgeComponent for CumulocityMapper {
     }
 }
 
+/// Declare an operation when enabled, and withdraw it when disabled.
+///
+/// The operation file is only removed when empty,
+/// i.e. not a custom operation defined by the user for the same operation name.
+pub(crate) fn declare_operation(
+    ops_dir: &Path,
+    operation: &str,
+    enabled: bool,
+) -> Result<(), CumulocityMapperError> {
+    let operation_file = ops_dir.join(operation);
+    if enabled {
+        if !operation_file.exists() {
+            std::fs::write(&operation_file, "")?;
+        }
+    } else if std::fs::metadata(&operation_file).map_or(false, |file| file.len() == 0) {
+        std::fs::remove_file(&operation_file)?;
+    }
+    Ok(())
+}
+
 fn create_directories(config_dir: &Path) -> Result<(), anyhow::Error> {
+    create_directory_with_user_group(
+        format!("{}/.{CUMULOCITY_MAPPER_NAME}", config_dir.display()),
+        "tedge",
+        "tedge",
+        0o775,
+    )?;
     create_directory_with_user_group(
         format!("{}/operations/c8y", config_dir.display()),
         "tedge",
@@ -197,7 +285,19 @@ mod tests {
         let size_threshold = SizeThreshold(MQTT_MESSAGE_SIZE_THRESHOLD);
         let operations = Operations::default();
 
+        let broker = test_mqtt_broker();
+        let mqtt_client = create_mqtt_client(
+            CUMULOCITY_MAPPER_NAME_TEST,
+            mqtt_channel::Config::default()
+                .with_host(MQTT_HOST)
+                .with_port(broker.port),
+            &create_mapper_config(&operations).in_topic_filter,
+            None,
+        )
+        .await?;
+
         let tmp_dir = TempTedgeDir::new();
+        let (async_messages, async_receiver) = async_messages_channel();
         let converter = Box::new(
             CumulocityConverter::from_logs_path(
                 size_threshold,
@@ -207,19 +307,14 @@ mod tests {
                 proxy,
                 tmp_dir.path().to_path_buf(),
                 tmp_dir.path().to_path_buf(),
+                async_messages,
             )
             .unwrap(),
         );
 
-        let broker = test_mqtt_broker();
-
-        let mut mapper = create_mapper(
-            CUMULOCITY_MAPPER_NAME_TEST,
-            MQTT_HOST.into(),
-            broker.port,
-            converter,
-        )
-        .await?;
+        let mut mapper =
+            create_mapper_with_client(CUMULOCITY_MAPPER_NAME_TEST, mqtt_client, converter)
+                .with_async_messages(async_receiver);
 
         // subscribe to `sub_topic`
         let mut messages = broker.messages_published_on(sub_topic).await;
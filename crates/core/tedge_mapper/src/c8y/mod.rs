@@ -1,11 +1,19 @@
 pub mod alarm_converter;
+pub mod availability;
+pub mod child_devices;
 pub mod converter;
 pub mod dynamic_discovery;
 pub mod error;
 mod fragments;
+pub mod inventory;
 pub mod json;
 pub mod mapper;
+mod operation_command;
+pub mod operation_output;
 mod serializer;
+mod service_monitor;
+pub mod shell_command;
+pub mod smartrest_templates;
 
 #[cfg(test)]
 mod tests;
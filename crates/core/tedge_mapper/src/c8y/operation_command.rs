@@ -0,0 +1,126 @@
+//! The commands launched by the mapper to execute Cumulocity operations,
+//! the custom operations as well as the `c8y_Command` operation.
+//!
+//! Whatever the operation, the command is launched in the background,
+//! the operation being marked as executing (`501`) as soon as the command has been launched,
+//! then as successful (`503`) or failed (`502`) once the command has completed.
+
+use super::converter::{
+    quote_smartrest_value, quote_smartrest_value_truncated, CREATE_EVENT_SMARTREST_CODE,
+    MAX_SMARTREST_MESSAGE_SIZE,
+};
+use super::error::CumulocityMapperError;
+use super::operation_output::progress_message;
+use logged_command::LoggedCommand;
+use mqtt_channel::{Message, Sender, SinkExt, Topic};
+use plugin_sm::operation_logs::{LogKind, OperationLogs};
+use std::process::Output;
+use tracing::error;
+
+const OPERATION_PROGRESS_EVENT_TYPE: &str = "c8y_OperationProgress";
+
+/// A command executing an operation, its outcome being reported to Cumulocity once completed.
+pub(crate) struct OperationCommand {
+    /// The name of the operation, as used in the SmartREST status messages.
+    pub operation_name: String,
+
+    /// The command executing the operation.
+    pub command: LoggedCommand,
+
+    /// Forward the progress messages printed by the command as operation progress events.
+    pub report_progress: bool,
+
+    /// The parameters of the successful status, extracted from the output of the command.
+    pub result_parameters: fn(&Output) -> Vec<String>,
+
+    /// The reason of the failed status, extracted from the output of the command.
+    pub failure_reason: fn(&Output) -> String,
+}
+
+impl OperationCommand {
+    /// Launch the command, the status messages being published on the `status_topic` of the target device.
+    ///
+    /// The parameters that don't fit the size limit of the SmartREST messages are truncated or dropped.
+    pub(crate) async fn launch(
+        self,
+        operation_logs: &OperationLogs,
+        mqtt_publisher: &Sender<Message>,
+        status_topic: &Topic,
+    ) -> Result<(), CumulocityMapperError> {
+        let OperationCommand {
+            operation_name,
+            mut command,
+            report_progress,
+            result_parameters,
+            failure_reason,
+        } = self;
+
+        let mut log_file = operation_logs
+            .new_log_file(LogKind::Operation(operation_name.clone()))
+            .await?;
+        let child = command
+            .spawn()
+            .map_err(|e| CumulocityMapperError::ExecuteFailed {
+                error_message: e.to_string(),
+                command: command.to_string(),
+                operation_name: operation_name.clone(),
+            })?;
+
+        let topic = status_topic.clone();
+        let mut mqtt_publisher = mqtt_publisher.clone();
+        let executing = Message::new(&topic, format!("501,{operation_name}"));
+        let _ = mqtt_publisher.send(executing).await;
+
+        tokio::spawn(async move {
+            let logger = log_file.buffer();
+            let on_output_line = |line: &str| {
+                if let Some(progress) = progress_message(line).filter(|_| report_progress) {
+                    let event = format!(
+                        "{CREATE_EVENT_SMARTREST_CODE},{OPERATION_PROGRESS_EVENT_TYPE},{}",
+                        quote_smartrest_value(&format!("{operation_name}: {progress}"))
+                    );
+                    // The progress is only reported if the messages are published fast enough
+                    let _ = mqtt_publisher.try_send(Message::new(&topic, event));
+                }
+            };
+            let status = match child.wait_with_output_lines(logger, on_output_line).await {
+                Ok(output) if output.status.success() => {
+                    status_message(&format!("503,{operation_name}"), result_parameters(&output))
+                }
+                Ok(output) => {
+                    status_message(&format!("502,{operation_name}"), [failure_reason(&output)])
+                }
+                Err(err) => status_message(&format!("502,{operation_name}"), [err.to_string()]),
+            };
+            if let Err(err) = mqtt_publisher.send(Message::new(&topic, status)).await {
+                error!("Fail to report the outcome of the {operation_name} operation: {err}");
+            }
+        });
+
+        Ok(())
+    }
+}
+
+/// How a command failed: with a non-zero exit status or killed by a signal.
+pub(crate) fn command_failure(output: &Output) -> String {
+    match output.status.code() {
+        Some(code) => format!("failed with exit status: {code}"),
+        None => "killed by a signal".to_string(),
+    }
+}
+
+/// A SmartREST status message, quoting the parameters and truncating them to the size limit of the messages.
+fn status_message(prefix: &str, parameters: impl IntoIterator<Item = String>) -> String {
+    let mut status = prefix.to_string();
+    for parameter in parameters {
+        let room = MAX_SMARTREST_MESSAGE_SIZE.saturating_sub(status.len() + 1);
+        match quote_smartrest_value_truncated(&parameter, room) {
+            Some(parameter) => {
+                status.push(',');
+                status.push_str(&parameter);
+            }
+            None => break,
+        }
+    }
+    status
+}
@@ -0,0 +1,91 @@
+//! The protocol used by the commands of custom operations to report progress and results.
+//!
+//! A command reports to the mapper by printing specific lines on its standard output,
+//! all the other lines being simply logged:
+//!
+//! - `:::progress::: <message>` is a progress message, forwarded to Cumulocity as soon as printed.
+//! - `:::result::: <value>` is a result parameter, sent along the successful status of the operation.
+//! - `:::begin-result:::` and `:::end-result:::` delimit a result parameter made of several lines.
+//!
+//! The result parameters are sent in the order they are printed.
+
+const PROGRESS_PREFIX: &str = ":::progress:::";
+const RESULT_PREFIX: &str = ":::result:::";
+const BEGIN_RESULT: &str = ":::begin-result:::";
+const END_RESULT: &str = ":::end-result:::";
+
+/// The progress message reported by a line of the command output, if any.
+pub fn progress_message(line: &str) -> Option<&str> {
+    line.strip_prefix(PROGRESS_PREFIX).map(str::trim)
+}
+
+/// The result parameters reported by the command along its output.
+pub fn result_parameters(stdout: &str) -> Vec<String> {
+    let mut parameters = Vec::new();
+    let mut multi_line_result: Option<Vec<&str>> = None;
+
+    for line in stdout.lines() {
+        match multi_line_result.as_mut() {
+            Some(result_lines) if line.trim_end() == END_RESULT => {
+                parameters.push(result_lines.join("\n"));
+                multi_line_result = None;
+            }
+            Some(result_lines) => result_lines.push(line),
+            None if line.trim_end() == BEGIN_RESULT => multi_line_result = Some(Vec::new()),
+            None => {
+                if let Some(result) = line.strip_prefix(RESULT_PREFIX) {
+                    parameters.push(result.trim().to_string());
+                }
+            }
+        }
+    }
+
+    // A result not properly closed is still a result
+    if let Some(result_lines) = multi_line_result {
+        parameters.push(result_lines.join("\n"));
+    }
+
+    parameters
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn progress_messages_are_prefixed() {
+        assert_eq!(
+            progress_message(":::progress::: 2 of 3 checks done"),
+            Some("2 of 3 checks done")
+        );
+        assert_eq!(progress_message("2 of 3 checks done"), None);
+        assert_eq!(progress_message(":::result::: OK"), None);
+    }
+
+    #[test]
+    fn result_parameters_are_extracted_from_the_output() {
+        let stdout = r#"Checking the network
+:::progress::: network OK
+:::result::: OK
+:::begin-result:::
+PING 8.8.8.8: 56 data bytes
+64 bytes from 8.8.8.8: icmp_seq=0 ttl=116 time=12.3 ms
+:::end-result:::
+done
+"#;
+
+        assert_eq!(
+            result_parameters(stdout),
+            vec![
+                "OK".to_string(),
+                "PING 8.8.8.8: 56 data bytes\n64 bytes from 8.8.8.8: icmp_seq=0 ttl=116 time=12.3 ms"
+                    .to_string()
+            ]
+        );
+    }
+
+    #[test]
+    fn plain_output_has_no_result_parameters() {
+        assert!(result_parameters("some output\nmore output\n").is_empty());
+    }
+}
@@ -1,12 +1,29 @@
 use json_writer::{JsonWriter, JsonWriterError};
-use tedge_api::measurement::MeasurementVisitor;
+use tedge_api::measurement::{MeasurementValue, MeasurementVisitor, UnsupportedMeasurementValue};
 use time::{format_description, OffsetDateTime};
 
 pub struct C8yJsonSerializer {
     json: JsonWriter,
     is_within_group: bool,
+    group: String,
+    is_group_written: bool,
     timestamp_present: bool,
-    default_timestamp: OffsetDateTime,
+    timestamp: OffsetDateTime,
+    values_count: usize,
+    text_values: Vec<(String, String)>,
+}
+
+/// A text measurement value.
+///
+/// Cumulocity measurements only support numeric values,
+/// hence text values are sent to Cumulocity as events
+/// with the measurement name as type and the value as text.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct C8yTextMeasurement {
+    /// The name of the measurement, prefixed by its group if any: `group.name`.
+    pub name: String,
+    pub value: String,
+    pub time: OffsetDateTime,
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -16,6 +33,9 @@ pub enum C8yJsonSerializationError {
 
     #[error(transparent)]
     JsonWriterError(#[from] JsonWriterError),
+
+    #[error(transparent)]
+    UnsupportedValue(#[from] UnsupportedMeasurementValue),
 }
 
 #[allow(clippy::enum_variant_names)]
@@ -60,8 +80,12 @@ impl C8yJsonSerializer {
         Self {
             json,
             is_within_group: false,
+            group: String::new(),
+            is_group_written: false,
             timestamp_present: false,
-            default_timestamp,
+            timestamp: default_timestamp,
+            values_count: 0,
+            text_values: Vec::new(),
         }
     }
 
@@ -71,7 +95,7 @@ impl C8yJsonSerializer {
         }
 
         if !self.timestamp_present {
-            self.visit_timestamp(self.default_timestamp)?;
+            self.visit_timestamp(self.timestamp)?;
         }
 
         assert!(self.timestamp_present);
@@ -80,18 +104,76 @@ impl C8yJsonSerializer {
         Ok(())
     }
 
-    fn write_value_obj(&mut self, value: f64) -> Result<(), C8yJsonSerializationError> {
+    fn write_value_obj(
+        &mut self,
+        value: f64,
+        unit: Option<&str>,
+    ) -> Result<(), C8yJsonSerializationError> {
         self.json.write_open_obj();
         self.json.write_key("value")?;
         self.json.write_f64(value)?;
+        if let Some(unit) = unit {
+            self.json.write_key("unit")?;
+            self.json.write_str(unit)?;
+        }
         self.json.write_close_obj();
         Ok(())
     }
 
+    /// Write a numeric value, opening the current group on its first value.
+    fn write_measurement(
+        &mut self,
+        key: &str,
+        value: f64,
+        unit: Option<&str>,
+    ) -> Result<(), C8yJsonSerializationError> {
+        self.values_count += 1;
+
+        if self.is_within_group {
+            if !self.is_group_written {
+                self.json.write_key(&self.group)?;
+                self.json.write_open_obj();
+                self.is_group_written = true;
+            }
+            self.json.write_key(key)?;
+            self.write_value_obj(value, unit)?;
+        } else {
+            self.json.write_key(key)?;
+            self.json.write_open_obj();
+            self.json.write_key(key)?;
+            self.write_value_obj(value, unit)?;
+            self.json.write_close_obj();
+        }
+        Ok(())
+    }
+
     pub fn into_string(mut self) -> Result<String, C8yJsonSerializationError> {
         self.end()?;
         Ok(self.json.clone().into_string()?)
     }
+
+    /// Return the c8y measurement, unless there is no numeric nor boolean value,
+    /// along with the text values that have to be sent apart.
+    pub fn into_measurement_and_text_values(
+        mut self,
+    ) -> Result<(Option<String>, Vec<C8yTextMeasurement>), C8yJsonSerializationError> {
+        self.end()?;
+
+        let measurement = if self.values_count > 0 {
+            Some(self.json.clone().into_string()?)
+        } else {
+            None
+        };
+
+        let time = self.timestamp;
+        let text_values = self
+            .text_values
+            .into_iter()
+            .map(|(name, value)| C8yTextMeasurement { name, value, time })
+            .collect();
+
+        Ok((measurement, text_values))
+    }
 }
 
 impl MeasurementVisitor for C8yJsonSerializer {
@@ -111,30 +193,55 @@ impl MeasurementVisitor for C8yJsonSerializer {
         )?;
 
         self.timestamp_present = true;
+        self.timestamp = timestamp;
         Ok(())
     }
 
     fn visit_measurement(&mut self, key: &str, value: f64) -> Result<(), Self::Error> {
-        self.json.write_key(key)?;
+        self.write_measurement(key, value, None)
+    }
 
-        if self.is_within_group {
-            self.write_value_obj(value)?;
+    /// Text values are not part of the c8y measurement, but are kept to be sent as events.
+    fn visit_text_measurement(&mut self, key: &str, value: &str) -> Result<(), Self::Error> {
+        let name = if self.is_within_group {
+            format!("{}.{}", self.group, key)
         } else {
-            self.json.write_open_obj();
-            self.json.write_key(key)?;
-            self.write_value_obj(value)?;
-            self.json.write_close_obj();
-        }
+            key.to_string()
+        };
+        self.text_values.push((name, value.to_string()));
         Ok(())
     }
 
+    /// Boolean values are sent to c8y as numeric values: 1 for `true` and 0 for `false`.
+    fn visit_boolean_measurement(&mut self, key: &str, value: bool) -> Result<(), Self::Error> {
+        self.write_measurement(key, if value { 1.0 } else { 0.0 }, None)
+    }
+
+    /// The unit is added to the c8y series, but ignored for text values.
+    fn visit_measurement_with_unit(
+        &mut self,
+        key: &str,
+        value: &MeasurementValue,
+        unit: &str,
+    ) -> Result<(), Self::Error> {
+        match value {
+            MeasurementValue::Number(value) => self.write_measurement(key, *value, Some(unit)),
+            MeasurementValue::Boolean(value) => {
+                self.write_measurement(key, if *value { 1.0 } else { 0.0 }, Some(unit))
+            }
+            MeasurementValue::Text(value) => self.visit_text_measurement(key, value),
+        }
+    }
+
     fn visit_start_group(&mut self, group: &str) -> Result<(), Self::Error> {
         if self.is_within_group {
             return Err(MeasurementStreamError::UnexpectedStartOfGroup.into());
         }
 
-        self.json.write_key(group)?;
-        self.json.write_open_obj();
+        // The group is only written along its first numeric value,
+        // to avoid empty fragments for groups made only of text values.
+        self.group.replace_range(.., group);
+        self.is_group_written = false;
         self.is_within_group = true;
         Ok(())
     }
@@ -144,7 +251,9 @@ impl MeasurementVisitor for C8yJsonSerializer {
             return Err(MeasurementStreamError::UnexpectedEndOfGroup.into());
         }
 
-        self.json.write_close_obj();
+        if self.is_group_written {
+            self.json.write_close_obj();
+        }
         self.is_within_group = false;
         Ok(())
     }
@@ -236,6 +345,156 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn serialize_boolean_values_as_numbers() -> anyhow::Result<()> {
+        let timestamp = datetime!(2021-06-22 17:03:14.123456789 +05:00);
+
+        let mut serializer = C8yJsonSerializer::new(timestamp, None);
+        serializer.visit_boolean_measurement("door_open", true)?;
+        serializer.visit_start_group("window")?;
+        serializer.visit_boolean_measurement("open", false)?;
+        serializer.visit_end_group()?;
+
+        let output = serializer.into_string()?;
+
+        let expected_output = json!({
+            "type": "ThinEdgeMeasurement",
+            "time": "2021-06-22T17:03:14.123456789+05:00",
+            "door_open": {
+                "door_open": {
+                    "value": 1.0
+                }
+            },
+            "window": {
+                "open": {
+                    "value": 0.0
+                }
+            }
+        });
+
+        assert_json_eq!(
+            serde_json::from_str::<serde_json::Value>(&output)?,
+            expected_output
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn serialize_text_values_apart() -> anyhow::Result<()> {
+        let timestamp = datetime!(2021-06-22 17:03:14.123456789 +05:00);
+
+        let mut serializer = C8yJsonSerializer::new(timestamp, None);
+        serializer.visit_text_measurement("state", "RUNNING")?;
+        serializer.visit_start_group("machine")?;
+        serializer.visit_text_measurement("mode", "auto")?;
+        serializer.visit_end_group()?;
+        serializer.visit_start_group("location")?;
+        serializer.visit_text_measurement("city", "Paris")?;
+        serializer.visit_measurement("alti", 2100.4)?;
+        serializer.visit_end_group()?;
+
+        let (measurement, text_values) = serializer.into_measurement_and_text_values()?;
+
+        let expected_output = json!({
+            "type": "ThinEdgeMeasurement",
+            "time": "2021-06-22T17:03:14.123456789+05:00",
+            "location": {
+                "alti": {
+                    "value": 2100.4
+                }
+            }
+        });
+
+        assert_json_eq!(
+            serde_json::from_str::<serde_json::Value>(&measurement.unwrap())?,
+            expected_output
+        );
+        assert_eq!(
+            text_values,
+            vec![
+                C8yTextMeasurement {
+                    name: "state".into(),
+                    value: "RUNNING".into(),
+                    time: timestamp
+                },
+                C8yTextMeasurement {
+                    name: "machine.mode".into(),
+                    value: "auto".into(),
+                    time: timestamp
+                },
+                C8yTextMeasurement {
+                    name: "location.city".into(),
+                    value: "Paris".into(),
+                    time: timestamp
+                },
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn serialize_only_text_values() -> anyhow::Result<()> {
+        let timestamp = datetime!(2021-06-22 17:03:14.123456789 +05:00);
+        let measurement_time = datetime!(2021-06-22 18:00:00 +05:00);
+
+        let mut serializer = C8yJsonSerializer::new(timestamp, None);
+        serializer.visit_text_measurement("state", "RUNNING")?;
+        serializer.visit_timestamp(measurement_time)?;
+
+        let (measurement, text_values) = serializer.into_measurement_and_text_values()?;
+
+        assert_eq!(measurement, None);
+        assert_eq!(
+            text_values,
+            vec![C8yTextMeasurement {
+                name: "state".into(),
+                value: "RUNNING".into(),
+                time: measurement_time
+            }]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn serialize_values_with_units() -> anyhow::Result<()> {
+        let timestamp = datetime!(2021-06-22 17:03:14.123456789 +05:00);
+
+        let mut serializer = C8yJsonSerializer::new(timestamp, None);
+        serializer.visit_measurement_with_unit("temperature", &25.5.into(), "°C")?;
+        serializer.visit_start_group("location")?;
+        serializer.visit_measurement_with_unit("alti", &2100.4.into(), "m")?;
+        serializer.visit_measurement("longi", 2200.4)?;
+        serializer.visit_end_group()?;
+
+        let output = serializer.into_string()?;
+
+        let expected_output = json!({
+            "type": "ThinEdgeMeasurement",
+            "time": "2021-06-22T17:03:14.123456789+05:00",
+            "temperature": {
+                "temperature": {
+                    "value": 25.5,
+                    "unit": "°C"
+                }
+            },
+            "location": {
+                "alti": {
+                    "value": 2100.4,
+                    "unit": "m"
+                },
+                "longi": {
+                    "value": 2200.4
+                }
+            }
+        });
+
+        assert_json_eq!(
+            serde_json::from_str::<serde_json::Value>(&output)?,
+            expected_output
+        );
+        Ok(())
+    }
+
     #[test]
     fn serialize_empty_message() -> anyhow::Result<()> {
         let timestamp = datetime!(2021-06-22 17:03:14.123456789 +05:00);
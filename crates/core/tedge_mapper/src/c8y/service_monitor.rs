@@ -0,0 +1,151 @@
+//! The health of the thin-edge.io services, as published on `tedge/health/<service>`.
+//!
+//! Each service publishes a retained `up` status when connected and registers a retained `down` status
+//! as last will, published by the broker as soon as the service is disconnected without notice.
+//! An alarm is raised when a service is down and cleared when the service is up again.
+//! These alarms are published on `tedge/alarms`, to be mapped along the other alarms.
+
+use super::availability::TEDGE_HEALTH_TOPIC;
+use mqtt_channel::{Message, Topic};
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::HashSet;
+
+const SERVICE_DOWN_ALARM_TOPIC: &str = "tedge/alarms/major/";
+const SERVICE_DOWN_ALARM_SUFFIX: &str = "-down";
+
+#[derive(Debug, Deserialize)]
+struct HealthStatus {
+    status: String,
+}
+
+/// The service and status of a health message.
+///
+/// Payloads that are not a health status, as the one of the bridge, are ignored.
+pub(crate) fn health_status(message: &Message) -> Option<(&str, String)> {
+    let service = message.topic.name.strip_prefix(TEDGE_HEALTH_TOPIC)?;
+    let health = serde_json::from_str::<HealthStatus>(message.payload_str().ok()?).ok()?;
+    Some((service, health.status))
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct ServiceMonitor {
+    /// The services with a raised alarm.
+    services_down: HashSet<String>,
+}
+
+impl ServiceMonitor {
+    /// Track the service alarms, including the retained alarms raised before the mapper started.
+    pub(crate) fn process_alarm_message(&mut self, message: &Message) {
+        let service = match message
+            .topic
+            .name
+            .strip_prefix(SERVICE_DOWN_ALARM_TOPIC)
+            .and_then(|alarm_type| alarm_type.strip_suffix(SERVICE_DOWN_ALARM_SUFFIX))
+        {
+            Some(service) if !service.contains('/') => service,
+            _ => return,
+        };
+        if message.payload_bytes().is_empty() {
+            self.services_down.remove(service);
+        } else {
+            self.services_down.insert(service.to_string());
+        }
+    }
+
+    /// Raise the alarm of a service that is down, and clear it when the service is up again.
+    pub(crate) fn process_health_message(&mut self, message: &Message) -> Vec<Message> {
+        let (service, status) = match health_status(message) {
+            Some(health) => health,
+            None => return vec![],
+        };
+        let is_down = status != "up";
+        if self.services_down.contains(service) == is_down {
+            return vec![];
+        }
+
+        let alarm_topic = Topic::new_unchecked(&format!(
+            "{SERVICE_DOWN_ALARM_TOPIC}{service}{SERVICE_DOWN_ALARM_SUFFIX}"
+        ));
+        let alarm = if is_down {
+            self.services_down.insert(service.to_string());
+            let text = json!({ "text": format!("The {service} service is {status}") });
+            Message::new(&alarm_topic, text.to_string())
+        } else {
+            self.services_down.remove(service);
+            Message::new(&alarm_topic, vec![])
+        };
+        vec![alarm.with_retain()]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn health_message(service: &str, payload: &str) -> Message {
+        Message::new(
+            &Topic::new_unchecked(&format!("tedge/health/{service}")),
+            payload,
+        )
+    }
+
+    #[test]
+    fn an_alarm_is_raised_when_a_service_is_down() {
+        let mut monitor = ServiceMonitor::default();
+
+        // Up: nothing to do
+        let messages =
+            monitor.process_health_message(&health_message("tedge-agent", r#"{"status":"up"}"#));
+        assert!(messages.is_empty());
+
+        // Down: an alarm is raised, once
+        let messages =
+            monitor.process_health_message(&health_message("tedge-agent", r#"{"status":"down"}"#));
+        assert_eq!(messages.len(), 1);
+        assert_eq!(
+            messages[0].topic.name,
+            "tedge/alarms/major/tedge-agent-down"
+        );
+        assert_eq!(
+            messages[0].payload_str().unwrap(),
+            r#"{"text":"The tedge-agent service is down"}"#
+        );
+        assert!(messages[0].retain);
+        let messages =
+            monitor.process_health_message(&health_message("tedge-agent", r#"{"status":"down"}"#));
+        assert!(messages.is_empty());
+
+        // Up again: the alarm is cleared
+        let messages =
+            monitor.process_health_message(&health_message("tedge-agent", r#"{"status":"up"}"#));
+        assert_eq!(messages.len(), 1);
+        assert_eq!(
+            messages[0].topic.name,
+            "tedge/alarms/major/tedge-agent-down"
+        );
+        assert!(messages[0].payload_bytes().is_empty());
+
+        // The bridge health status is not a JSON object
+        let messages = monitor.process_health_message(&health_message("mosquitto-c8y-bridge", "0"));
+        assert!(messages.is_empty());
+    }
+
+    #[test]
+    fn an_alarm_raised_before_the_mapper_started_is_cleared() {
+        let mut monitor = ServiceMonitor::default();
+        monitor.process_alarm_message(&Message::new(
+            &Topic::new_unchecked("tedge/alarms/major/c8y-log-plugin-down"),
+            r#"{"text":"The c8y-log-plugin service is down"}"#,
+        ));
+
+        let messages =
+            monitor.process_health_message(&health_message("c8y-log-plugin", r#"{"status":"up"}"#));
+        assert_eq!(messages.len(), 1);
+        assert_eq!(
+            messages[0].topic.name,
+            "tedge/alarms/major/c8y-log-plugin-down"
+        );
+        assert!(messages[0].payload_bytes().is_empty());
+    }
+}
@@ -0,0 +1,230 @@
+//! The `c8y_Command` operation, executing on the device the shell commands sent from Cumulocity.
+//!
+//! This operation is disabled by default and has to be enabled with `tedge config set c8y.command.enable true`.
+//! The output of a command, stdout followed by stderr, is returned as the result of the operation,
+//! truncated to the size limit of the SmartREST messages.
+
+use super::error::CumulocityMapperError;
+use super::mapper::declare_operation;
+use super::operation_command::{command_failure, OperationCommand};
+use c8y_api::smartrest::smartrest_deserializer::{
+    SmartRestCommandRequest, SmartRestRequestGeneric,
+};
+use logged_command::LoggedCommand;
+use mqtt_channel::{Message, Sender, Topic};
+use plugin_sm::operation_logs::OperationLogs;
+use std::path::Path;
+use std::process::Output;
+use std::time::Duration;
+use tedge_config::{
+    C8yCommandEnableSetting, C8yCommandTimeoutSetting, C8yCommandUserSetting,
+    ConfigSettingAccessor, ConfigSettingAccessorStringExt, TEdgeConfig,
+};
+
+pub const SHELL_COMMAND_OPERATION: &str = "c8y_Command";
+pub const SHELL_COMMAND_TEMPLATE: &str = "511";
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ShellCommandConfig {
+    /// Maximum time given to a command to complete, if any.
+    pub timeout: Option<Duration>,
+
+    /// The user running the commands, with `sudo`, rather than the user of the mapper.
+    pub user: Option<String>,
+}
+
+impl ShellCommandConfig {
+    /// The configuration of the `c8y_Command` operation, if enabled.
+    pub fn try_new(tedge_config: &TEdgeConfig) -> Result<Option<Self>, CumulocityMapperError> {
+        if !tedge_config.query(C8yCommandEnableSetting)?.is_set() {
+            return Ok(None);
+        }
+
+        // A timeout of 0 second disables the timeout
+        let timeout = tedge_config.query(C8yCommandTimeoutSetting)?.0;
+        Ok(Some(ShellCommandConfig {
+            timeout: (timeout > 0).then(|| Duration::from_secs(timeout)),
+            user: tedge_config.query_string_optional(C8yCommandUserSetting)?,
+        }))
+    }
+
+    fn command(&self, shell_command: &str) -> LoggedCommand {
+        let mut command = match &self.user {
+            Some(user) => {
+                let mut command = LoggedCommand::new("sudo");
+                command.arg("-n").arg("-u").arg(user).arg("sh");
+                command
+            }
+            None => LoggedCommand::new("sh"),
+        };
+        command.arg("-c").arg(shell_command);
+        if let Some(timeout) = self.timeout {
+            command.timeout(timeout);
+        }
+        command
+    }
+}
+
+/// Declare the `c8y_Command` operation when enabled, and withdraw it when disabled.
+pub fn declare_shell_command_operation(
+    ops_dir: &Path,
+    enabled: bool,
+) -> Result<(), CumulocityMapperError> {
+    declare_operation(ops_dir, SHELL_COMMAND_OPERATION, enabled)
+}
+
+/// Launch the shell command of a `c8y_Command` request.
+///
+/// The operation is marked as executing as soon as the command is launched,
+/// the output of the command being sent along the final status of the operation.
+pub async fn execute_shell_command(
+    payload: &str,
+    config: &ShellCommandConfig,
+    operation_logs: &OperationLogs,
+    mqtt_publisher: &Sender<Message>,
+    status_topic: &Topic,
+) -> Result<(), CumulocityMapperError> {
+    let request = SmartRestCommandRequest::from_smartrest(payload)?;
+
+    OperationCommand {
+        operation_name: SHELL_COMMAND_OPERATION.to_string(),
+        command: config.command(&request.command),
+        report_progress: false,
+        result_parameters: |output| vec![command_output(output)],
+        failure_reason,
+    }
+    .launch(operation_logs, mqtt_publisher, status_topic)
+    .await
+}
+
+/// The output of the command, stdout followed by stderr.
+fn command_output(output: &Output) -> String {
+    let mut text = String::from_utf8_lossy(&output.stdout).into_owned();
+    text.push_str(&String::from_utf8_lossy(&output.stderr));
+    text.trim_end().to_string()
+}
+
+fn failure_reason(output: &Output) -> String {
+    let status = format!("Command {}", command_failure(output));
+    match command_output(output).as_str() {
+        "" => status,
+        output => format!("{status}\n{output}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::c8y::converter::MAX_SMARTREST_MESSAGE_SIZE;
+    use futures::channel::mpsc;
+    use futures::StreamExt;
+    use tedge_test_utils::fs::TempTedgeDir;
+
+    #[test]
+    fn operation_file_is_only_removed_when_not_a_custom_operation() {
+        let ops_dir = TempTedgeDir::new();
+        let operation_file = ops_dir.path().join(SHELL_COMMAND_OPERATION);
+
+        declare_shell_command_operation(ops_dir.path(), true).unwrap();
+        assert!(operation_file.exists());
+        declare_shell_command_operation(ops_dir.path(), false).unwrap();
+        assert!(!operation_file.exists());
+
+        ops_dir.file(SHELL_COMMAND_OPERATION).with_raw_content(
+            "[exec]\ntopic = \"c8y/s/ds\"\non_message = \"511\"\ncommand = \"/bin/run\"",
+        );
+        declare_shell_command_operation(ops_dir.path(), false).unwrap();
+        assert!(operation_file.exists());
+    }
+
+    #[tokio::test]
+    async fn command_output_is_the_result_of_the_operation() {
+        let log_dir = TempTedgeDir::new();
+        let operation_logs = OperationLogs::try_new(log_dir.path().to_path_buf()).unwrap();
+        let (mqtt_publisher, mut published) = mpsc::channel(10);
+        let topic = Topic::new_unchecked("c8y/s/us");
+
+        execute_shell_command(
+            r#"511,device,"echo hello, world; echo oops >&2""#,
+            &ShellCommandConfig::default(),
+            &operation_logs,
+            &mqtt_publisher,
+            &topic,
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            published.next().await.unwrap().payload_str().unwrap(),
+            "501,c8y_Command"
+        );
+        assert_eq!(
+            published.next().await.unwrap().payload_str().unwrap(),
+            "503,c8y_Command,\"hello, world\noops\""
+        );
+
+        execute_shell_command(
+            "511,device,exit 3",
+            &ShellCommandConfig::default(),
+            &operation_logs,
+            &mqtt_publisher,
+            &topic,
+        )
+        .await
+        .unwrap();
+        published.next().await;
+        assert_eq!(
+            published.next().await.unwrap().payload_str().unwrap(),
+            "502,c8y_Command,\"Command failed with exit status: 3\""
+        );
+    }
+
+    #[tokio::test]
+    async fn long_outputs_are_truncated_to_the_smartrest_size_limit() {
+        let log_dir = TempTedgeDir::new();
+        let operation_logs = OperationLogs::try_new(log_dir.path().to_path_buf()).unwrap();
+        let (mqtt_publisher, mut published) = mpsc::channel(10);
+
+        execute_shell_command(
+            r#"511,device,"yes '""quoted""' | head -n 5000""#,
+            &ShellCommandConfig::default(),
+            &operation_logs,
+            &mqtt_publisher,
+            &Topic::new_unchecked("c8y/s/us"),
+        )
+        .await
+        .unwrap();
+        published.next().await;
+        let status = published.next().await.unwrap();
+        let status = status.payload_str().unwrap();
+        assert!(status.len() <= MAX_SMARTREST_MESSAGE_SIZE);
+        assert!(status.starts_with("503,c8y_Command,\"\"\"quoted\"\"\n"));
+        assert!(status.ends_with("\n[truncated]\""));
+    }
+
+    #[tokio::test]
+    async fn command_is_killed_on_timeout() {
+        let log_dir = TempTedgeDir::new();
+        let operation_logs = OperationLogs::try_new(log_dir.path().to_path_buf()).unwrap();
+        let (mqtt_publisher, mut published) = mpsc::channel(10);
+        let config = ShellCommandConfig {
+            timeout: Some(Duration::from_millis(100)),
+            user: None,
+        };
+
+        execute_shell_command(
+            "511,device,sleep 10",
+            &config,
+            &operation_logs,
+            &mqtt_publisher,
+            &Topic::new_unchecked("c8y/s/us"),
+        )
+        .await
+        .unwrap();
+        published.next().await;
+        let status = published.next().await.unwrap();
+        assert!(status
+            .payload_str()
+            .unwrap()
+            .starts_with("502,c8y_Command,"));
+    }
+}
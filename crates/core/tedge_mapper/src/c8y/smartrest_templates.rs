@@ -0,0 +1,430 @@
+//! Custom SmartREST 2.0 templates, registered by the mapper and used to send measurements and events
+//! in the compact `s/uc/<template>` format rather than as Cumulocity JSON.
+//!
+//! Each template collection is defined by a TOML file `<template>.toml` in the templates directory,
+//! the collection being named after the file:
+//!
+//! ```toml
+//! # Measurement sent as `101,<time>,<temperature>,<humidity>`
+//! [[measurement]]
+//! id = "101"
+//! type = "environment"
+//! series = ["temperature", "humidity"]
+//!
+//! # Measurement of a group, sent as `102,<time>,<x>,<y>`
+//! [[measurement]]
+//! id = "102"
+//! series = ["position.x", "position.y"]
+//!
+//! # Event sent as `201,<time>,<text>`
+//! [[event]]
+//! id = "201"
+//! type = "login_event"
+//! ```
+//!
+//! The templates are registered on start by publishing their definitions on `s/ut/<template>`.
+//! As Cumulocity doesn't update an existing template collection,
+//! a collection has to be renamed for a change of its templates to be taken into account.
+//!
+//! A thin-edge measurement of the main device is sent using a measurement template when it has all the series of that template,
+//! the other series being sent as Cumulocity JSON, if any.
+//! A thin-edge event of the main device is sent using the event template of its type, unless it has custom fragments.
+
+use super::converter::quote_smartrest_value;
+use super::error::CumulocityMapperError;
+use mqtt_channel::{Message, Topic};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::collections::HashSet;
+use std::path::Path;
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+use tracing::{error, info};
+
+use crate::core::error::ConversionError;
+
+pub const SMARTREST_TEMPLATES_DIRECTORY: &str = "c8y/smartrest-templates";
+const TEMPLATE_REGISTRATION_TOPIC: &str = "c8y/s/ut";
+const TEMPLATE_PUBLISH_TOPIC: &str = "c8y/s/uc";
+const DEFAULT_MEASUREMENT_TYPE: &str = "ThinEdgeMeasurement";
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SmartRestTemplates {
+    collections: Vec<TemplateCollection>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct TemplateCollection {
+    #[serde(skip)]
+    name: String,
+
+    #[serde(default, rename = "measurement")]
+    measurements: Vec<MeasurementTemplate>,
+
+    #[serde(default, rename = "event")]
+    events: Vec<EventTemplate>,
+}
+
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct MeasurementTemplate {
+    id: String,
+
+    /// The type of the Cumulocity measurement.
+    #[serde(rename = "type")]
+    measurement_type: Option<String>,
+
+    /// The thin-edge measurements sent by this template: `<name>` or `<group>.<name>`.
+    series: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct EventTemplate {
+    id: String,
+
+    /// The type of the thin-edge events sent by this template.
+    #[serde(rename = "type")]
+    event_type: String,
+}
+
+impl SmartRestTemplates {
+    /// Load the template collections defined in the given directory.
+    ///
+    /// An invalid template file is skipped, the other collections being still used.
+    pub fn load(templates_dir: &Path) -> Result<Self, CumulocityMapperError> {
+        let mut collections = Vec::new();
+        if !templates_dir.is_dir() {
+            return Ok(SmartRestTemplates { collections });
+        }
+
+        for entry in std::fs::read_dir(templates_dir)? {
+            let path = entry?.path();
+            if path
+                .extension()
+                .map_or(true, |extension| extension != "toml")
+            {
+                continue;
+            }
+            match TemplateCollection::load(&path) {
+                Ok(collection) => {
+                    info!(
+                        "Using the SmartREST template collection {}",
+                        collection.name
+                    );
+                    collections.push(collection);
+                }
+                Err(err) => error!(
+                    "Ignoring the SmartREST templates defined in {}: {err}",
+                    path.display()
+                ),
+            }
+        }
+        collections.sort_by(|a, b| a.name.cmp(&b.name));
+
+        Ok(SmartRestTemplates { collections })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.collections.is_empty()
+    }
+
+    /// The messages registering the template collections, one per collection.
+    pub fn registration_messages(&self) -> Vec<Message> {
+        self.collections
+            .iter()
+            .map(TemplateCollection::registration_message)
+            .collect()
+    }
+
+    /// Send the series of a thin-edge measurement using the matching measurement templates.
+    ///
+    /// Return the template messages along the thin-edge measurement
+    /// with the series not sent by these templates, if any.
+    pub fn convert_measurement(
+        &self,
+        tedge_measurement: &str,
+    ) -> Result<(Vec<Message>, Option<String>), ConversionError> {
+        let mut measurement: Map<String, Value> = serde_json::from_str(tedge_measurement)?;
+        let time = match measurement.get("time").and_then(Value::as_str) {
+            Some(time) => time.to_string(),
+            None => OffsetDateTime::now_utc().format(&Rfc3339)?,
+        };
+
+        let mut messages = Vec::new();
+        for collection in self.collections.iter() {
+            for template in collection.measurements.iter() {
+                let values: Option<Vec<String>> = template
+                    .series
+                    .iter()
+                    .map(|series| numeric_value(&measurement, series))
+                    .collect();
+                if let Some(values) = values {
+                    template
+                        .series
+                        .iter()
+                        .for_each(|series| remove_series(&mut measurement, series));
+                    messages.push(collection.message(&format!(
+                        "{},{time},{}",
+                        template.id,
+                        values.join(",")
+                    )));
+                }
+            }
+        }
+
+        let remaining = if messages.is_empty() {
+            Some(tedge_measurement.to_string())
+        } else if measurement.keys().any(|key| key != "time") {
+            Some(Value::Object(measurement).to_string())
+        } else {
+            None
+        };
+        Ok((messages, remaining))
+    }
+
+    /// Send an event using the event template of its type, if any.
+    pub fn convert_event(
+        &self,
+        event_type: &str,
+        text: &str,
+        time: &OffsetDateTime,
+    ) -> Result<Option<Message>, ConversionError> {
+        for collection in self.collections.iter() {
+            if let Some(template) = collection
+                .events
+                .iter()
+                .find(|template| template.event_type == event_type)
+            {
+                return Ok(Some(collection.message(&format!(
+                    "{},{},{}",
+                    template.id,
+                    time.format(&Rfc3339)?,
+                    quote_smartrest_value(text)
+                ))));
+            }
+        }
+        Ok(None)
+    }
+}
+
+impl TemplateCollection {
+    fn load(path: &Path) -> Result<Self, CumulocityMapperError> {
+        let invalid = |reason: String| CumulocityMapperError::InvalidSmartRestTemplate {
+            path: path.display().to_string(),
+            reason,
+        };
+
+        let name = path
+            .file_stem()
+            .and_then(|name| name.to_str())
+            .filter(|name| !name.is_empty() && !name.contains(['/', '+', '#', ' ']))
+            .ok_or_else(|| invalid("invalid template collection name".into()))?
+            .to_string();
+
+        let collection: TemplateCollection = toml::from_str(&std::fs::read_to_string(path)?)
+            .map_err(|err| invalid(err.to_string()))?;
+
+        let mut ids = HashSet::new();
+        let template_ids = collection
+            .measurements
+            .iter()
+            .map(|template| &template.id)
+            .chain(collection.events.iter().map(|template| &template.id));
+        for id in template_ids {
+            if !ids.insert(id) {
+                return Err(invalid(format!("duplicated template id: {id}")));
+            }
+        }
+        if let Some(template) = collection
+            .measurements
+            .iter()
+            .find(|template| template.series.is_empty())
+        {
+            return Err(invalid(format!("no series for template: {}", template.id)));
+        }
+
+        Ok(TemplateCollection { name, ..collection })
+    }
+
+    /// The SmartREST 2.0 definitions of the templates:
+    /// `10,<id>,POST,<api>,,<type>,,<path>,<value type>,` followed by the other custom values, if any.
+    ///
+    /// The time is left empty in these definitions, so it is given by each message.
+    fn registration_message(&self) -> Message {
+        let mut definitions = Vec::new();
+        for template in self.measurements.iter() {
+            let custom_values: Vec<String> = template
+                .series
+                .iter()
+                .map(|series| format!("{},NUMBER,", c8y_series_path(series)))
+                .collect();
+            definitions.push(format!(
+                "10,{},POST,MEASUREMENT,,{},,{}",
+                template.id,
+                template
+                    .measurement_type
+                    .as_deref()
+                    .unwrap_or(DEFAULT_MEASUREMENT_TYPE),
+                custom_values.join(",")
+            ));
+        }
+        for template in self.events.iter() {
+            definitions.push(format!(
+                "10,{},POST,EVENT,,{},,text,STRING,",
+                template.id, template.event_type
+            ));
+        }
+
+        let topic = Topic::new_unchecked(&format!("{TEMPLATE_REGISTRATION_TOPIC}/{}", self.name));
+        Message::new(&topic, definitions.join("\n"))
+    }
+
+    fn message(&self, payload: &str) -> Message {
+        let topic = Topic::new_unchecked(&format!("{TEMPLATE_PUBLISH_TOPIC}/{}", self.name));
+        Message::new(&topic, payload)
+    }
+}
+
+/// The path of a series in a Cumulocity measurement, as built by the JSON mapping:
+/// `<name>.<name>.value` for a single value and `<group>.<name>.value` for a series of a group.
+fn c8y_series_path(series: &str) -> String {
+    match series.split_once('.') {
+        Some((group, name)) => format!("{group}.{name}.value"),
+        None => format!("{series}.{series}.value"),
+    }
+}
+
+fn numeric_value(measurement: &Map<String, Value>, series: &str) -> Option<String> {
+    let value = match series.split_once('.') {
+        Some((group, name)) => measurement.get(group)?.get(name)?,
+        None => measurement.get(series)?,
+    };
+    value.as_f64().map(|_| value.to_string())
+}
+
+fn remove_series(measurement: &mut Map<String, Value>, series: &str) {
+    match series.split_once('.') {
+        Some((group, name)) => {
+            if let Some(Value::Object(values)) = measurement.get_mut(group) {
+                values.remove(name);
+                if values.is_empty() {
+                    measurement.remove(group);
+                }
+            }
+        }
+        None => {
+            measurement.remove(series);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use tedge_test_utils::fs::TempTedgeDir;
+
+    const TEMPLATES: &str = r#"
+        [[measurement]]
+        id = "101"
+        type = "environment"
+        series = ["temperature", "humidity"]
+
+        [[measurement]]
+        id = "102"
+        series = ["position.x", "position.y"]
+
+        [[event]]
+        id = "201"
+        type = "login_event"
+    "#;
+
+    fn load_templates() -> SmartRestTemplates {
+        let templates_dir = TempTedgeDir::new();
+        templates_dir
+            .file("tedge-templates.toml")
+            .with_raw_content(TEMPLATES);
+        templates_dir
+            .file("invalid.toml")
+            .with_raw_content("[[measurement]]\nid = \"101\"\nseries = []");
+        templates_dir
+            .file("README")
+            .with_raw_content("not a template");
+        SmartRestTemplates::load(templates_dir.path()).unwrap()
+    }
+
+    #[test]
+    fn templates_are_registered() {
+        let templates = load_templates();
+        let messages = templates.registration_messages();
+
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].topic.name, "c8y/s/ut/tedge-templates");
+        assert_eq!(
+            messages[0].payload_str().unwrap(),
+            "10,101,POST,MEASUREMENT,,environment,,temperature.temperature.value,NUMBER,,humidity.humidity.value,NUMBER,\n\
+             10,102,POST,MEASUREMENT,,ThinEdgeMeasurement,,position.x.value,NUMBER,,position.y.value,NUMBER,\n\
+             10,201,POST,EVENT,,login_event,,text,STRING,"
+        );
+    }
+
+    #[test]
+    fn measurements_are_sent_using_the_matching_templates() {
+        let templates = load_templates();
+        let (messages, remaining) = templates
+            .convert_measurement(
+                r#"{"time":"2021-04-30T17:03:14.123+02:00","temperature":21.5,"humidity":40,"position":{"x":1,"y":2},"pressure":1000}"#,
+            )
+            .unwrap();
+
+        let payloads: Vec<&str> = messages
+            .iter()
+            .map(|message| message.payload_str().unwrap())
+            .collect();
+        assert_eq!(
+            payloads,
+            vec![
+                "101,2021-04-30T17:03:14.123+02:00,21.5,40",
+                "102,2021-04-30T17:03:14.123+02:00,1,2"
+            ]
+        );
+        assert_eq!(messages[0].topic.name, "c8y/s/uc/tedge-templates");
+        assert_eq!(
+            serde_json::from_str::<Value>(&remaining.unwrap()).unwrap(),
+            json!({"time":"2021-04-30T17:03:14.123+02:00","pressure":1000})
+        );
+    }
+
+    #[test]
+    fn measurements_without_all_the_series_of_a_template_are_sent_as_json() {
+        let templates = load_templates();
+        let input = r#"{"temperature":21.5,"position":{"x":1}}"#;
+        let (messages, remaining) = templates.convert_measurement(input).unwrap();
+
+        assert!(messages.is_empty());
+        assert_eq!(remaining.as_deref(), Some(input));
+    }
+
+    #[test]
+    fn events_are_sent_using_the_template_of_their_type() {
+        let templates = load_templates();
+        let time = OffsetDateTime::parse("2021-04-30T17:03:14.123+02:00", &Rfc3339).unwrap();
+
+        let message = templates
+            .convert_event("login_event", "Someone logged in, as \"root\"", &time)
+            .unwrap()
+            .unwrap();
+        assert_eq!(message.topic.name, "c8y/s/uc/tedge-templates");
+        assert_eq!(
+            message.payload_str().unwrap(),
+            r#"201,2021-04-30T17:03:14.123+02:00,"Someone logged in, as ""root""""#
+        );
+
+        assert!(templates
+            .convert_event("logout_event", "Someone logged out", &time)
+            .unwrap()
+            .is_none());
+    }
+}
@@ -1,5 +1,7 @@
 use crate::core::{
-    converter::Converter, error::ConversionError, mapper::create_mapper,
+    converter::Converter,
+    error::ConversionError,
+    mapper::{async_messages_channel, create_mapper_with_client, create_mqtt_client},
     size_threshold::SizeThreshold,
 };
 use anyhow::Result;
@@ -10,12 +12,14 @@ use c8y_api::smartrest::{
     smartrest_deserializer::SmartRestJwtResponse,
 };
 use c8y_api::{
-    http_proxy::C8YHttpProxy,
-    json_c8y::{C8yCreateEvent, C8yUpdateSoftwareListResponse},
+    http_proxy::{C8YHttpProxy, MockC8YHttpProxy},
+    json_c8y::{
+        C8yAlarm, C8yAlarmStatus, C8yAlarmUpdate, C8yCreateEvent, C8yUpdateSoftwareListResponse,
+    },
 };
 
 use futures::StreamExt;
-use mqtt_channel::{Message, Topic};
+use mqtt_channel::{Message, Sender, Topic};
 use mqtt_tests::test_mqtt_server::MqttProcessHandler;
 use mqtt_tests::with_timeout::WithTimeout;
 use serde_json::json;
@@ -25,7 +29,12 @@ use tedge_test_utils::fs::TempTedgeDir;
 use test_case::test_case;
 use tokio::task::JoinHandle;
 
-use super::converter::{get_child_id_from_measurement_topic, CumulocityConverter};
+use super::alarm_converter::PublishedAlarms;
+use super::child_devices::ChildDeviceRegistry;
+use super::converter::{
+    create_mapper_config, get_child_id_from_measurement_topic, CumulocityConverter,
+};
+use super::smartrest_templates::SmartRestTemplates;
 
 const TEST_TIMEOUT_MS: Duration = Duration::from_millis(5000);
 const MQTT_HOST: &str = "127.0.0.1";
@@ -658,7 +667,7 @@ async fn test_sync_alarms() {
     assert!(converter.convert(&internal_alarm_message).await.is_empty());
 
     // When sync phase is complete, all pending alarms are returned
-    let sync_messages = converter.sync_messages();
+    let sync_messages = converter.sync_messages().await;
     assert_eq!(sync_messages.len(), 2);
 
     // The first message will be clear alarm message for pressure_alarm
@@ -713,7 +722,7 @@ async fn test_sync_child_alarms() {
     assert!(converter.convert(&internal_alarm_message).await.is_empty());
 
     // When sync phase is complete, all pending alarms are returned
-    let sync_messages = converter.sync_messages();
+    let sync_messages = converter.sync_messages().await;
     assert_eq!(sync_messages.len(), 2);
 
     // The first message will be clear alarm message for pressure_alarm
@@ -859,6 +868,47 @@ async fn convert_two_thin_edge_json_messages_given_different_child_id() {
     );
 }
 
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+#[serial]
+async fn convert_thin_edge_json_with_text_and_boolean_values() {
+    let cfg_dir = TempTedgeDir::new();
+    let (_temp_dir, mut converter) = create_c8y_converter(&cfg_dir);
+
+    let in_topic = "tedge/measurements";
+    let in_payload =
+        r#"{"state": "RUNNING", "door_open": true, "time": "2021-11-16T17:45:40+01:00"}"#;
+    let in_message = Message::new(&Topic::new_unchecked(in_topic), in_payload);
+
+    // Booleans are sent as numeric measurements, text values as events
+    let expected_c8y_json_message = Message::new(
+        &Topic::new_unchecked("c8y/measurement/measurements/create"),
+        r#"{"type":"ThinEdgeMeasurement","door_open":{"door_open":{"value":1.0}},"time":"2021-11-16T17:45:40+01:00"}"#,
+    );
+    let expected_smart_rest_event = Message::new(
+        &Topic::new_unchecked("c8y/s/us"),
+        r#"400,state,"RUNNING",2021-11-16T17:45:40+01:00"#,
+    );
+
+    let out_messages = converter.convert(&in_message).await;
+    assert_eq!(
+        out_messages,
+        vec![expected_c8y_json_message, expected_smart_rest_event]
+    );
+
+    // No measurement is sent when there are only text values
+    let in_message = Message::new(
+        &Topic::new_unchecked(in_topic),
+        r#"{"state": "STOPPED", "time": "2021-11-16T17:45:40+01:00"}"#,
+    );
+    let expected_smart_rest_event = Message::new(
+        &Topic::new_unchecked("c8y/s/us"),
+        r#"400,state,"STOPPED",2021-11-16T17:45:40+01:00"#,
+    );
+
+    let out_messages = converter.convert(&in_message).await;
+    assert_eq!(out_messages, vec![expected_smart_rest_event]);
+}
+
 #[test_case("tedge/measurements/test", Some("test".to_string()); "valid child id")]
 #[test_case("tedge/measurements/", None; "returns an error (empty value)")]
 #[test_case("tedge/measurements", None; "invalid child id (parent topic)")]
@@ -943,6 +993,295 @@ async fn convert_event_with_extra_fields_to_c8y_json() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+#[serial]
+async fn convert_alarm_lifecycle() -> Result<()> {
+    let cfg_dir = TempTedgeDir::new();
+    let mut http_proxy = MockC8YHttpProxy::new();
+    // No alarm on the cloud when the mapper starts
+    http_proxy
+        .expect_get_unresolved_alarms()
+        .times(1)
+        .returning(|_| Ok(vec![]));
+    // The id of the cloud alarm is then fetched once, for the first update
+    http_proxy
+        .expect_get_unresolved_alarms()
+        .times(1)
+        .returning(|_| Ok(vec![cloud_alarm("temperature_alarm", "CRITICAL")]));
+    http_proxy
+        .expect_update_alarm()
+        .withf(|id, update| {
+            id == "42"
+                && update.status == Some(C8yAlarmStatus::Acknowledged)
+                && update.text.is_none()
+        })
+        .times(1)
+        .returning(|_, _| Ok(()));
+    http_proxy
+        .expect_update_alarm()
+        .withf(|id, update| {
+            id == "42" && update.status.is_none() && update.text.as_deref() == Some("Cooled down")
+        })
+        .times(1)
+        .returning(|_, _| Ok(()));
+    let (_temp_dir, mut converter) = create_c8y_converter_with_proxy(&cfg_dir, http_proxy);
+    assert!(converter.sync_messages().await.is_empty());
+
+    let alarm_topic = "tedge/alarms/major/temperature_alarm";
+    let raise = Message::new(&Topic::new_unchecked(alarm_topic), r#"{"text": "Too hot"}"#);
+    let messages = converter.convert(&raise).await;
+    assert!(messages[0]
+        .payload_str()?
+        .starts_with(r#"302,temperature_alarm,"Too hot""#));
+
+    // Raising the same alarm with a new severity updates the alarm
+    let critical_topic = "tedge/alarms/critical/temperature_alarm";
+    let critical = Message::new(
+        &Topic::new_unchecked(critical_topic),
+        r#"{"text": "Too hot"}"#,
+    );
+    let messages = converter.convert(&critical).await;
+    assert_eq!(messages[0].payload_str()?, "305,temperature_alarm,CRITICAL");
+
+    // Clearing the message of the former severity leaves the alarm unchanged
+    let stale_clear = Message::new(&Topic::new_unchecked(alarm_topic), "");
+    let messages = converter.convert(&stale_clear).await;
+    assert_eq!(messages.len(), 1);
+    assert_eq!(
+        messages[0].topic.name,
+        "c8y-internal/alarms/major/temperature_alarm"
+    );
+
+    // The alarm is acknowledged over HTTP
+    let acknowledge = Message::new(
+        &Topic::new_unchecked(critical_topic),
+        r#"{"text": "Too hot", "status": "acknowledged"}"#,
+    );
+    let messages = converter.convert(&acknowledge).await;
+    assert_eq!(messages.len(), 1);
+
+    // The reason why the alarm is cleared is sent over HTTP, before the alarm is cleared
+    let clear = Message::new(
+        &Topic::new_unchecked(critical_topic),
+        r#"{"text": "Cooled down", "status": "cleared"}"#,
+    );
+    let messages = converter.convert(&clear).await;
+    assert_eq!(messages[0].payload_str()?, "306,temperature_alarm");
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+#[serial]
+async fn stale_cloud_alarms_are_cleared_on_sync() -> Result<()> {
+    let cfg_dir = TempTedgeDir::new();
+    let mut http_proxy = MockC8YHttpProxy::new();
+    http_proxy.expect_get_unresolved_alarms().returning(|_| {
+        Ok(vec![
+            cloud_alarm("temperature_alarm", "MAJOR"),
+            cloud_alarm("pressure_alarm", "MINOR"),
+            cloud_alarm("c8y_UnavailabilityAlarm", "MAJOR"),
+            cloud_alarm("maintenance_alarm", "WARNING"),
+        ])
+    });
+    let (_temp_dir, converter) = create_c8y_converter_with_proxy(&cfg_dir, http_proxy);
+
+    // Only the alarms published by thin-edge are cleared, not the ones raised from the cloud
+    let mapper_dir = TempTedgeDir::new();
+    let mut published_alarms = PublishedAlarms::load(mapper_dir.path());
+    published_alarms.record("temperature_alarm")?;
+    published_alarms.record("pressure_alarm")?;
+    let mut converter = converter.with_published_alarms(PublishedAlarms::load(mapper_dir.path()));
+
+    // The pressure alarm is still active on the device
+    let pressure_alarm = Message::new(
+        &Topic::new_unchecked("tedge/alarms/minor/pressure_alarm"),
+        r#"{"text": "Low pressure"}"#,
+    );
+    assert!(converter.convert(&pressure_alarm).await.is_empty());
+
+    let sync_messages = converter.sync_messages().await;
+    assert_eq!(sync_messages.len(), 2);
+    assert_eq!(sync_messages[0], pressure_alarm);
+    assert_eq!(
+        sync_messages[1].topic.name,
+        "tedge/alarms/major/temperature_alarm"
+    );
+    assert_eq!(sync_messages[1].payload_bytes().len(), 0);
+
+    let messages = converter.convert(&sync_messages[1]).await;
+    assert_eq!(messages[0].payload_str()?, "306,temperature_alarm");
+
+    Ok(())
+}
+
+fn cloud_alarm(alarm_type: &str, severity: &str) -> C8yAlarm {
+    C8yAlarm {
+        id: "42".into(),
+        alarm_type: alarm_type.into(),
+        severity: severity.into(),
+        status: C8yAlarmStatus::Active,
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn register_nested_child_devices() -> Result<()> {
+    let cfg_dir = TempTedgeDir::new();
+    let mapper_dir = TempTedgeDir::new();
+    let (_temp_dir, converter) = create_c8y_converter(&cfg_dir);
+    let mut converter = converter.with_child_registry(ChildDeviceRegistry::load(mapper_dir.path()));
+
+    let gateway = Message::new(
+        &Topic::new_unchecked("tedge/register/gateway1"),
+        r#"{"name": "Gateway", "type": "thin-edge.io-gateway"}"#,
+    );
+    let messages = converter.convert(&gateway).await;
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0].topic.name, "c8y/s/us");
+    assert_eq!(
+        messages[0].payload_str()?,
+        r#"101,gateway1,"Gateway","thin-edge.io-gateway""#
+    );
+
+    // A nested child device is created under its parent
+    let sensor = Message::new(
+        &Topic::new_unchecked("tedge/register/sensor1"),
+        r#"{"parent": "gateway1", "fragments": {"c8y_Hardware": {"model": "DS18B20"}}}"#,
+    );
+    let messages = converter.convert(&sensor).await;
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0].topic.name, "c8y/s/us/gateway1");
+    assert_eq!(
+        messages[0].payload_str()?,
+        r#"101,sensor1,"sensor1","thin-edge.io-child""#
+    );
+
+    let sync_messages = converter.sync_messages().await;
+    assert_eq!(sync_messages.len(), 1);
+    assert_eq!(
+        sync_messages[0].topic.name,
+        "c8y/inventory/managedObjects/update/sensor1"
+    );
+
+    // An unknown parent is rejected
+    let orphan = Message::new(
+        &Topic::new_unchecked("tedge/register/sensor2"),
+        r#"{"parent": "gateway2"}"#,
+    );
+    let messages = converter.convert(&orphan).await;
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0].topic.name, "tedge/errors");
+
+    // A parent is not removed before its children
+    let remove_gateway = Message::new(&Topic::new_unchecked("tedge/register/gateway1"), "");
+    let messages = converter.convert(&remove_gateway).await;
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0].topic.name, "tedge/errors");
+    let remove_sensor = Message::new(&Topic::new_unchecked("tedge/register/sensor1"), "");
+    assert!(converter.convert(&remove_sensor).await.is_empty());
+    assert!(converter.convert(&remove_gateway).await.is_empty());
+    assert_eq!(converter.convert(&gateway).await.len(), 1);
+    assert_eq!(converter.convert(&sensor).await.len(), 1);
+
+    // Once persisted, the child devices are not registered again
+    let (_temp_dir, converter) = create_c8y_converter(&cfg_dir);
+    let mut converter = converter.with_child_registry(ChildDeviceRegistry::load(mapper_dir.path()));
+    assert!(converter.convert(&gateway).await.is_empty());
+    let measurement = Message::new(
+        &Topic::new_unchecked("tedge/measurements/sensor1"),
+        r#"{"temperature": 21.5}"#,
+    );
+    let messages = converter.convert(&measurement).await;
+    assert_eq!(messages.len(), 1);
+    assert_eq!(
+        messages[0].topic.name,
+        "c8y/measurement/measurements/create"
+    );
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn convert_measurement_using_smartrest_templates() -> Result<()> {
+    let cfg_dir = TempTedgeDir::new();
+    cfg_dir.file("environment.toml").with_raw_content(
+        r#"
+        [[measurement]]
+        id = "101"
+        series = ["temperature", "humidity"]
+        "#,
+    );
+    let (_temp_dir, converter) = create_c8y_converter(&cfg_dir);
+    let mut converter =
+        converter.with_smartrest_templates(SmartRestTemplates::load(cfg_dir.path())?);
+
+    // The series of the template are sent using the template, the others as JSON
+    let measurement = Message::new(
+        &Topic::new_unchecked("tedge/measurements"),
+        r#"{"time":"2021-04-30T17:03:14.123+02:00","temperature":21.5,"humidity":40,"pressure":1000}"#,
+    );
+    let messages = converter.convert(&measurement).await;
+    assert_eq!(messages.len(), 2);
+    assert_eq!(messages[0].topic.name, "c8y/s/uc/environment");
+    assert_eq!(
+        messages[0].payload_str()?,
+        "101,2021-04-30T17:03:14.123+02:00,21.5,40"
+    );
+    assert_eq!(
+        messages[1].topic.name,
+        "c8y/measurement/measurements/create"
+    );
+    assert_json_include!(
+        actual: serde_json::from_str::<serde_json::Value>(messages[1].payload_str()?)?,
+        expected: json!({"pressure": {"pressure": {"value": 1000.0}}})
+    );
+    assert!(!messages[1].payload_str()?.contains("temperature"));
+
+    // The templates are not used for child devices
+    let child_measurement = Message::new(
+        &Topic::new_unchecked("tedge/measurements/child1"),
+        r#"{"temperature":21.5,"humidity":40}"#,
+    );
+    let messages = converter.convert(&child_measurement).await;
+    assert!(messages
+        .iter()
+        .all(|message| !message.topic.name.starts_with("c8y/s/uc")));
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn convert_inventory_fragments_for_child_device() -> Result<()> {
+    let cfg_dir = TempTedgeDir::new();
+    let (_temp_dir, mut converter) = create_c8y_converter(&cfg_dir);
+    let fragment_message = Message::new(
+        &Topic::new_unchecked("tedge/inventory/c8y_Hardware/child1"),
+        r#"{"model": "ESP32"}"#,
+    );
+
+    // The child device is registered on the first message, but the fragment only sent once synced
+    let messages = converter.convert(&fragment_message).await;
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0].topic.name, "c8y/s/us");
+    assert_eq!(
+        messages[0].payload_str()?,
+        "101,child1,child1,thin-edge.io-child"
+    );
+
+    let sync_messages = converter.sync_messages().await;
+    assert_eq!(sync_messages.len(), 1);
+    assert_eq!(
+        sync_messages[0].topic.name,
+        "c8y/inventory/managedObjects/update/child1"
+    );
+    assert_eq!(
+        serde_json::from_str::<serde_json::Value>(sync_messages[0].payload_str()?)?,
+        json!({"c8y_Hardware": {"model": "ESP32"}})
+    );
+
+    Ok(())
+}
+
 #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
 async fn test_convert_big_event() {
     let cfg_dir = TempTedgeDir::new();
@@ -1233,20 +1572,14 @@ async fn mapper_dynamically_updates_supported_operations_for_tedge_device() {
     let broker = mqtt_tests::test_mqtt_broker();
     let cfg_dir = TempTedgeDir::new();
     create_thin_edge_operations(&cfg_dir, vec!["c8y_TestOp1", "c8y_TestOp2"]);
-    let mut health_message = broker
-        .messages_published_on("tedge/health/c8y-mapper-test")
-        .await;
+    let mut health_message = mapper_health_messages(broker).await;
     let mut messages = broker.messages_published_on("c8y/s/us").await;
 
     let (_tmp_dir, sm_mapper) = start_c8y_mapper(broker.port, &cfg_dir).await.unwrap();
 
     publish_a_fake_jwt_token(broker).await;
     // Wait for the mapper to start properly and start the wacher for the directories
-    while let Ok(Some(msg)) = health_message.next().with_timeout(TEST_TIMEOUT_MS).await {
-        if msg.contains(r#"status":"up"#) {
-            break;
-        }
-    }
+    wait_for_mapper_initialization(&mut health_message).await;
 
     // Add an operation dynamically
     cfg_dir.dir("operations").dir("c8y").file("c8y_TestOp3");
@@ -1269,9 +1602,7 @@ async fn mapper_dynamically_updates_supported_operations_for_child_device() {
     let broker = mqtt_tests::test_mqtt_broker();
     let cfg_dir = TempTedgeDir::new();
     create_thin_edge_child_operations(&cfg_dir, vec!["c8y_ChildTestOp1", "c8y_ChildTestOp2"]);
-    let mut health_message = broker
-        .messages_published_on("tedge/health/c8y-mapper-test")
-        .await;
+    let mut health_message = mapper_health_messages(broker).await;
     let mut messages = broker.messages_published_on("c8y/s/us/child1").await;
 
     let (_tmp_dir, sm_mapper) = start_c8y_mapper(broker.port, &cfg_dir).await.unwrap();
@@ -1279,11 +1610,7 @@ async fn mapper_dynamically_updates_supported_operations_for_child_device() {
     publish_a_fake_jwt_token(broker).await;
 
     // Wait for the mapper to start properly and start the wacher for the directories
-    while let Ok(Some(msg)) = health_message.next().with_timeout(TEST_TIMEOUT_MS).await {
-        if msg.contains(r#"status":"up"#) {
-            break;
-        }
-    }
+    wait_for_mapper_initialization(&mut health_message).await;
     // Add a new operation for the child device
     cfg_dir
         .dir("operations")
@@ -1453,20 +1780,73 @@ impl C8YHttpProxy for FakeC8YHttpProxy {
     ) -> Result<String, SMCumulocityMapperError> {
         Ok("fake/upload/url".into())
     }
+
+    async fn get_unresolved_alarms(
+        &mut self,
+        _child_device_id: Option<String>,
+    ) -> Result<Vec<C8yAlarm>, SMCumulocityMapperError> {
+        Ok(vec![])
+    }
+
+    async fn update_alarm(
+        &mut self,
+        _alarm_id: &str,
+        _alarm_update: &C8yAlarmUpdate,
+    ) -> Result<(), SMCumulocityMapperError> {
+        Ok(())
+    }
+}
+
+/// The health messages of the test mapper, ignoring the status retained by a former test.
+async fn mapper_health_messages(
+    broker: &MqttProcessHandler,
+) -> futures::channel::mpsc::UnboundedReceiver<String> {
+    broker
+        .publish_with_opts(
+            "tedge/health/c8y-mapper-test",
+            "",
+            mqtt_channel::QoS::AtLeastOnce,
+            true,
+        )
+        .await
+        .unwrap();
+    broker
+        .messages_published_on("tedge/health/c8y-mapper-test")
+        .await
+}
+
+/// The mapper is up once connected, then once initialized.
+async fn wait_for_mapper_initialization(
+    health_message: &mut futures::channel::mpsc::UnboundedReceiver<String>,
+) {
+    let mut up_count = 0;
+    while let Ok(Some(msg)) = health_message.next().with_timeout(TEST_TIMEOUT_MS).await {
+        if msg.contains(r#"status":"up"#) {
+            up_count += 1;
+            if up_count == 2 {
+                break;
+            }
+        }
+    }
 }
 
 async fn start_c8y_mapper(
     mqtt_port: u16,
     ops_dir: &TempTedgeDir,
 ) -> Result<(TempTedgeDir, JoinHandle<()>), anyhow::Error> {
-    let (temp_dir, converter) = create_c8y_converter(ops_dir);
-    let mut mapper = create_mapper(
+    let mqtt_client = create_mqtt_client(
         "c8y-mapper-test",
-        MQTT_HOST.to_string(),
-        mqtt_port,
-        Box::new(converter),
+        mqtt_channel::Config::default()
+            .with_host(MQTT_HOST)
+            .with_port(mqtt_port),
+        &create_mapper_config(&Operations::default()).in_topic_filter,
+        None,
     )
     .await?;
+    let (async_messages, async_receiver) = async_messages_channel();
+    let (temp_dir, converter) = create_c8y_converter_with_publisher(ops_dir, async_messages);
+    let mut mapper = create_mapper_with_client("c8y-mapper-test", mqtt_client, Box::new(converter))
+        .with_async_messages(async_receiver);
     let ops_path = ops_dir.path().to_path_buf().join("operations").join("c8y");
     let mapper_task = tokio::spawn(async move {
         let _ = mapper.run(Some(&ops_path)).await;
@@ -1476,6 +1856,14 @@ async fn start_c8y_mapper(
 
 fn create_c8y_converter(
     ops_dir: &TempTedgeDir,
+) -> (TempTedgeDir, CumulocityConverter<FakeC8YHttpProxy>) {
+    let (mqtt_publisher, _) = futures::channel::mpsc::channel(10);
+    create_c8y_converter_with_publisher(ops_dir, mqtt_publisher)
+}
+
+fn create_c8y_converter_with_publisher(
+    ops_dir: &TempTedgeDir,
+    mqtt_publisher: Sender<Message>,
 ) -> (TempTedgeDir, CumulocityConverter<FakeC8YHttpProxy>) {
     let size_threshold = SizeThreshold(16 * 1024);
     let device_name = "test-device".into();
@@ -1493,6 +1881,28 @@ fn create_c8y_converter(
         http_proxy,
         tmp_dir.path().to_path_buf(),
         ops_dir.path().to_path_buf(),
+        mqtt_publisher,
+    )
+    .unwrap();
+    (tmp_dir, converter)
+}
+
+fn create_c8y_converter_with_proxy(
+    ops_dir: &TempTedgeDir,
+    http_proxy: MockC8YHttpProxy,
+) -> (TempTedgeDir, CumulocityConverter<MockC8YHttpProxy>) {
+    let (mqtt_publisher, _) = futures::channel::mpsc::channel(10);
+    let tmp_dir = TempTedgeDir::new();
+
+    let converter = CumulocityConverter::from_logs_path(
+        SizeThreshold(16 * 1024),
+        "test-device".into(),
+        "test-device-type".into(),
+        Operations::default(),
+        http_proxy,
+        tmp_dir.path().to_path_buf(),
+        ops_dir.path().to_path_buf(),
+        mqtt_publisher,
     )
     .unwrap();
     (tmp_dir, converter)
@@ -6,7 +6,8 @@ use crate::{
 };
 use async_trait::async_trait;
 use mqtt_channel::TopicFilter;
-use tedge_config::{ConfigSettingAccessor, MqttBindAddressSetting, MqttPortSetting, TEdgeConfig};
+use tedge_config::TEdgeConfig;
+use tedge_mqtt_config::MqttConfigExt;
 use tracing::{info, info_span, Instrument};
 
 const COLLECTD_MAPPER_NAME: &str = "tedge-mapper-collectd";
@@ -39,12 +40,8 @@ impl TEdgeComponent for CollectdMapper {
         tedge_config: TEdgeConfig,
         _config_dir: &Path,
     ) -> Result<(), anyhow::Error> {
-        let mqtt_port = tedge_config.query(MqttPortSetting)?.into();
-        let mqtt_host = tedge_config.query(MqttBindAddressSetting)?.to_string();
-
-        let device_monitor_config = DeviceMonitorConfig::default()
-            .with_port(mqtt_port)
-            .with_host(mqtt_host);
+        let device_monitor_config =
+            DeviceMonitorConfig::default().with_mqtt_config(tedge_config.mqtt_config()?);
 
         let device_monitor = DeviceMonitor::new(device_monitor_config);
         device_monitor
@@ -0,0 +1,66 @@
+use crate::core::error::ConversionError;
+
+use serde_json::{Map, Value};
+use tedge_api::{alarm::ThinEdgeAlarm, event::ThinEdgeEvent};
+use time::{format_description, OffsetDateTime};
+
+/// The JSON fields of an alarm, as sent to the clouds without a specific alarm model (Azure and AWS):
+/// `type`, `severity`, `status`, and `text` and `time` if any.
+///
+/// The alarm status is sent along, an empty payload clearing the alarm with the `cleared` status.
+/// The `default_time` is used when the alarm has no time.
+pub fn alarm_fields(
+    alarm: &ThinEdgeAlarm,
+    severity: &str,
+    default_time: Option<OffsetDateTime>,
+) -> Result<Map<String, Value>, ConversionError> {
+    let (text, time) = match &alarm.data {
+        Some(data) => (data.text.clone(), data.time),
+        None => (None, None),
+    };
+
+    let mut fields = Map::new();
+    fields.insert("type".into(), alarm.name.clone().into());
+    fields.insert("severity".into(), severity.into());
+    fields.insert("status".into(), alarm.status().as_str().into());
+    if let Some(text) = text {
+        fields.insert("text".into(), text.into());
+    }
+    insert_time(&mut fields, time.or(default_time))?;
+    Ok(fields)
+}
+
+/// The JSON fields of an event, as sent to the clouds without a specific event model (Azure and AWS):
+/// `type`, the custom fragments, and `text` and `time` if any.
+///
+/// The `default_time` is used when the event has no time.
+pub fn event_fields(
+    event: &ThinEdgeEvent,
+    default_time: Option<OffsetDateTime>,
+) -> Result<Map<String, Value>, ConversionError> {
+    let mut fields = Map::new();
+    fields.insert("type".into(), event.name.clone().into());
+    let time = match &event.data {
+        Some(data) => {
+            if let Some(text) = &data.text {
+                fields.insert("text".into(), text.as_str().into());
+            }
+            fields.extend(data.extras.clone());
+            data.time
+        }
+        None => None,
+    };
+    insert_time(&mut fields, time.or(default_time))?;
+    Ok(fields)
+}
+
+fn insert_time(
+    fields: &mut Map<String, Value>,
+    time: Option<OffsetDateTime>,
+) -> Result<(), ConversionError> {
+    if let Some(time) = time {
+        let time = time.format(&format_description::well_known::Rfc3339)?;
+        fields.insert("time".into(), time.into());
+    }
+    Ok(())
+}
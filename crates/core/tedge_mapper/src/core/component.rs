@@ -2,9 +2,8 @@ use std::path::Path;
 
 use async_trait::async_trait;
 use mqtt_channel::TopicFilter;
-use tedge_config::{
-    ConfigRepository, ConfigSettingAccessor, MqttBindAddressSetting, MqttPortSetting, TEdgeConfig,
-};
+use tedge_config::{ConfigRepository, TEdgeConfig};
+use tedge_mqtt_config::MqttConfigExt;
 use tracing::info;
 
 #[async_trait]
@@ -29,9 +28,8 @@ pub trait TEdgeComponent: Sync + Send {
             tedge_config::TEdgeConfigRepository::new(tedge_config::TEdgeConfigLocation::default());
         let tedge_config = config_repository.load()?;
 
-        let mqtt_config = mqtt_channel::Config::default()
-            .with_host(tedge_config.query(MqttBindAddressSetting)?.to_string())
-            .with_port(tedge_config.query(MqttPortSetting)?.into())
+        let mqtt_config = tedge_config
+            .mqtt_config()?
             .with_session_name(self.session_name())
             .with_clean_session(false);
 
@@ -65,7 +65,7 @@ pub trait Converter: Send + Sync {
     /// produce any additional messages as "sync messages" as a result of this processing.
     /// These sync messages will be processed by the mapper right after the sync window before it starts converting further messages.
     /// Typically used to do some processing on all messages received on mapper startup and derive additional messages out of those.
-    fn sync_messages(&mut self) -> Vec<Message> {
+    async fn sync_messages(&mut self) -> Vec<Message> {
         vec![]
     }
 
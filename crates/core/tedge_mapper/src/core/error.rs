@@ -60,6 +60,15 @@ pub enum ConversionError {
         #[from] tedge_api::event::error::ThinEdgeJsonDeserializerError,
     ),
 
+    #[error(transparent)]
+    FromAzureTwin(#[from] crate::az::twin::TwinError),
+
+    #[error(transparent)]
+    FromAzureDirectMethod(#[from] crate::az::methods::DirectMethodError),
+
+    #[error(transparent)]
+    FromAwsShadow(#[from] crate::aws::shadow::ShadowError),
+
     #[error(transparent)]
     FromThinEdgeJsonParser(#[from] tedge_api::parser::ThinEdgeJsonParserError),
 
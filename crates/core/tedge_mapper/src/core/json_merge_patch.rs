@@ -0,0 +1,23 @@
+use serde_json::{Map, Value};
+
+/// Apply a JSON merge patch (RFC 7396), as used by the Azure device twins
+/// and the AWS device shadows: the `null` values remove the corresponding properties.
+pub fn merge_patch(target: &mut Value, patch: &Value) {
+    match patch {
+        Value::Object(patch) => {
+            if !target.is_object() {
+                *target = Value::Object(Map::new());
+            }
+            if let Value::Object(target) = target {
+                for (key, value) in patch {
+                    if value.is_null() {
+                        target.remove(key);
+                    } else {
+                        merge_patch(target.entry(key.as_str()).or_insert(Value::Null), value);
+                    }
+                }
+            }
+        }
+        _ => *target = patch.clone(),
+    }
+}
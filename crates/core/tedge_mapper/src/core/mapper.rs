@@ -1,75 +1,120 @@
 use crate::c8y::dynamic_discovery::*;
-use crate::core::{converter::*, error::*};
+use crate::core::{converter::*, error::*, outbox::Outbox};
 use c8y_api::smartrest::topic::SMARTREST_PUBLISH_TOPIC;
+use futures::channel::mpsc;
 use mqtt_channel::{
-    Connection, Message, MqttError, SinkExt, StreamExt, Topic, TopicFilter, UnboundedReceiver,
-    UnboundedSender,
+    Connection, Message, MqttError, Receiver, Sender, SinkExt, StreamExt, Topic, TopicFilter,
+    UnboundedReceiver,
 };
 
 use std::path::Path;
 use std::time::Duration;
-use tedge_api::health::{health_check_topics, send_health_status};
+use tedge_api::health::{health_check_topics, send_health_status, with_health_status};
 use tedge_utils::notify::{fs_notify_stream, FsEvent};
 
 use tracing::{error, info, instrument, warn};
 const SYNC_WINDOW: Duration = Duration::from_secs(3);
+const ASYNC_MESSAGES_CAPACITY: usize = 64;
 use std::result::Result::Ok;
 
 pub async fn create_mapper(
     app_name: &str,
-    mqtt_host: String,
-    mqtt_port: u16,
+    mqtt_config: mqtt_channel::Config,
     converter: Box<dyn Converter<Error = ConversionError>>,
+    outbox: Option<Outbox>,
 ) -> Result<Mapper, anyhow::Error> {
-    info!("{} starting", app_name);
+    let mqtt_client = create_mqtt_client(
+        app_name,
+        mqtt_config,
+        converter.get_in_topic_filter(),
+        outbox.as_ref(),
+    )
+    .await?;
+
+    Ok(create_mapper_with_client(app_name, mqtt_client, converter).with_outbox(outbox))
+}
 
-    let health_check_topics: TopicFilter = health_check_topics(app_name);
+/// Connect a mapper to the MQTT bus, subscribing to the input topics of its converter,
+/// its health check topics and, when the mapper has an outbox, the health topic of the bridge.
+pub async fn create_mqtt_client(
+    app_name: &str,
+    mqtt_config: mqtt_channel::Config,
+    in_topic_filter: &TopicFilter,
+    outbox: Option<&Outbox>,
+) -> Result<Connection, anyhow::Error> {
+    info!("{} starting", app_name);
 
-    let mapper_config = converter.get_mapper_config();
-    let mut topic_filter = mapper_config.in_topic_filter.clone();
-    topic_filter.add_all(health_check_topics.clone());
+    let mut topic_filter = in_topic_filter.clone();
+    topic_filter.add_all(health_check_topics(app_name));
+    if let Some(outbox) = outbox {
+        topic_filter.add_unchecked(&outbox.bridge_health_topic().name);
+    }
 
     let mqtt_client =
-        Connection::new(&mqtt_config(app_name, &mqtt_host, mqtt_port, topic_filter)?).await?;
+        Connection::new(&mapper_mqtt_config(app_name, mqtt_config, topic_filter)).await?;
+    Ok(mqtt_client)
+}
 
+/// Create a mapper using an MQTT connection already established with [create_mqtt_client].
+pub fn create_mapper_with_client(
+    app_name: &str,
+    mqtt_client: Connection,
+    converter: Box<dyn Converter<Error = ConversionError>>,
+) -> Mapper {
     Mapper::subscribe_errors(mqtt_client.errors);
 
-    Ok(Mapper::new(
+    Mapper::new(
         app_name.to_string(),
         mqtt_client.received,
         mqtt_client.published,
         converter,
-        health_check_topics,
-    ))
+        health_check_topics(app_name),
+    )
 }
 
-pub fn mqtt_config(
+fn mapper_mqtt_config(
     name: &str,
-    host: &str,
-    port: u16,
+    mqtt_config: mqtt_channel::Config,
     topic_filter: TopicFilter,
-) -> Result<mqtt_channel::Config, anyhow::Error> {
-    Ok(mqtt_channel::Config::default()
-        .with_host(host)
-        .with_port(port)
+) -> mqtt_channel::Config {
+    with_health_status(mqtt_config, name)
         .with_session_name(name)
         .with_subscriptions(topic_filter)
-        .with_max_packet_size(10 * 1024 * 1024))
+        .with_received_droppable_topics(measurement_topics())
+        .with_max_packet_size(10 * 1024 * 1024)
+}
+
+/// The measurements can be dropped when the mapper is overloaded, unlike operation requests, alarms and events.
+fn measurement_topics() -> TopicFilter {
+    let mut topics = TopicFilter::new_unchecked("tedge/measurements");
+    topics.add_unchecked("tedge/measurements/+");
+    topics
+}
+
+/// Create a channel for the messages produced asynchronously by a converter,
+/// e.g. when an operation command completes, rather than as the conversion of an input message.
+///
+/// The receiver is given to the mapper with [Mapper::with_async_messages],
+/// so these messages are published as the converted ones, going through the outbox.
+pub fn async_messages_channel() -> (Sender<Message>, Receiver<Message>) {
+    mpsc::channel(ASYNC_MESSAGES_CAPACITY)
 }
 
 pub struct Mapper {
     mapper_name: String,
-    input: UnboundedReceiver<Message>,
-    output: UnboundedSender<Message>,
+    input: Receiver<Message>,
+    output: Sender<Message>,
     converter: Box<dyn Converter<Error = ConversionError>>,
     health_check_topics: TopicFilter,
+    outbox: Option<Outbox>,
+    async_messages: Option<Receiver<Message>>,
 }
 
 impl Mapper {
     pub fn new(
         mapper_name: String,
-        input: UnboundedReceiver<Message>,
-        output: UnboundedSender<Message>,
+        input: Receiver<Message>,
+        output: Sender<Message>,
         converter: Box<dyn Converter<Error = ConversionError>>,
         health_check_topics: TopicFilter,
     ) -> Self {
@@ -79,6 +124,21 @@ impl Mapper {
             output,
             converter,
             health_check_topics,
+            outbox: None,
+            async_messages: None,
+        }
+    }
+
+    /// Store the messages for the cloud while the bridge is down, and forward them once up.
+    pub fn with_outbox(self, outbox: Option<Outbox>) -> Self {
+        Self { outbox, ..self }
+    }
+
+    /// Publish the messages produced asynchronously by the converter, see [async_messages_channel].
+    pub fn with_async_messages(self, async_messages: Receiver<Message>) -> Self {
+        Self {
+            async_messages: Some(async_messages),
+            ..self
         }
     }
 
@@ -101,19 +161,14 @@ impl Mapper {
     async fn process_messages(&mut self, ops_dir: Option<&Path>) -> Result<(), MapperError> {
         let init_messages = self.converter.init_messages();
         for init_message in init_messages.into_iter() {
-            let _ = self.output.send(init_message).await;
+            self.publish(init_message).await;
         }
 
         // Start the sync phase here and process messages until the sync window times out
-        let _ = tokio::time::timeout(SYNC_WINDOW, async {
-            while let Some(message) = self.input.next().await {
-                self.process_message(message).await;
-            }
-        })
-        .await;
+        let _ = tokio::time::timeout(SYNC_WINDOW, self.process_input_messages()).await;
 
         // Once the sync phase is complete, retrieve all sync messages from the converter and process them
-        let sync_messages = self.converter.sync_messages();
+        let sync_messages = self.converter.sync_messages().await;
         for message in sync_messages {
             self.process_message(message).await;
         }
@@ -122,17 +177,78 @@ impl Mapper {
         Ok(())
     }
 
+    /// Process the input messages, as well as the asynchronous messages, till the input stream ends.
+    async fn process_input_messages(&mut self) {
+        loop {
+            tokio::select! {
+                message = self.input.next() => match message {
+                    Some(message) => self.process_message(message).await,
+                    None => break,
+                },
+                Some(message) = next_async_message(&mut self.async_messages) => {
+                    self.publish(message).await;
+                }
+            }
+        }
+    }
+
     async fn process_message(&mut self, message: Message) {
         if self.health_check_topics.accept(&message) {
             send_health_status(&mut self.output, &self.mapper_name).await;
+        } else if self.is_bridge_health_message(&message) {
+            self.process_bridge_status(&message).await;
         } else {
             let converted_messages = self.converter.convert(&message).await;
 
             for converted_message in converted_messages.into_iter() {
-                let _ = self.output.send(converted_message).await;
+                self.publish(converted_message).await;
+            }
+        }
+    }
+
+    fn is_bridge_health_message(&self, message: &Message) -> bool {
+        self.outbox
+            .as_ref()
+            .map_or(false, |outbox| outbox.is_bridge_health_message(message))
+    }
+
+    /// Update the bridge status and, when the bridge is up, replay in order all the stored messages.
+    async fn process_bridge_status(&mut self, message: &Message) {
+        if let Some(outbox) = self.outbox.as_mut() {
+            outbox.update_bridge_status(message);
+            if !outbox.is_bridge_up() || outbox.is_empty() {
+                return;
+            }
+
+            info!(
+                "Cloud bridge is up: forwarding {} stored messages",
+                outbox.len()
+            );
+            loop {
+                match outbox.pop() {
+                    Ok(Some(stored_message)) => {
+                        let _ = self.output.send(stored_message).await;
+                    }
+                    Ok(None) => break,
+                    Err(err) => error!("Failed to read a message from the outbox: {}", err),
+                }
             }
         }
     }
+
+    /// Send a message to the broker, unless this message is for the cloud while the bridge is down.
+    /// In the latter case, the message is stored in the outbox to be sent when the bridge is up again.
+    async fn publish(&mut self, message: Message) {
+        if let Some(outbox) = self.outbox.as_mut() {
+            if outbox.must_store(&message) {
+                match outbox.store(&message) {
+                    Ok(()) => return,
+                    Err(err) => error!("Failed to store a message in the outbox: {}", err),
+                }
+            }
+        }
+        let _ = self.output.send(message).await;
+    }
 }
 
 async fn process_messages(mapper: &mut Mapper, ops_dir: Option<&Path>) -> Result<(), MapperError> {
@@ -156,6 +272,9 @@ async fn process_messages(mapper: &mut Mapper, ops_dir: Option<&Path>) -> Result
                 Some(message) =  mapper.input.next() => {
                     mapper.process_message(message).await;
                 }
+                Some(message) = next_async_message(&mut mapper.async_messages) => {
+                    mapper.publish(message).await;
+                }
                 Some((path, file_event)) = fs_notification_stream.rx.recv() => {
 
                     match file_event {
@@ -166,13 +285,14 @@ async fn process_messages(mapper: &mut Mapper, ops_dir: Option<&Path>) -> Result
                                     &Topic::new_unchecked(SMARTREST_PUBLISH_TOPIC),
                                     format!("101,{child_id},{child_id},thin-edge.io-child"),
                                 );
-                                let _ = mapper.output.send(message).await;
+                                mapper.publish(message).await;
                             }
                         },
                         _ => {
                             match  process_inotify_events(&path, file_event) {
                                 Ok(Some(discovered_ops)) => {
-                                     let _ = mapper.output.send(mapper.converter.process_operation_update_message(discovered_ops)).await;
+                                     let message = mapper.converter.process_operation_update_message(discovered_ops);
+                                     mapper.publish(message).await;
                                 }
                                 Ok(None) => {}
                                 Err(e) => {eprintln!("Processing inotify event failed due to {}", e);}
@@ -184,13 +304,18 @@ async fn process_messages(mapper: &mut Mapper, ops_dir: Option<&Path>) -> Result
             }
         }
     } else {
-        while let Some(message) = mapper.input.next().await {
-            mapper.process_message(message).await;
-        }
+        mapper.process_input_messages().await;
         Ok(())
     }
 }
 
+async fn next_async_message(async_messages: &mut Option<Receiver<Message>>) -> Option<Message> {
+    match async_messages {
+        Some(async_messages) => async_messages.next().await,
+        None => std::future::pending().await,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -211,9 +336,9 @@ mod tests {
         let name = "mapper_under_test";
         let mut mapper = create_mapper(
             name,
-            "localhost".into(),
-            broker.port,
+            mqtt_channel::Config::default().with_port(broker.port),
             Box::new(UppercaseConverter::new()),
+            None,
         )
         .await?;
 
@@ -258,9 +383,9 @@ mod tests {
 
         let mut mapper = create_mapper(
             name,
-            "localhost".to_string(),
-            broker.port,
+            mqtt_channel::Config::default().with_port(broker.port),
             Box::new(UppercaseConverter::new()),
+            None,
         )
         .await?;
 
@@ -303,6 +428,106 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    #[serial_test::serial]
+    async fn messages_are_stored_while_the_bridge_is_down() -> Result<(), anyhow::Error> {
+        // Given an MQTT broker
+        let broker = mqtt_tests::test_mqtt_broker();
+
+        // Given a mapper with an outbox for the messages published on `out_*` topics
+        let name = "mapper_under_test";
+        let ttd = tedge_test_utils::fs::TempTedgeDir::new();
+        let bridge_health_topic = "tedge/health/mosquitto-test-bridge";
+        let outbox_config = crate::core::outbox::OutboxConfig::new(
+            ttd.path().join("outbox"),
+            bridge_health_topic,
+            "out_",
+        );
+        let outbox = Outbox::open(outbox_config)?;
+
+        let mut mapper = create_mapper(
+            name,
+            mqtt_channel::Config::default().with_port(broker.port),
+            Box::new(UppercaseConverter::new()),
+            Some(outbox),
+        )
+        .await?;
+
+        // Let's run the mapper in the background
+        tokio::spawn(async move {
+            let _ = mapper.run(None).await;
+        });
+        sleep(Duration::from_secs(1)).await;
+
+        let mut messages = broker.messages_published_on("out_topic").await;
+
+        // While the bridge is down, nothing is forwarded
+        broker.publish(bridge_health_topic, "0").await?;
+        broker.publish("in_topic", "first").await?;
+        broker.publish("in_topic", "second").await?;
+        let forwarded = tokio::time::timeout(Duration::from_secs(1), messages.next()).await;
+        assert!(forwarded.is_err());
+
+        // As soon as the bridge is up, the stored messages are forwarded in order
+        broker.publish(bridge_health_topic, "1").await?;
+        mqtt_tests::assert_received(&mut messages, Duration::from_secs(1), ["FIRST", "SECOND"])
+            .await;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    #[serial_test::serial]
+    async fn async_messages_are_stored_while_the_bridge_is_down() -> Result<(), anyhow::Error> {
+        // Given an MQTT broker
+        let broker = mqtt_tests::test_mqtt_broker();
+
+        // Given a mapper with an outbox and a channel for asynchronous messages
+        let name = "mapper_under_test";
+        let ttd = tedge_test_utils::fs::TempTedgeDir::new();
+        let bridge_health_topic = "tedge/health/mosquitto-test-bridge";
+        let outbox_config = crate::core::outbox::OutboxConfig::new(
+            ttd.path().join("outbox"),
+            bridge_health_topic,
+            "out_",
+        );
+        let outbox = Outbox::open(outbox_config)?;
+        let (mut async_messages, async_receiver) = async_messages_channel();
+
+        let mut mapper = create_mapper(
+            name,
+            mqtt_channel::Config::default().with_port(broker.port),
+            Box::new(UppercaseConverter::new()),
+            Some(outbox),
+        )
+        .await?
+        .with_async_messages(async_receiver);
+
+        // Let's run the mapper in the background
+        tokio::spawn(async move {
+            let _ = mapper.run(None).await;
+        });
+        sleep(Duration::from_secs(1)).await;
+
+        let mut messages = broker.messages_published_on("out_topic").await;
+
+        // While the bridge is down, the asynchronous messages are not forwarded
+        broker.publish(bridge_health_topic, "0").await?;
+        sleep(Duration::from_millis(100)).await;
+        let out_topic = Topic::new_unchecked("out_topic");
+        async_messages
+            .send(Message::new(&out_topic, "ASYNC"))
+            .await?;
+        let forwarded = tokio::time::timeout(Duration::from_secs(1), messages.next()).await;
+        assert!(forwarded.is_err());
+
+        // As soon as the bridge is up, they are forwarded
+        broker.publish(bridge_health_topic, "1").await?;
+        mqtt_tests::assert_received(&mut messages, Duration::from_secs(1), ["ASYNC"]).await;
+
+        Ok(())
+    }
+
     struct UppercaseConverter {
         mapper_config: MapperConfig,
     }
@@ -1,5 +1,8 @@
+pub mod cloud_json;
 pub mod component;
 pub mod converter;
 pub mod error;
+pub mod json_merge_patch;
 pub mod mapper;
+pub mod outbox;
 pub mod size_threshold;
@@ -0,0 +1,440 @@
+use mqtt_channel::{Message, QoS, Topic};
+use std::collections::VecDeque;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tedge_config::{
+    ConfigSettingAccessor, MapperOutboxEvictionSetting, MapperOutboxMaxSizeSetting,
+    OutboxEvictionPolicy, TEdgeConfig,
+};
+use tedge_utils::fs::atomically_write_file_sync;
+use tracing::{info, warn};
+
+const BRIDGE_UP_PAYLOAD: &str = "1";
+const BRIDGE_DOWN_PAYLOAD: &str = "0";
+const OUTBOX_DIRECTORY: &str = "outbox";
+const TEMP_FILE_EXTENSION: &str = "tmp";
+
+#[derive(Debug, thiserror::Error)]
+pub enum OutboxError {
+    #[error("Invalid outbox entry: {path}")]
+    InvalidEntry { path: PathBuf },
+
+    #[error(transparent)]
+    FromStdIo(#[from] std::io::Error),
+}
+
+#[derive(Debug, Clone)]
+pub struct OutboxConfig {
+    /// Directory where the messages are persisted
+    pub dir: PathBuf,
+
+    /// Maximum disk space used by the persisted messages
+    pub max_size: u64,
+
+    /// Which messages are dropped when `max_size` is reached
+    pub eviction: OutboxEvictionPolicy,
+
+    /// Topic on which the mosquitto bridge publishes its connection status
+    pub bridge_health_topic: Topic,
+
+    /// Prefix of the topics forwarded to the cloud by the bridge, which messages are stored while the bridge is down
+    ///
+    /// This must not cover request/response traffic, as twin or shadow requests,
+    /// that is meaningless when replayed after a disconnection.
+    pub cloud_topic_prefix: String,
+}
+
+impl OutboxConfig {
+    pub fn new(
+        dir: impl Into<PathBuf>,
+        bridge_health_topic: &str,
+        cloud_topic_prefix: &str,
+    ) -> Self {
+        OutboxConfig {
+            dir: dir.into(),
+            max_size: 10 * 1024 * 1024,
+            eviction: OutboxEvictionPolicy::DropOldest,
+            bridge_health_topic: Topic::new_unchecked(bridge_health_topic),
+            cloud_topic_prefix: cloud_topic_prefix.into(),
+        }
+    }
+
+    pub fn with_max_size(self, max_size: u64) -> Self {
+        Self { max_size, ..self }
+    }
+
+    pub fn with_eviction(self, eviction: OutboxEvictionPolicy) -> Self {
+        Self { eviction, ..self }
+    }
+}
+
+/// A disk-backed buffer for the messages to be forwarded to the cloud.
+///
+/// While the mosquitto bridge reports that the connection to the cloud is down,
+/// the messages published on the cloud topics are stored on disk,
+/// one file per message named after an ever-increasing index.
+/// These messages are replayed in order when the bridge is up again.
+#[derive(Debug)]
+pub struct Outbox {
+    config: OutboxConfig,
+    bridge_up: bool,
+    entries: VecDeque<OutboxEntry>,
+    size: u64,
+    next_index: u64,
+}
+
+#[derive(Debug)]
+struct OutboxEntry {
+    index: u64,
+    size: u64,
+}
+
+impl Outbox {
+    /// Open the outbox, reloading the messages persisted by a previous run.
+    pub fn open(config: OutboxConfig) -> Result<Outbox, OutboxError> {
+        fs::create_dir_all(&config.dir)?;
+
+        let mut entries = Vec::new();
+        for entry in fs::read_dir(&config.dir)? {
+            let path = entry?.path();
+            match entry_index(&path) {
+                Some(index) => {
+                    let size = fs::metadata(&path)?.len();
+                    entries.push(OutboxEntry { index, size });
+                }
+                None => {
+                    // Left-over of an interrupted write
+                    warn!("Removing unexpected outbox file: {}", path.display());
+                    let _ = fs::remove_file(&path);
+                }
+            }
+        }
+        entries.sort_by_key(|entry| entry.index);
+
+        let size = entries.iter().map(|entry| entry.size).sum();
+        let next_index = entries.last().map(|entry| entry.index + 1).unwrap_or(0);
+        if !entries.is_empty() {
+            info!(
+                "{} messages pending in the outbox {}",
+                entries.len(),
+                config.dir.display()
+            );
+        }
+
+        Ok(Outbox {
+            config,
+            bridge_up: true,
+            entries: entries.into(),
+            size,
+            next_index,
+        })
+    }
+
+    pub fn bridge_health_topic(&self) -> &Topic {
+        &self.config.bridge_health_topic
+    }
+
+    pub fn is_bridge_health_message(&self, message: &Message) -> bool {
+        message.topic == self.config.bridge_health_topic
+    }
+
+    /// Update the bridge status from a message received on the bridge health topic.
+    pub fn update_bridge_status(&mut self, message: &Message) {
+        match message.payload_str() {
+            Ok(BRIDGE_UP_PAYLOAD) => self.bridge_up = true,
+            Ok(BRIDGE_DOWN_PAYLOAD) => self.bridge_up = false,
+            _ => warn!(
+                "Unexpected bridge status on {}",
+                self.config.bridge_health_topic.name
+            ),
+        }
+    }
+
+    pub fn is_bridge_up(&self) -> bool {
+        self.bridge_up
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Disk space used by the stored messages
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// Tell if the given message has to be stored rather than published,
+    /// i.e. if this message is for the cloud while the bridge is down.
+    pub fn must_store(&self, message: &Message) -> bool {
+        !self.bridge_up
+            && message
+                .topic
+                .name
+                .starts_with(&self.config.cloud_topic_prefix)
+    }
+
+    /// Persist a message, evicting messages according to the eviction policy if the outbox is full.
+    pub fn store(&mut self, message: &Message) -> Result<(), OutboxError> {
+        let bytes = encode(message);
+        let size = bytes.len() as u64;
+
+        if size > self.config.max_size {
+            warn!(
+                "Dropping a message on {} too large for the outbox",
+                message.topic.name
+            );
+            return Ok(());
+        }
+
+        while self.size + size > self.config.max_size {
+            match self.config.eviction {
+                OutboxEvictionPolicy::DropOldest => {
+                    if let Some(entry) = self.entries.pop_front() {
+                        warn!("Outbox is full: dropping the oldest message");
+                        self.remove_entry(&entry)?;
+                    }
+                }
+                OutboxEvictionPolicy::DropNewest => {
+                    warn!(
+                        "Outbox is full: dropping a message on {}",
+                        message.topic.name
+                    );
+                    return Ok(());
+                }
+            }
+        }
+
+        let index = self.next_index;
+        let path = self.entry_path(index);
+        let temp_path = path.with_extension(TEMP_FILE_EXTENSION);
+        atomically_write_file_sync(temp_path, path, &bytes)?;
+
+        self.next_index += 1;
+        self.size += size;
+        self.entries.push_back(OutboxEntry { index, size });
+        Ok(())
+    }
+
+    /// Remove and return the oldest stored message.
+    pub fn pop(&mut self) -> Result<Option<Message>, OutboxError> {
+        match self.entries.pop_front() {
+            None => Ok(None),
+            Some(entry) => {
+                let path = self.entry_path(entry.index);
+                let bytes = fs::read(&path);
+                self.remove_entry(&entry)?;
+                decode(&bytes?)
+                    .map(Some)
+                    .ok_or(OutboxError::InvalidEntry { path })
+            }
+        }
+    }
+
+    fn remove_entry(&mut self, entry: &OutboxEntry) -> Result<(), OutboxError> {
+        self.size -= entry.size;
+        fs::remove_file(self.entry_path(entry.index))?;
+        Ok(())
+    }
+
+    fn entry_path(&self, index: u64) -> PathBuf {
+        self.config.dir.join(format!("{index:020}"))
+    }
+}
+
+/// Create the outbox of a mapper, as configured by the `mapper.outbox` settings.
+///
+/// Returns `None` if the outbox has been disabled by setting its maximum size to 0.
+pub fn create_outbox(
+    tedge_config: &TEdgeConfig,
+    mapper_dir: &Path,
+    bridge_health_topic: &str,
+    cloud_topic_prefix: &str,
+) -> Result<Option<Outbox>, anyhow::Error> {
+    let max_size: u64 = tedge_config.query(MapperOutboxMaxSizeSetting)?.into();
+    if max_size == 0 {
+        return Ok(None);
+    }
+    let eviction = tedge_config.query(MapperOutboxEvictionSetting)?;
+
+    let config = OutboxConfig::new(
+        mapper_dir.join(OUTBOX_DIRECTORY),
+        bridge_health_topic,
+        cloud_topic_prefix,
+    )
+    .with_max_size(max_size)
+    .with_eviction(eviction);
+
+    Ok(Some(Outbox::open(config)?))
+}
+
+fn entry_index(path: &Path) -> Option<u64> {
+    path.file_name()?.to_str()?.parse::<u64>().ok()
+}
+
+/// Encode a message as: `topic\nqos\nretain\npayload`
+fn encode(message: &Message) -> Vec<u8> {
+    let qos = match message.qos {
+        QoS::AtMostOnce => 0,
+        QoS::AtLeastOnce => 1,
+        QoS::ExactlyOnce => 2,
+    };
+    let retain = u8::from(message.retain);
+    let mut bytes = format!("{}\n{qos}\n{retain}\n", message.topic.name).into_bytes();
+    bytes.extend_from_slice(message.payload_bytes());
+    bytes
+}
+
+fn decode(bytes: &[u8]) -> Option<Message> {
+    let mut parts = bytes.splitn(4, |b| *b == b'\n');
+    let topic = std::str::from_utf8(parts.next()?).ok()?;
+    let qos = match parts.next()? {
+        b"0" => QoS::AtMostOnce,
+        b"1" => QoS::AtLeastOnce,
+        b"2" => QoS::ExactlyOnce,
+        _ => return None,
+    };
+    let retain = parts.next()? == b"1";
+    let payload = parts.next()?;
+
+    let message = Message::new(&Topic::new_unchecked(topic), payload).with_qos(qos);
+    if retain {
+        Some(message.with_retain())
+    } else {
+        Some(message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tedge_test_utils::fs::TempTedgeDir;
+
+    const BRIDGE_HEALTH_TOPIC: &str = "tedge/health/mosquitto-c8y-bridge";
+
+    fn outbox_config(dir: &TempTedgeDir) -> OutboxConfig {
+        OutboxConfig::new(dir.path().join("outbox"), BRIDGE_HEALTH_TOPIC, "c8y/")
+    }
+
+    fn bridge_status(payload: &str) -> Message {
+        Message::new(&Topic::new_unchecked(BRIDGE_HEALTH_TOPIC), payload)
+    }
+
+    fn cloud_message(payload: &str) -> Message {
+        Message::new(&Topic::new_unchecked("c8y/s/us"), payload)
+    }
+
+    #[test]
+    fn messages_are_stored_only_for_the_cloud_while_the_bridge_is_down() {
+        let ttd = TempTedgeDir::new();
+        let mut outbox = Outbox::open(outbox_config(&ttd)).unwrap();
+        let local_message = Message::new(&Topic::new_unchecked("tedge/errors"), "oops");
+
+        assert!(!outbox.must_store(&cloud_message("200,temp,1")));
+
+        outbox.update_bridge_status(&bridge_status("0"));
+        assert!(outbox.must_store(&cloud_message("200,temp,1")));
+        assert!(!outbox.must_store(&local_message));
+
+        outbox.update_bridge_status(&bridge_status("1"));
+        assert!(!outbox.must_store(&cloud_message("200,temp,1")));
+    }
+
+    #[test]
+    fn only_the_messages_under_the_cloud_topic_prefix_are_stored() {
+        let ttd = TempTedgeDir::new();
+        let config = OutboxConfig::new(
+            ttd.path().join("outbox"),
+            "tedge/health/mosquitto-az-bridge",
+            "az/messages/events/",
+        );
+        let mut outbox = Outbox::open(config).unwrap();
+        outbox.update_bridge_status(&Message::new(
+            &Topic::new_unchecked("tedge/health/mosquitto-az-bridge"),
+            "0",
+        ));
+
+        let telemetry = Message::new(&Topic::new_unchecked("az/messages/events/"), "{}");
+        let twin_request = Message::new(&Topic::new_unchecked("az/twin/GET/?$rid=1"), "");
+        let method_response =
+            Message::new(&Topic::new_unchecked("az/methods/res/200/?$rid=2"), "{}");
+        assert!(outbox.must_store(&telemetry));
+        assert!(!outbox.must_store(&twin_request));
+        assert!(!outbox.must_store(&method_response));
+    }
+
+    #[test]
+    fn stored_messages_are_replayed_in_order() {
+        let ttd = TempTedgeDir::new();
+        let mut outbox = Outbox::open(outbox_config(&ttd)).unwrap();
+        let retained = cloud_message("third").with_retain();
+
+        outbox.store(&cloud_message("first")).unwrap();
+        outbox.store(&cloud_message("second")).unwrap();
+        outbox.store(&retained).unwrap();
+        assert_eq!(outbox.len(), 3);
+
+        assert_eq!(outbox.pop().unwrap(), Some(cloud_message("first")));
+        assert_eq!(outbox.pop().unwrap(), Some(cloud_message("second")));
+        assert_eq!(outbox.pop().unwrap(), Some(retained));
+        assert_eq!(outbox.pop().unwrap(), None);
+        assert_eq!(outbox.size(), 0);
+    }
+
+    #[test]
+    fn stored_messages_survive_a_restart() {
+        let ttd = TempTedgeDir::new();
+        {
+            let mut outbox = Outbox::open(outbox_config(&ttd)).unwrap();
+            outbox.store(&cloud_message("first")).unwrap();
+            outbox.store(&cloud_message("second")).unwrap();
+        }
+
+        let mut outbox = Outbox::open(outbox_config(&ttd)).unwrap();
+        assert_eq!(outbox.len(), 2);
+        outbox.store(&cloud_message("third")).unwrap();
+
+        assert_eq!(outbox.pop().unwrap(), Some(cloud_message("first")));
+        assert_eq!(outbox.pop().unwrap(), Some(cloud_message("second")));
+        assert_eq!(outbox.pop().unwrap(), Some(cloud_message("third")));
+    }
+
+    #[test]
+    fn oldest_messages_are_evicted_when_the_outbox_is_full() {
+        let ttd = TempTedgeDir::new();
+        let message_size = encode(&cloud_message("msg-1")).len() as u64;
+        let config = outbox_config(&ttd)
+            .with_max_size(2 * message_size)
+            .with_eviction(OutboxEvictionPolicy::DropOldest);
+        let mut outbox = Outbox::open(config).unwrap();
+
+        outbox.store(&cloud_message("msg-1")).unwrap();
+        outbox.store(&cloud_message("msg-2")).unwrap();
+        outbox.store(&cloud_message("msg-3")).unwrap();
+
+        assert_eq!(outbox.len(), 2);
+        assert_eq!(outbox.pop().unwrap(), Some(cloud_message("msg-2")));
+        assert_eq!(outbox.pop().unwrap(), Some(cloud_message("msg-3")));
+    }
+
+    #[test]
+    fn newest_messages_are_dropped_when_the_outbox_is_full() {
+        let ttd = TempTedgeDir::new();
+        let message_size = encode(&cloud_message("msg-1")).len() as u64;
+        let config = outbox_config(&ttd)
+            .with_max_size(2 * message_size)
+            .with_eviction(OutboxEvictionPolicy::DropNewest);
+        let mut outbox = Outbox::open(config).unwrap();
+
+        outbox.store(&cloud_message("msg-1")).unwrap();
+        outbox.store(&cloud_message("msg-2")).unwrap();
+        outbox.store(&cloud_message("msg-3")).unwrap();
+
+        assert_eq!(outbox.len(), 2);
+        assert_eq!(outbox.pop().unwrap(), Some(cloud_message("msg-1")));
+        assert_eq!(outbox.pop().unwrap(), Some(cloud_message("msg-2")));
+    }
+}
@@ -1,8 +1,8 @@
 use std::{fmt, path::PathBuf};
 
 use crate::{
-    az::mapper::AzureMapper, c8y::mapper::CumulocityMapper, collectd::mapper::CollectdMapper,
-    core::component::TEdgeComponent,
+    aws::mapper::AwsMapper, az::mapper::AzureMapper, c8y::mapper::CumulocityMapper,
+    collectd::mapper::CollectdMapper, core::component::TEdgeComponent,
 };
 use clap::Parser;
 use flockfile::check_another_instance_is_not_running;
@@ -10,6 +10,7 @@ use tedge_config::system_services::{get_log_level, set_log_level};
 use tedge_config::DEFAULT_TEDGE_CONFIG_PATH;
 use tedge_config::*;
 
+mod aws;
 mod az;
 mod c8y;
 mod collectd;
@@ -18,6 +19,7 @@ mod core;
 fn lookup_component(component_name: &MapperName) -> Box<dyn TEdgeComponent> {
     match component_name {
         MapperName::Az => Box::new(AzureMapper::new()),
+        MapperName::Aws => Box::new(AwsMapper::new()),
         MapperName::Collectd => Box::new(CollectdMapper::new()),
         MapperName::C8y => Box::new(CumulocityMapper::new()),
     }
@@ -60,6 +62,7 @@ pub struct MapperOpt {
 #[derive(Debug, clap::Subcommand)]
 pub enum MapperName {
     Az,
+    Aws,
     C8y,
     Collectd,
 }
@@ -68,6 +71,7 @@ impl fmt::Display for MapperName {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             MapperName::Az => write!(f, "tedge-mapper-az"),
+            MapperName::Aws => write!(f, "tedge-mapper-aws"),
             MapperName::C8y => write!(f, "tedge-mapper-c8y"),
             MapperName::Collectd => write!(f, "tedge-mapper-collectd"),
         }
@@ -1,30 +1,19 @@
 use crate::error::WatchdogError;
 use freedesktop_entry_parser::parse_entry;
-use futures::channel::mpsc;
 use futures::stream::FuturesUnordered;
-use futures::StreamExt;
-use mqtt_channel::{Config, Message, PubChannel, Topic};
+use mqtt_channel::Config;
 use nanoid::nanoid;
-use serde::{Deserialize, Serialize};
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use std::{
     path::PathBuf,
     process::{self, Command, ExitStatus, Stdio},
 };
-use tedge_config::{
-    ConfigRepository, ConfigSettingAccessor, MqttBindAddressSetting, MqttPortSetting,
-    TEdgeConfigLocation,
-};
-use time::OffsetDateTime;
+use tedge_api::health::{health_status_topic, HealthCheckRequest};
+use tedge_api::request::{RequestClient, RequestError};
+use tedge_config::{ConfigRepository, TEdgeConfigLocation};
+use tedge_mqtt_config::MqttConfigExt;
 use tracing::{debug, error, info, warn};
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct HealthStatus {
-    status: String,
-    pid: u32,
-    time: i64,
-}
-
 pub async fn start_watchdog(tedge_config_dir: PathBuf) -> Result<(), anyhow::Error> {
     // Send ready notification to systemd.
     notify_systemd(process::id(), "--ready")?;
@@ -66,6 +55,7 @@ async fn start_watchdog_for_tedge_services(tedge_config_dir: PathBuf) {
     let tedge_services = vec![
         "tedge-mapper-c8y",
         "tedge-mapper-az",
+        "tedge-mapper-aws",
         "tedge-mapper-collectd",
         "tedge-agent",
         "c8y-log-plugin",
@@ -77,20 +67,11 @@ async fn start_watchdog_for_tedge_services(tedge_config_dir: PathBuf) {
     for service in tedge_services {
         match get_watchdog_sec(&format!("/lib/systemd/system/{service}.service")) {
             Ok(interval) => {
-                let req_topic = format!("tedge/health-check/{service}");
-                let res_topic = format!("tedge/health/{service}");
                 let tedge_config_location =
                     tedge_config::TEdgeConfigLocation::from_custom_root(tedge_config_dir.clone());
 
                 watchdog_tasks.push(tokio::spawn(async move {
-                    monitor_tedge_service(
-                        tedge_config_location,
-                        service,
-                        &req_topic,
-                        &res_topic,
-                        interval / 4,
-                    )
-                    .await
+                    monitor_tedge_service(tedge_config_location, service, interval / 4).await
                 }));
             }
 
@@ -106,34 +87,23 @@ async fn start_watchdog_for_tedge_services(tedge_config_dir: PathBuf) {
 async fn monitor_tedge_service(
     tedge_config_location: TEdgeConfigLocation,
     name: &str,
-    req_topic: &str,
-    res_topic: &str,
     interval: u64,
 ) -> Result<(), WatchdogError> {
     let client_id: &str = &format!("{}_{}", name, nanoid!());
     let mqtt_config = get_mqtt_config(tedge_config_location, client_id)?
-        .with_subscriptions(res_topic.try_into()?);
+        .with_subscriptions(health_status_topic(name).into());
     let client = mqtt_channel::Connection::new(&mqtt_config).await?;
-    let mut received = client.received;
-    let mut publisher = client.published;
+    let mut health_check_client = RequestClient::new(client.published, client.received)
+        .with_timeout(Duration::from_secs(interval));
 
     info!("Starting watchdog for {} service", name);
 
     loop {
-        let message = Message::new(&Topic::new(req_topic)?, "");
-        let _ = publisher
-            .publish(message)
-            .await
-            .map_err(|e| warn!("Publish failed with error: {}", e));
-
         let start = Instant::now();
 
-        let request_timestamp = OffsetDateTime::now_utc().unix_timestamp();
-        match tokio::time::timeout(
-            tokio::time::Duration::from_secs(interval),
-            get_latest_health_status_message(request_timestamp, &mut received),
-        )
-        .await
+        match health_check_client
+            .request(&HealthCheckRequest::new(name))
+            .await
         {
             Ok(health_status) => {
                 debug!(
@@ -142,54 +112,29 @@ async fn monitor_tedge_service(
                 );
                 notify_systemd(health_status.pid, "WATCHDOG=1")?;
             }
-            Err(_) => {
+            Err(RequestError::Timeout(_)) => {
                 warn!("No health check response received from {name} in time");
             }
+            Err(err) => {
+                warn!("Health check of {name} failed with error: {err}");
+            }
         }
 
         let elapsed = start.elapsed();
-        if elapsed < tokio::time::Duration::from_secs(interval) {
-            tokio::time::sleep(tokio::time::Duration::from_secs(interval) - elapsed).await;
+        if elapsed < Duration::from_secs(interval) {
+            tokio::time::sleep(Duration::from_secs(interval) - elapsed).await;
             warn!("tedge systemd watchdog not started because no services to monitor")
         }
     }
 }
 
-async fn get_latest_health_status_message(
-    request_timestamp: i64,
-    messages: &mut mpsc::UnboundedReceiver<Message>,
-) -> HealthStatus {
-    loop {
-        if let Some(message) = messages.next().await {
-            if let Ok(message) = message.payload_str() {
-                debug!("Health response received: {}", message);
-                if let Ok(health_status) = serde_json::from_str::<HealthStatus>(message) {
-                    if health_status.time >= request_timestamp {
-                        return health_status;
-                    } else {
-                        debug!(
-                            "Ignoring stale health response: {:?} older than request time: {}",
-                            health_status, request_timestamp
-                        );
-                    }
-                } else {
-                    error!("Invalid health response received: {}", message);
-                }
-            }
-        }
-    }
-}
-
 fn get_mqtt_config(
     tedge_config_location: TEdgeConfigLocation,
     client_id: &str,
 ) -> Result<Config, WatchdogError> {
     let config_repository = tedge_config::TEdgeConfigRepository::new(tedge_config_location);
     let tedge_config = config_repository.load()?;
-    let mqtt_config = Config::default()
-        .with_session_name(client_id)
-        .with_host(tedge_config.query(MqttBindAddressSetting)?.to_string())
-        .with_port(tedge_config.query(MqttPortSetting)?.into());
+    let mqtt_config = tedge_config.mqtt_config()?.with_session_name(client_id);
     Ok(mqtt_config)
 }
 
@@ -228,35 +173,37 @@ fn get_watchdog_sec(service_file: &str) -> Result<u64, WatchdogError> {
 #[cfg(test)]
 mod tests {
     use anyhow::Result;
+    use futures::channel::mpsc;
+    use mqtt_channel::{Message, PubChannel};
     use serde_json::json;
+    use time::OffsetDateTime;
 
     use super::*;
 
     #[tokio::test]
-    async fn test_get_latest_health_status_message() -> Result<()> {
-        let (mut sender, mut receiver) = mpsc::unbounded::<Message>();
-        let health_topic = Topic::new("tedge/health/test-service").expect("Valid topic");
-
-        for x in 1..5i64 {
+    async fn test_health_check_ignores_stale_health_status() -> Result<()> {
+        let (requests, _published) = mpsc::unbounded::<Message>();
+        let (mut sender, receiver) = mpsc::unbounded::<Message>();
+        let mut health_check_client =
+            RequestClient::new(requests, receiver).with_timeout(Duration::from_secs(1));
+        let request = HealthCheckRequest::new("test-service");
+
+        for time in [0, OffsetDateTime::now_utc().unix_timestamp()] {
             let health_status = json!({
                 "status": "up",
                 "pid": 123u32,
-                "time": x,
+                "time": time,
             })
             .to_string();
-            let health_message = Message::new(&health_topic, health_status);
+            let health_message = Message::new(&health_status_topic("test-service"), health_status);
             sender.publish(health_message).await?;
         }
 
-        let health_status = get_latest_health_status_message(3, &mut receiver).await;
-        assert_eq!(health_status.time, 3);
+        let health_status = health_check_client.request(&request).await?;
+        assert_ne!(health_status.time, 0);
 
-        let timeout_error = tokio::time::timeout(
-            tokio::time::Duration::from_secs(1),
-            get_latest_health_status_message(5, &mut receiver),
-        )
-        .await;
-        assert!(timeout_error.is_err());
+        let timeout_error = health_check_client.request(&request).await;
+        assert!(matches!(timeout_error, Err(RequestError::Timeout(_))));
 
         Ok(())
     }
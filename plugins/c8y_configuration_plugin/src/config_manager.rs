@@ -22,7 +22,7 @@ use tokio::sync::Mutex;
 use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::Duration;
-use tedge_api::health::{health_check_topics, send_health_status};
+use tedge_api::health::{health_check_topics, send_health_status, with_health_status};
 use tedge_utils::{notify::fs_notify_stream, paths::PathsError};
 
 use tedge_utils::notify::{FsEvent, NotifyStream};
@@ -55,7 +55,7 @@ pub enum ActiveOperationState {
 impl ConfigManager {
     pub async fn new(
         tedge_device_id: impl ToString,
-        mqtt_port: u16,
+        mqtt_config: mqtt_channel::Config,
         http_client: Arc<Mutex<dyn C8YHttpProxy>>,
         local_http_host: impl ToString,
         tmp_dir: PathBuf,
@@ -66,7 +66,7 @@ impl ConfigManager {
         let plugin_config =
             PluginConfig::new(&config_file_dir.join(DEFAULT_PLUGIN_CONFIG_FILE_NAME));
 
-        let mqtt_client = Self::create_mqtt_client(mqtt_port).await?;
+        let mqtt_client = Self::create_mqtt_client(mqtt_config).await?;
 
         let c8y_request_topics: TopicFilter = C8yTopic::SmartRestRequest.into();
         let health_check_topics = health_check_topics("c8y-configuration-plugin");
@@ -191,7 +191,9 @@ impl ConfigManager {
         }
     }
 
-    async fn create_mqtt_client(mqtt_port: u16) -> Result<mqtt_channel::Connection, anyhow::Error> {
+    async fn create_mqtt_client(
+        mqtt_config: mqtt_channel::Config,
+    ) -> Result<mqtt_channel::Connection, anyhow::Error> {
         let mut topic_filter =
             mqtt_channel::TopicFilter::new_unchecked(&C8yTopic::SmartRestRequest.to_string());
         topic_filter.add_all(health_check_topics("c8y-configuration-plugin"));
@@ -199,9 +201,8 @@ impl ConfigManager {
         topic_filter.add_all(ConfigOperationResponseTopic::SnapshotResponse.into());
         topic_filter.add_all(ConfigOperationResponseTopic::UpdateResponse.into());
 
-        let mqtt_config = mqtt_channel::Config::default()
+        let mqtt_config = with_health_status(mqtt_config, "c8y-configuration-plugin")
             .with_session_name("c8y-configuration-plugin")
-            .with_port(mqtt_port)
             .with_subscriptions(topic_filter);
 
         let mqtt_client = mqtt_channel::Connection::new(&mqtt_config).await?;
@@ -21,7 +21,7 @@ use c8y_api::smartrest::smartrest_serializer::{
 };
 use c8y_api::smartrest::topic::C8yTopic;
 use download::{Auth, DownloadInfo, Downloader};
-use mqtt_channel::{Message, SinkExt, Topic, UnboundedSender};
+use mqtt_channel::{Message, Sender, SinkExt, Topic};
 use tedge_api::OperationStatus;
 
 use serde_json::json;
@@ -38,7 +38,7 @@ use tracing::{info, warn};
 
 pub struct ConfigDownloadManager {
     tedge_device_id: String,
-    mqtt_publisher: UnboundedSender<Message>,
+    mqtt_publisher: Sender<Message>,
     http_client: Arc<Mutex<dyn C8YHttpProxy>>,
     local_http_host: String,
     config_dir: PathBuf,
@@ -49,7 +49,7 @@ pub struct ConfigDownloadManager {
 impl ConfigDownloadManager {
     pub fn new(
         tedge_device_id: String,
-        mqtt_publisher: UnboundedSender<Message>,
+        mqtt_publisher: Sender<Message>,
         http_client: Arc<Mutex<dyn C8YHttpProxy>>,
         local_http_host: String,
         config_dir: PathBuf,
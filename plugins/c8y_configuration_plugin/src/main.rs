@@ -17,13 +17,14 @@ use c8y_api::http_proxy::{C8YHttpProxy, JwtAuthHttpProxy};
 use clap::Parser;
 use config_manager::ConfigManager;
 use tedge_config::system_services::{get_log_level, set_log_level};
+use tedge_mqtt_config::MqttConfigExt;
 use tokio::sync::Mutex;
 
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use tedge_config::{
     ConfigRepository, ConfigSettingAccessor, DeviceIdSetting, HttpBindAddressSetting,
-    HttpPortSetting, MqttPortSetting, TEdgeConfig, TmpPathSetting, DEFAULT_TEDGE_CONFIG_PATH,
+    HttpPortSetting, TEdgeConfig, TmpPathSetting, DEFAULT_TEDGE_CONFIG_PATH,
 };
 use tedge_utils::file::{create_directory_with_user_group, create_file_with_user_group};
 use tracing::{error, info};
@@ -96,7 +97,7 @@ async fn main() -> Result<(), anyhow::Error> {
 
     let tedge_device_id = tedge_config.query(DeviceIdSetting)?;
 
-    let mqtt_port = tedge_config.query(MqttPortSetting)?.into();
+    let mqtt_config = tedge_config.mqtt_config()?;
     let http_client = create_http_client(&tedge_config).await?;
     let http_client: Arc<Mutex<dyn C8YHttpProxy>> = Arc::new(Mutex::new(http_client));
     let tmp_dir = tedge_config.query(TmpPathSetting)?.into();
@@ -107,7 +108,7 @@ async fn main() -> Result<(), anyhow::Error> {
 
     let mut config_manager = ConfigManager::new(
         tedge_device_id,
-        mqtt_port,
+        mqtt_config,
         http_client,
         local_http_host,
         tmp_dir,
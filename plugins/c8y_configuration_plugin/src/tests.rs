@@ -56,7 +56,7 @@ async fn test_handle_config_upload_request_tedge_device() -> anyhow::Result<()>
 
     let mut config_manager = ConfigManager::new(
         tedge_device_id,
-        broker.port,
+        mqtt_channel::Config::default().with_port(broker.port),
         Arc::new(Mutex::new(c8y_http_client)),
         mockito::server_address().to_string(),
         ttd.path().to_path_buf(),
@@ -144,7 +144,7 @@ async fn test_handle_config_upload_request_child_device() -> anyhow::Result<()>
 
     let mut config_manager = ConfigManager::new(
         tedge_device_id,
-        broker.port,
+        mqtt_channel::Config::default().with_port(broker.port),
         Arc::new(Mutex::new(c8y_http_client)),
         mockito::server_address().to_string(),
         tmp_dir.path().to_path_buf(),
@@ -215,7 +215,7 @@ async fn test_handle_config_upload_executing_response_child_device() -> anyhow::
 
     let mut config_manager = ConfigManager::new(
         tedge_device_id,
-        broker.port,
+        mqtt_channel::Config::default().with_port(broker.port),
         Arc::new(Mutex::new(c8y_http_client)),
         mockito::server_address().to_string(),
         ttd.path().to_path_buf(),
@@ -282,7 +282,7 @@ async fn test_handle_config_upload_failed_response_child_device() -> anyhow::Res
 
     let mut config_manager = ConfigManager::new(
         tedge_device_id,
-        broker.port,
+        mqtt_channel::Config::default().with_port(broker.port),
         Arc::new(Mutex::new(c8y_http_client)),
         mockito::server_address().to_string(),
         ttd.path().to_path_buf(),
@@ -339,7 +339,7 @@ async fn test_invalid_config_snapshot_response_child_device() -> anyhow::Result<
 
     let mut config_manager = ConfigManager::new(
         tedge_device_id,
-        broker.port,
+        mqtt_channel::Config::default().with_port(broker.port),
         Arc::new(Mutex::new(c8y_http_client)),
         mockito::server_address().to_string(),
         tmp_dir.path().to_path_buf(),
@@ -403,7 +403,7 @@ async fn test_no_config_snapshot_response_child_device() -> anyhow::Result<()> {
 
     let mut config_manager = ConfigManager::new(
         tedge_device_id,
-        broker.port,
+        mqtt_channel::Config::default().with_port(broker.port),
         Arc::new(Mutex::new(c8y_http_client)),
         mockito::server_address().to_string(),
         tmp_dir.path().to_path_buf(),
@@ -485,7 +485,7 @@ async fn test_handle_config_upload_successful_response_child_device() -> anyhow:
 
     let mut config_manager = ConfigManager::new(
         tedge_device_id,
-        broker.port,
+        mqtt_channel::Config::default().with_port(broker.port),
         Arc::new(Mutex::new(c8y_http_client)),
         mockito::server_address().to_string(),
         ttd.path().to_path_buf(),
@@ -559,7 +559,7 @@ async fn test_child_config_upload_successful_response_mapped_to_failed_without_u
 
     let mut config_manager = ConfigManager::new(
         tedge_device_id,
-        broker.port,
+        mqtt_channel::Config::default().with_port(broker.port),
         Arc::new(Mutex::new(c8y_http_client)),
         mockito::server_address().to_string(),
         tmp_dir.to_path_buf(),
@@ -639,7 +639,7 @@ async fn test_handle_config_update_request_tedge_device() -> anyhow::Result<()>
 
     let mut config_manager = ConfigManager::new(
         tedge_device_id,
-        broker.port,
+        mqtt_channel::Config::default().with_port(broker.port),
         Arc::new(Mutex::new(c8y_http_client)),
         mockito::server_address().to_string(),
         tmp_dir.to_path_buf(),
@@ -718,7 +718,7 @@ async fn test_handle_config_update_request_child_device() -> anyhow::Result<()>
 
     let mut config_manager = ConfigManager::new(
         tedge_device_id,
-        broker.port,
+        mqtt_channel::Config::default().with_port(broker.port),
         Arc::new(Mutex::new(c8y_http_client)),
         mockito::server_address().to_string(),
         tmp_dir.to_path_buf(),
@@ -802,7 +802,7 @@ async fn test_c8y_config_download_child_device_fail_on_broken_url() -> anyhow::R
 
     let mut config_manager = ConfigManager::new(
         tedge_device_id,
-        broker.port,
+        mqtt_channel::Config::default().with_port(broker.port),
         Arc::new(Mutex::new(c8y_http_client)),
         mockito::server_address().to_string(),
         tmp_dir.to_path_buf(),
@@ -870,7 +870,7 @@ async fn test_handle_config_update_successful_response_child_device() -> anyhow:
 
     let mut config_manager = ConfigManager::new(
         tedge_device_id,
-        broker.port,
+        mqtt_channel::Config::default().with_port(broker.port),
         Arc::new(Mutex::new(c8y_http_client)),
         mockito::server_address().to_string(),
         ttd.to_path_buf(),
@@ -927,7 +927,7 @@ async fn test_invalid_config_update_response_child_device() -> anyhow::Result<()
 
     let mut config_manager = ConfigManager::new(
         tedge_device_id,
-        broker.port,
+        mqtt_channel::Config::default().with_port(broker.port),
         Arc::new(Mutex::new(c8y_http_client)),
         mockito::server_address().to_string(),
         tmp_dir.to_path_buf(),
@@ -987,7 +987,7 @@ async fn test_handle_multiline_config_upload_requests() -> anyhow::Result<()> {
 
     let mut config_manager = ConfigManager::new(
         tedge_device_id,
-        broker.port,
+        mqtt_channel::Config::default().with_port(broker.port),
         Arc::new(Mutex::new(c8y_http_client)),
         mockito::server_address().to_string(),
         ttd.path().to_path_buf(),
@@ -23,7 +23,7 @@ use c8y_api::smartrest::{
 };
 use tedge_api::OperationStatus;
 
-use mqtt_channel::{Message, SinkExt, Topic, UnboundedSender};
+use mqtt_channel::{Message, Sender, SinkExt, Topic};
 use tedge_utils::{
     file::{create_directory_with_user_group, create_file_with_user_group},
     timers::Timers,
@@ -67,7 +67,7 @@ impl TryIntoOperationStatusMessage for UploadConfigFileStatusMessage {
 
 pub struct ConfigUploadManager {
     tedge_device_id: String,
-    mqtt_publisher: UnboundedSender<Message>,
+    mqtt_publisher: Sender<Message>,
     http_client: Arc<Mutex<dyn C8YHttpProxy>>,
     local_http_host: String,
     config_dir: PathBuf,
@@ -77,7 +77,7 @@ pub struct ConfigUploadManager {
 impl ConfigUploadManager {
     pub fn new(
         tedge_device_id: String,
-        mqtt_publisher: UnboundedSender<Message>,
+        mqtt_publisher: Sender<Message>,
         http_client: Arc<Mutex<dyn C8YHttpProxy>>,
         local_http_host: String,
         config_dir: PathBuf,